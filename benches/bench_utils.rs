@@ -52,6 +52,7 @@ pub fn setup_logging(
                 log_max_body_size: 20480,
                 log_directory_mode: switchboard::config::LogDirectoryMode::Default,
                 log_max_age_days: None,
+                ..Default::default()
             });
 
             match logger::init_tracing(&config) {
@@ -75,6 +76,7 @@ pub fn setup_logging(
                 log_max_body_size: 20480,
                 log_directory_mode: switchboard::config::LogDirectoryMode::Default,
                 log_max_age_days: None,
+                ..Default::default()
             });
 
             match logger::init_tracing(&config) {
@@ -98,6 +100,7 @@ pub fn setup_logging(
                 log_max_body_size: 20480,
                 log_directory_mode: switchboard::config::LogDirectoryMode::Default,
                 log_max_age_days: None,
+                ..Default::default()
             });
 
             match logger::init_tracing(&config) {
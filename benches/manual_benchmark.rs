@@ -219,6 +219,7 @@ fn setup_logging(
                 log_max_body_size: 20480,
                 log_directory_mode: switchboard::config::LogDirectoryMode::Default,
                 log_max_age_days: None,
+                ..Default::default()
             });
 
             match logger::init_tracing(&config) {
@@ -242,6 +243,7 @@ fn setup_logging(
                 log_max_body_size: 20480,
                 log_directory_mode: switchboard::config::LogDirectoryMode::Default,
                 log_max_age_days: None,
+                ..Default::default()
             });
 
             match logger::init_tracing(&config) {
@@ -265,6 +267,7 @@ fn setup_logging(
                 log_max_body_size: 20480,
                 log_directory_mode: switchboard::config::LogDirectoryMode::Default,
                 log_max_age_days: None,
+                ..Default::default()
             });
 
             match logger::init_tracing(&config) {
@@ -83,6 +83,7 @@ fn run_benchmark(mode: LogMode, iterations: usize, body: &Bytes) -> Duration {
                 log_max_body_size: 20480,
                 log_directory_mode: switchboard::config::LogDirectoryMode::Default,
                 log_max_age_days: None,
+                ..Default::default()
             });
 
             match logger::init_tracing(&config) {
@@ -108,6 +109,7 @@ fn run_benchmark(mode: LogMode, iterations: usize, body: &Bytes) -> Duration {
                 log_max_body_size: 20480,
                 log_directory_mode: switchboard::config::LogDirectoryMode::Default,
                 log_max_age_days: None,
+                ..Default::default()
             });
 
             match logger::init_tracing(&config) {
@@ -133,6 +135,7 @@ fn run_benchmark(mode: LogMode, iterations: usize, body: &Bytes) -> Duration {
                 log_max_body_size: 20480,
                 log_directory_mode: switchboard::config::LogDirectoryMode::Default,
                 log_max_age_days: None,
+                ..Default::default()
             });
 
             match logger::init_tracing(&config) {
@@ -226,6 +229,7 @@ fn test_nonblocking_io() {
         log_max_body_size: 20480,
         log_directory_mode: switchboard::config::LogDirectoryMode::Default,
         log_max_age_days: None,
+        ..Default::default()
     });
 
     let guard = logger::init_tracing(&config);
@@ -0,0 +1,23 @@
+// Build script capturing the git commit the binary is built from
+//
+// The hash is exposed to the crate as SWITCHBOARD_GIT_COMMIT. Builds outside a
+// git checkout (e.g. from a source tarball) or without git installed get "unknown".
+
+use std::process::Command;
+
+fn main() {
+    let commit = Command::new("git")
+        .args(["rev-parse", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|hash| hash.trim().to_string())
+        .filter(|hash| !hash.is_empty())
+        .unwrap_or_else(|| "unknown".to_string());
+
+    println!("cargo:rustc-env=SWITCHBOARD_GIT_COMMIT={}", commit);
+    // Re-run when HEAD moves, either to another branch or to a new commit on it
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs/heads");
+}
@@ -0,0 +1,239 @@
+//! Exponential backoff and retrying of async operations
+//!
+//! `Backoff` yields the delays between attempts: `base * 2^n`, capped at a maximum,
+//! optionally with jitter so that many instances retrying at once spread out.
+//! `retry_async` runs an operation until it succeeds, fails with an error that is
+//! not worth retrying, or runs out of retries.
+//!
+//! Key features:
+//! - Delays grow exponentially up to a cap; a constant delay is the special case base = max
+//! - Jitter picks each delay uniformly from its upper half, keeping a lower bound
+//! - Retries are logged at WARN with the attempt number, delay and error
+
+use rand::Rng;
+use std::fmt;
+use std::future::Future;
+use std::time::Duration;
+use tracing::warn;
+
+/// Iterator over the delays between retry attempts
+///
+/// Endless: the number of retries is bounded by the [`RetryPolicy`] using it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Backoff {
+    base: Duration,
+    max: Duration,
+    jitter: bool,
+    attempt: u32,
+}
+
+impl Backoff {
+    /// Creates a backoff starting at `base` and doubling up to `max`
+    pub fn new(base: Duration, max: Duration) -> Self {
+        Self {
+            base,
+            max,
+            jitter: false,
+            attempt: 0,
+        }
+    }
+
+    /// Creates a backoff that always waits `delay`
+    pub fn constant(delay: Duration) -> Self {
+        Self::new(delay, delay)
+    }
+
+    /// Randomizes each delay between half of it and all of it
+    pub fn with_jitter(mut self) -> Self {
+        self.jitter = true;
+        self
+    }
+}
+
+impl Iterator for Backoff {
+    type Item = Duration;
+
+    fn next(&mut self) -> Option<Duration> {
+        let factor = 1u32.checked_shl(self.attempt).unwrap_or(u32::MAX);
+        let delay = self.base.saturating_mul(factor).min(self.max);
+        self.attempt = self.attempt.saturating_add(1);
+
+        if self.jitter && !delay.is_zero() {
+            Some(rand::thread_rng().gen_range(delay / 2..=delay))
+        } else {
+            Some(delay)
+        }
+    }
+}
+
+/// How often and how patiently an operation is retried
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Retries after the first failed attempt (0 = no retries)
+    pub max_retries: u32,
+    /// Delays before each retry
+    pub backoff: Backoff,
+}
+
+impl RetryPolicy {
+    /// Creates a policy retrying up to `max_retries` times, waiting as `backoff` says
+    pub fn new(max_retries: u32, backoff: Backoff) -> Self {
+        Self {
+            max_retries,
+            backoff,
+        }
+    }
+}
+
+/// Runs `f` until it succeeds, retrying errors for which `is_retryable` returns true
+///
+/// # Arguments
+/// * `policy` - Number of retries and the delays before them
+/// * `is_retryable` - Whether an error may go away by trying again
+/// * `f` - Starts one attempt of the operation
+///
+/// # Returns
+/// The first success, the first error that is not retryable, or the error of the
+/// last attempt once the retries are used up
+pub async fn retry_async<F, Fut, T, E>(
+    policy: &RetryPolicy,
+    is_retryable: impl Fn(&E) -> bool,
+    mut f: F,
+) -> Result<T, E>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+    E: fmt::Display,
+{
+    let mut delays = policy.backoff.clone().take(policy.max_retries as usize);
+    let mut retries = 0;
+    loop {
+        let error = match f().await {
+            Ok(value) => return Ok(value),
+            Err(e) => e,
+        };
+        if !is_retryable(&error) {
+            return Err(error);
+        }
+        let Some(delay) = delays.next() else {
+            return Err(error);
+        };
+
+        retries += 1;
+        warn!(
+            error = %error,
+            attempt = retries,
+            max_attempts = policy.max_retries,
+            delay_ms = delay.as_millis() as u64,
+            "Operation failed, retrying"
+        );
+        tokio::time::sleep(delay).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn test_delays_double_up_to_the_cap() {
+        let delays: Vec<_> = Backoff::new(ms(100), ms(1000)).take(7).collect();
+        assert_eq!(
+            delays,
+            [100, 200, 400, 800, 1000, 1000, 1000].map(ms).to_vec()
+        );
+
+        // Far past the point where 2^n overflows, the cap still holds
+        let mut backoff = Backoff::new(ms(1), ms(5000));
+        assert_eq!(backoff.nth(100), Some(ms(5000)));
+    }
+
+    #[test]
+    fn test_constant_backoff() {
+        let delays: Vec<_> = Backoff::constant(ms(250)).take(3).collect();
+        assert_eq!(delays, vec![ms(250); 3]);
+    }
+
+    #[test]
+    fn test_jitter_stays_within_upper_half() {
+        let plain = Backoff::new(ms(100), ms(1000));
+        let jittered = plain.clone().with_jitter();
+        for (delay, jittered) in plain.zip(jittered).take(50) {
+            assert!(jittered <= delay && jittered >= delay / 2, "{:?}", jittered);
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_retries_until_success() {
+        let policy = RetryPolicy::new(5, Backoff::new(ms(100), ms(1000)));
+        let calls = Cell::new(0);
+        let started = tokio::time::Instant::now();
+
+        let result = retry_async(
+            &policy,
+            |_: &String| true,
+            || {
+                calls.set(calls.get() + 1);
+                let attempt = calls.get();
+                async move {
+                    if attempt < 3 {
+                        Err(format!("failure {}", attempt))
+                    } else {
+                        Ok(attempt)
+                    }
+                }
+            },
+        )
+        .await;
+
+        assert_eq!(result, Ok(3));
+        // Waited 100ms, then 200ms
+        assert_eq!(started.elapsed(), ms(300));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_gives_up_after_max_retries() {
+        let policy = RetryPolicy::new(2, Backoff::constant(ms(10)));
+        let calls = Cell::new(0);
+
+        let result: Result<(), String> = retry_async(
+            &policy,
+            |_| true,
+            || {
+                calls.set(calls.get() + 1);
+                let attempt = calls.get();
+                async move { Err(format!("failure {}", attempt)) }
+            },
+        )
+        .await;
+
+        assert_eq!(result, Err("failure 3".to_string()));
+        assert_eq!(calls.get(), 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_non_retryable_error_stops_immediately() {
+        let policy = RetryPolicy::new(5, Backoff::constant(ms(10)));
+        let calls = Cell::new(0);
+        let started = tokio::time::Instant::now();
+
+        let result: Result<(), String> = retry_async(
+            &policy,
+            |e: &String| e != "fatal",
+            || {
+                calls.set(calls.get() + 1);
+                async { Err("fatal".to_string()) }
+            },
+        )
+        .await;
+
+        assert_eq!(result, Err("fatal".to_string()));
+        assert_eq!(calls.get(), 1);
+        assert_eq!(started.elapsed(), Duration::ZERO);
+    }
+}
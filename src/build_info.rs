@@ -0,0 +1,18 @@
+//! Information about the build this binary was produced from
+//!
+//! The git commit is captured by `build.rs` at compile time, so a running proxy
+//! can report exactly which code it is running. Builds made outside a git
+//! checkout report `"unknown"`.
+
+use serde_json::{json, Value};
+
+/// Git commit hash the binary was built from, or `"unknown"`
+pub const BUILD_COMMIT: &str = env!("SWITCHBOARD_GIT_COMMIT");
+
+/// Log field carrying the build commit when `log_build_info` is enabled
+pub const BUILD_COMMIT_FIELD: &str = "build.commit";
+
+/// Returns the build details reported by the health check
+pub fn build_json() -> Value {
+    json!({ "commit": BUILD_COMMIT })
+}
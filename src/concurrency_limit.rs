@@ -0,0 +1,179 @@
+//! Limit on the number of requests proxied at the same time
+//!
+//! Each request holds a permit from a shared semaphore while the proxy handles it.
+//! When every permit is taken, a request either fails immediately or, when a queue
+//! timeout is configured, waits up to that long for a permit before giving up.
+//! Waiting smooths out short bursts instead of turning them into 503s.
+//!
+//! Key features:
+//! - An unset limit (None) never rejects or delays anything
+//! - Permits are released when dropped
+//! - The time spent waiting is reported so it can be logged
+
+use axum::{
+    body::{boxed, Empty},
+    http::StatusCode,
+    response::Response,
+};
+use hyper::header;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::config::CONCURRENCY_LIMIT_RETRY_AFTER_SECS;
+
+/// Shared limit on concurrently handled requests
+#[derive(Debug)]
+pub struct ConcurrencyLimiter {
+    /// Permits for in-flight requests (None = unlimited)
+    semaphore: Option<Arc<Semaphore>>,
+    /// How long a request may wait for a permit (None = fail immediately)
+    queue_timeout: Option<Duration>,
+}
+
+impl ConcurrencyLimiter {
+    /// Creates a limiter allowing `max_concurrent` requests at once (None = unlimited)
+    ///
+    /// # Arguments
+    /// * `max_concurrent` - Maximum number of requests handled at once
+    /// * `queue_timeout` - How long a request may wait for a permit when saturated
+    pub fn new(max_concurrent: Option<usize>, queue_timeout: Option<Duration>) -> Self {
+        Self {
+            semaphore: max_concurrent.map(|max| Arc::new(Semaphore::new(max))),
+            queue_timeout,
+        }
+    }
+
+    /// Acquires a permit, waiting up to the queue timeout when saturated
+    ///
+    /// # Returns
+    /// A permit that is released when dropped, or None if no permit became
+    /// available in time
+    pub async fn acquire(&self) -> Option<ConcurrencyPermit> {
+        let Some(semaphore) = &self.semaphore else {
+            return Some(ConcurrencyPermit::unlimited());
+        };
+
+        // Fast path: no waiting (and no wait time to report) when a permit is free
+        if let Ok(permit) = Arc::clone(semaphore).try_acquire_owned() {
+            return Some(ConcurrencyPermit::new(permit, Duration::ZERO));
+        }
+
+        let queue_timeout = self.queue_timeout?;
+        let wait_start = Instant::now();
+        match tokio::time::timeout(queue_timeout, Arc::clone(semaphore).acquire_owned()).await {
+            Ok(Ok(permit)) => Some(ConcurrencyPermit::new(permit, wait_start.elapsed())),
+            // Timed out, or the semaphore was closed (never happens, as it is never closed)
+            _ => None,
+        }
+    }
+}
+
+/// Permission to handle one request, released on drop
+#[derive(Debug)]
+pub struct ConcurrencyPermit {
+    _permit: Option<OwnedSemaphorePermit>,
+    queue_wait: Duration,
+}
+
+impl ConcurrencyPermit {
+    fn new(permit: OwnedSemaphorePermit, queue_wait: Duration) -> Self {
+        Self {
+            _permit: Some(permit),
+            queue_wait,
+        }
+    }
+
+    fn unlimited() -> Self {
+        Self {
+            _permit: None,
+            queue_wait: Duration::ZERO,
+        }
+    }
+
+    /// Returns how long the request waited for this permit
+    pub fn queue_wait(&self) -> Duration {
+        self.queue_wait
+    }
+}
+
+/// Builds the 503 response returned when no permit is available in time
+///
+/// The Retry-After header tells well-behaved clients to back off briefly,
+/// since permits free up as soon as in-flight requests complete.
+pub fn concurrency_limit_response() -> Response {
+    Response::builder()
+        .status(StatusCode::SERVICE_UNAVAILABLE)
+        .header(
+            header::RETRY_AFTER,
+            CONCURRENCY_LIMIT_RETRY_AFTER_SECS.to_string(),
+        )
+        .body(boxed(Empty::new()))
+        // Static status and header values cannot fail to build
+        .expect("concurrency limit response should always build")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_unlimited_never_waits() {
+        let limiter = ConcurrencyLimiter::new(None, None);
+
+        let permits: Vec<_> = futures_util::future::join_all((0..100).map(|_| limiter.acquire()))
+            .await
+            .into_iter()
+            .collect::<Option<_>>()
+            .expect("Unlimited limiter should always grant permits");
+
+        assert_eq!(permits.len(), 100);
+    }
+
+    #[tokio::test]
+    async fn test_saturated_without_queue_rejects_immediately() {
+        let limiter = ConcurrencyLimiter::new(Some(1), None);
+
+        let held = limiter
+            .acquire()
+            .await
+            .expect("First permit should be free");
+        assert_eq!(held.queue_wait(), Duration::ZERO);
+        assert!(limiter.acquire().await.is_none());
+
+        // Dropping the permit frees it for the next request
+        drop(held);
+        assert!(limiter.acquire().await.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_queued_request_gets_released_permit() {
+        let limiter = Arc::new(ConcurrencyLimiter::new(
+            Some(1),
+            Some(Duration::from_secs(5)),
+        ));
+        let held = limiter.acquire().await.unwrap();
+
+        let waiter = tokio::spawn({
+            let limiter = Arc::clone(&limiter);
+            async move { limiter.acquire().await.map(|permit| permit.queue_wait()) }
+        });
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        drop(held);
+
+        let queue_wait = waiter
+            .await
+            .unwrap()
+            .expect("Queued request should get the released permit");
+        assert!(queue_wait >= Duration::from_millis(50));
+    }
+
+    #[tokio::test]
+    async fn test_queued_request_times_out() {
+        let limiter = ConcurrencyLimiter::new(Some(1), Some(Duration::from_millis(20)));
+        let _held = limiter.acquire().await.unwrap();
+
+        assert!(limiter.acquire().await.is_none());
+    }
+}
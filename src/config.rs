@@ -17,8 +17,86 @@
 //! - `DEFAULT_LOG_BODIES` - Whether to log request/response bodies
 //! - `DEFAULT_LOG_FILE_PATH` - Default log file path
 //! - `DEFAULT_LOG_MAX_BODY_SIZE` - Maximum log size for bodies
+//! - `MAX_ALLOWED_LOG_BODY_SIZE` - Largest accepted log size for bodies (10MB, larger values are clamped)
 //! - `DEFAULT_LOG_DIRECTORY_MODE` - Permissions for log directories on Unix
 //! - `DEFAULT_LOG_MAX_AGE_DAYS` - How long to retain logs (None = indefinite)
+//! - `DEFAULT_DEPLOYMENT_ENV` - Deployment environment tag for logs (None = untagged)
+//! - `DEFAULT_MAX_TOTAL_BUFFERED_BYTES` - Global cap on buffered body bytes (None = unlimited)
+//! - `DEFAULT_SERVER_TIMING` - Whether to emit a Server-Timing header (false)
+//! - `DEFAULT_ADMIN_TOKEN` - Bearer token for admin endpoints (None = admin endpoints disabled)
+//! - `DEFAULT_LOG_BODY_SCHEMA_ONLY` - Log only JSON keys of request bodies (false)
+//! - `DEFAULT_LOG_JSON_INDENT` - Indent width of JSON bodies in logs (2; 0 = compact)
+//! - `DEFAULT_TLS_CERT_PATH` / `DEFAULT_TLS_KEY_PATH` - PEM certificate and key for HTTPS (None = plain HTTP)
+//! - `DEFAULT_REDACT_BODY_FIELDS` - JSON body fields redacted in logs (none)
+//! - `DEFAULT_REDACT_QUERY_PARAMS` - Query parameters redacted in logged URLs (none)
+//! - `DEFAULT_DEDUPE_REPEATED_LOGS` - Suppress identical consecutive log events (false)
+//! - `DEFAULT_GENERATE_TRACEPARENT` - Create a W3C traceparent for untraced requests (false)
+//! - `EmptyBodyPolicy::default()` - How empty POST bodies are forwarded (passthrough)
+//! - `DEFAULT_MAX_CONCURRENT_REQUESTS` - Cap on requests handled at once (None = unlimited)
+//! - `DEFAULT_QUEUE_TIMEOUT_MS` - How long a request waits for a free slot (None = no waiting)
+//! - `TimestampFormat::default()` - Timestamp format of log events (rfc3339)
+//! - `DEFAULT_ERROR_LOG_TO_STDERR` - Send WARN/ERROR console output to stderr (false)
+//! - `DEFAULT_MAX_STREAM_DURATION_SECS` - Wall-clock cap on streaming responses (None = unlimited)
+//! - `DEFAULT_LOG_RESOLVED_IP` - Record the resolved upstream IP per request (false)
+//! - `DEFAULT_BIND_RETRY_ATTEMPTS` / `DEFAULT_BIND_RETRY_DELAY_MS` - Retries while the port is in use (3 x 500ms)
+//! - `DEFAULT_MODEL_RATE_LIMITS` - Per-model requests per minute (none = unlimited)
+//! - `DEFAULT_STREAM_REQUEST_BODY` - Stream request bodies upstream instead of buffering (false)
+//! - `DEFAULT_LOG_REQUEST_SEQUENCE` - Record a per-process request sequence number (false)
+//! - `DEFAULT_LOG_KEY_FINGERPRINT` - Record a fingerprint of the API key per request (false)
+//! - `DEFAULT_RESPONSE_CACHE_TTL_SECS` - How long deterministic responses are cached (None = disabled)
+//! - `DEFAULT_STDOUT_MAX_FIELD_LEN` - Longest field value shown in pretty stdout logs (None = unlimited)
+//! - `DEFAULT_NORMALIZE_PATH` - Collapse duplicate slashes in forwarded paths (false)
+//! - `DEFAULT_WARN_ON_DEPRECATION` - Warn when the upstream flags a deprecation (true)
+//! - `DEFAULT_WARMUP_ON_START` - Open an upstream connection at startup (false)
+//! - `DEFAULT_HEARTBEAT_INTERVAL_SECS` - How often a liveness heartbeat is logged (None = disabled)
+//! - `DEFAULT_UPSTREAM_HOST_ALLOWLIST` - Hosts requests may be forwarded to (None = any host)
+//! - `DEFAULT_LOG_SOCKET_PATH` - Unix socket receiving JSON log lines (None = disabled)
+//! - `DEFAULT_MAX_CONCURRENT_STREAMS` - Cap on streaming responses open at once (None = unlimited)
+//! - `DEFAULT_DRY_RUN_PROXY` - Answer requests without forwarding them (false)
+//! - `DEFAULT_MAX_SPAN_FIELDS` - Cap on dynamically keyed fields logged per event (64)
+//! - `DEFAULT_ENFORCE_MAX_TOKENS` - Ceiling on `max_tokens` of Messages requests (None = not enforced)
+//! - `DEFAULT_WARN_ON_STREAM_MISMATCH` - Warn when a response ignores the request's `stream` flag (true)
+//! - `DEFAULT_SHUTDOWN_STREAM_GRACE_SECS` - Time open streams get to finish on shutdown (300 seconds)
+//! - `DEFAULT_LOG_BUILD_INFO` - Tag every log event with the build commit (false)
+//! - `DEFAULT_ALLOW_CLIENT_LOG_OPTOUT` - Honour the client's detail logging opt-out header (false)
+//! - `DEFAULT_LOG_USAGE_ON_CLEANUP` - Log log directory disk usage after cleanup (false)
+//! - `DEFAULT_ADD_FORWARDED_HEADERS` - Add `X-Forwarded-*` headers to forwarded requests (false)
+//! - `DEFAULT_MAINTENANCE_MODE` - Answer proxied requests with 503 maintenance (false)
+//! - `DEFAULT_MAINTENANCE_RETRY_AFTER_SECS` - Retry-After of maintenance responses (60)
+//! - `DEFAULT_LOG_BODY_READ_TIMING` - Record request body read time on the span (false)
+//! - `ColorMode::default()` - When pretty stdout logs use ANSI colors (auto)
+//! - `RequestIdFormat::default()` - Format of generated request IDs (uuid)
+//! - `DEFAULT_FORWARD_PARTIAL_BODIES` - Forward truncated upstream response bodies (false)
+//! - `DEFAULT_TCP_NODELAY` - Set `TCP_NODELAY` on client connections (false)
+//! - `DEFAULT_LISTEN_BACKLOG` - Listen backlog (None - 1024, capped by the OS)
+//! - `DEFAULT_LOG_FILE_PREFIX` - Name prefix of log files (None - from `log_file_path`)
+//! - `DEFAULT_LOG_FILE_DATE_IN_NAME` - Date inside rotated log file names (false)
+//! - `DEFAULT_FORCE_CHUNKED_ON_REWRITE` - Send rewritten request bodies chunked (false)
+//! - `DEFAULT_ALLOW_WEBSOCKET` - Tunnel WebSocket upgrade requests (false)
+//! - `DEFAULT_SANITIZE_LOG_OUTPUT` - Escape control characters in logged bodies and headers (true)
+//! - `DEFAULT_LOG_RATE_LIMIT_EVENTS` - Dedicated event for upstream 429/529 responses (true)
+//! - `DEFAULT_LOG_BASE_DIR` - Base directory of log files (None - detected from the environment)
+//! - `DEFAULT_ROUTE_API_KEYS` - API keys per route (none = `ANTHROPIC_API_KEY` for every request)
+//! - `DEFAULT_REQUIRE_HEADERS` - Headers every client request must carry (none)
+//! - `DEFAULT_CORS_ENABLED` - CORS preflight handling for browser clients (false)
+//! - `DEFAULT_CORS_ALLOW_ORIGINS` - Origins allowed by CORS (*)
+//! - `DEFAULT_CORS_ALLOW_HEADERS` - Request headers allowed by CORS (Anthropic API headers)
+//! - `DEFAULT_CORS_ALLOW_METHODS` - Methods allowed by CORS (GET, POST, OPTIONS)
+//! - `DEFAULT_UPSTREAM_RETRIES` - Resends of idempotent requests after a retryable upstream status (0)
+//! - `DEFAULT_RETRYABLE_STATUSES` - Upstream statuses that are retried (502, 503, 504, 529)
+//! - `DEFAULT_WARN_ON_CONTENT_TYPE_ANOMALY` - Warning on unexpected request/response content types (false)
+//! - `DEFAULT_LOG_EMPTY_BODIES` - Events for empty bodies (true)
+//! - `DEFAULT_INJECT_REQUEST_ID_IN_ERRORS` - Request ID added to JSON error bodies (false)
+//! - `DEFAULT_WARN_ON_SSE_TRAILING_DATA` - Warning on data after the end of an SSE stream (true)
+//! - `DEFAULT_REQUEST_DEADLINE_MS` - Deadline for a whole request (None - 10-minute client timeout)
+//! - `DEFAULT_UPSTREAM_TIMEOUT_HEADER` - Header telling the upstream the remaining deadline (None)
+//! - `DEFAULT_MAX_PATH_LENGTH` - Longest path and query of a request (None - unlimited)
+//! - `DEFAULT_ADMIN_ENABLED` - Admin endpoints switch (None - enabled when `ADMIN_TOKEN` is set)
+//! - `DEFAULT_MAP_UPSTREAM_5XX_TO` - Status reported for upstream 5xx responses (None - the real status)
+//! - `DEFAULT_RESPONSE_BUFFER_LIMIT_BYTES` - Size up to which responses are buffered (None - unlimited)
+//! - `DEFAULT_LOG_UPSTREAM_REQUEST_ID` - Upstream request ID logging (true)
+//! - `DEFAULT_ORDER_FILE_LOGS` - File log reordering by timestamp (false)
+//! - `DEFAULT_ALLOW_CLIENT_LOG_LEVEL` - Honour the client's per-request log level header (false)
 //!
 //! # Usage
 //!
@@ -51,10 +129,98 @@
 //! | `LOG_MAX_BODY_SIZE` | Max body size to log | 20480 |
 //! | `LOG_DIRECTORY_MODE` | Directory mode | Default |
 //! | `LOG_MAX_AGE_DAYS` | Log retention period | None |
-
+//! | `DEPLOYMENT_ENV` | Environment name attached to every log event | None |
+//! | `MAX_TOTAL_BUFFERED_BYTES` | Cap on body bytes buffered across in-flight requests | None |
+//! | `SERVER_TIMING` | Add `Server-Timing: proxy;dur=<ms>` to responses | false |
+//! | `ADMIN_TOKEN` | Bearer token required by `/admin/*` endpoints | None (disabled) |
+//! | `LOG_BODY_SCHEMA_ONLY` | Log request body JSON keys instead of content | false |
+//! | `LOG_JSON_INDENT` | Spaces per indent level of logged JSON bodies (0 = single line) | 2 |
+//! | `TLS_CERT_PATH` | PEM certificate chain for serving HTTPS | None (HTTP) |
+//! | `TLS_KEY_PATH` | PEM private key matching `TLS_CERT_PATH` | None (HTTP) |
+//! | `REDACT_BODY_FIELDS` | Comma-separated dotted JSON paths redacted in logged bodies | None |
+//! | `REDACT_QUERY_PARAMS` | Comma-separated query parameter names redacted in logged URLs | None |
+//! | `DEDUPE_REPEATED_LOGS` | Summarize identical consecutive log events instead of repeating them | false |
+//! | `GENERATE_TRACEPARENT` | Add a W3C `traceparent` to forwarded requests that lack one | false |
+//! | `EMPTY_POST_BODY` | Empty POST body handling (passthrough/empty_json) | passthrough |
+//! | `AUTH_MODE` | Who supplies the upstream API key (inject/passthrough) | inject |
+//! | `MAX_CONCURRENT_REQUESTS` | Cap on requests handled at once | None |
+//! | `QUEUE_TIMEOUT_MS` | How long a request waits for a free slot before a 503 | None |
+//! | `LOG_TIMESTAMP_FORMAT` | Log event timestamps (rfc3339/epoch_millis/epoch_secs) | rfc3339 |
+//! | `ERROR_LOG_TO_STDERR` | Write WARN/ERROR console output to stderr instead of stdout | false |
+//! | `MAX_STREAM_DURATION_SECS` | Truncate streaming responses running longer than this | None |
+//! | `LOG_RESOLVED_IP` | Record the upstream host's resolved IP as `upstream.ip` | false |
+//! | `BIND_RETRY_ATTEMPTS` | Bind retries while the port is still in use | 3 |
+//! | `BIND_RETRY_DELAY_MS` | Delay between bind retries | 500 |
+//! | `MODEL_RATE_LIMITS` | Comma-separated `model=requests_per_minute` limits | None |
+//! | `STREAM_REQUEST_BODY` | Stream request bodies upstream when nothing inspects them | false |
+//! | `LOG_REQUEST_SEQUENCE` | Record a per-process request sequence number as `req_seq` | false |
+//! | `LOG_KEY_FINGERPRINT` | Record a short SHA-256 fingerprint of the API key as `key_fingerprint` | false |
+//! | `RESPONSE_CACHE_TTL_SECS` | Cache responses to temperature-0, non-streaming messages requests | None |
+//! | `STDOUT_MAX_FIELD_LEN` | Truncate field values longer than this in pretty stdout logs | None |
+//! | `NORMALIZE_PATH` | Collapse consecutive slashes in the request path before forwarding | false |
+//! | `WARN_ON_DEPRECATION` | Log a warning when an upstream response carries a deprecation header | true |
+//! | `WARMUP_ON_START` | Send one background request upstream after binding to prime the connection pool | false |
+//! | `HEARTBEAT_INTERVAL_SECS` | Log a heartbeat with uptime and requests served at this interval | None |
+//! | `UPSTREAM_HOST_ALLOWLIST` | Comma-separated hosts requests may be forwarded to | None (any) |
+//! | `LOG_SOCKET_PATH` | Unix stream socket to send JSON log lines to (Unix only) | None |
+//! | `MAX_CONCURRENT_STREAMS` | Cap on streaming responses open at once | None |
+//! | `DRY_RUN_PROXY` | Log requests but return a canned response instead of forwarding | false |
+//! | `MAX_SPAN_FIELDS` | Cap on dynamically keyed fields (headers, JSON keys) logged per event | 64 |
+//! | `ENFORCE_MAX_TOKENS` | Clamp `max_tokens` of Messages requests to this ceiling | None |
+//! | `WARN_ON_STREAM_MISMATCH` | Warn when a response's streaming disagrees with the request's `stream` field | true |
+//! | `SHUTDOWN_STREAM_GRACE_SECS` | Seconds open streams get to finish after a shutdown signal | 300 |
+//! | `LOG_BUILD_INFO` | Tag every log event with the build's git commit (`build.commit`) | false |
+//! | `ALLOW_CLIENT_LOG_OPTOUT` | Let clients skip detail logging with `x-switchboard-no-log: true` | false |
+//! | `LOG_USAGE_ON_CLEANUP` | Log the log directories' disk usage after each cleanup run | false |
+//! | `ADD_FORWARDED_HEADERS` | Add `X-Forwarded-For`/`-Host`/`-Proto` to forwarded requests | false |
+//! | `MAINTENANCE_MODE` | Answer proxied requests with 503 maintenance instead of forwarding | false |
+//! | `MAINTENANCE_RETRY_AFTER_SECS` | `Retry-After` seconds sent with maintenance responses | 60 |
+//! | `LOG_BODY_READ_TIMING` | Record the request body read time as `request_body_read_ms` | false |
+//! | `LOG_COLOR` | ANSI colors in pretty stdout logs (auto/always/never) | auto |
+//! | `REQUEST_ID_FORMAT` | Format of generated request IDs (uuid/short) | uuid |
+//! | `FORWARD_PARTIAL_BODIES` | Forward truncated upstream response bodies instead of a 502 | false |
+//! | `TCP_NODELAY` | Set `TCP_NODELAY` on client connections | false |
+//! | `LISTEN_BACKLOG` | Queue length of connections waiting to be accepted | None (1024) |
+//! | `LOG_FILE_PREFIX` | Name of log files without `.log` and the date | None (from LOG_FILE_PATH) |
+//! | `LOG_FILE_DATE_IN_NAME` | Name daily log files `<prefix>-YYYY-MM-DD.log` | false |
+//! | `FORCE_CHUNKED_ON_REWRITE` | Send request bodies the proxy rewrote chunked, without Content-Length | false |
+//! | `ALLOW_WEBSOCKET` | Tunnel WebSocket upgrade requests instead of answering 501 | false |
+//! | `SANITIZE_LOG_OUTPUT` | Escape control characters in logged bodies and header values | true |
+//! | `LOG_RATE_LIMIT_EVENTS` | Log a dedicated warning for upstream 429 and 529 responses | true |
+//! | `LOG_BASE_DIR` | Base directory of log files | None (detected) |
+//! | `ROUTE_API_KEYS` | Comma-separated `path_prefix=VARIABLE` API key routes | None |
+//! | `REQUIRE_HEADERS` | Comma-separated header names clients must send | None |
+//! | `CORS_ENABLED` | Answer CORS preflights locally and add the allow-origin header | false |
+//! | `CORS_ALLOW_ORIGINS` | Comma-separated origins allowed by CORS | * |
+//! | `CORS_ALLOW_HEADERS` | Comma-separated request headers allowed by CORS | Anthropic API headers |
+//! | `CORS_ALLOW_METHODS` | Comma-separated methods allowed by CORS | GET, POST, OPTIONS |
+//! | `UPSTREAM_RETRIES` | Times an idempotent request is resent after a retryable upstream status | 0 |
+//! | `RETRYABLE_STATUSES` | Comma-separated upstream statuses that are retried | 502,503,504,529 |
+//! | `WARN_ON_CONTENT_TYPE_ANOMALY` | Warn on non-JSON Messages requests and non-JSON/SSE 200 responses | false |
+//! | `LOG_EMPTY_BODIES` | Log events for empty request and response bodies | true |
+//! | `INJECT_REQUEST_ID_IN_ERRORS` | Add the request ID to JSON error bodies as `request_id` | false |
+//! | `WARN_ON_SSE_TRAILING_DATA` | Warn on data after a stream's terminal event | true |
+//! | `REQUEST_DEADLINE_MS` | Deadline for a whole request in milliseconds | None |
+//! | `UPSTREAM_TIMEOUT_HEADER` | Header carrying the remaining request deadline upstream | None |
+//! | `MAX_PATH_LENGTH` | Longest path and query before a 414 | None |
+//! | `ADMIN_ENABLED` | Serve the `/admin/*` endpoints; true without `ADMIN_TOKEN` fails loading | None (ADMIN_TOKEN set) |
+//! | `MAP_UPSTREAM_5XX_TO` | Status sent to clients in place of any upstream 5xx | None |
+//! | `RESPONSE_BUFFER_LIMIT_BYTES` | Response bytes buffered before streaming the rest | None |
+//! | `LOG_UPSTREAM_REQUEST_ID` | Record the upstream `request-id` as a span field | true |
+//! | `ORDER_FILE_LOGS` | Write file log lines in timestamp order | false |
+//! | `ALLOW_CLIENT_LOG_LEVEL` | Let clients raise their request's level with `x-switchboard-log-level` | false |
+
+use hyper::header::{HeaderName, HeaderValue, InvalidHeaderValue};
+use hyper::Method;
+use serde::{Serialize, Serializer};
+use serde_json::Value;
+use std::collections::HashMap;
 use std::env;
+use std::fmt;
 use std::sync::OnceLock;
+use thiserror::Error;
 use tracing::{info, warn};
+use tracing_subscriber::EnvFilter;
 
 // Configuration Default Constants
 
@@ -98,6 +264,12 @@ pub const DEFAULT_LOG_FILE_PATH: &str = "./switchboard.log";
 /// Prevents excessive log file growth while retaining meaningful content
 pub const DEFAULT_LOG_MAX_BODY_SIZE: usize = 20480;
 
+/// Largest accepted `LOG_MAX_BODY_SIZE` in bytes (10MB)
+///
+/// Larger values are clamped, so a typo cannot make the proxy format gigabyte-sized
+/// log strings.
+pub const MAX_ALLOWED_LOG_BODY_SIZE: usize = 10 * 1024 * 1024;
+
 /// Default directory permission mode on Unix-like systems (0o750)
 ///
 /// Provides owner read/write/execute, group read/execute, and no permissions for others
@@ -109,12 +281,409 @@ pub const DEFAULT_LOG_DIRECTORY_MODE: u32 = 0o750;
 /// By default, no automatic log cleanup is performed
 pub const DEFAULT_LOG_MAX_AGE_DAYS: Option<u32> = None;
 
+/// Default deployment environment name (None = logs are not tagged)
+///
+/// Only meaningful when logs from several environments end up in the same place
+pub const DEFAULT_DEPLOYMENT_ENV: Option<&str> = None;
+
+/// Default cap on body bytes buffered across all in-flight requests (None = unlimited)
+///
+/// A safety valve against memory exhaustion under high concurrency, separate from
+/// any per-request limits
+pub const DEFAULT_MAX_TOTAL_BUFFERED_BYTES: Option<u64> = None;
+
+/// Retry-After value (seconds) sent when the buffered-bytes budget is exhausted
+///
+/// Budget pressure is transient, so clients are told to retry almost immediately
+pub const BUFFER_BUDGET_RETRY_AFTER_SECS: u64 = 1;
+
+/// Whether to add a Server-Timing header with proxy overhead by default (false)
+///
+/// Off by default since it exposes timing details to clients
+pub const DEFAULT_SERVER_TIMING: bool = false;
+
+/// Default admin token (None = admin endpoints are disabled)
+///
+/// Admin endpoints change runtime behavior, so they stay off unless a token is configured
+pub const DEFAULT_ADMIN_TOKEN: Option<&str> = None;
+
+/// Whether request bodies are logged as JSON keys only by default (false)
+///
+/// Full content logging stays the default; schema-only trades detail for privacy
+pub const DEFAULT_LOG_BODY_SCHEMA_ONLY: bool = false;
+
+/// Default indent width of JSON bodies in logs, in spaces (2)
+///
+/// Matches `serde_json`'s pretty printer; 0 logs each body on a single line
+pub const DEFAULT_LOG_JSON_INDENT: usize = 2;
+
+/// Default TLS certificate path (None = serve plain HTTP)
+///
+/// TLS is usually terminated by a load balancer, so the proxy serves HTTP unless configured
+pub const DEFAULT_TLS_CERT_PATH: Option<&str> = None;
+
+/// Default TLS private key path (None = serve plain HTTP)
+pub const DEFAULT_TLS_KEY_PATH: Option<&str> = None;
+
+/// Default JSON body fields to redact in logs (none)
+///
+/// Which fields hold secrets depends on the client, so nothing is redacted unless configured
+pub const DEFAULT_REDACT_BODY_FIELDS: &[&str] = &[];
+
+/// Default query parameters whose values are redacted in logged URLs (none)
+///
+/// Which parameters carry tokens depends on the client, so nothing is redacted unless configured
+pub const DEFAULT_REDACT_QUERY_PARAMS: &[&str] = &[];
+
+/// Whether identical consecutive log events are suppressed by default (false)
+///
+/// Every event is written unless deduplication is explicitly enabled
+pub const DEFAULT_DEDUPE_REPEATED_LOGS: bool = false;
+
+/// Whether a traceparent is generated for requests without one by default (false)
+///
+/// Incoming trace context is always propagated; starting new traces is opt-in
+pub const DEFAULT_GENERATE_TRACEPARENT: bool = false;
+
+/// Default cap on requests handled at once (None = unlimited)
+///
+/// Limits load on the upstream and on the proxy itself during bursts
+pub const DEFAULT_MAX_CONCURRENT_REQUESTS: Option<usize> = None;
+
+/// Default time a request waits for a free slot when saturated (None = reject immediately)
+///
+/// Queuing is opt-in so a saturated proxy fails fast unless told otherwise
+pub const DEFAULT_QUEUE_TIMEOUT_MS: Option<u64> = None;
+
+/// Retry-After value (seconds) sent when no request slot is available
+///
+/// Slots free up as soon as in-flight requests complete, so clients retry almost immediately
+pub const CONCURRENCY_LIMIT_RETRY_AFTER_SECS: u64 = 1;
+
+/// Whether WARN/ERROR console output goes to stderr by default (false)
+///
+/// Everything stays on stdout unless the deployment separates the two streams
+pub const DEFAULT_ERROR_LOG_TO_STDERR: bool = false;
+
+/// Default cap on how long a streaming response may run (None = unlimited)
+///
+/// Legitimate streams can run for minutes, so only deployments that need a bound set one
+pub const DEFAULT_MAX_STREAM_DURATION_SECS: Option<u64> = None;
+
+/// Whether the resolved upstream IP is recorded per request by default (false)
+///
+/// Only useful when debugging DNS or routing, and costs an occasional extra lookup
+pub const DEFAULT_LOG_RESOLVED_IP: bool = false;
+
+/// Default number of bind retries while the port is still in use (3)
+///
+/// Covers a previous instance that is still releasing the port during a rolling restart
+pub const DEFAULT_BIND_RETRY_ATTEMPTS: u32 = 3;
+
+/// Default delay between bind retries in milliseconds (500)
+///
+/// Together with the attempt count, waits up to a couple of seconds before giving up
+pub const DEFAULT_BIND_RETRY_DELAY_MS: u64 = 500;
+
+/// Default per-model rate limits as (model, requests per minute) (none)
+///
+/// Upstream limits depend on the account, so no model is limited unless configured
+pub const DEFAULT_MODEL_RATE_LIMITS: &[(&str, u32)] = &[];
+
+/// Whether request bodies are streamed upstream instead of buffered by default (false)
+///
+/// Buffering keeps request logging and body-based features working out of the box
+pub const DEFAULT_STREAM_REQUEST_BODY: bool = false;
+
+/// Whether a per-process request sequence number is recorded by default (false)
+///
+/// The request ID already identifies each request; the sequence number only adds ordering
+pub const DEFAULT_LOG_REQUEST_SEQUENCE: bool = false;
+
+/// Whether a fingerprint of the API key is recorded per request by default (false)
+///
+/// Only useful when several deployments use different keys and logs are compared
+pub const DEFAULT_LOG_KEY_FINGERPRINT: bool = false;
+
+/// Number of hex characters of the SHA-256 digest kept in a key fingerprint (8)
+///
+/// Enough to tell a handful of keys apart, far too little to help recover a key
+pub const KEY_FINGERPRINT_HEX_LEN: usize = 8;
+
+/// Default lifetime of cached responses to deterministic requests (None = caching disabled)
+///
+/// Serving stored responses changes what clients see, so caching is opt-in
+pub const DEFAULT_RESPONSE_CACHE_TTL_SECS: Option<u64> = None;
+
+/// Default maximum length of a field value in pretty stdout logs (None = unlimited)
+///
+/// Console output shows values in full unless asked otherwise; the log file always does
+pub const DEFAULT_STDOUT_MAX_FIELD_LEN: Option<usize> = None;
+
+/// Whether duplicate slashes in request paths are collapsed by default (false)
+///
+/// Forwarding paths exactly as received avoids surprising rewrites
+pub const DEFAULT_NORMALIZE_PATH: bool = false;
+
+/// Whether upstream deprecation headers are logged as warnings by default (true)
+///
+/// Deprecations announce future breakage, which operators should hear about early
+pub const DEFAULT_WARN_ON_DEPRECATION: bool = true;
+
+/// Whether a warm-up request primes the upstream connection pool at startup by default (false)
+///
+/// The warm-up request reaches the upstream, so it is only sent when asked for
+pub const DEFAULT_WARMUP_ON_START: bool = false;
+
+/// Default interval between heartbeat log events (None = no heartbeat)
+///
+/// Busy deployments log plenty on their own, so the heartbeat is opt-in
+pub const DEFAULT_HEARTBEAT_INTERVAL_SECS: Option<u64> = None;
+
+/// Default upstream hosts requests may be forwarded to (None = any host)
+///
+/// The target URL comes from trusted configuration, so no allowlist is needed by default
+pub const DEFAULT_UPSTREAM_HOST_ALLOWLIST: Option<&[&str]> = None;
+
+/// Default Unix socket for shipping JSON log lines (None = no socket output)
+///
+/// Shipping logs needs a local collector, which only the deployment knows about
+pub const DEFAULT_LOG_SOCKET_PATH: Option<&str> = None;
+
+/// Default cap on streaming responses open at once (None = unlimited)
+///
+/// Streams hold a connection for the whole generation, so they may need a tighter cap
+pub const DEFAULT_MAX_CONCURRENT_STREAMS: Option<usize> = None;
+
+/// Whether requests are answered with a canned response instead of forwarded (false)
+///
+/// A proxy that never reaches the upstream is only useful for trying out configuration
+pub const DEFAULT_DRY_RUN_PROXY: bool = false;
+
+/// Default cap on dynamically keyed fields (headers, JSON keys) logged per event
+///
+/// Far above what a normal request carries, so only pathological input is truncated
+pub const DEFAULT_MAX_SPAN_FIELDS: usize = 64;
+
+/// Default ceiling on `max_tokens` of Messages requests (None = not enforced)
+///
+/// Rewriting client requests is a cost policy each deployment has to opt into
+pub const DEFAULT_ENFORCE_MAX_TOKENS: Option<u64> = None;
+
+/// Whether a response that ignores the request's `stream` flag is logged as a warning (true)
+///
+/// A client expecting events that receives one JSON body (or the reverse) is usually broken
+pub const DEFAULT_WARN_ON_STREAM_MISMATCH: bool = true;
+
+/// Default time open streams get to finish once shutdown starts (300 seconds)
+///
+/// Long generations stream for minutes, far beyond the grace given to ordinary requests
+pub const DEFAULT_SHUTDOWN_STREAM_GRACE_SECS: u64 = 300;
+
+/// Default for tagging every log event with the build's git commit
+///
+/// Off by default: the commit is constant per process, so it mostly adds bytes to every line.
+pub const DEFAULT_LOG_BUILD_INFO: bool = false;
+
+/// Default for honouring the client's `x-switchboard-no-log` header
+///
+/// Off by default so clients cannot hide their requests' details from the logs unless the operator allows it.
+pub const DEFAULT_ALLOW_CLIENT_LOG_OPTOUT: bool = false;
+
+/// Default for logging log directory disk usage after each cleanup run
+///
+/// Off by default because measuring walks every file in the log directories.
+pub const DEFAULT_LOG_USAGE_ON_CLEANUP: bool = false;
+
+/// Default for adding `X-Forwarded-*` headers to forwarded requests
+///
+/// Off by default so the upstream does not learn client addresses unless the operator wants it to.
+pub const DEFAULT_ADD_FORWARDED_HEADERS: bool = false;
+
+/// Default for answering every proxied request with 503 maintenance
+///
+/// Operators switch this on (usually via `/admin/reload`) to take the proxy out of rotation.
+pub const DEFAULT_MAINTENANCE_MODE: bool = false;
+
+/// Default `Retry-After` (seconds) sent with maintenance responses
+///
+/// A minute is long enough to spare the proxy repeated retries, short enough for clients to notice its return.
+pub const DEFAULT_MAINTENANCE_RETRY_AFTER_SECS: u64 = 60;
+
+/// Default for recording how long reading the request body took
+///
+/// Off by default; enable it to tell slow clients apart from a slow upstream.
+pub const DEFAULT_LOG_BODY_READ_TIMING: bool = false;
+
+/// Default for forwarding what arrived of a response body the upstream cut short
+///
+/// Off by default: a truncated body is usually unusable, so clients get a clear 502 instead.
+pub const DEFAULT_FORWARD_PARTIAL_BODIES: bool = false;
+
+/// Default for disabling Nagle's algorithm on client connections
+///
+/// Off by default, keeping the OS behaviour; enabling it lowers latency for small requests.
+pub const DEFAULT_TCP_NODELAY: bool = false;
+
+/// Default length of the queue of connections waiting to be accepted
+///
+/// None uses `listener::FALLBACK_LISTEN_BACKLOG`, the backlog tokio's own bind uses.
+pub const DEFAULT_LISTEN_BACKLOG: Option<u32> = None;
+
+/// Default name prefix of log files
+///
+/// None derives it from `log_file_path`, so `switchboard.log` keeps the prefix `switchboard`.
+pub const DEFAULT_LOG_FILE_PREFIX: Option<&str> = None;
+
+/// Default for putting the date inside rotated log file names
+///
+/// Off by default, keeping the `switchboard.log.2024-01-02` names of earlier releases.
+pub const DEFAULT_LOG_FILE_DATE_IN_NAME: bool = false;
+
+/// Default for sending rewritten request bodies chunked
+///
+/// Off by default: a rewritten body is sent with its recomputed, exact Content-Length.
+pub const DEFAULT_FORCE_CHUNKED_ON_REWRITE: bool = false;
+
+/// Default for tunnelling WebSocket upgrade requests to the upstream
+///
+/// Off by default: the Anthropic API has no WebSocket endpoints, so upgrades are refused with 501.
+pub const DEFAULT_ALLOW_WEBSOCKET: bool = false;
+
+/// Default for escaping control characters in logged bodies and header values
+///
+/// On by default: a body carrying ANSI escape sequences could otherwise drive the terminal showing the logs.
+pub const DEFAULT_SANITIZE_LOG_OUTPUT: bool = true;
+
+/// Default for logging a dedicated event when the upstream rate limits
+///
+/// On by default: 429 and 529 responses are the signals alerting rules most often need to target.
+pub const DEFAULT_LOG_RATE_LIMIT_EVENTS: bool = true;
+
+/// Default base directory of log files
+///
+/// None picks the base directory from the detected environment (`./logs`, XDG or `/var/log/switchboard`).
+pub const DEFAULT_LOG_BASE_DIR: Option<&str> = None;
+
+/// Default API keys per route, as (path prefix, environment variable holding the key) pairs
+///
+/// Empty, so every request uses `ANTHROPIC_API_KEY`.
+pub const DEFAULT_ROUTE_API_KEYS: &[(&str, &str)] = &[];
+
+/// Default headers every client request must carry (none)
+///
+/// Attribution headers are deployment-specific, so nothing is required unless configured.
+pub const DEFAULT_REQUIRE_HEADERS: &[&str] = &[];
+
+/// Default setting for answering CORS preflight requests (disabled)
+///
+/// Disabled so that OPTIONS requests are proxied unchanged unless browser clients are expected.
+pub const DEFAULT_CORS_ENABLED: bool = false;
+
+/// Default origins allowed to call the proxy from a browser (any)
+///
+/// Only used when CORS is enabled; deployments serving known web apps should list their origins.
+pub const DEFAULT_CORS_ALLOW_ORIGINS: &[&str] = &["*"];
+
+/// Default request headers browsers may send through CORS
+///
+/// The headers Anthropic API clients send; the API key headers only matter in passthrough mode.
+pub const DEFAULT_CORS_ALLOW_HEADERS: &[&str] = &[
+    "content-type",
+    "anthropic-version",
+    "anthropic-beta",
+    "x-api-key",
+    "authorization",
+];
+
+/// Default methods browsers may use through CORS
+///
+/// The Anthropic API is used with GET and POST; OPTIONS covers the preflight itself.
+pub const DEFAULT_CORS_ALLOW_METHODS: &[&str] = &["GET", "POST", "OPTIONS"];
+
+/// Default number of times a request is resent after a retryable upstream status (0 = never)
+///
+/// Only idempotent requests are retried, and resending them still adds upstream load,
+/// so it is opt-in.
+pub const DEFAULT_UPSTREAM_RETRIES: u32 = 0;
+
+/// Default upstream statuses worth retrying
+///
+/// Gateway errors and Anthropic's 529 overloaded are transient; other statuses are not retried.
+pub const DEFAULT_RETRYABLE_STATUSES: &[u16] = &[502, 503, 504, 529];
+
+/// Default setting for warning about unexpected content types (disabled)
+///
+/// A debugging aid for client and upstream integration issues, so it is opt-in.
+pub const DEFAULT_WARN_ON_CONTENT_TYPE_ANOMALY: bool = false;
+
+/// Default setting for logging empty request and response bodies (enabled)
+///
+/// Enabled to keep the "body empty" events; high-traffic GET endpoints may turn them off as noise.
+pub const DEFAULT_LOG_EMPTY_BODIES: bool = true;
+
+/// Default setting for adding the request ID to JSON error bodies (disabled)
+///
+/// Disabled so that error bodies reach clients exactly as the upstream sent them.
+pub const DEFAULT_INJECT_REQUEST_ID_IN_ERRORS: bool = false;
+
+/// Default setting for warning about data after the end of an SSE stream (enabled)
+///
+/// Trailing bytes are rare and point at a broken upstream, so they are worth a warning by default.
+pub const DEFAULT_WARN_ON_SSE_TRAILING_DATA: bool = true;
+
+/// Default deadline for handling a request, in milliseconds (None - only the client's 10-minute timeout)
+///
+/// Long generations can take minutes, so no shorter deadline is imposed unless configured.
+pub const DEFAULT_REQUEST_DEADLINE_MS: Option<u64> = None;
+
+/// Default header telling the upstream how much of the request deadline is left (none)
+///
+/// Only cooperative upstreams understand such a header, so none is sent unless named.
+pub const DEFAULT_UPSTREAM_TIMEOUT_HEADER: Option<&str> = None;
+
+/// Default limit on the length of a request's path and query (None - unlimited)
+///
+/// The HTTP server already bounds the request line, so no tighter limit is imposed unless configured.
+pub const DEFAULT_MAX_PATH_LENGTH: Option<usize> = None;
+
+/// Default setting for the admin endpoints (None - enabled exactly when `ADMIN_TOKEN` is set)
+///
+/// Deriving it from the token keeps existing deployments working; setting it makes the intent explicit.
+pub const DEFAULT_ADMIN_ENABLED: Option<bool> = None;
+
+/// Default status that upstream 5xx responses are reported as (None - the real status)
+///
+/// Clients see the upstream's own status unless a gateway needs failures normalized.
+pub const DEFAULT_MAP_UPSTREAM_5XX_TO: Option<u16> = None;
+
+/// Default size up to which non-streaming responses are buffered (None - unlimited)
+///
+/// Buffering whole responses keeps body logging and caching working for every size.
+pub const DEFAULT_RESPONSE_BUFFER_LIMIT_BYTES: Option<usize> = None;
+
+/// Default setting for recording Anthropic's request ID (true)
+///
+/// Anthropic support asks for its `request-id`, so it is kept alongside the proxy's own ID.
+pub const DEFAULT_LOG_UPSTREAM_REQUEST_ID: bool = true;
+
+/// Default setting for writing file log lines in timestamp order (false)
+///
+/// Reordering delays every line and holds recent lines in memory, so it is opt-in.
+pub const DEFAULT_ORDER_FILE_LOGS: bool = false;
+
+/// Default setting for honouring the client's per-request log level header (false)
+///
+/// Letting clients raise verbosity can flood the logs, so it must be switched on explicitly.
+pub const DEFAULT_ALLOW_CLIENT_LOG_LEVEL: bool = false;
+
 /// Specifies how log directory should be determined
 ///
 /// This enum controls how the application selects the base directory for logs,
 /// allowing for different deployment scenarios (development, user installation,
 /// system service).
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum LogDirectoryMode {
     /// Automatically determine the log directory based on environment detection
     ///
@@ -140,19 +709,101 @@ pub enum LogDirectoryMode {
     System,
 }
 
+/// Specifies how a POST request with a zero-length body is forwarded
+///
+/// Upstream endpoints differ: some reject a POST without a body, others expect
+/// an empty JSON object.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EmptyBodyPolicy {
+    /// Forward the empty body as-is
+    #[default]
+    Passthrough,
+
+    /// Replace the empty body with `{}` and send it as `application/json`
+    EmptyJson,
+}
+
+/// Specifies who supplies the API key sent upstream
+///
+/// Either the proxy authenticates every request with its own key, or it trusts
+/// clients to bring theirs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuthMode {
+    /// Replace any client credentials with `ANTHROPIC_API_KEY` as `x-api-key`
+    #[default]
+    Inject,
+
+    /// Forward the client's `x-api-key`/`Authorization` unchanged; no key is needed
+    Passthrough,
+}
+
+/// Specifies how timestamps are written in log events (stdout and file)
+///
+/// RFC 3339 is human-readable; the epoch variants suit downstream tools that
+/// index on numeric time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimestampFormat {
+    /// RFC 3339 / ISO-8601 in UTC, e.g. `2024-01-01T12:00:00.000000Z`
+    #[default]
+    Rfc3339,
+
+    /// Milliseconds since the Unix epoch
+    EpochMillis,
+
+    /// Whole seconds since the Unix epoch
+    EpochSecs,
+}
+
+/// Specifies when pretty stdout logs are colored with ANSI escape codes
+///
+/// Colors help on a terminal but garble logs captured by CI or redirected to a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ColorMode {
+    /// Color only when stdout is a terminal
+    #[default]
+    Auto,
+
+    /// Always color, e.g. for log viewers that render ANSI codes
+    Always,
+
+    /// Never color
+    Never,
+}
+
+/// Specifies the format of the ID generated for each request (`req_id`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RequestIdFormat {
+    /// A hyphenated UUID v4, e.g. `67e55044-10b1-426f-9247-bb680e5fe0c8`
+    #[default]
+    Uuid,
+
+    /// 11 base62 characters, e.g. `3kTMd8xZq0B`
+    Short,
+}
+
 /// Configuration for the application
 ///
 /// Holds all the configuration values needed by the application,
 /// loaded from environment variables with sensible defaults.
-#[derive(Debug, Clone)]
+///
+/// Serializing a Config (e.g. for admin responses) never exposes secrets:
+/// the API key and admin token are replaced with a redaction marker.
+/// Debug output is derived from the same redacted form.
+#[derive(Clone, Serialize)]
 pub struct Config {
     /// HTTP port to listen on
     pub port: String,
     /// API key for authenticating with Anthropic API
+    #[serde(serialize_with = "serialize_redacted")]
     pub anthropic_api_key: String,
     /// Target URL for the Anthropic API
     pub anthropic_target_url: String,
-    /// Minimum log level for stdout output (info, debug, etc.)
+    /// Minimum log level for stdout output (info, debug, etc., or per-target directives)
     pub log_stdout_level: String,
     /// Format for stdout log output (json or pretty)
     pub log_format: String,
@@ -160,7 +811,7 @@ pub struct Config {
     pub log_bodies: bool,
     /// Path to the comprehensive log file
     pub log_file_path: String,
-    /// Minimum log level for file output (debug, trace, etc.)
+    /// Minimum log level for file output (debug, trace, etc., or per-target directives)
     pub log_file_level: String,
     /// Maximum size for logged bodies before truncation (bytes)
     pub log_max_body_size: usize,
@@ -170,6 +821,303 @@ pub struct Config {
     /// When set to Some(days), logs older than this will be deleted automatically in development
     /// When set to None (default), no automatic cleanup occurs
     pub log_max_age_days: Option<u32>,
+    /// Deployment environment name (e.g. "staging", "prod")
+    /// When set, every log event carries it as a `deployment.environment` field
+    pub deployment_env: Option<String>,
+    /// Maximum number of body bytes buffered across all in-flight requests
+    /// When the budget would be exceeded, requests are rejected with 503 and Retry-After
+    /// When set to None (default), buffering is unbounded
+    pub max_total_buffered_bytes: Option<u64>,
+    /// Whether to add a `Server-Timing: proxy;dur=<ms>` header to responses
+    /// The duration is proxy overhead only: total time minus time spent waiting on upstream
+    pub server_timing: bool,
+    /// Bearer token required to call `/admin/*` endpoints
+    /// When None (default), admin endpoints reject every request
+    #[serde(serialize_with = "serialize_redacted_option")]
+    pub admin_token: Option<String>,
+    /// Log only the top-level JSON keys (and message count) of request bodies
+    /// Lets operators see request shapes without capturing prompt content
+    pub log_body_schema_only: bool,
+    /// Spaces per indent level when logging JSON bodies (0 = compact, single line)
+    pub log_json_indent: usize,
+    /// Path to a PEM certificate chain; with `tls_key_path`, enables HTTPS
+    pub tls_cert_path: Option<String>,
+    /// Path to the PEM private key matching `tls_cert_path`
+    pub tls_key_path: Option<String>,
+    /// Dotted JSON field paths (e.g. `metadata.user_id`) redacted in logged bodies
+    /// Only the logged representation changes; forwarded bodies are never modified
+    pub redact_body_fields: Vec<String>,
+    /// Query parameter names (case-insensitive) whose values are redacted in logged URLs
+    /// Only the logged representation changes; forwarded URLs are never modified
+    pub redact_query_params: Vec<String>,
+    /// Suppress identical consecutive log events (same message and level) past a threshold
+    /// Suppressed events are replaced by a "repeated N times" summary; applied at startup only
+    pub dedupe_repeated_logs: bool,
+    /// Generate a W3C `traceparent` for forwarded requests that arrive without one
+    /// An incoming `traceparent` is always forwarded unchanged
+    pub generate_traceparent: bool,
+    /// How POST requests with a zero-length body are forwarded (passthrough|empty_json)
+    pub empty_post_body: EmptyBodyPolicy,
+    /// Whether the proxy injects its own API key or forwards the client's
+    /// `ANTHROPIC_API_KEY` is only required in `Inject` mode; applied at startup
+    pub auth_mode: AuthMode,
+    /// Maximum number of requests handled at once; excess requests get 503 with Retry-After
+    /// When set to None (default), concurrency is unbounded; applied at startup only
+    pub max_concurrent_requests: Option<usize>,
+    /// How long (ms) a request waits for a free slot when `max_concurrent_requests` is reached
+    /// When set to None (default), saturated requests are rejected immediately
+    pub queue_timeout_ms: Option<u64>,
+    /// Timestamp format of log events (rfc3339|epoch_millis|epoch_secs); applied at startup only
+    pub log_timestamp_format: TimestampFormat,
+    /// Write WARN and ERROR console events to stderr, everything else to stdout
+    /// When false (default), all console output goes to stdout; applied at startup only
+    pub error_log_to_stderr: bool,
+    /// Maximum wall-clock time (seconds) a streaming response may run before it is truncated
+    /// When set to None (default), streams run until the upstream ends them
+    pub max_stream_duration_secs: Option<u64>,
+    /// Resolve the upstream host (cached briefly) and record its IP as the `upstream.ip` span field
+    /// Failed resolutions are recorded as `unresolved`; forwarding is unaffected
+    pub log_resolved_ip: bool,
+    /// Times to retry binding the listen port when it is still in use (0 = fail immediately)
+    /// Other bind errors, such as permission denied, always fail immediately
+    pub bind_retry_attempts: u32,
+    /// Delay between bind retries (milliseconds)
+    pub bind_retry_delay_ms: u64,
+    /// Requests per minute allowed for each model, enforced with a token bucket per model
+    /// Requests for models not listed are unlimited; applied at startup only
+    pub model_rate_limits: HashMap<String, u32>,
+    /// Forward request bodies as a stream instead of buffering them in memory
+    /// Only takes effect while nothing needs the body (see `streams_request_body`)
+    pub stream_request_body: bool,
+    /// Record a monotonic per-process request number as the `req_seq` span field
+    /// Numbers start at 1 and restart with the process; use `req_id` to identify requests
+    pub log_request_sequence: bool,
+    /// Record a short, non-reversible fingerprint of the API key as the `key_fingerprint`
+    /// span field, to tell which key served a request without logging the key
+    pub log_key_fingerprint: bool,
+    /// How long responses to deterministic requests are cached, in seconds (None = disabled)
+    /// Only `POST /v1/messages` with temperature 0 and no streaming is cached; applied at startup
+    pub response_cache_ttl_secs: Option<u64>,
+    /// Maximum characters of a single field value in pretty stdout logs (None = unlimited)
+    /// Longer values end in an ellipsis; JSON output and the log file keep full values
+    pub stdout_max_field_len: Option<usize>,
+    /// Collapse consecutive slashes in the request path (e.g. `/v1//messages`) before
+    /// forwarding; the query string is forwarded unchanged
+    pub normalize_path: bool,
+    /// Log a warning naming the header and the request's model when an upstream response
+    /// carries an `anthropic-deprecation` or `deprecation` header
+    pub warn_on_deprecation: bool,
+    /// After binding, send one background GET to the upstream base URL so the first
+    /// client request reuses an established connection; failures are ignored
+    pub warmup_on_start: bool,
+    /// Seconds between `heartbeat` info events reporting uptime and requests served
+    /// (None or 0 = disabled); shows the process is alive during quiet periods
+    pub heartbeat_interval_secs: Option<u64>,
+    /// Hosts (case-insensitive) requests may be forwarded to (None = any host)
+    /// Checked against `anthropic_target_url` at load and enforced per request with 403
+    pub upstream_host_allowlist: Option<Vec<String>>,
+    /// Unix stream socket of a local log collector receiving JSON log lines (None = disabled)
+    /// Uses the file log level; reconnects if the collector restarts. Unix only
+    pub log_socket_path: Option<String>,
+    /// Maximum number of streaming responses open at once; excess streams get 503 with Retry-After
+    /// Independent of `max_concurrent_requests`; None (default) = unbounded, applied at startup only
+    pub max_concurrent_streams: Option<usize>,
+    /// Log requests as usual but answer with a canned 200 instead of forwarding them
+    /// When false (default), requests are forwarded upstream; applied at startup only
+    pub dry_run_proxy: bool,
+    /// Maximum number of dynamically keyed fields (headers, JSON keys) logged per event
+    /// Beyond this, the rest are dropped and `fields_truncated: true` is logged; startup only
+    pub max_span_fields: usize,
+    /// Ceiling on `max_tokens` of `POST /v1/messages` JSON bodies, clamped or injected if absent
+    /// When set to None (default), bodies are forwarded unchanged; applied at startup only
+    pub enforce_max_tokens: Option<u64>,
+    /// Warn when a successful response's streaming disagrees with the request's `stream` field
+    /// When false, the mismatch is only visible in the span fields
+    pub warn_on_stream_mismatch: bool,
+    /// Seconds open streaming responses get to finish after a shutdown signal
+    /// Ordinary requests get `SHUTDOWN_REQUEST_GRACE`; whatever remains is then force-closed
+    pub shutdown_stream_grace_secs: u64,
+    /// Whether every log event carries the build's git commit as `build.commit`
+    /// (the commit is always reported by the startup log and `/healthz`)
+    pub log_build_info: bool,
+    /// Whether clients may send `x-switchboard-no-log: true` to skip detail logging
+    /// (headers, bodies, stream chunks) for a request; the request span is still logged
+    pub allow_client_log_optout: bool,
+    /// Whether each log cleanup run finishes by logging the disk used by the log
+    /// directories (also available from `GET /admin/stats`)
+    pub log_usage_on_cleanup: bool,
+    /// Whether forwarded requests get `X-Forwarded-For`, `X-Forwarded-Host` and
+    /// `X-Forwarded-Proto` describing the client request
+    pub add_forwarded_headers: bool,
+    /// Whether proxied requests are answered with 503 `{"error":"maintenance"}`
+    /// instead of being forwarded; `/healthz` keeps answering (reloadable)
+    pub maintenance_mode: bool,
+    /// `Retry-After` value in seconds sent with maintenance responses (reloadable)
+    pub maintenance_retry_after_secs: u64,
+    /// Whether the time spent reading the client's request body is recorded as
+    /// `request_body_read_ms` on the request span
+    pub log_body_read_timing: bool,
+    /// When pretty stdout logs use ANSI colors (auto|always|never); applied at startup only
+    pub log_color: ColorMode,
+    /// Format of the ID generated for each request (uuid|short)
+    pub request_id_format: RequestIdFormat,
+    /// Whether a non-streaming response body the upstream cut short is forwarded as
+    /// received (with a warning) instead of answering 502
+    pub forward_partial_bodies: bool,
+    /// Whether client connections set `TCP_NODELAY`, sending small writes at once
+    pub tcp_nodelay: bool,
+    /// Length of the queue of connections waiting to be accepted (None = 1024, capped by the OS)
+    pub listen_backlog: Option<u32>,
+    /// Name of log files without the `.log` extension and date (None = the file
+    /// name of `log_file_path` without `.log`)
+    pub log_file_prefix: Option<String>,
+    /// Whether daily log files are named `<prefix>-YYYY-MM-DD.log` instead of
+    /// `<prefix>.log.YYYY-MM-DD`
+    pub log_file_date_in_name: bool,
+    /// Whether a request body the proxy rewrote is sent chunked, without Content-Length
+    pub force_chunked_on_rewrite: bool,
+    /// Whether `Upgrade: websocket` requests are tunnelled to the upstream (otherwise 501)
+    pub allow_websocket: bool,
+    /// Whether control characters (other than newline and tab) in logged bodies, stream
+    /// chunks and header values are escaped
+    pub sanitize_log_output: bool,
+    /// Whether upstream 429 and 529 responses get a dedicated warning event
+    pub log_rate_limit_events: bool,
+    /// Base directory of log files, overriding the environment's (None = detected);
+    /// the `app`/`test` subdirectories are still appended
+    pub log_base_dir: Option<String>,
+    /// Path prefixes paired with the environment variable holding the API key injected
+    /// for matching requests; the longest matching prefix wins (empty = `anthropic_api_key` only)
+    pub route_api_keys: Vec<(String, String)>,
+    /// Values of the variables named in `route_api_keys`, keyed by variable name
+    #[serde(serialize_with = "serialize_redacted_map")]
+    pub route_api_key_values: HashMap<String, String>,
+    /// Lowercase names of headers clients must send; requests missing one get 400,
+    /// and the values of present ones are recorded on the request span
+    pub require_headers: Vec<String>,
+    /// Answer OPTIONS requests locally as CORS preflights and add the allow-origin
+    /// header to proxied responses (false = OPTIONS is proxied)
+    pub cors_enabled: bool,
+    /// Origins allowed by CORS; `*` allows any, otherwise a listed request `Origin` is echoed
+    pub cors_allow_origins: Vec<String>,
+    /// Lowercase request header names advertised in `Access-Control-Allow-Headers`
+    pub cors_allow_headers: Vec<String>,
+    /// Uppercase methods advertised in `Access-Control-Allow-Methods`
+    pub cors_allow_methods: Vec<String>,
+    /// Times an idempotent request with a buffered body is resent after a status in
+    /// `retryable_statuses`, with exponential backoff (0 = no retries)
+    pub upstream_retries: u32,
+    /// Upstream statuses after which a request is retried, up to `upstream_retries` times
+    pub retryable_statuses: Vec<u16>,
+    /// Warn when a POST to `/v1/messages` is not JSON, or a 200 response is neither JSON nor SSE
+    pub warn_on_content_type_anomaly: bool,
+    /// Whether empty request/response bodies are logged as "body empty" INFO events
+    pub log_empty_bodies: bool,
+    /// Add the proxy's request ID as `request_id` to JSON object bodies of 4xx/5xx responses
+    pub inject_request_id_in_errors: bool,
+    /// Warn when a streamed response has data after its terminal event
+    /// (`message_stop` or `[DONE]`); the data is forwarded regardless
+    pub warn_on_sse_trailing_data: bool,
+    /// Deadline for a whole request, counted from its arrival; the upstream request
+    /// gets whatever is left as its timeout (None = the client's 10-minute timeout)
+    pub request_deadline_ms: Option<u64>,
+    /// Lowercase name of a header carrying the milliseconds left of `request_deadline_ms`
+    /// to the upstream, e.g. `x-timeout-ms` (None = not sent)
+    pub upstream_timeout_header: Option<String>,
+    /// Longest path and query, in bytes, a request may have before it is answered
+    /// with 414 URI Too Long (None = unlimited)
+    pub max_path_length: Option<usize>,
+    /// Whether the `/admin/*` endpoints are served; loading fails if this is requested
+    /// without an `admin_token`, so admin access is never unauthenticated
+    pub admin_enabled: bool,
+    /// Status sent to clients in place of any upstream 5xx, with the real one in
+    /// `x-switchboard-original-status` (None = forward the real status)
+    pub map_upstream_5xx_to: Option<u16>,
+    /// Bytes of a non-streaming response buffered before the proxy switches to
+    /// streaming the rest to the client, unlogged and uncached (None = always buffer)
+    pub response_buffer_limit_bytes: Option<usize>,
+    /// Whether the upstream `request-id` response header is recorded as the
+    /// `anthropic.request_id` span field
+    pub log_upstream_request_id: bool,
+    /// Whether file log lines are held briefly and written in timestamp order, at the
+    /// cost of delaying each line by `log_reorder::REORDER_WINDOW`
+    pub order_file_logs: bool,
+    /// Whether clients may send `x-switchboard-log-level: <level>` to log their request
+    /// at a more verbose level than the configured filters
+    pub allow_client_log_level: bool,
+}
+
+/// Errors that prevent a configuration from being loaded
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ConfigError {
+    /// The API key is required to forward requests in inject mode and has no default
+    #[error("ANTHROPIC_API_KEY must be set for forwarding unless AUTH_MODE is passthrough")]
+    MissingApiKey,
+
+    /// A route in `ROUTE_API_KEYS` names a variable that holds no key
+    #[error("ROUTE_API_KEYS maps '{prefix}' to {var}, which is not set")]
+    MissingRouteApiKey {
+        /// Path prefix of the route
+        prefix: String,
+        /// Name of the unset environment variable
+        var: String,
+    },
+
+    /// The configured target URL points at a host outside `UPSTREAM_HOST_ALLOWLIST`
+    #[error("ANTHROPIC_TARGET_URL host '{0}' is not in UPSTREAM_HOST_ALLOWLIST")]
+    UpstreamHostNotAllowed(String),
+
+    /// A variable's value is malformed and has no sensible fallback
+    #[error("{var} has an invalid value '{value}': {reason}")]
+    InvalidFormat {
+        /// Name of the environment variable
+        var: &'static str,
+        /// The rejected value
+        value: String,
+        /// Why the value was rejected
+        reason: String,
+    },
+}
+
+/// Marker written in place of secret values when a Config is serialized
+pub const REDACTED_VALUE: &str = "[REDACTED]";
+
+/// Config fields holding secrets, never reported by `Config::diff`
+const SECRET_FIELDS: [&str; 3] = ["anthropic_api_key", "admin_token", "route_api_key_values"];
+
+/// One field that differs between two configurations
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldChange {
+    /// Name of the Config field
+    pub field: String,
+    /// Serialized value before the change
+    pub old: Value,
+    /// Serialized value after the change
+    pub new: Value,
+}
+
+/// Serializes a secret as the redaction marker
+fn serialize_redacted<S: Serializer>(_secret: &str, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(REDACTED_VALUE)
+}
+
+/// Serializes an optional secret as the redaction marker, keeping None visible
+fn serialize_redacted_option<S: Serializer>(
+    secret: &Option<String>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match secret {
+        Some(_) => serializer.serialize_str(REDACTED_VALUE),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// Serializes a map of secrets with every value replaced by the redaction marker
+fn serialize_redacted_map<S: Serializer>(
+    secrets: &HashMap<String, String>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_map(secrets.keys().map(|name| (name, REDACTED_VALUE)))
 }
 
 /// Default implementation for Config
@@ -191,15 +1139,1435 @@ impl Default for Config {
             log_max_body_size: DEFAULT_LOG_MAX_BODY_SIZE,
             log_directory_mode: LogDirectoryMode::Default,
             log_max_age_days: DEFAULT_LOG_MAX_AGE_DAYS,
+            deployment_env: DEFAULT_DEPLOYMENT_ENV.map(String::from),
+            max_total_buffered_bytes: DEFAULT_MAX_TOTAL_BUFFERED_BYTES,
+            server_timing: DEFAULT_SERVER_TIMING,
+            admin_token: DEFAULT_ADMIN_TOKEN.map(String::from),
+            log_body_schema_only: DEFAULT_LOG_BODY_SCHEMA_ONLY,
+            log_json_indent: DEFAULT_LOG_JSON_INDENT,
+            tls_cert_path: DEFAULT_TLS_CERT_PATH.map(String::from),
+            tls_key_path: DEFAULT_TLS_KEY_PATH.map(String::from),
+            redact_body_fields: default_redact_body_fields(),
+            redact_query_params: default_redact_query_params(),
+            dedupe_repeated_logs: DEFAULT_DEDUPE_REPEATED_LOGS,
+            generate_traceparent: DEFAULT_GENERATE_TRACEPARENT,
+            empty_post_body: EmptyBodyPolicy::default(),
+            auth_mode: AuthMode::default(),
+            max_concurrent_requests: DEFAULT_MAX_CONCURRENT_REQUESTS,
+            queue_timeout_ms: DEFAULT_QUEUE_TIMEOUT_MS,
+            log_timestamp_format: TimestampFormat::default(),
+            error_log_to_stderr: DEFAULT_ERROR_LOG_TO_STDERR,
+            max_stream_duration_secs: DEFAULT_MAX_STREAM_DURATION_SECS,
+            log_resolved_ip: DEFAULT_LOG_RESOLVED_IP,
+            bind_retry_attempts: DEFAULT_BIND_RETRY_ATTEMPTS,
+            bind_retry_delay_ms: DEFAULT_BIND_RETRY_DELAY_MS,
+            model_rate_limits: default_model_rate_limits(),
+            stream_request_body: DEFAULT_STREAM_REQUEST_BODY,
+            log_request_sequence: DEFAULT_LOG_REQUEST_SEQUENCE,
+            log_key_fingerprint: DEFAULT_LOG_KEY_FINGERPRINT,
+            response_cache_ttl_secs: DEFAULT_RESPONSE_CACHE_TTL_SECS,
+            stdout_max_field_len: DEFAULT_STDOUT_MAX_FIELD_LEN,
+            normalize_path: DEFAULT_NORMALIZE_PATH,
+            warn_on_deprecation: DEFAULT_WARN_ON_DEPRECATION,
+            warmup_on_start: DEFAULT_WARMUP_ON_START,
+            heartbeat_interval_secs: DEFAULT_HEARTBEAT_INTERVAL_SECS,
+            upstream_host_allowlist: default_upstream_host_allowlist(),
+            log_socket_path: DEFAULT_LOG_SOCKET_PATH.map(String::from),
+            max_concurrent_streams: DEFAULT_MAX_CONCURRENT_STREAMS,
+            dry_run_proxy: DEFAULT_DRY_RUN_PROXY,
+            max_span_fields: DEFAULT_MAX_SPAN_FIELDS,
+            enforce_max_tokens: DEFAULT_ENFORCE_MAX_TOKENS,
+            warn_on_stream_mismatch: DEFAULT_WARN_ON_STREAM_MISMATCH,
+            shutdown_stream_grace_secs: DEFAULT_SHUTDOWN_STREAM_GRACE_SECS,
+            log_build_info: DEFAULT_LOG_BUILD_INFO,
+            allow_client_log_optout: DEFAULT_ALLOW_CLIENT_LOG_OPTOUT,
+            log_usage_on_cleanup: DEFAULT_LOG_USAGE_ON_CLEANUP,
+            add_forwarded_headers: DEFAULT_ADD_FORWARDED_HEADERS,
+            maintenance_mode: DEFAULT_MAINTENANCE_MODE,
+            maintenance_retry_after_secs: DEFAULT_MAINTENANCE_RETRY_AFTER_SECS,
+            log_body_read_timing: DEFAULT_LOG_BODY_READ_TIMING,
+            log_color: ColorMode::default(),
+            request_id_format: RequestIdFormat::default(),
+            forward_partial_bodies: DEFAULT_FORWARD_PARTIAL_BODIES,
+            tcp_nodelay: DEFAULT_TCP_NODELAY,
+            listen_backlog: DEFAULT_LISTEN_BACKLOG,
+            log_file_prefix: DEFAULT_LOG_FILE_PREFIX.map(String::from),
+            log_file_date_in_name: DEFAULT_LOG_FILE_DATE_IN_NAME,
+            force_chunked_on_rewrite: DEFAULT_FORCE_CHUNKED_ON_REWRITE,
+            allow_websocket: DEFAULT_ALLOW_WEBSOCKET,
+            sanitize_log_output: DEFAULT_SANITIZE_LOG_OUTPUT,
+            log_rate_limit_events: DEFAULT_LOG_RATE_LIMIT_EVENTS,
+            log_base_dir: DEFAULT_LOG_BASE_DIR.map(String::from),
+            route_api_keys: default_route_api_keys(),
+            route_api_key_values: HashMap::new(),
+            require_headers: default_require_headers(),
+            cors_enabled: DEFAULT_CORS_ENABLED,
+            cors_allow_origins: default_cors_allow_origins(),
+            cors_allow_headers: default_cors_allow_headers(),
+            cors_allow_methods: default_cors_allow_methods(),
+            upstream_retries: DEFAULT_UPSTREAM_RETRIES,
+            retryable_statuses: DEFAULT_RETRYABLE_STATUSES.to_vec(),
+            warn_on_content_type_anomaly: DEFAULT_WARN_ON_CONTENT_TYPE_ANOMALY,
+            log_empty_bodies: DEFAULT_LOG_EMPTY_BODIES,
+            inject_request_id_in_errors: DEFAULT_INJECT_REQUEST_ID_IN_ERRORS,
+            warn_on_sse_trailing_data: DEFAULT_WARN_ON_SSE_TRAILING_DATA,
+            request_deadline_ms: DEFAULT_REQUEST_DEADLINE_MS,
+            upstream_timeout_header: DEFAULT_UPSTREAM_TIMEOUT_HEADER.map(String::from),
+            max_path_length: DEFAULT_MAX_PATH_LENGTH,
+            admin_enabled: DEFAULT_ADMIN_TOKEN.is_some(),
+            map_upstream_5xx_to: DEFAULT_MAP_UPSTREAM_5XX_TO,
+            response_buffer_limit_bytes: DEFAULT_RESPONSE_BUFFER_LIMIT_BYTES,
+            log_upstream_request_id: DEFAULT_LOG_UPSTREAM_REQUEST_ID,
+            order_file_logs: DEFAULT_ORDER_FILE_LOGS,
+            allow_client_log_level: DEFAULT_ALLOW_CLIENT_LOG_LEVEL,
         }
     }
 }
 
-/// Global static configuration instance, initialized once on first access
-///
-/// Uses OnceLock for thread-safe lazy initialization
-pub static CONFIG: OnceLock<Config> = OnceLock::new();
-
+/// Returns `DEFAULT_MODEL_RATE_LIMITS` as an owned map
+fn default_model_rate_limits() -> HashMap<String, u32> {
+    DEFAULT_MODEL_RATE_LIMITS
+        .iter()
+        .map(|(model, limit)| (model.to_string(), *limit))
+        .collect()
+}
+
+/// Parses `model=limit` pairs separated by commas, e.g. `claude-3-opus=10,claude-3-haiku=100`
+///
+/// Blank entries are ignored; malformed entries are skipped with a warning so one
+/// typo does not disable every other limit.
+fn parse_model_rate_limits(value: &str) -> HashMap<String, u32> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .filter_map(|entry| {
+            let parsed = entry.split_once('=').and_then(|(model, limit)| {
+                let model = model.trim();
+                let limit = limit.trim().parse::<u32>().ok()?;
+                (!model.is_empty()).then(|| (model.to_string(), limit))
+            });
+            if parsed.is_none() {
+                warn!(
+                    var = "MODEL_RATE_LIMITS",
+                    entry = %entry,
+                    "Ignoring malformed model rate limit, expected model=requests_per_minute"
+                );
+            }
+            parsed
+        })
+        .collect()
+}
+
+/// Returns true if `prefix` is `path` or a leading run of its segments
+fn path_has_prefix(path: &str, prefix: &str) -> bool {
+    match path.strip_prefix(prefix) {
+        Some(rest) => rest.is_empty() || rest.starts_with('/') || prefix.ends_with('/'),
+        None => false,
+    }
+}
+
+/// Returns `DEFAULT_REQUIRE_HEADERS` as owned strings
+fn default_require_headers() -> Vec<String> {
+    DEFAULT_REQUIRE_HEADERS
+        .iter()
+        .map(|name| name.to_string())
+        .collect()
+}
+
+/// Returns `DEFAULT_CORS_ALLOW_ORIGINS` as owned strings
+fn default_cors_allow_origins() -> Vec<String> {
+    DEFAULT_CORS_ALLOW_ORIGINS
+        .iter()
+        .map(|origin| origin.to_string())
+        .collect()
+}
+
+/// Returns `DEFAULT_CORS_ALLOW_HEADERS` as owned strings
+fn default_cors_allow_headers() -> Vec<String> {
+    DEFAULT_CORS_ALLOW_HEADERS
+        .iter()
+        .map(|name| name.to_string())
+        .collect()
+}
+
+/// Returns `DEFAULT_CORS_ALLOW_METHODS` as owned strings
+fn default_cors_allow_methods() -> Vec<String> {
+    DEFAULT_CORS_ALLOW_METHODS
+        .iter()
+        .map(|method| method.to_string())
+        .collect()
+}
+
+/// Parses comma-separated HTTP methods, uppercased
+///
+/// Blank entries are ignored; entries that are not valid methods are skipped with a warning.
+fn parse_methods(var: &'static str, value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|method| method.trim().to_ascii_uppercase())
+        .filter(|method| !method.is_empty())
+        .filter(|method| match Method::from_bytes(method.as_bytes()) {
+            Ok(_) => true,
+            Err(_) => {
+                warn!(var, entry = %method, "Ignoring invalid HTTP method");
+                false
+            }
+        })
+        .collect()
+}
+
+/// Parses comma-separated HTTP status codes, ignoring blank entries
+///
+/// # Errors
+/// Returns `ConfigError::InvalidFormat` naming `var` if an entry is not a number
+/// from 100 to 599
+fn parse_status_codes(var: &'static str, value: &str) -> Result<Vec<u16>, ConfigError> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|status| !status.is_empty())
+        .map(|status| {
+            status
+                .parse::<u16>()
+                .ok()
+                .filter(|code| (100..=599).contains(code))
+                .ok_or_else(|| ConfigError::InvalidFormat {
+                    var,
+                    value: value.to_string(),
+                    reason: format!("'{}' is not an HTTP status code", status),
+                })
+        })
+        .collect()
+}
+
+/// Parses a single header name, lowercased as `HeaderMap` stores it
+///
+/// # Errors
+/// Returns `ConfigError::InvalidFormat` naming `var` if `value` is not a valid header name
+fn parse_header_name(var: &'static str, value: &str) -> Result<String, ConfigError> {
+    HeaderName::from_bytes(value.trim().as_bytes())
+        .map(|header_name| header_name.as_str().to_string())
+        .map_err(|_| ConfigError::InvalidFormat {
+            var,
+            value: value.to_string(),
+            reason: format!("'{}' is not a valid header name", value.trim()),
+        })
+}
+
+/// Parses comma-separated header names, lowercased as `HeaderMap` stores them
+///
+/// Blank entries are ignored.
+///
+/// # Errors
+/// Returns `ConfigError::InvalidFormat` naming `var` if an entry is not a valid
+/// header name, as no request could ever carry it
+fn parse_header_names(var: &'static str, value: &str) -> Result<Vec<String>, ConfigError> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(|name| {
+            parse_header_name(var, name).map_err(|_| ConfigError::InvalidFormat {
+                var,
+                value: value.to_string(),
+                reason: format!("'{}' is not a valid header name", name),
+            })
+        })
+        .collect()
+}
+
+/// Returns `DEFAULT_ROUTE_API_KEYS` as owned pairs
+fn default_route_api_keys() -> Vec<(String, String)> {
+    DEFAULT_ROUTE_API_KEYS
+        .iter()
+        .map(|(prefix, var)| (prefix.to_string(), var.to_string()))
+        .collect()
+}
+
+/// Parses `path_prefix=VARIABLE` pairs separated by commas, e.g. `/v1/messages/batches=BATCH_KEY`
+///
+/// Blank entries are ignored; malformed entries are skipped with a warning, like
+/// `MODEL_RATE_LIMITS`. Prefixes must start with `/`.
+fn parse_route_api_keys(value: &str) -> Vec<(String, String)> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .filter_map(|entry| {
+            let parsed = entry.split_once('=').and_then(|(prefix, var)| {
+                let prefix = prefix.trim();
+                let var = var.trim();
+                (prefix.starts_with('/') && !var.is_empty())
+                    .then(|| (prefix.to_string(), var.to_string()))
+            });
+            if parsed.is_none() {
+                warn!(
+                    var = "ROUTE_API_KEYS",
+                    entry = %entry,
+                    "Ignoring malformed API key route, expected /path_prefix=VARIABLE"
+                );
+            }
+            parsed
+        })
+        .collect()
+}
+
+/// Formats the redacted serialized form, so debug output never includes secrets
+impl fmt::Debug for Config {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let redacted = serde_json::to_value(self).map_err(|_| fmt::Error)?;
+        write!(f, "Config {}", redacted)
+    }
+}
+
+/// Returns `DEFAULT_UPSTREAM_HOST_ALLOWLIST` as owned strings
+fn default_upstream_host_allowlist() -> Option<Vec<String>> {
+    DEFAULT_UPSTREAM_HOST_ALLOWLIST.map(|hosts| hosts.iter().map(|host| host.to_string()).collect())
+}
+
+/// Returns `DEFAULT_REDACT_QUERY_PARAMS` as owned strings
+fn default_redact_query_params() -> Vec<String> {
+    DEFAULT_REDACT_QUERY_PARAMS
+        .iter()
+        .map(|param| param.to_string())
+        .collect()
+}
+
+/// Returns `DEFAULT_REDACT_BODY_FIELDS` as owned strings
+fn default_redact_body_fields() -> Vec<String> {
+    DEFAULT_REDACT_BODY_FIELDS
+        .iter()
+        .map(|field| field.to_string())
+        .collect()
+}
+
+impl Config {
+    /// Builds a configuration from the given environment variables
+    ///
+    /// Reads only `vars`, never the process environment, so tests can exercise
+    /// parsing and validation deterministically. `load_config` is a thin wrapper
+    /// that passes in the real environment. Empty values are treated as unset.
+    ///
+    /// # Errors
+    /// Returns `ConfigError::MissingApiKey` if `ANTHROPIC_API_KEY` is unset or empty
+    /// while `AUTH_MODE` is `inject`, and `ConfigError::UpstreamHostNotAllowed` if
+    /// `UPSTREAM_HOST_ALLOWLIST` is set and excludes the host of `ANTHROPIC_TARGET_URL`,
+    /// and `ConfigError::InvalidFormat` if `LOG_LEVEL` or `LOG_FILE_LEVEL` is not a valid
+    /// filter directive string, an entry of `RETRYABLE_STATUSES` is not a status code,
+    /// an entry of `REQUIRE_HEADERS` or `CORS_ALLOW_HEADERS` or `UPSTREAM_TIMEOUT_HEADER`
+    /// is not a valid header name,
+    /// `ADMIN_ENABLED` is true without an `ADMIN_TOKEN`, or `ANTHROPIC_TARGET_URL` has no host.
+    /// Returns `ConfigError::MissingRouteApiKey` if a variable named in `ROUTE_API_KEYS`
+    /// is unset or empty
+    pub fn from_env_map(vars: &HashMap<String, String>) -> Result<Config, ConfigError> {
+        let anthropic_api_key = env_value(vars, "ANTHROPIC_API_KEY").unwrap_or_default();
+        let config = read_config(vars, anthropic_api_key);
+        if config.auth_mode == AuthMode::Inject && config.anthropic_api_key.is_empty() {
+            return Err(ConfigError::MissingApiKey);
+        }
+        if let Some((prefix, var)) = config
+            .route_api_keys
+            .iter()
+            .find(|(_, var)| !config.route_api_key_values.contains_key(var))
+        {
+            return Err(ConfigError::MissingRouteApiKey {
+                prefix: prefix.clone(),
+                var: var.clone(),
+            });
+        }
+        validate_level_directives("LOG_LEVEL", &config.log_stdout_level)?;
+        validate_level_directives("LOG_FILE_LEVEL", &config.log_file_level)?;
+        if config.admin_enabled && config.admin_token.is_none() {
+            return Err(ConfigError::InvalidFormat {
+                var: "ADMIN_ENABLED",
+                value: env_value(vars, "ADMIN_ENABLED").unwrap_or_default(),
+                reason: "admin endpoints require a non-empty ADMIN_TOKEN".to_string(),
+            });
+        }
+        if let Some(statuses) = env_value(vars, "RETRYABLE_STATUSES") {
+            parse_status_codes("RETRYABLE_STATUSES", &statuses)?;
+        }
+        for var in ["REQUIRE_HEADERS", "CORS_ALLOW_HEADERS"] {
+            if let Some(names) = env_value(vars, var) {
+                parse_header_names(var, &names)?;
+            }
+        }
+        if let Some(name) = env_value(vars, "UPSTREAM_TIMEOUT_HEADER") {
+            parse_header_name("UPSTREAM_TIMEOUT_HEADER", &name)?;
+        }
+        let target_host = config
+            .anthropic_target_url
+            .parse::<hyper::Uri>()
+            .ok()
+            .and_then(|uri| uri.host().map(String::from))
+            .filter(|host| !host.is_empty())
+            .ok_or_else(|| ConfigError::InvalidFormat {
+                var: "ANTHROPIC_TARGET_URL",
+                value: config.anthropic_target_url.clone(),
+                reason: "must be an absolute URL with a host, e.g. https://api.anthropic.com"
+                    .to_string(),
+            })?;
+        if !config.upstream_host_allowed(&target_host) {
+            return Err(ConfigError::UpstreamHostNotAllowed(target_host));
+        }
+        Ok(config)
+    }
+
+    /// Returns true if requests may be forwarded to `host`
+    ///
+    /// Any host is allowed without an allowlist; otherwise the host must match an
+    /// entry, ignoring case.
+    pub fn upstream_host_allowed(&self, host: &str) -> bool {
+        self.upstream_host_allowlist
+            .as_ref()
+            .is_none_or(|allowlist| {
+                allowlist
+                    .iter()
+                    .any(|allowed| allowed.eq_ignore_ascii_case(host))
+            })
+    }
+
+    /// Builds the `x-api-key` header value for an upstream request to `path`
+    ///
+    /// This is the only place an API key becomes a header value. The key is chosen
+    /// by [`api_key_for_path`](Self::api_key_for_path). The value is marked sensitive,
+    /// so its Debug output is redacted and HTTP/2 never indexes it.
+    ///
+    /// # Errors
+    /// Returns an error if the key contains characters not allowed in a header.
+    /// The error does not include the key.
+    pub fn api_key_header_value(&self, path: &str) -> Result<HeaderValue, InvalidHeaderValue> {
+        let mut value = HeaderValue::from_str(self.api_key_for_path(path))?;
+        value.set_sensitive(true);
+        Ok(value)
+    }
+
+    /// Returns the API key to inject for a request to `path`
+    ///
+    /// The route in `route_api_keys` with the longest prefix matching whole segments
+    /// of `path` picks the key, so `/v1/messages` matches `/v1/messages/batches` but
+    /// not `/v1/messagesx`. Without a matching route, `anthropic_api_key` is used.
+    pub fn api_key_for_path(&self, path: &str) -> &str {
+        self.route_api_keys
+            .iter()
+            .filter(|(prefix, _)| path_has_prefix(path, prefix))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .and_then(|(_, var)| self.route_api_key_values.get(var))
+            .unwrap_or(&self.anthropic_api_key)
+    }
+
+    /// Returns true if some enabled feature needs the full request body before forwarding
+    ///
+    /// Body logging, per-model rate limits (which read the model), the empty-JSON
+    /// substitution for empty POST bodies and the `max_tokens` ceiling all inspect the body.
+    pub fn request_body_required(&self) -> bool {
+        self.log_bodies
+            || !self.model_rate_limits.is_empty()
+            || self.empty_post_body == EmptyBodyPolicy::EmptyJson
+            || self.enforce_max_tokens.is_some()
+    }
+
+    /// Returns true if request bodies should be streamed upstream rather than buffered
+    pub fn streams_request_body(&self) -> bool {
+        self.stream_request_body && !self.request_body_required()
+    }
+
+    /// Lists the fields whose values differ in `other`, in field name order
+    ///
+    /// Values are compared in their serialized form. Secret fields are skipped
+    /// entirely, so the result is safe to log.
+    pub fn diff(&self, other: &Config) -> Vec<FieldChange> {
+        let (Ok(Value::Object(old)), Ok(Value::Object(mut new))) =
+            (serde_json::to_value(self), serde_json::to_value(other))
+        else {
+            return Vec::new();
+        };
+
+        old.into_iter()
+            .filter(|(field, _)| !SECRET_FIELDS.contains(&field.as_str()))
+            .filter_map(|(field, old)| {
+                let new = new.remove(&field).unwrap_or(Value::Null);
+                (old != new).then_some(FieldChange { field, old, new })
+            })
+            .collect()
+    }
+
+    /// Returns a copy of this config with the runtime-reloadable fields taken from `fresh`
+    ///
+    /// Only fields that are read per request can change at runtime. Secrets, the listen
+    /// port, the upstream URL and anything consumed once at startup (log levels, paths,
+    /// buffering budget) keep their current values. The config has no slow-request
+    /// warning threshold (`slow_request_warn_ms`), so that is not reloadable yet.
+    ///
+    /// # Arguments
+    /// * `fresh` - A newly loaded config providing the reloadable values
+    pub fn with_reloaded_fields(&self, fresh: &Config) -> Config {
+        Config {
+            log_bodies: fresh.log_bodies,
+            log_max_body_size: fresh.log_max_body_size,
+            log_body_schema_only: fresh.log_body_schema_only,
+            log_json_indent: fresh.log_json_indent,
+            redact_body_fields: fresh.redact_body_fields.clone(),
+            redact_query_params: fresh.redact_query_params.clone(),
+            server_timing: fresh.server_timing,
+            maintenance_mode: fresh.maintenance_mode,
+            maintenance_retry_after_secs: fresh.maintenance_retry_after_secs,
+            ..self.clone()
+        }
+    }
+}
+
+/// Computes a short fingerprint identifying a secret without revealing it
+///
+/// The fingerprint is the first `KEY_FINGERPRINT_HEX_LEN` hex characters of the
+/// secret's SHA-256 digest: stable for the same secret, different for different
+/// secrets (barring a rare collision), and not reversible.
+pub fn key_fingerprint(secret: &str) -> String {
+    let digest = ring::digest::digest(&ring::digest::SHA256, secret.as_bytes());
+    digest.as_ref()[..KEY_FINGERPRINT_HEX_LEN / 2]
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Checks that a log level is a valid `EnvFilter` directive string
+///
+/// Besides single levels like `debug`, this accepts per-target directives such as
+/// `switchboard=debug,reqwest=warn`, rejecting only what logging could not parse.
+fn validate_level_directives(var: &'static str, value: &str) -> Result<(), ConfigError> {
+    EnvFilter::try_new(value)
+        .map(|_| ())
+        .map_err(|e| ConfigError::InvalidFormat {
+            var,
+            value: value.to_string(),
+            reason: e.to_string(),
+        })
+}
+
+/// Parse a boolean environment variable, falling back to `default`
+///
+/// Accepts "true"/"false" (case-insensitive) and "1"/"0". Any other value is
+/// ambiguous, so a warning is logged and the default is used instead.
+///
+/// # Arguments
+/// * `vars` - Environment variables by name
+/// * `var` - Name of the environment variable
+/// * `default` - Value used when the variable is unset or ambiguous
+fn parse_bool_env(vars: &HashMap<String, String>, var: &str, default: bool) -> bool {
+    match env_value(vars, var) {
+        Some(value) => {
+            // Check if it's a valid boolean representation
+            if value.to_lowercase() == "true"
+                || value.to_lowercase() == "false"
+                || value == "0"
+                || value == "1"
+            {
+                // Only consider "false" and "0" as false values (maintain existing behavior)
+                value.to_lowercase() != "false" && value != "0"
+            } else {
+                // Non-standard boolean value, log a warning
+                warn!(
+                    var = var,
+                    value = %value,
+                    default = default,
+                    "Ambiguous boolean value in environment variable, using default"
+                );
+                default
+            }
+        }
+        None => default, // Use default if not set
+    }
+}
+
+/// Looks up an environment variable, treating an empty value the same as unset
+fn env_value(vars: &HashMap<String, String>, var: &str) -> Option<String> {
+    vars.get(var).filter(|value| !value.is_empty()).cloned()
+}
+
+/// Snapshot of the process environment (variables that are not valid UTF-8 are skipped)
+fn process_env() -> HashMap<String, String> {
+    env::vars_os()
+        .filter_map(|(name, value)| Some((name.into_string().ok()?, value.into_string().ok()?)))
+        .collect()
+}
+
+/// Global static configuration instance, initialized once on first access
+///
+/// Uses OnceLock for thread-safe lazy initialization
+pub static CONFIG: OnceLock<Config> = OnceLock::new();
+
+/// Read every configuration value from a map of environment variables
+///
+/// Shared by startup loading, runtime reloads and `Config::from_env_map`. The API
+/// key is passed in rather than read here so a reload can never fail on (or
+/// change) the secret.
+///
+/// # Arguments
+/// * `vars` - Environment variables by name
+/// * `anthropic_api_key` - The API key to place in the resulting config
+fn read_config(vars: &HashMap<String, String>, anthropic_api_key: String) -> Config {
+    // Load configuration values with sensible defaults
+    let port = env_value(vars, "PORT").unwrap_or_else(|| DEFAULT_PORT.to_string());
+
+    let anthropic_target_url = env_value(vars, "ANTHROPIC_TARGET_URL")
+        .unwrap_or_else(|| DEFAULT_ANTHROPIC_TARGET_URL.to_string());
+
+    let log_stdout_level =
+        env_value(vars, "LOG_LEVEL").unwrap_or_else(|| DEFAULT_LOG_STDOUT_LEVEL.to_string());
+    let log_format =
+        env_value(vars, "LOG_FORMAT").unwrap_or_else(|| DEFAULT_LOG_FORMAT.to_string());
+
+    // Parse LOG_BODIES with error handling for non-boolean values
+    let log_bodies = parse_bool_env(vars, "LOG_BODIES", DEFAULT_LOG_BODIES);
+
+    // Load file logging configuration
+    let log_file_path =
+        env_value(vars, "LOG_FILE_PATH").unwrap_or_else(|| DEFAULT_LOG_FILE_PATH.to_string());
+    let log_file_level =
+        env_value(vars, "LOG_FILE_LEVEL").unwrap_or_else(|| DEFAULT_LOG_FILE_LEVEL.to_string());
+
+    // Parse LOG_MAX_BODY_SIZE with error handling
+    let log_max_body_size = env_value(vars, "LOG_MAX_BODY_SIZE")
+        .and_then(|size_str| {
+            size_str.parse::<usize>().ok().or_else(|| {
+                warn!(
+                    var = "LOG_MAX_BODY_SIZE",
+                    value = %size_str,
+                    default = DEFAULT_LOG_MAX_BODY_SIZE,
+                    "Failed to parse numeric environment variable, using default"
+                );
+                None
+            })
+        })
+        .unwrap_or(DEFAULT_LOG_MAX_BODY_SIZE); // Default if not set or invalid
+    let log_max_body_size = if log_max_body_size > MAX_ALLOWED_LOG_BODY_SIZE {
+        warn!(
+            var = "LOG_MAX_BODY_SIZE",
+            value = log_max_body_size,
+            max = MAX_ALLOWED_LOG_BODY_SIZE,
+            "Value exceeds the maximum, clamping"
+        );
+        MAX_ALLOWED_LOG_BODY_SIZE
+    } else {
+        log_max_body_size
+    };
+
+    // Parse LOG_DIRECTORY_MODE environment variable
+    let log_directory_mode = env_value(vars, "LOG_DIRECTORY_MODE")
+        .map(|mode| match mode.to_lowercase().as_str() {
+            "xdg" => LogDirectoryMode::Xdg,
+            "system" => LogDirectoryMode::System,
+            _ => LogDirectoryMode::Default,
+        })
+        .unwrap_or(LogDirectoryMode::Default);
+
+    // Parse LOG_MAX_AGE_DAYS with error handling
+    let log_max_age_days = env_value(vars, "LOG_MAX_AGE_DAYS").and_then(|days_str| {
+        days_str.parse::<u32>().ok().or_else(|| {
+            // Format default value for human-readable log message
+            let default_display = match DEFAULT_LOG_MAX_AGE_DAYS {
+                Some(days) => days.to_string(),
+                None => "no cleanup".to_string(),
+            };
+
+            warn!(
+                var = "LOG_MAX_AGE_DAYS",
+                value = %days_str,
+                default = ?DEFAULT_LOG_MAX_AGE_DAYS,
+                default_display = %default_display,
+                "Failed to parse numeric environment variable, using default"
+            );
+            None
+        })
+    });
+
+    // Treat an empty DEPLOYMENT_ENV the same as unset so logs aren't tagged with ""
+    let deployment_env = env_value(vars, "DEPLOYMENT_ENV")
+        .filter(|name| !name.trim().is_empty())
+        .or_else(|| DEFAULT_DEPLOYMENT_ENV.map(String::from));
+
+    // Parse MAX_TOTAL_BUFFERED_BYTES with error handling
+    let max_total_buffered_bytes = env_value(vars, "MAX_TOTAL_BUFFERED_BYTES")
+        .and_then(|bytes_str| {
+            bytes_str.parse::<u64>().ok().or_else(|| {
+                warn!(
+                    var = "MAX_TOTAL_BUFFERED_BYTES",
+                    value = %bytes_str,
+                    default = ?DEFAULT_MAX_TOTAL_BUFFERED_BYTES,
+                    "Failed to parse numeric environment variable, using default"
+                );
+                None
+            })
+        })
+        .or(DEFAULT_MAX_TOTAL_BUFFERED_BYTES);
+
+    // Parse SERVER_TIMING with error handling for non-boolean values
+    let server_timing = parse_bool_env(vars, "SERVER_TIMING", DEFAULT_SERVER_TIMING);
+
+    // Treat an empty ADMIN_TOKEN as unset so an empty bearer can never authenticate
+    let admin_token = env_value(vars, "ADMIN_TOKEN")
+        .filter(|token| !token.trim().is_empty())
+        .or_else(|| DEFAULT_ADMIN_TOKEN.map(String::from));
+
+    // Parse LOG_BODY_SCHEMA_ONLY with error handling for non-boolean values
+    let log_body_schema_only =
+        parse_bool_env(vars, "LOG_BODY_SCHEMA_ONLY", DEFAULT_LOG_BODY_SCHEMA_ONLY);
+
+    // Parse LOG_JSON_INDENT with error handling
+    let log_json_indent = env_value(vars, "LOG_JSON_INDENT")
+        .and_then(|indent_str| {
+            indent_str.parse::<usize>().ok().or_else(|| {
+                warn!(
+                    var = "LOG_JSON_INDENT",
+                    value = %indent_str,
+                    default = DEFAULT_LOG_JSON_INDENT,
+                    "Failed to parse numeric environment variable, using default"
+                );
+                None
+            })
+        })
+        .unwrap_or(DEFAULT_LOG_JSON_INDENT);
+
+    // TLS is enabled only when both certificate and key paths are provided
+    let tls_cert_path = env_value(vars, "TLS_CERT_PATH")
+        .filter(|path| !path.trim().is_empty())
+        .or_else(|| DEFAULT_TLS_CERT_PATH.map(String::from));
+
+    let tls_key_path = env_value(vars, "TLS_KEY_PATH")
+        .filter(|path| !path.trim().is_empty())
+        .or_else(|| DEFAULT_TLS_KEY_PATH.map(String::from));
+
+    // Parse REDACT_BODY_FIELDS as a comma-separated list, ignoring blank entries
+    let redact_body_fields = env_value(vars, "REDACT_BODY_FIELDS")
+        .map(|fields| {
+            fields
+                .split(',')
+                .map(str::trim)
+                .filter(|field| !field.is_empty())
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_else(default_redact_body_fields);
+
+    // Parse REDACT_QUERY_PARAMS as a comma-separated list, ignoring blank entries
+    let redact_query_params = env_value(vars, "REDACT_QUERY_PARAMS")
+        .map(|params| {
+            params
+                .split(',')
+                .map(str::trim)
+                .filter(|param| !param.is_empty())
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_else(default_redact_query_params);
+
+    // Parse DEDUPE_REPEATED_LOGS with error handling for non-boolean values
+    let dedupe_repeated_logs =
+        parse_bool_env(vars, "DEDUPE_REPEATED_LOGS", DEFAULT_DEDUPE_REPEATED_LOGS);
+
+    // Parse GENERATE_TRACEPARENT with error handling for non-boolean values
+    let generate_traceparent =
+        parse_bool_env(vars, "GENERATE_TRACEPARENT", DEFAULT_GENERATE_TRACEPARENT);
+
+    // Parse EMPTY_POST_BODY, keeping the default for unrecognized values
+    let empty_post_body = env_value(vars, "EMPTY_POST_BODY")
+        .map(|policy| match policy.to_lowercase().as_str() {
+            "passthrough" => EmptyBodyPolicy::Passthrough,
+            "empty_json" => EmptyBodyPolicy::EmptyJson,
+            _ => {
+                warn!(
+                    var = "EMPTY_POST_BODY",
+                    value = %policy,
+                    default = ?EmptyBodyPolicy::default(),
+                    "Unrecognized empty body policy, using default"
+                );
+                EmptyBodyPolicy::default()
+            }
+        })
+        .unwrap_or_default();
+
+    // Parse AUTH_MODE, keeping the default for unrecognized values
+    let auth_mode = env_value(vars, "AUTH_MODE")
+        .map(|mode| match mode.to_lowercase().as_str() {
+            "inject" => AuthMode::Inject,
+            "passthrough" => AuthMode::Passthrough,
+            _ => {
+                warn!(
+                    var = "AUTH_MODE",
+                    value = %mode,
+                    default = ?AuthMode::default(),
+                    "Unrecognized auth mode, using default"
+                );
+                AuthMode::default()
+            }
+        })
+        .unwrap_or_default();
+
+    // Parse MAX_CONCURRENT_REQUESTS with error handling
+    let max_concurrent_requests = env_value(vars, "MAX_CONCURRENT_REQUESTS")
+        .and_then(|max_str| {
+            max_str.parse::<usize>().ok().or_else(|| {
+                warn!(
+                    var = "MAX_CONCURRENT_REQUESTS",
+                    value = %max_str,
+                    default = ?DEFAULT_MAX_CONCURRENT_REQUESTS,
+                    "Failed to parse numeric environment variable, using default"
+                );
+                None
+            })
+        })
+        .or(DEFAULT_MAX_CONCURRENT_REQUESTS);
+
+    // Parse QUEUE_TIMEOUT_MS with error handling
+    let queue_timeout_ms = env_value(vars, "QUEUE_TIMEOUT_MS")
+        .and_then(|ms_str| {
+            ms_str.parse::<u64>().ok().or_else(|| {
+                warn!(
+                    var = "QUEUE_TIMEOUT_MS",
+                    value = %ms_str,
+                    default = ?DEFAULT_QUEUE_TIMEOUT_MS,
+                    "Failed to parse numeric environment variable, using default"
+                );
+                None
+            })
+        })
+        .or(DEFAULT_QUEUE_TIMEOUT_MS);
+
+    // Parse LOG_TIMESTAMP_FORMAT, keeping the default for unrecognized values
+    let log_timestamp_format = env_value(vars, "LOG_TIMESTAMP_FORMAT")
+        .map(|format| match format.to_lowercase().as_str() {
+            "rfc3339" => TimestampFormat::Rfc3339,
+            "epoch_millis" => TimestampFormat::EpochMillis,
+            "epoch_secs" => TimestampFormat::EpochSecs,
+            _ => {
+                warn!(
+                    var = "LOG_TIMESTAMP_FORMAT",
+                    value = %format,
+                    default = ?TimestampFormat::default(),
+                    "Unrecognized log timestamp format, using default"
+                );
+                TimestampFormat::default()
+            }
+        })
+        .unwrap_or_default();
+
+    // Parse ERROR_LOG_TO_STDERR with error handling for non-boolean values
+    let error_log_to_stderr =
+        parse_bool_env(vars, "ERROR_LOG_TO_STDERR", DEFAULT_ERROR_LOG_TO_STDERR);
+
+    // Parse MAX_STREAM_DURATION_SECS with error handling
+    let max_stream_duration_secs = env_value(vars, "MAX_STREAM_DURATION_SECS")
+        .and_then(|secs_str| {
+            secs_str.parse::<u64>().ok().or_else(|| {
+                warn!(
+                    var = "MAX_STREAM_DURATION_SECS",
+                    value = %secs_str,
+                    default = ?DEFAULT_MAX_STREAM_DURATION_SECS,
+                    "Failed to parse numeric environment variable, using default"
+                );
+                None
+            })
+        })
+        .or(DEFAULT_MAX_STREAM_DURATION_SECS);
+
+    // Parse LOG_RESOLVED_IP with error handling for non-boolean values
+    let log_resolved_ip = parse_bool_env(vars, "LOG_RESOLVED_IP", DEFAULT_LOG_RESOLVED_IP);
+
+    // Parse BIND_RETRY_ATTEMPTS with error handling
+    let bind_retry_attempts = env_value(vars, "BIND_RETRY_ATTEMPTS")
+        .and_then(|attempts_str| {
+            attempts_str.parse::<u32>().ok().or_else(|| {
+                warn!(
+                    var = "BIND_RETRY_ATTEMPTS",
+                    value = %attempts_str,
+                    default = DEFAULT_BIND_RETRY_ATTEMPTS,
+                    "Failed to parse numeric environment variable, using default"
+                );
+                None
+            })
+        })
+        .unwrap_or(DEFAULT_BIND_RETRY_ATTEMPTS);
+
+    // Parse BIND_RETRY_DELAY_MS with error handling
+    let bind_retry_delay_ms = env_value(vars, "BIND_RETRY_DELAY_MS")
+        .and_then(|delay_str| {
+            delay_str.parse::<u64>().ok().or_else(|| {
+                warn!(
+                    var = "BIND_RETRY_DELAY_MS",
+                    value = %delay_str,
+                    default = DEFAULT_BIND_RETRY_DELAY_MS,
+                    "Failed to parse numeric environment variable, using default"
+                );
+                None
+            })
+        })
+        .unwrap_or(DEFAULT_BIND_RETRY_DELAY_MS);
+
+    // Parse MODEL_RATE_LIMITS as comma-separated model=limit pairs
+    let model_rate_limits = env_value(vars, "MODEL_RATE_LIMITS")
+        .map(|limits| parse_model_rate_limits(&limits))
+        .unwrap_or_else(default_model_rate_limits);
+
+    // Parse STREAM_REQUEST_BODY with error handling for non-boolean values
+    let stream_request_body =
+        parse_bool_env(vars, "STREAM_REQUEST_BODY", DEFAULT_STREAM_REQUEST_BODY);
+
+    // Parse LOG_REQUEST_SEQUENCE with error handling for non-boolean values
+    let log_request_sequence =
+        parse_bool_env(vars, "LOG_REQUEST_SEQUENCE", DEFAULT_LOG_REQUEST_SEQUENCE);
+
+    // Parse LOG_KEY_FINGERPRINT with error handling for non-boolean values
+    let log_key_fingerprint =
+        parse_bool_env(vars, "LOG_KEY_FINGERPRINT", DEFAULT_LOG_KEY_FINGERPRINT);
+
+    // Parse RESPONSE_CACHE_TTL_SECS with error handling
+    let response_cache_ttl_secs = env_value(vars, "RESPONSE_CACHE_TTL_SECS")
+        .and_then(|secs_str| {
+            secs_str.parse::<u64>().ok().or_else(|| {
+                warn!(
+                    var = "RESPONSE_CACHE_TTL_SECS",
+                    value = %secs_str,
+                    default = ?DEFAULT_RESPONSE_CACHE_TTL_SECS,
+                    "Failed to parse numeric environment variable, using default"
+                );
+                None
+            })
+        })
+        .or(DEFAULT_RESPONSE_CACHE_TTL_SECS);
+
+    // Parse STDOUT_MAX_FIELD_LEN with error handling
+    let stdout_max_field_len = env_value(vars, "STDOUT_MAX_FIELD_LEN")
+        .and_then(|len_str| {
+            len_str.parse::<usize>().ok().or_else(|| {
+                warn!(
+                    var = "STDOUT_MAX_FIELD_LEN",
+                    value = %len_str,
+                    default = ?DEFAULT_STDOUT_MAX_FIELD_LEN,
+                    "Failed to parse numeric environment variable, using default"
+                );
+                None
+            })
+        })
+        .or(DEFAULT_STDOUT_MAX_FIELD_LEN);
+
+    // Parse NORMALIZE_PATH with error handling for non-boolean values
+    let normalize_path = parse_bool_env(vars, "NORMALIZE_PATH", DEFAULT_NORMALIZE_PATH);
+
+    // Parse WARN_ON_DEPRECATION with error handling for non-boolean values
+    let warn_on_deprecation =
+        parse_bool_env(vars, "WARN_ON_DEPRECATION", DEFAULT_WARN_ON_DEPRECATION);
+
+    // Parse WARMUP_ON_START with error handling for non-boolean values
+    let warmup_on_start = parse_bool_env(vars, "WARMUP_ON_START", DEFAULT_WARMUP_ON_START);
+
+    // Parse HEARTBEAT_INTERVAL_SECS with error handling
+    let heartbeat_interval_secs = env_value(vars, "HEARTBEAT_INTERVAL_SECS")
+        .and_then(|secs_str| {
+            secs_str.parse::<u64>().ok().or_else(|| {
+                warn!(
+                    var = "HEARTBEAT_INTERVAL_SECS",
+                    value = %secs_str,
+                    default = ?DEFAULT_HEARTBEAT_INTERVAL_SECS,
+                    "Failed to parse numeric environment variable, using default"
+                );
+                None
+            })
+        })
+        .or(DEFAULT_HEARTBEAT_INTERVAL_SECS);
+
+    // Parse UPSTREAM_HOST_ALLOWLIST as a comma-separated list, ignoring blank entries
+    let upstream_host_allowlist = env_value(vars, "UPSTREAM_HOST_ALLOWLIST")
+        .map(|hosts| {
+            hosts
+                .split(',')
+                .map(str::trim)
+                .filter(|host| !host.is_empty())
+                .map(String::from)
+                .collect()
+        })
+        .or_else(default_upstream_host_allowlist);
+
+    // Get LOG_SOCKET_PATH, falling back to the default (no socket output)
+    let log_socket_path =
+        env_value(vars, "LOG_SOCKET_PATH").or_else(|| DEFAULT_LOG_SOCKET_PATH.map(String::from));
+
+    // Parse MAX_CONCURRENT_STREAMS with error handling
+    let max_concurrent_streams = env_value(vars, "MAX_CONCURRENT_STREAMS")
+        .and_then(|max_str| {
+            max_str.parse::<usize>().ok().or_else(|| {
+                warn!(
+                    var = "MAX_CONCURRENT_STREAMS",
+                    value = %max_str,
+                    default = ?DEFAULT_MAX_CONCURRENT_STREAMS,
+                    "Failed to parse numeric environment variable, using default"
+                );
+                None
+            })
+        })
+        .or(DEFAULT_MAX_CONCURRENT_STREAMS);
+
+    // Parse DRY_RUN_PROXY with error handling for non-boolean values
+    let dry_run_proxy = parse_bool_env(vars, "DRY_RUN_PROXY", DEFAULT_DRY_RUN_PROXY);
+
+    // Parse MAX_SPAN_FIELDS with error handling
+    let max_span_fields = env_value(vars, "MAX_SPAN_FIELDS")
+        .and_then(|max_str| {
+            max_str.parse::<usize>().ok().or_else(|| {
+                warn!(
+                    var = "MAX_SPAN_FIELDS",
+                    value = %max_str,
+                    default = DEFAULT_MAX_SPAN_FIELDS,
+                    "Failed to parse numeric environment variable, using default"
+                );
+                None
+            })
+        })
+        .unwrap_or(DEFAULT_MAX_SPAN_FIELDS);
+
+    // Parse ENFORCE_MAX_TOKENS with error handling
+    let enforce_max_tokens = env_value(vars, "ENFORCE_MAX_TOKENS")
+        .and_then(|max_str| {
+            max_str.parse::<u64>().ok().or_else(|| {
+                warn!(
+                    var = "ENFORCE_MAX_TOKENS",
+                    value = %max_str,
+                    default = ?DEFAULT_ENFORCE_MAX_TOKENS,
+                    "Failed to parse numeric environment variable, using default"
+                );
+                None
+            })
+        })
+        .or(DEFAULT_ENFORCE_MAX_TOKENS);
+
+    // Parse WARN_ON_STREAM_MISMATCH with error handling for non-boolean values
+    let warn_on_stream_mismatch = parse_bool_env(
+        vars,
+        "WARN_ON_STREAM_MISMATCH",
+        DEFAULT_WARN_ON_STREAM_MISMATCH,
+    );
+
+    // Parse SHUTDOWN_STREAM_GRACE_SECS with error handling
+    let shutdown_stream_grace_secs = env_value(vars, "SHUTDOWN_STREAM_GRACE_SECS")
+        .and_then(|secs_str| {
+            secs_str.parse::<u64>().ok().or_else(|| {
+                warn!(
+                    var = "SHUTDOWN_STREAM_GRACE_SECS",
+                    value = %secs_str,
+                    default = DEFAULT_SHUTDOWN_STREAM_GRACE_SECS,
+                    "Failed to parse numeric environment variable, using default"
+                );
+                None
+            })
+        })
+        .unwrap_or(DEFAULT_SHUTDOWN_STREAM_GRACE_SECS);
+
+    // Parse LOG_BUILD_INFO with error handling for non-boolean values
+    let log_build_info = parse_bool_env(vars, "LOG_BUILD_INFO", DEFAULT_LOG_BUILD_INFO);
+
+    // Parse ALLOW_CLIENT_LOG_OPTOUT with error handling for non-boolean values
+    let allow_client_log_optout = parse_bool_env(
+        vars,
+        "ALLOW_CLIENT_LOG_OPTOUT",
+        DEFAULT_ALLOW_CLIENT_LOG_OPTOUT,
+    );
+
+    // Parse LOG_USAGE_ON_CLEANUP with error handling for non-boolean values
+    let log_usage_on_cleanup =
+        parse_bool_env(vars, "LOG_USAGE_ON_CLEANUP", DEFAULT_LOG_USAGE_ON_CLEANUP);
+
+    // Parse ADD_FORWARDED_HEADERS with error handling for non-boolean values
+    let add_forwarded_headers =
+        parse_bool_env(vars, "ADD_FORWARDED_HEADERS", DEFAULT_ADD_FORWARDED_HEADERS);
+
+    // Parse MAINTENANCE_MODE with error handling for non-boolean values
+    let maintenance_mode = parse_bool_env(vars, "MAINTENANCE_MODE", DEFAULT_MAINTENANCE_MODE);
+
+    // Parse MAINTENANCE_RETRY_AFTER_SECS with error handling for non-numeric values
+    let maintenance_retry_after_secs = env_value(vars, "MAINTENANCE_RETRY_AFTER_SECS")
+        .and_then(|secs_str| {
+            secs_str.parse::<u64>().ok().or_else(|| {
+                warn!(
+                    var = "MAINTENANCE_RETRY_AFTER_SECS",
+                    value = %secs_str,
+                    default = DEFAULT_MAINTENANCE_RETRY_AFTER_SECS,
+                    "Failed to parse numeric environment variable, using default"
+                );
+                None
+            })
+        })
+        .unwrap_or(DEFAULT_MAINTENANCE_RETRY_AFTER_SECS);
+
+    // Parse LOG_BODY_READ_TIMING with error handling for non-boolean values
+    let log_body_read_timing =
+        parse_bool_env(vars, "LOG_BODY_READ_TIMING", DEFAULT_LOG_BODY_READ_TIMING);
+
+    // Parse LOG_COLOR, keeping the default for unrecognized values
+    let log_color = env_value(vars, "LOG_COLOR")
+        .map(|mode| match mode.to_lowercase().as_str() {
+            "auto" => ColorMode::Auto,
+            "always" => ColorMode::Always,
+            "never" => ColorMode::Never,
+            _ => {
+                warn!(
+                    var = "LOG_COLOR",
+                    value = %mode,
+                    default = ?ColorMode::default(),
+                    "Unrecognized log color mode, using default"
+                );
+                ColorMode::default()
+            }
+        })
+        .unwrap_or_default();
+
+    // Parse REQUEST_ID_FORMAT, keeping the default for unrecognized values
+    let request_id_format = env_value(vars, "REQUEST_ID_FORMAT")
+        .map(|format| match format.to_lowercase().as_str() {
+            "uuid" => RequestIdFormat::Uuid,
+            "short" => RequestIdFormat::Short,
+            _ => {
+                warn!(
+                    var = "REQUEST_ID_FORMAT",
+                    value = %format,
+                    default = ?RequestIdFormat::default(),
+                    "Unrecognized request ID format, using default"
+                );
+                RequestIdFormat::default()
+            }
+        })
+        .unwrap_or_default();
+
+    // Parse FORWARD_PARTIAL_BODIES with error handling for non-boolean values
+    let forward_partial_bodies = parse_bool_env(
+        vars,
+        "FORWARD_PARTIAL_BODIES",
+        DEFAULT_FORWARD_PARTIAL_BODIES,
+    );
+
+    // Parse TCP_NODELAY with error handling for non-boolean values
+    let tcp_nodelay = parse_bool_env(vars, "TCP_NODELAY", DEFAULT_TCP_NODELAY);
+
+    // Parse LISTEN_BACKLOG with error handling for non-numeric values
+    let listen_backlog = env_value(vars, "LISTEN_BACKLOG")
+        .and_then(|backlog_str| {
+            backlog_str.parse::<u32>().ok().or_else(|| {
+                warn!(
+                    var = "LISTEN_BACKLOG",
+                    value = %backlog_str,
+                    default = ?DEFAULT_LISTEN_BACKLOG,
+                    "Failed to parse numeric environment variable, using default"
+                );
+                None
+            })
+        })
+        .or(DEFAULT_LISTEN_BACKLOG);
+
+    // Treat an empty LOG_FILE_PREFIX the same as unset so files aren't named "-2024-01-02.log"
+    let log_file_prefix = env_value(vars, "LOG_FILE_PREFIX")
+        .map(|prefix| prefix.trim().to_string())
+        .filter(|prefix| !prefix.is_empty())
+        .or_else(|| DEFAULT_LOG_FILE_PREFIX.map(String::from));
+
+    // Parse LOG_FILE_DATE_IN_NAME with error handling for non-boolean values
+    let log_file_date_in_name =
+        parse_bool_env(vars, "LOG_FILE_DATE_IN_NAME", DEFAULT_LOG_FILE_DATE_IN_NAME);
+
+    // Parse FORCE_CHUNKED_ON_REWRITE with error handling for non-boolean values
+    let force_chunked_on_rewrite = parse_bool_env(
+        vars,
+        "FORCE_CHUNKED_ON_REWRITE",
+        DEFAULT_FORCE_CHUNKED_ON_REWRITE,
+    );
+
+    // Parse ALLOW_WEBSOCKET with error handling for non-boolean values
+    let allow_websocket = parse_bool_env(vars, "ALLOW_WEBSOCKET", DEFAULT_ALLOW_WEBSOCKET);
+
+    // Parse SANITIZE_LOG_OUTPUT with error handling for non-boolean values
+    let sanitize_log_output =
+        parse_bool_env(vars, "SANITIZE_LOG_OUTPUT", DEFAULT_SANITIZE_LOG_OUTPUT);
+
+    // Parse LOG_RATE_LIMIT_EVENTS with error handling for non-boolean values
+    let log_rate_limit_events =
+        parse_bool_env(vars, "LOG_RATE_LIMIT_EVENTS", DEFAULT_LOG_RATE_LIMIT_EVENTS);
+
+    // Treat an empty LOG_BASE_DIR the same as unset so logs don't land in the working directory
+    let log_base_dir = env_value(vars, "LOG_BASE_DIR")
+        .map(|dir| dir.trim().to_string())
+        .filter(|dir| !dir.is_empty())
+        .or_else(|| DEFAULT_LOG_BASE_DIR.map(String::from));
+
+    // Parse ROUTE_API_KEYS as comma-separated prefix=VARIABLE pairs
+    let route_api_keys = env_value(vars, "ROUTE_API_KEYS")
+        .map(|routes| parse_route_api_keys(&routes))
+        .unwrap_or_else(default_route_api_keys);
+    let route_api_key_values = route_api_keys
+        .iter()
+        .filter_map(|(_, var)| env_value(vars, var).map(|key| (var.clone(), key)))
+        .collect();
+
+    // Parse REQUIRE_HEADERS; `from_env_map` rejects invalid names before they get here
+    let require_headers = env_value(vars, "REQUIRE_HEADERS")
+        .and_then(|names| parse_header_names("REQUIRE_HEADERS", &names).ok())
+        .unwrap_or_else(default_require_headers);
+
+    // Parse CORS_ENABLED with error handling for non-boolean values
+    let cors_enabled = parse_bool_env(vars, "CORS_ENABLED", DEFAULT_CORS_ENABLED);
+
+    // Parse CORS_ALLOW_ORIGINS as a comma-separated list, ignoring blank entries
+    let cors_allow_origins = env_value(vars, "CORS_ALLOW_ORIGINS")
+        .map(|origins| {
+            origins
+                .split(',')
+                .map(str::trim)
+                .filter(|origin| !origin.is_empty())
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_else(default_cors_allow_origins);
+
+    // Parse CORS_ALLOW_HEADERS; `from_env_map` rejects invalid names before they get here
+    let cors_allow_headers = env_value(vars, "CORS_ALLOW_HEADERS")
+        .and_then(|names| parse_header_names("CORS_ALLOW_HEADERS", &names).ok())
+        .unwrap_or_else(default_cors_allow_headers);
+
+    // Parse CORS_ALLOW_METHODS as a comma-separated list of methods, ignoring blank entries
+    let cors_allow_methods = env_value(vars, "CORS_ALLOW_METHODS")
+        .map(|methods| parse_methods("CORS_ALLOW_METHODS", &methods))
+        .unwrap_or_else(default_cors_allow_methods);
+
+    // Parse UPSTREAM_RETRIES with error handling
+    let upstream_retries = env_value(vars, "UPSTREAM_RETRIES")
+        .and_then(|retries_str| {
+            retries_str.parse::<u32>().ok().or_else(|| {
+                warn!(
+                    var = "UPSTREAM_RETRIES",
+                    value = %retries_str,
+                    default = DEFAULT_UPSTREAM_RETRIES,
+                    "Failed to parse numeric environment variable, using default"
+                );
+                None
+            })
+        })
+        .unwrap_or(DEFAULT_UPSTREAM_RETRIES);
+
+    // Parse RETRYABLE_STATUSES; `from_env_map` rejects invalid lists before they get here
+    let retryable_statuses = env_value(vars, "RETRYABLE_STATUSES")
+        .and_then(|statuses| parse_status_codes("RETRYABLE_STATUSES", &statuses).ok())
+        .unwrap_or_else(|| DEFAULT_RETRYABLE_STATUSES.to_vec());
+
+    // Parse WARN_ON_CONTENT_TYPE_ANOMALY with error handling for non-boolean values
+    let warn_on_content_type_anomaly = parse_bool_env(
+        vars,
+        "WARN_ON_CONTENT_TYPE_ANOMALY",
+        DEFAULT_WARN_ON_CONTENT_TYPE_ANOMALY,
+    );
+
+    // Parse LOG_EMPTY_BODIES with error handling for non-boolean values
+    let log_empty_bodies = parse_bool_env(vars, "LOG_EMPTY_BODIES", DEFAULT_LOG_EMPTY_BODIES);
+
+    // Parse INJECT_REQUEST_ID_IN_ERRORS with error handling for non-boolean values
+    let inject_request_id_in_errors = parse_bool_env(
+        vars,
+        "INJECT_REQUEST_ID_IN_ERRORS",
+        DEFAULT_INJECT_REQUEST_ID_IN_ERRORS,
+    );
+
+    // Parse WARN_ON_SSE_TRAILING_DATA with error handling for non-boolean values
+    let warn_on_sse_trailing_data = parse_bool_env(
+        vars,
+        "WARN_ON_SSE_TRAILING_DATA",
+        DEFAULT_WARN_ON_SSE_TRAILING_DATA,
+    );
+
+    // Parse REQUEST_DEADLINE_MS with error handling
+    let request_deadline_ms = env_value(vars, "REQUEST_DEADLINE_MS")
+        .and_then(|ms_str| {
+            ms_str.parse::<u64>().ok().or_else(|| {
+                warn!(
+                    var = "REQUEST_DEADLINE_MS",
+                    value = %ms_str,
+                    default = ?DEFAULT_REQUEST_DEADLINE_MS,
+                    "Failed to parse numeric environment variable, using default"
+                );
+                None
+            })
+        })
+        .or(DEFAULT_REQUEST_DEADLINE_MS);
+
+    // Parse UPSTREAM_TIMEOUT_HEADER; `from_env_map` rejects invalid names before they get here
+    let upstream_timeout_header = env_value(vars, "UPSTREAM_TIMEOUT_HEADER")
+        .and_then(|name| parse_header_name("UPSTREAM_TIMEOUT_HEADER", &name).ok())
+        .or_else(|| DEFAULT_UPSTREAM_TIMEOUT_HEADER.map(String::from));
+
+    // Parse MAX_PATH_LENGTH with error handling
+    let max_path_length = env_value(vars, "MAX_PATH_LENGTH")
+        .and_then(|length_str| {
+            length_str.parse::<usize>().ok().or_else(|| {
+                warn!(
+                    var = "MAX_PATH_LENGTH",
+                    value = %length_str,
+                    default = ?DEFAULT_MAX_PATH_LENGTH,
+                    "Failed to parse numeric environment variable, using default"
+                );
+                None
+            })
+        })
+        .or(DEFAULT_MAX_PATH_LENGTH);
+
+    // Parse ADMIN_ENABLED, enabling admin endpoints by default exactly when a token is set
+    let admin_enabled = parse_bool_env(
+        vars,
+        "ADMIN_ENABLED",
+        DEFAULT_ADMIN_ENABLED.unwrap_or(admin_token.is_some()),
+    );
+
+    // Parse MAP_UPSTREAM_5XX_TO as a status code, keeping the default for invalid values
+    let map_upstream_5xx_to = env_value(vars, "MAP_UPSTREAM_5XX_TO")
+        .and_then(|status_str| {
+            status_str
+                .parse::<u16>()
+                .ok()
+                .filter(|status| (100..=599).contains(status))
+                .or_else(|| {
+                    warn!(
+                        var = "MAP_UPSTREAM_5XX_TO",
+                        value = %status_str,
+                        default = ?DEFAULT_MAP_UPSTREAM_5XX_TO,
+                        "Invalid HTTP status code, using default"
+                    );
+                    None
+                })
+        })
+        .or(DEFAULT_MAP_UPSTREAM_5XX_TO);
+
+    // Parse RESPONSE_BUFFER_LIMIT_BYTES with error handling
+    let response_buffer_limit_bytes = env_value(vars, "RESPONSE_BUFFER_LIMIT_BYTES")
+        .and_then(|limit_str| {
+            limit_str.parse::<usize>().ok().or_else(|| {
+                warn!(
+                    var = "RESPONSE_BUFFER_LIMIT_BYTES",
+                    value = %limit_str,
+                    default = ?DEFAULT_RESPONSE_BUFFER_LIMIT_BYTES,
+                    "Failed to parse numeric environment variable, using default"
+                );
+                None
+            })
+        })
+        .or(DEFAULT_RESPONSE_BUFFER_LIMIT_BYTES);
+
+    // Parse LOG_UPSTREAM_REQUEST_ID
+    let log_upstream_request_id = parse_bool_env(
+        vars,
+        "LOG_UPSTREAM_REQUEST_ID",
+        DEFAULT_LOG_UPSTREAM_REQUEST_ID,
+    );
+
+    // Parse ORDER_FILE_LOGS
+    let order_file_logs = parse_bool_env(vars, "ORDER_FILE_LOGS", DEFAULT_ORDER_FILE_LOGS);
+
+    // Parse ALLOW_CLIENT_LOG_LEVEL with error handling for non-boolean values
+    let allow_client_log_level = parse_bool_env(
+        vars,
+        "ALLOW_CLIENT_LOG_LEVEL",
+        DEFAULT_ALLOW_CLIENT_LOG_LEVEL,
+    );
+
+    Config {
+        port,
+        anthropic_api_key,
+        anthropic_target_url,
+        log_stdout_level,
+        log_format,
+        log_bodies,
+        log_file_path,
+        log_file_level,
+        log_max_body_size,
+        log_directory_mode,
+        log_max_age_days,
+        deployment_env,
+        max_total_buffered_bytes,
+        server_timing,
+        admin_token,
+        log_body_schema_only,
+        log_json_indent,
+        tls_cert_path,
+        tls_key_path,
+        redact_body_fields,
+        redact_query_params,
+        dedupe_repeated_logs,
+        generate_traceparent,
+        empty_post_body,
+        auth_mode,
+        max_concurrent_requests,
+        queue_timeout_ms,
+        log_timestamp_format,
+        error_log_to_stderr,
+        max_stream_duration_secs,
+        log_resolved_ip,
+        bind_retry_attempts,
+        bind_retry_delay_ms,
+        model_rate_limits,
+        stream_request_body,
+        log_request_sequence,
+        log_key_fingerprint,
+        response_cache_ttl_secs,
+        stdout_max_field_len,
+        normalize_path,
+        warn_on_deprecation,
+        warmup_on_start,
+        heartbeat_interval_secs,
+        upstream_host_allowlist,
+        log_socket_path,
+        max_concurrent_streams,
+        dry_run_proxy,
+        max_span_fields,
+        enforce_max_tokens,
+        warn_on_stream_mismatch,
+        shutdown_stream_grace_secs,
+        log_build_info,
+        allow_client_log_optout,
+        log_usage_on_cleanup,
+        add_forwarded_headers,
+        maintenance_mode,
+        maintenance_retry_after_secs,
+        log_body_read_timing,
+        log_color,
+        request_id_format,
+        forward_partial_bodies,
+        tcp_nodelay,
+        listen_backlog,
+        log_file_prefix,
+        log_file_date_in_name,
+        force_chunked_on_rewrite,
+        allow_websocket,
+        sanitize_log_output,
+        log_rate_limit_events,
+        log_base_dir,
+        route_api_keys,
+        route_api_key_values,
+        require_headers,
+        cors_enabled,
+        cors_allow_origins,
+        cors_allow_headers,
+        cors_allow_methods,
+        upstream_retries,
+        retryable_statuses,
+        warn_on_content_type_anomaly,
+        log_empty_bodies,
+        inject_request_id_in_errors,
+        warn_on_sse_trailing_data,
+        request_deadline_ms,
+        upstream_timeout_header,
+        max_path_length,
+        admin_enabled,
+        map_upstream_5xx_to,
+        response_buffer_limit_bytes,
+        log_upstream_request_id,
+        order_file_logs,
+        allow_client_log_level,
+    }
+}
+
 /// Load application configuration from environment variables
 ///
 /// This function will:
@@ -215,109 +2583,10 @@ pub fn load_config() -> &'static Config {
         dotenvy::dotenv().ok();
         info!("Loading configuration from environment...");
 
-        // Load configuration values with sensible defaults
-        let port = env::var("PORT").unwrap_or_else(|_| DEFAULT_PORT.to_string());
-
-        // API key is mandatory
-        let anthropic_api_key =
-            env::var("ANTHROPIC_API_KEY").expect("ANTHROPIC_API_KEY must be set for forwarding");
-
-        let anthropic_target_url = env::var("ANTHROPIC_TARGET_URL")
-            .unwrap_or_else(|_| DEFAULT_ANTHROPIC_TARGET_URL.to_string());
-
-        let log_stdout_level =
-            env::var("LOG_LEVEL").unwrap_or_else(|_| DEFAULT_LOG_STDOUT_LEVEL.to_string());
-        let log_format = env::var("LOG_FORMAT").unwrap_or_else(|_| DEFAULT_LOG_FORMAT.to_string());
-
-        // Parse LOG_BODIES with error handling for non-boolean values
-        let log_bodies = match env::var("LOG_BODIES") {
-            Ok(value) => {
-                // Check if it's a valid boolean representation
-                if value.to_lowercase() == "true"
-                    || value.to_lowercase() == "false"
-                    || value == "0"
-                    || value == "1"
-                {
-                    // Only consider "false" and "0" as false values (maintain existing behavior)
-                    value.to_lowercase() != "false" && value != "0"
-                } else {
-                    // Non-standard boolean value, log a warning
-                    warn!(
-                        var = "LOG_BODIES",
-                        value = %value,
-                        default = DEFAULT_LOG_BODIES,
-                        "Ambiguous boolean value in environment variable, using default"
-                    );
-                    DEFAULT_LOG_BODIES
-                }
-            }
-            Err(_) => DEFAULT_LOG_BODIES, // Use default if not set
-        };
-
-        // Load file logging configuration
-        let log_file_path =
-            env::var("LOG_FILE_PATH").unwrap_or_else(|_| DEFAULT_LOG_FILE_PATH.to_string());
-        let log_file_level =
-            env::var("LOG_FILE_LEVEL").unwrap_or_else(|_| DEFAULT_LOG_FILE_LEVEL.to_string());
-
-        // Parse LOG_MAX_BODY_SIZE with error handling
-        let log_max_body_size = env::var("LOG_MAX_BODY_SIZE")
-            .ok()
-            .and_then(|size_str| {
-                size_str.parse::<usize>().ok().or_else(|| {
-                    warn!(
-                        var = "LOG_MAX_BODY_SIZE",
-                        value = %size_str,
-                        default = DEFAULT_LOG_MAX_BODY_SIZE,
-                        "Failed to parse numeric environment variable, using default"
-                    );
-                    None
-                })
-            })
-            .unwrap_or(DEFAULT_LOG_MAX_BODY_SIZE); // Default if not set or invalid
-
-        // Parse LOG_DIRECTORY_MODE environment variable
-        let log_directory_mode = env::var("LOG_DIRECTORY_MODE")
-            .map(|mode| match mode.to_lowercase().as_str() {
-                "xdg" => LogDirectoryMode::Xdg,
-                "system" => LogDirectoryMode::System,
-                _ => LogDirectoryMode::Default,
-            })
-            .unwrap_or(LogDirectoryMode::Default);
-
-        // Parse LOG_MAX_AGE_DAYS with error handling
-        let log_max_age_days = env::var("LOG_MAX_AGE_DAYS").ok().and_then(|days_str| {
-            days_str.parse::<u32>().ok().or_else(|| {
-                // Format default value for human-readable log message
-                let default_display = match DEFAULT_LOG_MAX_AGE_DAYS {
-                    Some(days) => days.to_string(),
-                    None => "no cleanup".to_string(),
-                };
-
-                warn!(
-                    var = "LOG_MAX_AGE_DAYS",
-                    value = %days_str,
-                    default = ?DEFAULT_LOG_MAX_AGE_DAYS,
-                    default_display = %default_display,
-                    "Failed to parse numeric environment variable, using default"
-                );
-                None
-            })
-        });
-
-        let loaded_config = Config {
-            port,
-            anthropic_api_key,
-            anthropic_target_url,
-            log_stdout_level,
-            log_format,
-            log_bodies,
-            log_file_path,
-            log_file_level,
-            log_max_body_size,
-            log_directory_mode,
-            log_max_age_days,
-        };
+        // Invalid settings are fatal, as is a missing API key when AUTH_MODE is inject
+        // (passthrough forwards the client's credentials and needs no key)
+        let loaded_config =
+            Config::from_env_map(&process_env()).unwrap_or_else(|e| panic!("{}", e));
 
         // Log configuration values, but omit the API key for security
         info!(
@@ -331,6 +2600,86 @@ pub fn load_config() -> &'static Config {
             log_max_body_size = loaded_config.log_max_body_size,
             log_directory_mode = ?loaded_config.log_directory_mode,
             log_max_age_days = ?loaded_config.log_max_age_days,
+            deployment_env = ?loaded_config.deployment_env,
+            max_total_buffered_bytes = ?loaded_config.max_total_buffered_bytes,
+            server_timing = loaded_config.server_timing,
+            admin_token_set = loaded_config.admin_token.is_some(),
+            log_body_schema_only = loaded_config.log_body_schema_only,
+            log_json_indent = loaded_config.log_json_indent,
+            tls_cert_path = ?loaded_config.tls_cert_path,
+            tls_key_path = ?loaded_config.tls_key_path,
+            redact_body_fields = ?loaded_config.redact_body_fields,
+            redact_query_params = ?loaded_config.redact_query_params,
+            dedupe_repeated_logs = loaded_config.dedupe_repeated_logs,
+            generate_traceparent = loaded_config.generate_traceparent,
+            empty_post_body = ?loaded_config.empty_post_body,
+            auth_mode = ?loaded_config.auth_mode,
+            max_concurrent_requests = ?loaded_config.max_concurrent_requests,
+            queue_timeout_ms = ?loaded_config.queue_timeout_ms,
+            log_timestamp_format = ?loaded_config.log_timestamp_format,
+            error_log_to_stderr = loaded_config.error_log_to_stderr,
+            max_stream_duration_secs = ?loaded_config.max_stream_duration_secs,
+            log_resolved_ip = loaded_config.log_resolved_ip,
+            bind_retry_attempts = loaded_config.bind_retry_attempts,
+            bind_retry_delay_ms = loaded_config.bind_retry_delay_ms,
+            model_rate_limits = ?loaded_config.model_rate_limits,
+            stream_request_body = loaded_config.stream_request_body,
+            log_request_sequence = loaded_config.log_request_sequence,
+            log_key_fingerprint = loaded_config.log_key_fingerprint,
+            response_cache_ttl_secs = ?loaded_config.response_cache_ttl_secs,
+            stdout_max_field_len = ?loaded_config.stdout_max_field_len,
+            normalize_path = loaded_config.normalize_path,
+            warn_on_deprecation = loaded_config.warn_on_deprecation,
+            warmup_on_start = loaded_config.warmup_on_start,
+            heartbeat_interval_secs = ?loaded_config.heartbeat_interval_secs,
+            upstream_host_allowlist = ?loaded_config.upstream_host_allowlist,
+            log_socket_path = ?loaded_config.log_socket_path,
+            max_concurrent_streams = ?loaded_config.max_concurrent_streams,
+            dry_run_proxy = loaded_config.dry_run_proxy,
+            max_span_fields = loaded_config.max_span_fields,
+            enforce_max_tokens = ?loaded_config.enforce_max_tokens,
+            warn_on_stream_mismatch = loaded_config.warn_on_stream_mismatch,
+            shutdown_stream_grace_secs = loaded_config.shutdown_stream_grace_secs,
+            log_build_info = loaded_config.log_build_info,
+            allow_client_log_optout = loaded_config.allow_client_log_optout,
+            log_usage_on_cleanup = loaded_config.log_usage_on_cleanup,
+            add_forwarded_headers = loaded_config.add_forwarded_headers,
+            maintenance_mode = loaded_config.maintenance_mode,
+            maintenance_retry_after_secs = loaded_config.maintenance_retry_after_secs,
+            log_body_read_timing = loaded_config.log_body_read_timing,
+            log_color = ?loaded_config.log_color,
+            request_id_format = ?loaded_config.request_id_format,
+            forward_partial_bodies = loaded_config.forward_partial_bodies,
+            tcp_nodelay = loaded_config.tcp_nodelay,
+            listen_backlog = ?loaded_config.listen_backlog,
+            log_file_prefix = ?loaded_config.log_file_prefix,
+            log_file_date_in_name = loaded_config.log_file_date_in_name,
+            force_chunked_on_rewrite = loaded_config.force_chunked_on_rewrite,
+            allow_websocket = loaded_config.allow_websocket,
+            sanitize_log_output = loaded_config.sanitize_log_output,
+            log_rate_limit_events = loaded_config.log_rate_limit_events,
+            log_base_dir = ?loaded_config.log_base_dir,
+            route_api_keys = ?loaded_config.route_api_keys,
+            require_headers = ?loaded_config.require_headers,
+            cors_enabled = loaded_config.cors_enabled,
+            cors_allow_origins = ?loaded_config.cors_allow_origins,
+            cors_allow_headers = ?loaded_config.cors_allow_headers,
+            cors_allow_methods = ?loaded_config.cors_allow_methods,
+            upstream_retries = loaded_config.upstream_retries,
+            retryable_statuses = ?loaded_config.retryable_statuses,
+            warn_on_content_type_anomaly = loaded_config.warn_on_content_type_anomaly,
+            log_empty_bodies = loaded_config.log_empty_bodies,
+            inject_request_id_in_errors = loaded_config.inject_request_id_in_errors,
+            warn_on_sse_trailing_data = loaded_config.warn_on_sse_trailing_data,
+            request_deadline_ms = ?loaded_config.request_deadline_ms,
+            upstream_timeout_header = ?loaded_config.upstream_timeout_header,
+            max_path_length = ?loaded_config.max_path_length,
+            admin_enabled = loaded_config.admin_enabled,
+            map_upstream_5xx_to = ?loaded_config.map_upstream_5xx_to,
+            response_buffer_limit_bytes = ?loaded_config.response_buffer_limit_bytes,
+            log_upstream_request_id = loaded_config.log_upstream_request_id,
+            order_file_logs = loaded_config.order_file_logs,
+            allow_client_log_level = loaded_config.allow_client_log_level,
             "Configuration loaded"
         );
 
@@ -338,132 +2687,616 @@ pub fn load_config() -> &'static Config {
     })
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::collections::HashMap;
-    use std::env;
-    use std::sync::Mutex;
-    use std::sync::Once;
-
-    // Use a mutex to ensure environment variable tests don't interfere with each other
-    static ENV_MUTEX: Mutex<()> = Mutex::new(());
-    static INIT: Once = Once::new();
-
-    // Initialize test environment exactly once
-    fn initialize() {
-        INIT.call_once(|| {
-            // Initialize test environment here
-        });
-    }
-
-    // A function to create a test config with specific environment variables
-    fn create_test_config_with_env(env_vars: HashMap<&str, &str>) -> Config {
-        // Ensure synchronization across tests
-        let _lock = ENV_MUTEX.lock().unwrap();
-        initialize();
-
-        // Save current environment
-        let mut old_vars = HashMap::new();
-        for (key, _) in env_vars.iter() {
-            old_vars.insert(*key, env::var(*key).ok());
-        }
-
-        // Set provided environment variables
-        for (key, value) in env_vars.iter() {
-            // Only set non-empty environment variables
-            if !value.is_empty() {
-                env::set_var(key, value);
-            } else {
-                env::remove_var(key);
-            }
-        }
-
-        // Create the config (similar to create_test_config but cleaner)
-        let port = env::var("PORT").unwrap_or_else(|_| DEFAULT_PORT.to_string());
-        let anthropic_api_key =
-            env::var("ANTHROPIC_API_KEY").unwrap_or_else(|_| "test-api-key".to_string());
-        let anthropic_target_url = env::var("ANTHROPIC_TARGET_URL")
-            .unwrap_or_else(|_| DEFAULT_ANTHROPIC_TARGET_URL.to_string());
-        let log_stdout_level =
-            env::var("LOG_LEVEL").unwrap_or_else(|_| DEFAULT_LOG_STDOUT_LEVEL.to_string());
-        let log_format = env::var("LOG_FORMAT").unwrap_or_else(|_| DEFAULT_LOG_FORMAT.to_string());
-        let log_bodies = env::var("LOG_BODIES")
-            .map(|v| v.to_lowercase() != "false" && v != "0")
-            .unwrap_or(DEFAULT_LOG_BODIES);
-        let log_file_path =
-            env::var("LOG_FILE_PATH").unwrap_or_else(|_| DEFAULT_LOG_FILE_PATH.to_string());
-        let log_file_level =
-            env::var("LOG_FILE_LEVEL").unwrap_or_else(|_| DEFAULT_LOG_FILE_LEVEL.to_string());
-        let log_max_body_size = env::var("LOG_MAX_BODY_SIZE")
-            .ok()
-            .and_then(|size_str| {
-                size_str.parse::<usize>().ok().or_else(|| {
-                    eprintln!(
-                        "Failed to parse LOG_MAX_BODY_SIZE: '{}', using default {}",
-                        size_str, DEFAULT_LOG_MAX_BODY_SIZE
-                    );
-                    None
-                })
-            })
-            .unwrap_or(DEFAULT_LOG_MAX_BODY_SIZE);
-
-        // Parse LOG_DIRECTORY_MODE
-        let log_directory_mode = env::var("LOG_DIRECTORY_MODE")
-            .map(|mode| match mode.to_lowercase().as_str() {
-                "xdg" => LogDirectoryMode::Xdg,
-                "system" => LogDirectoryMode::System,
-                _ => LogDirectoryMode::Default,
-            })
-            .unwrap_or(LogDirectoryMode::Default);
-
-        let config = Config {
-            port,
-            anthropic_api_key,
-            anthropic_target_url,
-            log_stdout_level,
-            log_format,
-            log_bodies,
-            log_file_path,
-            log_file_level,
-            log_max_body_size,
-            log_directory_mode,
-            log_max_age_days: None,
-        };
+/// Documentation for one supported environment variable
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnvVarDoc {
+    /// Name of the environment variable
+    pub name: &'static str,
+    /// One-line description of what the variable controls
+    pub description: &'static str,
+    /// Default value, rendered as it would be written in `.env` (None = unset)
+    pub default: Option<String>,
+    /// Whether startup fails when the variable is missing
+    pub required: bool,
+}
 
-        // Restore old environment
-        for (key, value_opt) in old_vars {
-            match value_opt {
-                Some(value) => env::set_var(key, value),
-                None => env::remove_var(key),
-            }
+/// Lists every supported environment variable with its default and description
+///
+/// Defaults are taken from the `DEFAULT_*` constants so the list stays in sync
+/// with what `load_config` actually uses.
+pub fn env_var_docs() -> Vec<EnvVarDoc> {
+    fn doc(name: &'static str, description: &'static str, default: Option<String>) -> EnvVarDoc {
+        EnvVarDoc {
+            name,
+            description,
+            default,
+            required: false,
         }
-
-        config
     }
 
-    #[test]
-    fn test_default_values() {
-        // For default values, we just need the API key (required) and all others empty/unset
-        let mut env_vars = HashMap::new();
-        env_vars.insert("ANTHROPIC_API_KEY", "test-api-key");
+    let redact_body_fields = DEFAULT_REDACT_BODY_FIELDS.join(",");
+    let redact_query_params = DEFAULT_REDACT_QUERY_PARAMS.join(",");
+    let model_rate_limits = DEFAULT_MODEL_RATE_LIMITS
+        .iter()
+        .map(|(model, limit)| format!("{}={}", model, limit))
+        .collect::<Vec<_>>()
+        .join(",");
 
-        // These variables should be unset for default tests, not empty strings
-        let vars_to_unset = vec![
+    vec![
+        doc(
             "PORT",
+            "HTTP port to listen on",
+            Some(DEFAULT_PORT.to_string()),
+        ),
+        EnvVarDoc {
+            name: "ANTHROPIC_API_KEY",
+            description: "API key sent upstream as x-api-key",
+            default: None,
+            required: true,
+        },
+        doc(
             "ANTHROPIC_TARGET_URL",
+            "Upstream Anthropic API endpoint",
+            Some(DEFAULT_ANTHROPIC_TARGET_URL.to_string()),
+        ),
+        doc(
             "LOG_LEVEL",
+            "Minimum log level for stdout (trace, debug, info, warn, error)",
+            Some(DEFAULT_LOG_STDOUT_LEVEL.to_string()),
+        ),
+        doc(
             "LOG_FORMAT",
+            "Stdout log format (pretty or json)",
+            Some(DEFAULT_LOG_FORMAT.to_string()),
+        ),
+        doc(
             "LOG_BODIES",
+            "Log request and response bodies",
+            Some(DEFAULT_LOG_BODIES.to_string()),
+        ),
+        doc(
             "LOG_FILE_PATH",
+            "Log file name (placed in the environment's log directory)",
+            Some(DEFAULT_LOG_FILE_PATH.to_string()),
+        ),
+        doc(
             "LOG_FILE_LEVEL",
+            "Minimum log level for the log file",
+            Some(DEFAULT_LOG_FILE_LEVEL.to_string()),
+        ),
+        doc(
             "LOG_MAX_BODY_SIZE",
-        ];
+            "Bodies larger than this many bytes only have their size logged",
+            Some(DEFAULT_LOG_MAX_BODY_SIZE.to_string()),
+        ),
+        doc(
+            "LOG_DIRECTORY_MODE",
+            "How the log directory is chosen (default, xdg, system)",
+            Some(serialized_name(LogDirectoryMode::default())),
+        ),
+        doc(
+            "LOG_MAX_AGE_DAYS",
+            "Delete log files older than this many days (unset = keep forever)",
+            DEFAULT_LOG_MAX_AGE_DAYS.map(|days| days.to_string()),
+        ),
+        doc(
+            "DEPLOYMENT_ENV",
+            "Environment name attached to every log event",
+            DEFAULT_DEPLOYMENT_ENV.map(String::from),
+        ),
+        doc(
+            "MAX_TOTAL_BUFFERED_BYTES",
+            "Cap on body bytes buffered across in-flight requests (unset = unlimited)",
+            DEFAULT_MAX_TOTAL_BUFFERED_BYTES.map(|bytes| bytes.to_string()),
+        ),
+        doc(
+            "SERVER_TIMING",
+            "Add a Server-Timing header reporting proxy overhead",
+            Some(DEFAULT_SERVER_TIMING.to_string()),
+        ),
+        doc(
+            "ADMIN_TOKEN",
+            "Bearer token for /admin/* endpoints (unset = admin endpoints disabled)",
+            DEFAULT_ADMIN_TOKEN.map(String::from),
+        ),
+        doc(
+            "LOG_BODY_SCHEMA_ONLY",
+            "Log only the top-level JSON keys of request bodies",
+            Some(DEFAULT_LOG_BODY_SCHEMA_ONLY.to_string()),
+        ),
+        doc(
+            "LOG_JSON_INDENT",
+            "Spaces per indent level of JSON bodies in logs (0 = single line)",
+            Some(DEFAULT_LOG_JSON_INDENT.to_string()),
+        ),
+        doc(
+            "TLS_CERT_PATH",
+            "PEM certificate chain for serving HTTPS (requires TLS_KEY_PATH)",
+            DEFAULT_TLS_CERT_PATH.map(String::from),
+        ),
+        doc(
+            "TLS_KEY_PATH",
+            "PEM private key matching TLS_CERT_PATH",
+            DEFAULT_TLS_KEY_PATH.map(String::from),
+        ),
+        doc(
+            "REDACT_BODY_FIELDS",
+            "Comma-separated dotted JSON paths redacted in logged bodies",
+            Some(redact_body_fields).filter(|fields| !fields.is_empty()),
+        ),
+        doc(
+            "REDACT_QUERY_PARAMS",
+            "Comma-separated query parameter names redacted in logged URLs",
+            Some(redact_query_params).filter(|params| !params.is_empty()),
+        ),
+        doc(
+            "DEDUPE_REPEATED_LOGS",
+            "Summarize identical consecutive log events instead of repeating them",
+            Some(DEFAULT_DEDUPE_REPEATED_LOGS.to_string()),
+        ),
+        doc(
+            "GENERATE_TRACEPARENT",
+            "Add a W3C traceparent to forwarded requests that lack one",
+            Some(DEFAULT_GENERATE_TRACEPARENT.to_string()),
+        ),
+        doc(
+            "EMPTY_POST_BODY",
+            "How empty POST bodies are forwarded (passthrough, empty_json)",
+            Some(serialized_name(EmptyBodyPolicy::default())),
+        ),
+        doc(
+            "AUTH_MODE",
+            "Who supplies the upstream API key (inject, passthrough)",
+            Some(serialized_name(AuthMode::default())),
+        ),
+        doc(
+            "MAX_CONCURRENT_REQUESTS",
+            "Cap on requests handled at once (unset = unlimited)",
+            DEFAULT_MAX_CONCURRENT_REQUESTS.map(|max| max.to_string()),
+        ),
+        doc(
+            "QUEUE_TIMEOUT_MS",
+            "How long a request waits for a free slot before a 503 (unset = no waiting)",
+            DEFAULT_QUEUE_TIMEOUT_MS.map(|ms| ms.to_string()),
+        ),
+        doc(
+            "LOG_TIMESTAMP_FORMAT",
+            "Timestamp format of log events (rfc3339, epoch_millis, epoch_secs)",
+            Some(serialized_name(TimestampFormat::default())),
+        ),
+        doc(
+            "ERROR_LOG_TO_STDERR",
+            "Write WARN/ERROR console output to stderr instead of stdout",
+            Some(DEFAULT_ERROR_LOG_TO_STDERR.to_string()),
+        ),
+        doc(
+            "MAX_STREAM_DURATION_SECS",
+            "Truncate streaming responses running longer than this (unset = unlimited)",
+            DEFAULT_MAX_STREAM_DURATION_SECS.map(|secs| secs.to_string()),
+        ),
+        doc(
+            "LOG_RESOLVED_IP",
+            "Record the upstream host's resolved IP on each request span",
+            Some(DEFAULT_LOG_RESOLVED_IP.to_string()),
+        ),
+        doc(
+            "BIND_RETRY_ATTEMPTS",
+            "Times to retry binding the port while it is still in use",
+            Some(DEFAULT_BIND_RETRY_ATTEMPTS.to_string()),
+        ),
+        doc(
+            "BIND_RETRY_DELAY_MS",
+            "Delay between bind retries in milliseconds",
+            Some(DEFAULT_BIND_RETRY_DELAY_MS.to_string()),
+        ),
+        doc(
+            "MODEL_RATE_LIMITS",
+            "Comma-separated model=requests_per_minute limits (unlisted models are unlimited)",
+            Some(model_rate_limits).filter(|limits| !limits.is_empty()),
+        ),
+        doc(
+            "STREAM_REQUEST_BODY",
+            "Stream request bodies upstream when no logging or body-based feature needs them",
+            Some(DEFAULT_STREAM_REQUEST_BODY.to_string()),
+        ),
+        doc(
+            "LOG_REQUEST_SEQUENCE",
+            "Record a per-process request sequence number on each request span",
+            Some(DEFAULT_LOG_REQUEST_SEQUENCE.to_string()),
+        ),
+        doc(
+            "LOG_KEY_FINGERPRINT",
+            "Record a short SHA-256 fingerprint of the API key on each request span",
+            Some(DEFAULT_LOG_KEY_FINGERPRINT.to_string()),
+        ),
+        doc(
+            "RESPONSE_CACHE_TTL_SECS",
+            "Cache responses to temperature-0, non-streaming /v1/messages requests for this long (unset = disabled)",
+            DEFAULT_RESPONSE_CACHE_TTL_SECS.map(|secs| secs.to_string()),
+        ),
+        doc(
+            "STDOUT_MAX_FIELD_LEN",
+            "Truncate field values longer than this many characters in pretty stdout logs (unset = unlimited)",
+            DEFAULT_STDOUT_MAX_FIELD_LEN.map(|len| len.to_string()),
+        ),
+        doc(
+            "NORMALIZE_PATH",
+            "Collapse consecutive slashes in the request path before forwarding",
+            Some(DEFAULT_NORMALIZE_PATH.to_string()),
+        ),
+        doc(
+            "WARN_ON_DEPRECATION",
+            "Log a warning when an upstream response carries a deprecation header",
+            Some(DEFAULT_WARN_ON_DEPRECATION.to_string()),
+        ),
+        doc(
+            "WARMUP_ON_START",
+            "Send one background request upstream after binding to prime the connection pool",
+            Some(DEFAULT_WARMUP_ON_START.to_string()),
+        ),
+        doc(
+            "HEARTBEAT_INTERVAL_SECS",
+            "Log a heartbeat with uptime and requests served this often (unset = disabled)",
+            DEFAULT_HEARTBEAT_INTERVAL_SECS.map(|secs| secs.to_string()),
+        ),
+        doc(
+            "UPSTREAM_HOST_ALLOWLIST",
+            "Comma-separated hosts requests may be forwarded to (unset = any host)",
+            DEFAULT_UPSTREAM_HOST_ALLOWLIST.map(|hosts| hosts.join(",")),
+        ),
+        doc(
+            "LOG_SOCKET_PATH",
+            "Unix stream socket to send JSON log lines to (Unix only; unset = disabled)",
+            DEFAULT_LOG_SOCKET_PATH.map(String::from),
+        ),
+        doc(
+            "MAX_CONCURRENT_STREAMS",
+            "Cap on streaming responses open at once (unset = unlimited)",
+            DEFAULT_MAX_CONCURRENT_STREAMS.map(|max| max.to_string()),
+        ),
+        doc(
+            "DRY_RUN_PROXY",
+            "Log requests but answer with a canned response instead of forwarding them",
+            Some(DEFAULT_DRY_RUN_PROXY.to_string()),
+        ),
+        doc(
+            "MAX_SPAN_FIELDS",
+            "Cap on dynamically keyed fields (headers, JSON keys) logged per event",
+            Some(DEFAULT_MAX_SPAN_FIELDS.to_string()),
+        ),
+        doc(
+            "ENFORCE_MAX_TOKENS",
+            "Clamp max_tokens of Messages requests to this ceiling (unset = not enforced)",
+            DEFAULT_ENFORCE_MAX_TOKENS.map(|max| max.to_string()),
+        ),
+        doc(
+            "WARN_ON_STREAM_MISMATCH",
+            "Warn when a response's streaming disagrees with the request's stream field",
+            Some(DEFAULT_WARN_ON_STREAM_MISMATCH.to_string()),
+        ),
+        doc(
+            "SHUTDOWN_STREAM_GRACE_SECS",
+            "Seconds open streams get to finish after a shutdown signal",
+            Some(DEFAULT_SHUTDOWN_STREAM_GRACE_SECS.to_string()),
+        ),
+        doc(
+            "LOG_BUILD_INFO",
+            "Tag every log event with the build's git commit",
+            Some(DEFAULT_LOG_BUILD_INFO.to_string()),
+        ),
+        doc(
+            "ALLOW_CLIENT_LOG_OPTOUT",
+            "Let clients skip detail logging with x-switchboard-no-log: true",
+            Some(DEFAULT_ALLOW_CLIENT_LOG_OPTOUT.to_string()),
+        ),
+        doc(
+            "LOG_USAGE_ON_CLEANUP",
+            "Log the log directories' disk usage after each cleanup run",
+            Some(DEFAULT_LOG_USAGE_ON_CLEANUP.to_string()),
+        ),
+        doc(
+            "ADD_FORWARDED_HEADERS",
+            "Add X-Forwarded-For/Host/Proto headers to forwarded requests",
+            Some(DEFAULT_ADD_FORWARDED_HEADERS.to_string()),
+        ),
+        doc(
+            "MAINTENANCE_MODE",
+            "Answer proxied requests with 503 maintenance instead of forwarding",
+            Some(DEFAULT_MAINTENANCE_MODE.to_string()),
+        ),
+        doc(
+            "MAINTENANCE_RETRY_AFTER_SECS",
+            "Retry-After seconds sent with maintenance responses",
+            Some(DEFAULT_MAINTENANCE_RETRY_AFTER_SECS.to_string()),
+        ),
+        doc(
+            "LOG_BODY_READ_TIMING",
+            "Record request body read time as request_body_read_ms",
+            Some(DEFAULT_LOG_BODY_READ_TIMING.to_string()),
+        ),
+        doc(
+            "LOG_COLOR",
+            "ANSI colors in pretty stdout logs (auto, always, never)",
+            Some(serialized_name(ColorMode::default())),
+        ),
+        doc(
+            "REQUEST_ID_FORMAT",
+            "Format of generated request IDs (uuid, short)",
+            Some(serialized_name(RequestIdFormat::default())),
+        ),
+        doc(
+            "FORWARD_PARTIAL_BODIES",
+            "Forward truncated upstream response bodies as received instead of a 502",
+            Some(DEFAULT_FORWARD_PARTIAL_BODIES.to_string()),
+        ),
+        doc(
+            "TCP_NODELAY",
+            "Set TCP_NODELAY on client connections",
+            Some(DEFAULT_TCP_NODELAY.to_string()),
+        ),
+        doc(
+            "LISTEN_BACKLOG",
+            "Length of the queue of connections waiting to be accepted (unset = 1024)",
+            DEFAULT_LISTEN_BACKLOG.map(|backlog| backlog.to_string()),
+        ),
+        doc(
+            "LOG_FILE_PREFIX",
+            "Name of log files without the .log extension and date (unset = taken from LOG_FILE_PATH)",
+            DEFAULT_LOG_FILE_PREFIX.map(String::from),
+        ),
+        doc(
+            "LOG_FILE_DATE_IN_NAME",
+            "Name daily log files <prefix>-YYYY-MM-DD.log instead of <prefix>.log.YYYY-MM-DD",
+            Some(DEFAULT_LOG_FILE_DATE_IN_NAME.to_string()),
+        ),
+        doc(
+            "FORCE_CHUNKED_ON_REWRITE",
+            "Send request bodies the proxy rewrote chunked instead of with a recomputed Content-Length",
+            Some(DEFAULT_FORCE_CHUNKED_ON_REWRITE.to_string()),
+        ),
+        doc(
+            "ALLOW_WEBSOCKET",
+            "Tunnel WebSocket upgrade requests to the upstream instead of answering 501",
+            Some(DEFAULT_ALLOW_WEBSOCKET.to_string()),
+        ),
+        doc(
+            "SANITIZE_LOG_OUTPUT",
+            "Escape control characters in logged bodies and header values",
+            Some(DEFAULT_SANITIZE_LOG_OUTPUT.to_string()),
+        ),
+        doc(
+            "LOG_RATE_LIMIT_EVENTS",
+            "Log a dedicated warning event for upstream 429 and 529 responses",
+            Some(DEFAULT_LOG_RATE_LIMIT_EVENTS.to_string()),
+        ),
+        doc(
+            "LOG_BASE_DIR",
+            "Base directory of log files, in place of the detected one (unset = detected from the environment)",
+            DEFAULT_LOG_BASE_DIR.map(String::from),
+        ),
+        doc(
+            "ROUTE_API_KEYS",
+            "Comma-separated path_prefix=VARIABLE pairs naming the variable holding the API key for matching requests",
+            None,
+        ),
+        doc(
+            "REQUIRE_HEADERS",
+            "Comma-separated header names every client request must carry (unset = none)",
+            None,
+        ),
+        doc(
+            "CORS_ENABLED",
+            "Answer OPTIONS requests as CORS preflights and add Access-Control-Allow-Origin to responses",
+            Some(DEFAULT_CORS_ENABLED.to_string()),
+        ),
+        doc(
+            "CORS_ALLOW_ORIGINS",
+            "Comma-separated origins allowed by CORS, or * for any",
+            Some(DEFAULT_CORS_ALLOW_ORIGINS.join(",")),
+        ),
+        doc(
+            "CORS_ALLOW_HEADERS",
+            "Comma-separated request headers allowed by CORS",
+            Some(DEFAULT_CORS_ALLOW_HEADERS.join(",")),
+        ),
+        doc(
+            "CORS_ALLOW_METHODS",
+            "Comma-separated methods allowed by CORS",
+            Some(DEFAULT_CORS_ALLOW_METHODS.join(",")),
+        ),
+        doc(
+            "UPSTREAM_RETRIES",
+            "Times an idempotent request is resent after a retryable upstream status",
+            Some(DEFAULT_UPSTREAM_RETRIES.to_string()),
+        ),
+        doc(
+            "RETRYABLE_STATUSES",
+            "Comma-separated upstream statuses that are retried",
+            Some(DEFAULT_RETRYABLE_STATUSES.iter().map(u16::to_string).collect::<Vec<_>>().join(",")),
+        ),
+        doc(
+            "WARN_ON_CONTENT_TYPE_ANOMALY",
+            "Warn when a Messages request is not JSON or a 200 response is neither JSON nor SSE",
+            Some(DEFAULT_WARN_ON_CONTENT_TYPE_ANOMALY.to_string()),
+        ),
+        doc(
+            "LOG_EMPTY_BODIES",
+            "Log an INFO event for empty request and response bodies",
+            Some(DEFAULT_LOG_EMPTY_BODIES.to_string()),
+        ),
+        doc(
+            "INJECT_REQUEST_ID_IN_ERRORS",
+            "Add the proxy's request ID to JSON error bodies as request_id",
+            Some(DEFAULT_INJECT_REQUEST_ID_IN_ERRORS.to_string()),
+        ),
+        doc(
+            "WARN_ON_SSE_TRAILING_DATA",
+            "Warn when a stream has data after its terminal message_stop or [DONE] event",
+            Some(DEFAULT_WARN_ON_SSE_TRAILING_DATA.to_string()),
+        ),
+        doc(
+            "REQUEST_DEADLINE_MS",
+            "Deadline for a whole request in milliseconds (unset = the client's 10-minute timeout)",
+            DEFAULT_REQUEST_DEADLINE_MS.map(|ms| ms.to_string()),
+        ),
+        doc(
+            "UPSTREAM_TIMEOUT_HEADER",
+            "Header carrying the milliseconds left of REQUEST_DEADLINE_MS to the upstream (unset = not sent)",
+            DEFAULT_UPSTREAM_TIMEOUT_HEADER.map(String::from),
+        ),
+        doc(
+            "MAX_PATH_LENGTH",
+            "Longest path and query in bytes before a 414 (unset = unlimited)",
+            DEFAULT_MAX_PATH_LENGTH.map(|length| length.to_string()),
+        ),
+        doc(
+            "ADMIN_ENABLED",
+            "Serve the /admin endpoints; requires ADMIN_TOKEN (unset = enabled when ADMIN_TOKEN is set)",
+            DEFAULT_ADMIN_ENABLED.map(|enabled| enabled.to_string()),
+        ),
+        doc(
+            "MAP_UPSTREAM_5XX_TO",
+            "Status sent to clients in place of any upstream 5xx (unset = the real status)",
+            DEFAULT_MAP_UPSTREAM_5XX_TO.map(|status| status.to_string()),
+        ),
+        doc(
+            "RESPONSE_BUFFER_LIMIT_BYTES",
+            "Bytes of a response buffered before streaming the rest (unset = always buffer)",
+            DEFAULT_RESPONSE_BUFFER_LIMIT_BYTES.map(|limit| limit.to_string()),
+        ),
+        doc(
+            "LOG_UPSTREAM_REQUEST_ID",
+            "Record the upstream request-id header as anthropic.request_id",
+            Some(DEFAULT_LOG_UPSTREAM_REQUEST_ID.to_string()),
+        ),
+        doc(
+            "ORDER_FILE_LOGS",
+            "Hold file log lines briefly and write them in timestamp order",
+            Some(DEFAULT_ORDER_FILE_LOGS.to_string()),
+        ),
+        doc(
+            "ALLOW_CLIENT_LOG_LEVEL",
+            "Let clients raise one request's log level with x-switchboard-log-level",
+            Some(DEFAULT_ALLOW_CLIENT_LOG_LEVEL.to_string()),
+        ),
+    ]
+}
+
+/// Returns the name a unit enum variant serializes to (its environment variable spelling)
+fn serialized_name<T: Serialize>(value: T) -> String {
+    serde_json::to_value(value)
+        .ok()
+        .and_then(|value| value.as_str().map(String::from))
+        .unwrap_or_default()
+}
 
-        // Explicitly remove them from the environment
-        for var in vars_to_unset {
-            env::remove_var(var);
+/// Renders a commented `.env` template covering every supported variable
+///
+/// Required variables are left uncommented with an empty value; optional ones
+/// are commented out and show their default, so the template can be copied to
+/// `.env` as-is and edited.
+pub fn env_template() -> String {
+    let mut template = String::from(
+        "# Switchboard configuration\n\
+         # Generated by `switchboard --generate-env-template`\n",
+    );
+
+    for var in env_var_docs() {
+        template.push('\n');
+        template.push_str(&format!("# {}\n", var.description));
+        if var.required {
+            template.push_str("# Required\n");
+            template.push_str(&format!("{}=\n", var.name));
+        } else {
+            let default = var.default.unwrap_or_default();
+            template.push_str(&format!("# {}={}\n", var.name, default));
         }
+    }
+
+    template
+}
+
+/// Adds `dotenv_vars` to `vars` without replacing any variable already set
+///
+/// Matches `dotenvy::dotenv` at startup, where the real environment wins over `.env`.
+fn with_dotenv_defaults(
+    mut vars: HashMap<String, String>,
+    dotenv_vars: impl IntoIterator<Item = (String, String)>,
+) -> HashMap<String, String> {
+    for (name, value) in dotenv_vars {
+        vars.entry(name).or_insert(value);
+    }
+    vars
+}
+
+/// Re-read the environment and apply the reloadable subset on top of `current`
+///
+/// The `.env` file is re-read so edits to it take effect without a restart. As at
+/// startup, variables set in the real environment take precedence over it; the
+/// process environment itself is left untouched. Non-reloadable fields are always
+/// taken from `current`.
+///
+/// # Arguments
+/// * `current` - The configuration currently in effect
+///
+/// # Returns
+/// The configuration that should replace `current`
+///
+/// # Errors
+/// Returns the `ConfigError` of `Config::from_env_map` if the re-read environment
+/// is invalid, in which case `current` should stay in effect
+pub fn reload_config(current: &Config) -> Result<Config, ConfigError> {
+    info!("Reloading runtime-adjustable configuration from environment...");
+
+    // Pick up edits to the .env file (ignore errors if not found)
+    let dotenv_vars = dotenvy::from_filename_iter(".env")
+        .into_iter()
+        .flatten()
+        .filter_map(Result::ok);
+    let mut vars = with_dotenv_defaults(process_env(), dotenv_vars);
+    // The API key is not reloadable; supply the current one for validation
+    vars.entry("ANTHROPIC_API_KEY".to_string())
+        .or_insert_with(|| current.anthropic_api_key.clone());
+
+    let fresh = Config::from_env_map(&vars)?;
+    let reloaded = current.with_reloaded_fields(&fresh);
+
+    info!(
+        log_bodies = reloaded.log_bodies,
+        log_max_body_size = reloaded.log_max_body_size,
+        log_body_schema_only = reloaded.log_body_schema_only,
+        log_json_indent = reloaded.log_json_indent,
+        redact_body_fields = ?reloaded.redact_body_fields,
+        redact_query_params = ?reloaded.redact_query_params,
+        server_timing = reloaded.server_timing,
+        maintenance_mode = reloaded.maintenance_mode,
+        maintenance_retry_after_secs = reloaded.maintenance_retry_after_secs,
+        "Configuration reloaded"
+    );
+
+    Ok(reloaded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Build a config from the given variables, supplying a test API key if none is given
+    fn create_test_config_with_env(env_vars: HashMap<&str, &str>) -> Config {
+        let mut vars: HashMap<String, String> = env_vars
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+        vars.entry("ANTHROPIC_API_KEY".to_string())
+            .or_insert_with(|| "test-api-key".to_string());
+
+        Config::from_env_map(&vars).expect("Test config should load")
+    }
+
+    #[test]
+    fn test_default_values() {
+        // For default values, we just need the API key (required) and all others unset
+        let env_vars = HashMap::from([("ANTHROPIC_API_KEY", "test-api-key")]);
 
         let config = create_test_config_with_env(env_vars);
 
@@ -570,17 +3403,32 @@ mod tests {
 
         let config = create_test_config_with_env(env_vars);
 
-        // The behavior is platform-dependent, so we should check that:
-        // 1. Either the value is correctly parsed as usize::MAX, OR
-        // 2. The value is at least the default (20480) and not something invalid
-        assert!(config.log_max_body_size == usize::MAX || config.log_max_body_size >= 20480);
+        // Parsed fine, but far above what can be formatted safely
+        assert_eq!(config.log_max_body_size, MAX_ALLOWED_LOG_BODY_SIZE);
+    }
+
+    #[test]
+    fn test_log_max_body_size_clamped_to_maximum() {
+        let config_with = |size: String| {
+            create_test_config_with_env(HashMap::from([
+                ("ANTHROPIC_API_KEY", "test-api-key"),
+                ("LOG_MAX_BODY_SIZE", size.as_str()),
+            ]))
+        };
+
+        let over = config_with((2 * 1024 * 1024 * 1024_usize).to_string());
+        assert_eq!(over.log_max_body_size, MAX_ALLOWED_LOG_BODY_SIZE);
+
+        let at = config_with(MAX_ALLOWED_LOG_BODY_SIZE.to_string());
+        assert_eq!(at.log_max_body_size, MAX_ALLOWED_LOG_BODY_SIZE);
+
+        let below = config_with((MAX_ALLOWED_LOG_BODY_SIZE - 1).to_string());
+        assert_eq!(below.log_max_body_size, MAX_ALLOWED_LOG_BODY_SIZE - 1);
     }
 
     #[test]
     fn test_empty_string_environment_variable() {
-        // In Rust, setting an environment variable to an empty string with env::set_var
-        // is equivalent to removing it for env::var (returns Err)
-        // Our test utility now removes empty string vars to match this behavior
+        // An empty value is treated the same as an unset variable
         let mut env_vars = HashMap::new();
         env_vars.insert("ANTHROPIC_API_KEY", "test-api-key");
 
@@ -661,14 +3509,11 @@ mod tests {
 
     #[test]
     fn test_edge_case_unusual_path() {
-        // We'll use the create_test_config_with_env function directly, which properly
-        // manages environment variable state
         let env_vars = HashMap::from([
             ("ANTHROPIC_API_KEY", "test-api-key"),
             ("LOG_FILE_PATH", "/dev/null/unusual/../path.log"),
         ]);
 
-        // This function already handles setting and restoring environment variables safely
         let config = create_test_config_with_env(env_vars);
 
         // The issue happens on Linux where it doesn't properly use the LOG_FILE_PATH value
@@ -680,4 +3525,531 @@ mod tests {
             config.log_file_path
         );
     }
+
+    #[test]
+    fn test_env_template_lists_variables_with_descriptions() {
+        let template = env_template();
+
+        assert!(template
+            .contains("# API key sent upstream as x-api-key\n# Required\nANTHROPIC_API_KEY=\n"));
+        assert!(template.contains(
+            "# Minimum log level for stdout (trace, debug, info, warn, error)\n# LOG_LEVEL=info\n"
+        ));
+        assert!(template.contains("# EMPTY_POST_BODY=passthrough\n"));
+        assert!(template.contains("# LOG_DIRECTORY_MODE=default\n"));
+
+        // Every variable read from the environment is documented exactly once (matching whole
+        // names, as LOG_LEVEL= is also the end of ALLOW_CLIENT_LOG_LEVEL=)
+        for var in env_var_docs() {
+            let assignment = format!("{}=", var.name);
+            assert_eq!(
+                template
+                    .lines()
+                    .filter(|line| line.trim_start_matches("# ").starts_with(&assignment))
+                    .count(),
+                1,
+                "{} should appear once",
+                var.name
+            );
+        }
+    }
+
+    #[test]
+    fn test_api_key_header_value_never_leaks_key() {
+        let secret = "sk-ant-test-secret-key";
+        let config = Config {
+            anthropic_api_key: secret.to_string(),
+            admin_token: Some("admin-secret".to_string()),
+            ..Config::default()
+        };
+
+        let value = config
+            .api_key_header_value("/v1/messages")
+            .expect("Key should be a valid header value");
+        assert_eq!(value.as_bytes(), secret.as_bytes());
+        assert!(value.is_sensitive());
+
+        // Neither the header value nor the config it came from reveal secrets in Debug
+        for debug in [format!("{:?}", value), format!("{:?}", config)] {
+            assert!(!debug.contains(secret), "Debug leaked the key: {}", debug);
+            assert!(!debug.contains("admin-secret"));
+        }
+        assert!(format!("{:?}", config).contains(REDACTED_VALUE));
+
+        // Invalid keys produce an error that does not echo the key
+        let invalid = Config {
+            anthropic_api_key: "bad\nkey".to_string(),
+            ..Config::default()
+        };
+        let err = invalid.api_key_header_value("/v1/messages").unwrap_err();
+        assert!(!err.to_string().contains("bad"));
+    }
+
+    #[test]
+    fn test_parse_model_rate_limits_skips_malformed_entries() {
+        let limits = parse_model_rate_limits(
+            " claude-3-opus = 10, claude-3-haiku=100,,broken,=5,claude-x=many",
+        );
+
+        assert_eq!(limits.len(), 2);
+        assert_eq!(limits["claude-3-opus"], 10);
+        assert_eq!(limits["claude-3-haiku"], 100);
+    }
+
+    #[test]
+    fn test_route_api_keys_pick_longest_matching_prefix() {
+        let vars = |routes: &str| {
+            HashMap::from([
+                ("ANTHROPIC_API_KEY".to_string(), "default-key".to_string()),
+                ("ROUTE_API_KEYS".to_string(), routes.to_string()),
+                ("MESSAGES_KEY".to_string(), "messages-key".to_string()),
+                ("BATCH_KEY".to_string(), "batch-key".to_string()),
+            ])
+        };
+        let config = Config::from_env_map(&vars(
+            "/v1/messages=MESSAGES_KEY, /v1/messages/batches=BATCH_KEY,,broken,v1=BATCH_KEY",
+        ))
+        .unwrap();
+
+        assert_eq!(config.route_api_keys.len(), 2);
+        assert_eq!(config.api_key_for_path("/v1/messages"), "messages-key");
+        assert_eq!(
+            config.api_key_for_path("/v1/messages/batches/1"),
+            "batch-key"
+        );
+        // Prefixes match whole segments only
+        assert_eq!(config.api_key_for_path("/v1/messagesx"), "default-key");
+        assert_eq!(config.api_key_for_path("/v1/models"), "default-key");
+        // Route keys are secrets too
+        assert!(!format!("{:?}", config).contains("batch-key"));
+
+        // Every referenced variable must hold a key
+        assert_eq!(
+            Config::from_env_map(&vars("/v1/complete=UNSET_KEY")).unwrap_err(),
+            ConfigError::MissingRouteApiKey {
+                prefix: "/v1/complete".to_string(),
+                var: "UNSET_KEY".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_header_names_lowercases_and_rejects_invalid() {
+        assert_eq!(
+            parse_header_names("REQUIRE_HEADERS", " X-Team-Id,,x-project "),
+            Ok(vec!["x-team-id".to_string(), "x-project".to_string()])
+        );
+        assert!(matches!(
+            parse_header_names("REQUIRE_HEADERS", "x-team-id,x team id"),
+            Err(ConfigError::InvalidFormat {
+                var: "REQUIRE_HEADERS",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_with_dotenv_defaults_keeps_environment_values() {
+        let process = HashMap::from([("LOG_BODIES".to_string(), "false".to_string())]);
+        let dotenv = [
+            ("LOG_BODIES".to_string(), "true".to_string()),
+            ("SERVER_TIMING".to_string(), "true".to_string()),
+        ];
+
+        let vars = with_dotenv_defaults(process, dotenv);
+        assert_eq!(vars["LOG_BODIES"], "false");
+        assert_eq!(vars["SERVER_TIMING"], "true");
+    }
+
+    #[test]
+    fn test_from_env_map_validates_upstream_timeout_header() {
+        let vars = |name: &str| -> HashMap<String, String> {
+            [
+                ("ANTHROPIC_API_KEY", "map-api-key"),
+                ("UPSTREAM_TIMEOUT_HEADER", name),
+            ]
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+        };
+
+        let config = Config::from_env_map(&vars(" X-Timeout-Ms ")).unwrap();
+        assert_eq!(
+            config.upstream_timeout_header.as_deref(),
+            Some("x-timeout-ms")
+        );
+
+        for invalid in ["x timeout ms", "x-timeout-ms,x-deadline"] {
+            let err = Config::from_env_map(&vars(invalid)).unwrap_err();
+            assert!(
+                matches!(
+                    err,
+                    ConfigError::InvalidFormat {
+                        var: "UPSTREAM_TIMEOUT_HEADER",
+                        ..
+                    }
+                ),
+                "{} should be rejected, got {:?}",
+                invalid,
+                err
+            );
+        }
+    }
+
+    #[test]
+    fn test_from_env_map_rejects_invalid_cors_allow_header() {
+        let vars: HashMap<String, String> = [
+            ("ANTHROPIC_API_KEY", "map-api-key"),
+            ("CORS_ALLOW_HEADERS", "content-type,anthropic version"),
+        ]
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect();
+
+        let err = Config::from_env_map(&vars).unwrap_err();
+        assert!(
+            matches!(
+                err,
+                ConfigError::InvalidFormat {
+                    var: "CORS_ALLOW_HEADERS",
+                    ref reason,
+                    ..
+                } if reason.contains("anthropic version")
+            ),
+            "got {:?}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_from_env_map_rejects_invalid_required_header() {
+        let vars: HashMap<String, String> = [
+            ("ANTHROPIC_API_KEY", "map-api-key"),
+            ("REQUIRE_HEADERS", "x-project,x team id"),
+        ]
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect();
+
+        let err = Config::from_env_map(&vars).unwrap_err();
+        assert!(
+            matches!(
+                err,
+                ConfigError::InvalidFormat {
+                    var: "REQUIRE_HEADERS",
+                    ref reason,
+                    ..
+                } if reason.contains("x team id")
+            ),
+            "got {:?}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_from_env_map_requires_api_key() {
+        assert_eq!(
+            Config::from_env_map(&HashMap::new()).unwrap_err(),
+            ConfigError::MissingApiKey
+        );
+
+        let empty_key = HashMap::from([("ANTHROPIC_API_KEY".to_string(), String::new())]);
+        assert_eq!(
+            Config::from_env_map(&empty_key).unwrap_err(),
+            ConfigError::MissingApiKey
+        );
+
+        // Explicitly injecting still needs a key
+        let inject = HashMap::from([("AUTH_MODE".to_string(), "inject".to_string())]);
+        assert_eq!(
+            Config::from_env_map(&inject).unwrap_err(),
+            ConfigError::MissingApiKey
+        );
+    }
+
+    #[test]
+    fn test_from_env_map_rejects_target_url_without_host() {
+        for url in ["https://", "/v1", "not a url"] {
+            let vars = HashMap::from([
+                ("ANTHROPIC_API_KEY".to_string(), "map-api-key".to_string()),
+                ("ANTHROPIC_TARGET_URL".to_string(), url.to_string()),
+            ]);
+            assert!(
+                matches!(
+                    Config::from_env_map(&vars),
+                    Err(ConfigError::InvalidFormat {
+                        var: "ANTHROPIC_TARGET_URL",
+                        ref value,
+                        ..
+                    }) if value == url
+                ),
+                "{} should be rejected",
+                url
+            );
+        }
+    }
+
+    #[test]
+    fn test_from_env_map_checks_target_host_against_allowlist() {
+        let vars = |allowlist: &str| -> HashMap<String, String> {
+            [
+                ("ANTHROPIC_API_KEY", "map-api-key"),
+                ("ANTHROPIC_TARGET_URL", "https://api.anthropic.com"),
+                ("UPSTREAM_HOST_ALLOWLIST", allowlist),
+            ]
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+        };
+
+        let config = Config::from_env_map(&vars(" API.anthropic.com , ,proxy.internal")).unwrap();
+        assert_eq!(
+            config.upstream_host_allowlist,
+            Some(vec![
+                "API.anthropic.com".to_string(),
+                "proxy.internal".to_string()
+            ])
+        );
+        assert!(config.upstream_host_allowed("api.anthropic.com"));
+        assert!(!config.upstream_host_allowed("169.254.169.254"));
+
+        assert_eq!(
+            Config::from_env_map(&vars("proxy.internal")).unwrap_err(),
+            ConfigError::UpstreamHostNotAllowed("api.anthropic.com".to_string())
+        );
+    }
+
+    #[test]
+    fn test_passthrough_auth_mode_makes_api_key_optional() {
+        let passthrough = HashMap::from([("AUTH_MODE".to_string(), "Passthrough".to_string())]);
+        let config = Config::from_env_map(&passthrough).unwrap();
+
+        assert_eq!(config.auth_mode, AuthMode::Passthrough);
+        assert!(config.anthropic_api_key.is_empty());
+    }
+
+    #[test]
+    fn test_from_env_map_validates_level_directives() {
+        let vars = |level: &str| -> HashMap<String, String> {
+            [
+                ("ANTHROPIC_API_KEY", "map-api-key"),
+                ("LOG_FILE_LEVEL", level),
+            ]
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+        };
+
+        // Per-target directives are accepted as well as single levels
+        let config = Config::from_env_map(&vars("switchboard=debug,reqwest=warn")).unwrap();
+        assert_eq!(config.log_file_level, "switchboard=debug,reqwest=warn");
+
+        let err = Config::from_env_map(&vars("switchboard=loud")).unwrap_err();
+        assert!(
+            matches!(
+                err,
+                ConfigError::InvalidFormat {
+                    var: "LOG_FILE_LEVEL",
+                    ..
+                }
+            ),
+            "Malformed directive should be rejected, got {:?}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_from_env_map_fails_closed_without_admin_token() {
+        let vars = |pairs: &[(&str, &str)]| -> HashMap<String, String> {
+            [("ANTHROPIC_API_KEY", "map-api-key")]
+                .iter()
+                .chain(pairs)
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect()
+        };
+
+        // Enabling admin endpoints with a missing or empty token is refused
+        for token in [None, Some("  ")] {
+            let mut pairs = vec![("ADMIN_ENABLED", "true")];
+            pairs.extend(token.map(|token| ("ADMIN_TOKEN", token)));
+            let err = Config::from_env_map(&vars(&pairs)).unwrap_err();
+            assert!(
+                matches!(
+                    err,
+                    ConfigError::InvalidFormat {
+                        var: "ADMIN_ENABLED",
+                        ..
+                    }
+                ),
+                "Admin without a token should be rejected, got {:?}",
+                err
+            );
+        }
+
+        // Unset, the switch follows the token
+        assert!(!Config::from_env_map(&vars(&[])).unwrap().admin_enabled);
+        let with_token = Config::from_env_map(&vars(&[("ADMIN_TOKEN", "admin-secret")])).unwrap();
+        assert!(with_token.admin_enabled);
+
+        // Explicitly off, a token does not enable them
+        let switched_off = Config::from_env_map(&vars(&[
+            ("ADMIN_TOKEN", "admin-secret"),
+            ("ADMIN_ENABLED", "false"),
+        ]))
+        .unwrap();
+        assert!(!switched_off.admin_enabled);
+    }
+
+    #[test]
+    fn test_from_env_map_validates_retryable_statuses() {
+        let vars = |statuses: &str| -> HashMap<String, String> {
+            [
+                ("ANTHROPIC_API_KEY", "map-api-key"),
+                ("RETRYABLE_STATUSES", statuses),
+            ]
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+        };
+
+        let config = Config::from_env_map(&vars(" 408, 503,,529 ")).unwrap();
+        assert_eq!(config.retryable_statuses, vec![408, 503, 529]);
+
+        for invalid in ["503,oops", "503,600", "99"] {
+            let err = Config::from_env_map(&vars(invalid)).unwrap_err();
+            assert!(
+                matches!(
+                    err,
+                    ConfigError::InvalidFormat {
+                        var: "RETRYABLE_STATUSES",
+                        ..
+                    }
+                ),
+                "{} should be rejected, got {:?}",
+                invalid,
+                err
+            );
+        }
+    }
+
+    #[test]
+    fn test_from_env_map_validates_values() {
+        let vars: HashMap<String, String> = [
+            ("ANTHROPIC_API_KEY", "map-api-key"),
+            ("MAX_CONCURRENT_REQUESTS", "not-a-number"),
+            ("SERVER_TIMING", "maybe"),
+            ("EMPTY_POST_BODY", "EMPTY_JSON"),
+            ("ADMIN_TOKEN", "   "),
+            ("MODEL_RATE_LIMITS", "claude-3-opus=10,broken"),
+        ]
+        .into_iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect();
+
+        let config = Config::from_env_map(&vars).unwrap();
+
+        assert_eq!(config.anthropic_api_key, "map-api-key");
+        // Invalid and ambiguous values fall back to their defaults
+        assert_eq!(
+            config.max_concurrent_requests,
+            DEFAULT_MAX_CONCURRENT_REQUESTS
+        );
+        assert_eq!(config.server_timing, DEFAULT_SERVER_TIMING);
+        assert_eq!(config.empty_post_body, EmptyBodyPolicy::EmptyJson);
+        assert_eq!(config.admin_token, None);
+        assert_eq!(
+            config.model_rate_limits,
+            HashMap::from([("claude-3-opus".to_string(), 10)])
+        );
+    }
+
+    #[test]
+    fn test_request_body_streamed_only_when_not_inspected() {
+        let env_vars = HashMap::from([("STREAM_REQUEST_BODY", "true"), ("LOG_BODIES", "false")]);
+        let config = create_test_config_with_env(env_vars.clone());
+        assert!(config.streams_request_body());
+
+        // Any feature that reads the body forces buffering
+        for (var, value) in [
+            ("LOG_BODIES", "true"),
+            ("MODEL_RATE_LIMITS", "claude-3-opus=10"),
+            ("EMPTY_POST_BODY", "empty_json"),
+        ] {
+            let mut env_vars = env_vars.clone();
+            env_vars.insert(var, value);
+            let config = create_test_config_with_env(env_vars);
+            assert!(
+                !config.streams_request_body(),
+                "{} should force buffering",
+                var
+            );
+        }
+    }
+
+    #[test]
+    fn test_key_fingerprint_is_stable_and_hides_key() {
+        let key = "sk-ant-REDACTED";
+        let fingerprint = key_fingerprint(key);
+
+        assert_eq!(fingerprint.len(), KEY_FINGERPRINT_HEX_LEN);
+        assert!(fingerprint.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_eq!(fingerprint, key_fingerprint(key));
+        assert_ne!(fingerprint, key_fingerprint("sk-ant-api03-other-key"));
+
+        // No fragment of the key (of fingerprint-relevant length) appears in it
+        for window in key.as_bytes().windows(4) {
+            let fragment = std::str::from_utf8(window).unwrap();
+            assert!(!fingerprint.contains(fragment), "Leaked {:?}", fragment);
+        }
+    }
+
+    #[test]
+    fn test_log_color_parsing() {
+        for (value, expected) in [
+            ("auto", ColorMode::Auto),
+            ("NEVER", ColorMode::Never),
+            ("always", ColorMode::Always),
+            ("rainbow", ColorMode::default()),
+        ] {
+            let config = create_test_config_with_env(HashMap::from([("LOG_COLOR", value)]));
+            assert_eq!(config.log_color, expected, "LOG_COLOR={}", value);
+        }
+    }
+
+    #[test]
+    fn test_socket_options_parsing() {
+        let config = create_test_config_with_env(HashMap::new());
+        assert!(!config.tcp_nodelay);
+        assert_eq!(config.listen_backlog, None);
+
+        let config = create_test_config_with_env(HashMap::from([
+            ("TCP_NODELAY", "true"),
+            ("LISTEN_BACKLOG", "4096"),
+        ]));
+        assert!(config.tcp_nodelay);
+        assert_eq!(config.listen_backlog, Some(4096));
+
+        let config = create_test_config_with_env(HashMap::from([("LISTEN_BACKLOG", "lots")]));
+        assert_eq!(config.listen_backlog, None);
+    }
+
+    #[test]
+    fn test_diff_reports_changed_fields_without_secrets() {
+        let current = create_test_config_with_env(HashMap::new());
+        assert!(current.diff(&current.clone()).is_empty());
+
+        let mut changed = current.clone();
+        changed.server_timing = !current.server_timing;
+        changed.anthropic_api_key = "rotated-api-key".to_string();
+        changed.admin_token = Some("new-admin-token".to_string());
+
+        assert_eq!(
+            current.diff(&changed),
+            vec![FieldChange {
+                field: "server_timing".to_string(),
+                old: Value::Bool(current.server_timing),
+                new: Value::Bool(changed.server_timing),
+            }]
+        );
+    }
 }
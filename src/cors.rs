@@ -0,0 +1,159 @@
+//! CORS handling for browser clients calling the proxy directly
+//!
+//! Browsers send an `OPTIONS` preflight before cross-origin requests that carry
+//! custom headers such as `x-api-key`. With `cors_enabled`, the proxy answers those
+//! preflights itself, and proxied responses carry `Access-Control-Allow-Origin` so
+//! the browser lets the page read them.
+//!
+//! Key features:
+//! - `*` in the allowed origins allows any origin; otherwise listed origins are echoed
+//! - Echoed origins add `Vary: Origin`, so caches keep responses per origin
+//! - Origins that are not allowed get no CORS headers, so the browser blocks them
+
+use crate::config::Config;
+use axum::body::{boxed, Empty};
+use axum::response::Response;
+use hyper::header::{self, HeaderMap, HeaderValue};
+use hyper::StatusCode;
+
+/// Returns the `Access-Control-Allow-Origin` value for a request, if CORS allows it
+///
+/// # Arguments
+/// * `config` - Configuration with the CORS settings
+/// * `headers` - Headers of the client request
+///
+/// # Returns
+/// None when CORS is disabled, the request has no `Origin` (not a browser
+/// cross-origin request), or its origin is not allowed
+pub fn allowed_origin(config: &Config, headers: &HeaderMap) -> Option<HeaderValue> {
+    if !config.cors_enabled {
+        return None;
+    }
+    let origin = headers.get(header::ORIGIN)?;
+    let origins = &config.cors_allow_origins;
+    if origins.iter().any(|allowed| allowed == "*") {
+        Some(HeaderValue::from_static("*"))
+    } else if origins
+        .iter()
+        .any(|allowed| allowed.as_bytes() == origin.as_bytes())
+    {
+        Some(origin.clone())
+    } else {
+        None
+    }
+}
+
+/// Sets the allow-origin header on a response, replacing any the upstream sent
+///
+/// An echoed origin also adds `Vary: Origin`, as the response then depends on it.
+pub fn apply_allow_origin(headers: &mut HeaderMap, origin: &HeaderValue) {
+    headers.insert(header::ACCESS_CONTROL_ALLOW_ORIGIN, origin.clone());
+    if origin != "*" {
+        headers.append(header::VARY, HeaderValue::from_static("origin"));
+    }
+}
+
+/// Builds the response to a CORS preflight, answered without contacting the upstream
+///
+/// # Arguments
+/// * `config` - Configuration with the allowed methods and headers
+/// * `origin` - Allowed origin of the request, from [`allowed_origin`]
+pub fn preflight_response(config: &Config, origin: Option<&HeaderValue>) -> Response {
+    let mut response = Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_LENGTH, "0")
+        .body(boxed(Empty::new()))
+        // Static status and header values cannot fail to build
+        .expect("CORS preflight response should always build");
+
+    if let Some(origin) = origin {
+        let headers = response.headers_mut();
+        apply_allow_origin(headers, origin);
+        // The lists were validated as methods and header names when the config was read
+        if let Ok(methods) = HeaderValue::from_str(&config.cors_allow_methods.join(", ")) {
+            headers.insert(header::ACCESS_CONTROL_ALLOW_METHODS, methods);
+        }
+        if let Ok(allowed) = HeaderValue::from_str(&config.cors_allow_headers.join(", ")) {
+            headers.insert(header::ACCESS_CONTROL_ALLOW_HEADERS, allowed);
+        }
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cors_config(origins: &[&str]) -> Config {
+        Config {
+            cors_enabled: true,
+            cors_allow_origins: origins.iter().map(|origin| origin.to_string()).collect(),
+            ..Config::default()
+        }
+    }
+
+    fn with_origin(origin: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::ORIGIN, HeaderValue::from_str(origin).unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_allowed_origin() {
+        let any = cors_config(&["*"]);
+        assert_eq!(
+            allowed_origin(&any, &with_origin("https://a.example")),
+            Some(HeaderValue::from_static("*"))
+        );
+        // Not a cross-origin browser request
+        assert_eq!(allowed_origin(&any, &HeaderMap::new()), None);
+
+        let listed = cors_config(&["https://a.example", "https://b.example"]);
+        assert_eq!(
+            allowed_origin(&listed, &with_origin("https://b.example")),
+            Some(HeaderValue::from_static("https://b.example"))
+        );
+        assert_eq!(
+            allowed_origin(&listed, &with_origin("https://evil.example")),
+            None
+        );
+
+        let disabled = Config {
+            cors_enabled: false,
+            ..any
+        };
+        assert_eq!(
+            allowed_origin(&disabled, &with_origin("https://a.example")),
+            None
+        );
+    }
+
+    #[test]
+    fn test_echoed_origin_varies_by_origin() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::ACCESS_CONTROL_ALLOW_ORIGIN,
+            HeaderValue::from_static("*"),
+        );
+        apply_allow_origin(&mut headers, &HeaderValue::from_static("https://a.example"));
+
+        assert_eq!(
+            headers[header::ACCESS_CONTROL_ALLOW_ORIGIN],
+            "https://a.example"
+        );
+        assert_eq!(headers[header::VARY], "origin");
+    }
+
+    #[test]
+    fn test_preflight_without_allowed_origin_has_no_cors_headers() {
+        let response = preflight_response(&cors_config(&["https://a.example"]), None);
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(!response
+            .headers()
+            .contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN));
+        assert!(!response
+            .headers()
+            .contains_key(header::ACCESS_CONTROL_ALLOW_METHODS));
+    }
+}
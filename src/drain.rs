@@ -0,0 +1,244 @@
+//! Two-phase draining of in-flight work during shutdown
+//!
+//! Once shutdown starts, ordinary requests get `SHUTDOWN_REQUEST_GRACE` to finish.
+//! Streaming responses can legitimately run for minutes, so open streams get a
+//! separate, longer grace (`shutdown_stream_grace_secs`) before the remaining
+//! connections are force-closed.
+//!
+//! Key features:
+//! - Requests and open streams are counted separately, each by a guard released on drop
+//! - Phase one waits for all in-flight work, up to the request grace
+//! - Phase two waits for open streams only, up to the stream grace
+//! - Both graces are measured from the start of the drain
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::Instant;
+use tracing::{info, warn};
+
+/// How long non-streaming requests may run once shutdown starts
+pub const SHUTDOWN_REQUEST_GRACE: Duration = Duration::from_secs(30);
+
+/// How often the in-flight counts are checked while draining
+const DRAIN_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Counts of requests and streaming responses currently in flight
+#[derive(Debug, Default)]
+pub struct InFlight {
+    /// Requests whose handler is still running
+    requests: AtomicUsize,
+    /// Streaming response bodies still being sent
+    streams: AtomicUsize,
+}
+
+/// Which in-flight count a guard holds
+#[derive(Debug, Clone, Copy)]
+enum InFlightKind {
+    Request,
+    Stream,
+}
+
+impl InFlight {
+    /// Counts a request as in flight until the returned guard is dropped
+    pub fn track_request(self: &Arc<Self>) -> InFlightGuard {
+        InFlightGuard::new(Arc::clone(self), InFlightKind::Request)
+    }
+
+    /// Counts a streaming response as open until the returned guard is dropped
+    pub fn track_stream(self: &Arc<Self>) -> InFlightGuard {
+        InFlightGuard::new(Arc::clone(self), InFlightKind::Stream)
+    }
+
+    /// Returns the number of requests in flight
+    pub fn requests(&self) -> usize {
+        self.requests.load(Ordering::SeqCst)
+    }
+
+    /// Returns the number of streaming responses open
+    pub fn streams(&self) -> usize {
+        self.streams.load(Ordering::SeqCst)
+    }
+
+    fn counter(&self, kind: InFlightKind) -> &AtomicUsize {
+        match kind {
+            InFlightKind::Request => &self.requests,
+            InFlightKind::Stream => &self.streams,
+        }
+    }
+}
+
+/// Keeps a request or stream counted as in flight, released on drop
+#[derive(Debug)]
+pub struct InFlightGuard {
+    in_flight: Arc<InFlight>,
+    kind: InFlightKind,
+}
+
+impl InFlightGuard {
+    fn new(in_flight: Arc<InFlight>, kind: InFlightKind) -> Self {
+        in_flight.counter(kind).fetch_add(1, Ordering::SeqCst);
+        Self { in_flight, kind }
+    }
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        self.in_flight
+            .counter(self.kind)
+            .fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// What was still in flight when draining ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DrainOutcome {
+    /// Requests still running, to be force-closed
+    pub requests: usize,
+    /// Streams still open, to be force-closed
+    pub streams: usize,
+}
+
+impl DrainOutcome {
+    /// Returns true if everything finished within its grace
+    pub fn is_complete(&self) -> bool {
+        self.requests == 0 && self.streams == 0
+    }
+}
+
+/// Waits for in-flight work to finish, giving open streams longer than requests
+///
+/// Phase one waits for all requests and streams, up to `request_grace`. If streams
+/// are still open then, phase two waits for them alone until `stream_grace` has
+/// passed since the drain started. A stream grace shorter than the request grace
+/// is treated as equal to it.
+///
+/// # Returns
+/// What was still in flight when the drain ended (and so should be force-closed)
+pub async fn drain(
+    in_flight: &InFlight,
+    request_grace: Duration,
+    stream_grace: Duration,
+) -> DrainOutcome {
+    let start = Instant::now();
+    info!(
+        requests = in_flight.requests(),
+        streams = in_flight.streams(),
+        "Draining in-flight requests before shutdown"
+    );
+
+    let all_done = || in_flight.requests() == 0 && in_flight.streams() == 0;
+    wait_until(all_done, start + request_grace).await;
+
+    if in_flight.streams() > 0 && stream_grace > request_grace {
+        info!(
+            streams = in_flight.streams(),
+            stream_grace_secs = stream_grace.as_secs(),
+            "Request grace over, waiting for open streams"
+        );
+        wait_until(|| in_flight.streams() == 0, start + stream_grace).await;
+    }
+
+    let outcome = DrainOutcome {
+        requests: in_flight.requests(),
+        streams: in_flight.streams(),
+    };
+    if outcome.is_complete() {
+        info!("In-flight requests drained");
+    } else {
+        warn!(
+            requests = outcome.requests,
+            streams = outcome.streams,
+            "Shutdown grace expired, force-closing remaining connections"
+        );
+    }
+    outcome
+}
+
+/// Polls `done` until it returns true or `deadline` passes
+async fn wait_until(done: impl Fn() -> bool, deadline: Instant) {
+    while !done() && Instant::now() < deadline {
+        tokio::time::sleep_until(deadline.min(Instant::now() + DRAIN_POLL_INTERVAL)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REQUEST_GRACE: Duration = Duration::from_secs(30);
+    const STREAM_GRACE: Duration = Duration::from_secs(300);
+
+    /// Drops `guard` after `delay`, simulating work finishing
+    fn finish_after(guard: InFlightGuard, delay: Duration) {
+        tokio::spawn(async move {
+            tokio::time::sleep(delay).await;
+            drop(guard);
+        });
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_drain_completes_once_work_finishes() {
+        let in_flight = Arc::new(InFlight::default());
+        finish_after(in_flight.track_request(), Duration::from_secs(2));
+        finish_after(in_flight.track_stream(), Duration::from_secs(5));
+
+        let start = Instant::now();
+        let outcome = drain(&in_flight, REQUEST_GRACE, STREAM_GRACE).await;
+
+        assert!(outcome.is_complete());
+        assert!(start.elapsed() < Duration::from_secs(6));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_streams_get_longer_than_requests() {
+        let in_flight = Arc::new(InFlight::default());
+        let _stuck_request = in_flight.track_request();
+        finish_after(in_flight.track_stream(), Duration::from_secs(120));
+
+        let start = Instant::now();
+        let outcome = drain(&in_flight, REQUEST_GRACE, STREAM_GRACE).await;
+
+        // The stream outlived the request grace but finished within its own
+        assert_eq!(
+            outcome,
+            DrainOutcome {
+                requests: 1,
+                streams: 0
+            }
+        );
+        assert!(start.elapsed() >= Duration::from_secs(120));
+        assert!(start.elapsed() < STREAM_GRACE);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_requests_alone_only_get_request_grace() {
+        let in_flight = Arc::new(InFlight::default());
+        let _stuck_request = in_flight.track_request();
+
+        let start = Instant::now();
+        let outcome = drain(&in_flight, REQUEST_GRACE, STREAM_GRACE).await;
+
+        assert_eq!(outcome.requests, 1);
+        assert!(start.elapsed() >= REQUEST_GRACE);
+        assert!(start.elapsed() < REQUEST_GRACE + DRAIN_POLL_INTERVAL * 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_stuck_stream_force_closed_after_stream_grace() {
+        let in_flight = Arc::new(InFlight::default());
+        let _stuck_stream = in_flight.track_stream();
+
+        let start = Instant::now();
+        let outcome = drain(&in_flight, REQUEST_GRACE, STREAM_GRACE).await;
+
+        assert_eq!(
+            outcome,
+            DrainOutcome {
+                requests: 0,
+                streams: 1
+            }
+        );
+        assert!(start.elapsed() >= STREAM_GRACE);
+    }
+}
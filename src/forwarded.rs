@@ -0,0 +1,138 @@
+//! `X-Forwarded-*` headers describing the original client request
+//!
+//! When the proxy is the edge, the upstream only sees the proxy's own address,
+//! scheme and host. With forwarded headers enabled, the forwarded request carries
+//! the client's IP, the scheme it connected with and the Host it asked for.
+//!
+//! Key features:
+//! - `X-Forwarded-For` keeps any incoming chain and appends the client IP
+//! - `X-Forwarded-Host` and `X-Forwarded-Proto` set by a proxy in front are kept
+//! - Multiple incoming `X-Forwarded-For` lines are merged into one list
+
+use hyper::header::{HeaderName, HeaderValue};
+use hyper::{header, HeaderMap, Uri};
+use std::net::IpAddr;
+
+/// Header listing the client and every proxy the request passed through
+pub const X_FORWARDED_FOR: HeaderName = HeaderName::from_static("x-forwarded-for");
+
+/// Header carrying the Host the client originally requested
+pub const X_FORWARDED_HOST: HeaderName = HeaderName::from_static("x-forwarded-host");
+
+/// Header carrying the scheme (`http` or `https`) the client connected with
+pub const X_FORWARDED_PROTO: HeaderName = HeaderName::from_static("x-forwarded-proto");
+
+/// Adds `X-Forwarded-*` headers to a request about to be forwarded
+///
+/// # Arguments
+/// * `forward_headers` - Headers of the outgoing request, updated in place
+/// * `original_headers` - Headers of the client request
+/// * `original_uri` - URI of the client request, for its authority when there is no Host
+/// * `client_ip` - The connected client's address, if known
+/// * `proto` - The scheme the client connected with
+pub fn add_forwarded_headers(
+    forward_headers: &mut HeaderMap,
+    original_headers: &HeaderMap,
+    original_uri: &Uri,
+    client_ip: Option<IpAddr>,
+    proto: &'static str,
+) {
+    // Merge the existing chain into one value, then append this hop's client
+    let mut chain: Vec<String> = original_headers
+        .get_all(&X_FORWARDED_FOR)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+        .collect();
+    if let Some(ip) = client_ip {
+        chain.push(ip.to_string());
+    }
+    forward_headers.remove(&X_FORWARDED_FOR);
+    if !chain.is_empty() {
+        // Every part came from a valid header value or an IP address, so this cannot fail
+        if let Ok(value) = HeaderValue::from_str(&chain.join(", ")) {
+            forward_headers.insert(X_FORWARDED_FOR, value);
+        }
+    }
+
+    // A proxy further out saw the client's request first, so its values win
+    if !original_headers.contains_key(&X_FORWARDED_HOST) {
+        let host = original_headers
+            .get(header::HOST)
+            .cloned()
+            .or_else(|| HeaderValue::from_str(original_uri.authority()?.as_str()).ok());
+        if let Some(host) = host {
+            forward_headers.insert(X_FORWARDED_HOST, host);
+        }
+    }
+    if !original_headers.contains_key(&X_FORWARDED_PROTO) {
+        forward_headers.insert(X_FORWARDED_PROTO, HeaderValue::from_static(proto));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn forwarded(original_headers: &HeaderMap, client_ip: Option<IpAddr>) -> HeaderMap {
+        // Non-hop-by-hop headers such as X-Forwarded-For are copied before this runs
+        let mut forward_headers = original_headers.clone();
+        forward_headers.remove(header::HOST);
+        add_forwarded_headers(
+            &mut forward_headers,
+            original_headers,
+            &Uri::from_static("/v1/messages"),
+            client_ip,
+            "https",
+        );
+        forward_headers
+    }
+
+    #[test]
+    fn test_headers_built_from_client_connection() {
+        let mut original = HeaderMap::new();
+        original.insert(header::HOST, HeaderValue::from_static("proxy.example.com"));
+
+        let headers = forwarded(&original, Some("203.0.113.7".parse().unwrap()));
+
+        assert_eq!(headers[&X_FORWARDED_FOR], "203.0.113.7");
+        assert_eq!(headers[&X_FORWARDED_HOST], "proxy.example.com");
+        assert_eq!(headers[&X_FORWARDED_PROTO], "https");
+    }
+
+    #[test]
+    fn test_existing_chain_is_appended_to() {
+        let mut original = HeaderMap::new();
+        original.append(&X_FORWARDED_FOR, HeaderValue::from_static("198.51.100.1"));
+        original.append(
+            &X_FORWARDED_FOR,
+            HeaderValue::from_static("10.0.0.2, 10.0.0.3"),
+        );
+        original.insert(
+            &X_FORWARDED_HOST,
+            HeaderValue::from_static("edge.example.com"),
+        );
+        original.insert(&X_FORWARDED_PROTO, HeaderValue::from_static("http"));
+        original.insert(header::HOST, HeaderValue::from_static("internal:8080"));
+
+        let headers = forwarded(&original, Some("10.0.0.4".parse().unwrap()));
+
+        let chain: Vec<_> = headers.get_all(&X_FORWARDED_FOR).iter().collect();
+        assert_eq!(chain, vec!["198.51.100.1, 10.0.0.2, 10.0.0.3, 10.0.0.4"]);
+        assert_eq!(headers[&X_FORWARDED_HOST], "edge.example.com");
+        assert_eq!(headers[&X_FORWARDED_PROTO], "http");
+    }
+
+    #[test]
+    fn test_unknown_client_keeps_existing_chain() {
+        let mut original = HeaderMap::new();
+        original.insert(&X_FORWARDED_FOR, HeaderValue::from_static("198.51.100.1"));
+
+        let headers = forwarded(&original, None);
+
+        assert_eq!(headers[&X_FORWARDED_FOR], "198.51.100.1");
+        // Without a Host header or an absolute URI there is no host to report
+        assert!(!headers.contains_key(&X_FORWARDED_HOST));
+    }
+}
@@ -0,0 +1,425 @@
+//! Bounded gzip decoding for logging compressed bodies
+//!
+//! The upstream rarely compresses responses, and logging is the only place the proxy
+//! needs their plain bytes, so a small inflater (RFC 1951 inside RFC 1952 framing) is
+//! enough. Decoding stops at a caller-chosen limit, so a tiny compressed body cannot
+//! expand into a huge allocation. Forwarded bytes are never decoded.
+//!
+//! Key features:
+//! - Stored, fixed Huffman and dynamic Huffman blocks
+//! - Optional gzip header fields (extra, name, comment, header CRC) are skipped
+//! - Only the first gzip member is decoded; the CRC and size trailer are not checked
+
+use hyper::{header, HeaderMap};
+use thiserror::Error;
+
+/// Errors that prevent a gzip body from being decoded
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum GzipError {
+    /// The data does not start with a gzip header using deflate
+    #[error("Body is not a gzip stream")]
+    NotGzip,
+
+    /// The data ends in the middle of the header or a deflate block
+    #[error("Gzip stream ended unexpectedly")]
+    UnexpectedEof,
+
+    /// The deflate data is malformed
+    #[error("Invalid deflate data: {0}")]
+    InvalidData(&'static str),
+
+    /// The decoded body would be longer than the limit
+    #[error("Decompressed body exceeds {0} bytes")]
+    TooLarge(usize),
+}
+
+/// Gzip magic bytes followed by the deflate compression method
+const GZIP_MAGIC: [u8; 3] = [0x1f, 0x8b, 0x08];
+
+/// Header flag bits announcing optional fields
+const FLAG_HCRC: u8 = 0x02;
+const FLAG_EXTRA: u8 = 0x04;
+const FLAG_NAME: u8 = 0x08;
+const FLAG_COMMENT: u8 = 0x10;
+
+/// Longest Huffman code in deflate
+const MAX_CODE_BITS: usize = 15;
+
+/// Base match lengths of length symbols 257..=285, and their extra bits
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+
+/// Base distances of distance symbols 0..=29, and their extra bits
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+
+/// Order in which code length code lengths are stored in dynamic blocks
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+/// Returns true if the last content coding applied to the body is gzip
+pub fn is_gzip_encoded(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::CONTENT_ENCODING)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.rsplit(',').next())
+        .map(|coding| {
+            let coding = coding.trim();
+            coding.eq_ignore_ascii_case("gzip") || coding.eq_ignore_ascii_case("x-gzip")
+        })
+        .unwrap_or(false)
+}
+
+/// Decodes a gzip body, failing if the result would exceed `limit` bytes
+///
+/// # Arguments
+/// * `data` - The gzip-encoded body
+/// * `limit` - Maximum number of decoded bytes
+pub fn decompress(data: &[u8], limit: usize) -> Result<Vec<u8>, GzipError> {
+    let deflate = skip_header(data)?;
+    Inflater {
+        input: BitReader::new(deflate),
+        output: Vec::new(),
+        limit,
+    }
+    .run()
+}
+
+/// Returns the deflate data following the gzip header
+fn skip_header(data: &[u8]) -> Result<&[u8], GzipError> {
+    if data.len() < 10 || data[..3] != GZIP_MAGIC {
+        return Err(GzipError::NotGzip);
+    }
+    let flags = data[3];
+    let mut rest = &data[10..];
+
+    if flags & FLAG_EXTRA != 0 {
+        let len = match rest {
+            [low, high, ..] => usize::from(u16::from_le_bytes([*low, *high])),
+            _ => return Err(GzipError::UnexpectedEof),
+        };
+        rest = rest.get(2 + len..).ok_or(GzipError::UnexpectedEof)?;
+    }
+    for flag in [FLAG_NAME, FLAG_COMMENT] {
+        if flags & flag != 0 {
+            let end = rest
+                .iter()
+                .position(|&byte| byte == 0)
+                .ok_or(GzipError::UnexpectedEof)?;
+            rest = &rest[end + 1..];
+        }
+    }
+    if flags & FLAG_HCRC != 0 {
+        rest = rest.get(2..).ok_or(GzipError::UnexpectedEof)?;
+    }
+    Ok(rest)
+}
+
+/// Reads deflate's least-significant-bit-first bit stream
+struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    buffer: u32,
+    count: u32,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        BitReader {
+            data,
+            pos: 0,
+            buffer: 0,
+            count: 0,
+        }
+    }
+
+    /// Reads `n` bits (at most 16), first bit in the lowest position
+    fn bits(&mut self, n: u32) -> Result<u32, GzipError> {
+        while self.count < n {
+            let byte = *self.data.get(self.pos).ok_or(GzipError::UnexpectedEof)?;
+            self.buffer |= u32::from(byte) << self.count;
+            self.pos += 1;
+            self.count += 8;
+        }
+        let value = self.buffer & ((1 << n) - 1);
+        self.buffer >>= n;
+        self.count -= n;
+        Ok(value)
+    }
+
+    /// Drops the bits left in the current byte, as stored blocks start byte-aligned
+    fn align(&mut self) {
+        let partial = self.count % 8;
+        self.buffer >>= partial;
+        self.count -= partial;
+    }
+}
+
+/// A canonical Huffman code, decoded one bit at a time
+struct Huffman {
+    /// Number of codes of each length
+    counts: [u16; MAX_CODE_BITS + 1],
+    /// Symbols ordered by code length, then by symbol
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    /// Builds the code from the code length of every symbol (0 = unused)
+    fn new(lengths: &[u8]) -> Result<Self, GzipError> {
+        let mut counts = [0u16; MAX_CODE_BITS + 1];
+        for &len in lengths {
+            counts[usize::from(len)] += 1;
+        }
+        counts[0] = 0;
+
+        // More codes of a length than the shorter ones leave room for is not a prefix code
+        let mut left: i32 = 1;
+        for &count in &counts[1..] {
+            left = (left << 1) - i32::from(count);
+            if left < 0 {
+                return Err(GzipError::InvalidData("over-subscribed Huffman code"));
+            }
+        }
+
+        let mut symbols: Vec<u16> = (0..lengths.len() as u16)
+            .filter(|&symbol| lengths[usize::from(symbol)] != 0)
+            .collect();
+        symbols.sort_by_key(|&symbol| lengths[usize::from(symbol)]);
+        Ok(Huffman { counts, symbols })
+    }
+
+    /// Reads one symbol
+    fn decode(&self, input: &mut BitReader<'_>) -> Result<u16, GzipError> {
+        let mut code: u32 = 0;
+        let mut first: u32 = 0;
+        let mut index: u32 = 0;
+        for &count in &self.counts[1..] {
+            code |= input.bits(1)?;
+            let count = u32::from(count);
+            if code < first + count {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err(GzipError::InvalidData("invalid Huffman code"))
+    }
+}
+
+/// Decodes deflate blocks into a bounded output buffer
+struct Inflater<'a> {
+    input: BitReader<'a>,
+    output: Vec<u8>,
+    limit: usize,
+}
+
+impl Inflater<'_> {
+    fn run(mut self) -> Result<Vec<u8>, GzipError> {
+        loop {
+            let last = self.input.bits(1)? == 1;
+            match self.input.bits(2)? {
+                0 => self.stored_block()?,
+                1 => {
+                    let (literals, distances) = fixed_codes()?;
+                    self.compressed_block(&literals, &distances)?;
+                }
+                2 => {
+                    let (literals, distances) = self.dynamic_codes()?;
+                    self.compressed_block(&literals, &distances)?;
+                }
+                _ => return Err(GzipError::InvalidData("invalid block type")),
+            }
+            if last {
+                return Ok(self.output);
+            }
+        }
+    }
+
+    fn push(&mut self, byte: u8) -> Result<(), GzipError> {
+        if self.output.len() >= self.limit {
+            return Err(GzipError::TooLarge(self.limit));
+        }
+        self.output.push(byte);
+        Ok(())
+    }
+
+    fn stored_block(&mut self) -> Result<(), GzipError> {
+        self.input.align();
+        let len = self.input.bits(16)?;
+        let complement = self.input.bits(16)?;
+        if len != !complement & 0xffff {
+            return Err(GzipError::InvalidData("stored block length mismatch"));
+        }
+        for _ in 0..len {
+            let byte = self.input.bits(8)? as u8;
+            self.push(byte)?;
+        }
+        Ok(())
+    }
+
+    fn compressed_block(
+        &mut self,
+        literals: &Huffman,
+        distances: &Huffman,
+    ) -> Result<(), GzipError> {
+        loop {
+            let symbol = usize::from(literals.decode(&mut self.input)?);
+            match symbol {
+                0..=255 => self.push(symbol as u8)?,
+                256 => return Ok(()),
+                _ => {
+                    let index = symbol - 257;
+                    if index >= LENGTH_BASE.len() {
+                        return Err(GzipError::InvalidData("invalid length symbol"));
+                    }
+                    let length = u32::from(LENGTH_BASE[index])
+                        + self.input.bits(u32::from(LENGTH_EXTRA[index]))?;
+
+                    let index = usize::from(distances.decode(&mut self.input)?);
+                    if index >= DISTANCE_BASE.len() {
+                        return Err(GzipError::InvalidData("invalid distance symbol"));
+                    }
+                    let distance = (u32::from(DISTANCE_BASE[index])
+                        + self.input.bits(u32::from(DISTANCE_EXTRA[index]))?)
+                        as usize;
+                    if distance > self.output.len() {
+                        return Err(GzipError::InvalidData("distance too far back"));
+                    }
+
+                    // Copy byte by byte, as the match may overlap the bytes it produces
+                    for _ in 0..length {
+                        let byte = self.output[self.output.len() - distance];
+                        self.push(byte)?;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Reads the code definitions at the start of a dynamic block
+    fn dynamic_codes(&mut self) -> Result<(Huffman, Huffman), GzipError> {
+        let literal_count = self.input.bits(5)? as usize + 257;
+        let distance_count = self.input.bits(5)? as usize + 1;
+        let code_length_count = self.input.bits(4)? as usize + 4;
+        if literal_count > 286 || distance_count > 30 {
+            return Err(GzipError::InvalidData("too many codes"));
+        }
+
+        let mut code_length_lengths = [0u8; 19];
+        for &symbol in &CODE_LENGTH_ORDER[..code_length_count] {
+            code_length_lengths[symbol] = self.input.bits(3)? as u8;
+        }
+        let code_lengths = Huffman::new(&code_length_lengths)?;
+
+        let mut lengths = Vec::with_capacity(literal_count + distance_count);
+        while lengths.len() < literal_count + distance_count {
+            let (len, repeat) = match code_lengths.decode(&mut self.input)? {
+                symbol @ 0..=15 => (symbol as u8, 1),
+                16 => {
+                    let previous = *lengths
+                        .last()
+                        .ok_or(GzipError::InvalidData("repeat with no previous length"))?;
+                    (previous, 3 + self.input.bits(2)?)
+                }
+                17 => (0, 3 + self.input.bits(3)?),
+                _ => (0, 11 + self.input.bits(7)?),
+            };
+            if lengths.len() + repeat as usize > literal_count + distance_count {
+                return Err(GzipError::InvalidData("too many code lengths"));
+            }
+            lengths.extend(std::iter::repeat_n(len, repeat as usize));
+        }
+        if lengths[256] == 0 {
+            return Err(GzipError::InvalidData("missing end-of-block code"));
+        }
+
+        Ok((
+            Huffman::new(&lengths[..literal_count])?,
+            Huffman::new(&lengths[literal_count..])?,
+        ))
+    }
+}
+
+/// Builds the codes of fixed Huffman blocks
+fn fixed_codes() -> Result<(Huffman, Huffman), GzipError> {
+    let mut literal_lengths = [8u8; 288];
+    literal_lengths[144..256].fill(9);
+    literal_lengths[256..280].fill(7);
+    Ok((Huffman::new(&literal_lengths)?, Huffman::new(&[5u8; 30])?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hyper::header::HeaderValue;
+
+    /// `hello hello hello`, compressed by gzip with a fixed Huffman block
+    const FIXED_HELLO: [u8; 28] = [
+        0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x03, 0xcb, 0x48, 0xcd, 0xc9, 0xc9,
+        0x57, 0xc8, 0x40, 0x90, 0x00, 0x80, 0x88, 0xf9, 0xe5, 0x11, 0x00, 0x00, 0x00,
+    ];
+
+    #[test]
+    fn test_decompresses_fixed_huffman_block() {
+        assert_eq!(
+            decompress(&FIXED_HELLO, 1024).unwrap(),
+            b"hello hello hello"
+        );
+    }
+
+    #[test]
+    fn test_decompresses_stored_block_after_optional_fields() {
+        let mut data = vec![0x1f, 0x8b, 0x08, FLAG_EXTRA | FLAG_NAME | FLAG_HCRC];
+        data.extend([0; 6]);
+        data.extend([2, 0, 0xaa, 0xbb]); // extra field
+        data.extend(b"body.txt\0");
+        data.extend([0x12, 0x34]); // header CRC
+        data.extend([0x01, 0x06, 0x00, 0xf9, 0xff]);
+        data.extend(b"stored");
+
+        assert_eq!(decompress(&data, 1024).unwrap(), b"stored");
+    }
+
+    #[test]
+    fn test_stops_at_limit() {
+        assert_eq!(decompress(&FIXED_HELLO, 5), Err(GzipError::TooLarge(5)));
+    }
+
+    #[test]
+    fn test_rejects_bad_input() {
+        assert_eq!(
+            decompress(b"{\"plain\":true}", 1024),
+            Err(GzipError::NotGzip)
+        );
+        assert_eq!(
+            decompress(&FIXED_HELLO[..15], 1024),
+            Err(GzipError::UnexpectedEof)
+        );
+    }
+
+    #[test]
+    fn test_detects_gzip_content_encoding() {
+        let mut headers = HeaderMap::new();
+        assert!(!is_gzip_encoded(&headers));
+        headers.insert(header::CONTENT_ENCODING, HeaderValue::from_static("GZIP"));
+        assert!(is_gzip_encoded(&headers));
+        headers.insert(
+            header::CONTENT_ENCODING,
+            HeaderValue::from_static("gzip, br"),
+        );
+        assert!(!is_gzip_encoded(&headers));
+    }
+}
@@ -0,0 +1,93 @@
+//! Health check endpoint for load balancers and orchestrators
+//!
+//! `GET /healthz` answers without contacting the upstream, so it only reports
+//! whether this process is serving. The basic response is deliberately cheap,
+//! reporting only the status and the git commit the binary was built from.
+//! With `?verbose=true` it also reports on the log subsystem, which otherwise
+//! fails silently (e.g. when the log directory stops being writable).
+
+use arc_swap::ArcSwap;
+use axum::{
+    body::{boxed, Full},
+    extract::Query,
+    http::StatusCode,
+    response::Response,
+    routing::get,
+    Router,
+};
+use hyper::header;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+use crate::build_info;
+use crate::config::Config;
+use crate::fs_utils;
+use crate::logger::{LogPathResolver, LogType};
+
+/// Path of the health check endpoint
+pub const HEALTH_PATH: &str = "/healthz";
+
+/// Query parameters accepted by the health check
+#[derive(Debug, Default, Deserialize)]
+struct HealthQuery {
+    /// Include log subsystem details
+    #[serde(default)]
+    verbose: bool,
+}
+
+/// Creates the router holding the health check endpoint
+///
+/// # Arguments
+///
+/// * `config` - The live configuration, used to locate the log file
+pub fn health_router(config: Arc<ArcSwap<Config>>) -> Router {
+    Router::new().route(
+        HEALTH_PATH,
+        get(move |Query(query): Query<HealthQuery>| {
+            let config = config.load_full();
+            async move { health_response(&config, query.verbose) }
+        }),
+    )
+}
+
+/// Builds the health check response, with log subsystem details when `verbose`
+fn health_response(config: &Config, verbose: bool) -> Response {
+    let mut body = json!({ "status": "ok", "build": build_info::build_json() });
+    if verbose {
+        body["logging"] = logging_status(config);
+    }
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(boxed(Full::from(body.to_string())))
+        // Static header values and an owned body cannot fail to build
+        .expect("health JSON response should always build")
+}
+
+/// Reports where application logs are written and whether their directory is writable
+///
+/// The path is computed without touching the filesystem beyond the writability check.
+pub fn logging_status(config: &Config) -> Value {
+    let log_path = match LogPathResolver::new(config, LogType::Application).resolve_readonly() {
+        Ok(path) => path,
+        Err(e) => {
+            return json!({
+                "log_path": null,
+                "writable": false,
+                "error": e.to_string(),
+            })
+        }
+    };
+
+    let writable = log_path
+        .parent()
+        .map(|dir| fs_utils::check_writable(dir).is_ok())
+        .unwrap_or(false);
+
+    json!({
+        "log_path": log_path.display().to_string(),
+        "writable": writable,
+    })
+}
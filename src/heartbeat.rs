@@ -0,0 +1,40 @@
+//! Periodic heartbeat log events
+//!
+//! In quiet periods a healthy proxy may log nothing for a long time, which looks
+//! the same as a hung process or a broken log pipeline. When enabled, a background
+//! task logs a `heartbeat` event at a fixed interval with the process uptime and
+//! the number of requests served so far.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+use tokio::time::MissedTickBehavior;
+use tracing::info;
+
+use crate::proxy_handler::ProxyState;
+
+/// Starts logging a heartbeat every `interval` until the returned task is aborted
+///
+/// The first heartbeat is logged one full interval after the call.
+///
+/// # Arguments
+/// * `interval` - Time between heartbeats; must be non-zero
+/// * `state` - Shared proxy state, read for the number of requests served
+pub fn spawn_heartbeat(interval: Duration, state: Arc<ProxyState>) -> JoinHandle<()> {
+    let started = Instant::now();
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        // The first tick completes immediately; skip it so startup isn't a heartbeat
+        ticker.tick().await;
+
+        loop {
+            ticker.tick().await;
+            info!(
+                uptime_secs = started.elapsed().as_secs(),
+                requests_served = state.requests_served(),
+                "heartbeat"
+            );
+        }
+    })
+}
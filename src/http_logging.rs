@@ -0,0 +1,1226 @@
+//! Structured logging of proxied requests and responses
+//!
+//! This module turns request/response metadata and bodies into structured log events.
+//! It is used by the proxy handler for every request, and by benchmarks and tests directly.
+//!
+//! Key features:
+//! - Sensitive headers (`Authorization`, `x-api-key`) are always redacted
+//! - Body logging can be disabled, size-limited, or reduced to a schema-only summary
+//! - Configured JSON body fields are redacted in logs (the forwarded body is untouched)
+//! - Configured query parameters are redacted in logged URLs (the forwarded URL is untouched)
+//! - JSON bodies are pretty-printed with a configurable indent, or logged compactly
+//! - Dynamically keyed fields (headers, JSON keys) are capped, marking `fields_truncated`
+//! - Control characters in logged bodies and header values are escaped (log injection)
+
+use bytes::Bytes;
+use hyper::header::{HeaderName, HeaderValue};
+use hyper::{header, HeaderMap, Uri};
+use serde::Serialize;
+use serde_json::Value;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::time::Duration;
+use tracing::{debug, info, info_span};
+
+use crate::config::{Config, REDACTED_VALUE};
+use crate::gzip::{self, GzipError};
+
+/// Options controlling how request/response bodies (and request URLs) are logged
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BodyLogOptions {
+    /// Whether body content (or its schema) is logged at all
+    pub log_bodies: bool,
+    /// Bodies larger than this many bytes only have their size logged
+    pub max_body_size: usize,
+    /// Log only the top-level JSON keys (and message count) instead of content
+    pub schema_only: bool,
+    /// Dotted JSON field paths (e.g. `metadata.user_id`) whose values are redacted
+    pub redact_fields: Vec<String>,
+    /// Spaces per indent level of logged JSON bodies (0 = single line)
+    pub json_indent: usize,
+    /// Query parameter names (case-insensitive) whose values are redacted in logged URLs
+    pub redact_query_params: Vec<String>,
+    /// Maximum number of dynamically keyed fields (headers, JSON keys) logged per event
+    pub max_span_fields: usize,
+    /// Whether control characters in logged bodies and header values are escaped
+    pub sanitize: bool,
+    /// Whether empty bodies get a "body empty" event (false = nothing is logged for them)
+    pub log_empty_bodies: bool,
+}
+
+impl BodyLogOptions {
+    /// Builds body logging options from the application configuration
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            log_bodies: config.log_bodies,
+            max_body_size: config.log_max_body_size,
+            schema_only: config.log_body_schema_only,
+            redact_fields: config.redact_body_fields.clone(),
+            json_indent: config.log_json_indent,
+            redact_query_params: config.redact_query_params.clone(),
+            max_span_fields: config.max_span_fields,
+            sanitize: config.sanitize_log_output,
+            log_empty_bodies: config.log_empty_bodies,
+        }
+    }
+}
+
+impl Default for BodyLogOptions {
+    fn default() -> Self {
+        Self::from_config(&Config::default())
+    }
+}
+
+/// Breakdown of where the time for a proxied response went
+///
+/// `upstream` runs from just before the upstream request is sent until its status
+/// and headers arrive; `body_read` covers reading the upstream body afterwards.
+/// Whatever remains of `total` is time spent in the proxy itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ResponseTimings {
+    /// Total time since the request arrived
+    pub total: Duration,
+    /// Time waiting for the upstream status and headers, if measured
+    pub upstream: Option<Duration>,
+    /// Time reading the upstream body, if measured
+    pub body_read: Option<Duration>,
+}
+
+impl From<Duration> for ResponseTimings {
+    /// Timings where only the total duration is known
+    fn from(total: Duration) -> Self {
+        Self {
+            total,
+            ..Self::default()
+        }
+    }
+}
+
+/// How a request or response body is represented in logs
+///
+/// Returned by [`format_body_for_log`], which holds every decision about a body's
+/// log representation, so the edge cases can be tested without a tracing subscriber.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BodyLogOutcome {
+    /// The body is empty
+    Empty,
+    /// The body is logged as `pretty`: formatted JSON, or the text as is
+    Content {
+        /// The text to log
+        pretty: String,
+    },
+    /// Body logging is disabled, so only the size is logged
+    SizeOnly,
+    /// The body is over the size limit, so only the size is logged
+    TooLarge,
+    /// The body is not text (a binary content type, or invalid UTF-8), so only the size is logged
+    Binary,
+}
+
+/// Decides how a body is logged, formatting it when its content is logged
+///
+/// Uses the default JSON indent, field redaction and sanitizing; see
+/// [`format_body_for_log_with_options`] for the configurable variant.
+///
+/// # Arguments
+/// * `body` - The raw body
+/// * `log_bodies` - Whether body content is logged at all
+/// * `max_size` - Bodies larger than this many bytes are not logged
+/// * `content_type` - The body's `Content-Type`, if any
+#[allow(dead_code)] // ALLOWANCE: Library API for tests and fuzzing; the proxy uses the options variant
+pub fn format_body_for_log(
+    body: &[u8],
+    log_bodies: bool,
+    max_size: usize,
+    content_type: Option<&str>,
+) -> BodyLogOutcome {
+    let options = BodyLogOptions {
+        log_bodies,
+        max_body_size: max_size,
+        ..BodyLogOptions::default()
+    };
+    format_body_for_log_with_options(body, content_type, &options)
+}
+
+/// Decides how a body is logged using the full set of body logging options
+///
+/// Behaves like [`format_body_for_log`], applying the options' JSON indent,
+/// field redaction and sanitizing to logged content. Schema-only logging is left
+/// to the caller.
+pub fn format_body_for_log_with_options(
+    body: &[u8],
+    content_type: Option<&str>,
+    options: &BodyLogOptions,
+) -> BodyLogOutcome {
+    if body.is_empty() {
+        BodyLogOutcome::Empty
+    } else if body.len() > options.max_body_size {
+        BodyLogOutcome::TooLarge
+    } else if !options.log_bodies {
+        BodyLogOutcome::SizeOnly
+    } else if content_type.is_some_and(is_binary_content_type) || std::str::from_utf8(body).is_err()
+    {
+        BodyLogOutcome::Binary
+    } else {
+        BodyLogOutcome::Content {
+            pretty: body_content_for_log(body, options),
+        }
+    }
+}
+
+/// Returns true if a `Content-Type` names media that is never readable as text
+fn is_binary_content_type(content_type: &str) -> bool {
+    let essence = content_type
+        .split(';')
+        .next()
+        .unwrap_or("")
+        .trim()
+        .to_ascii_lowercase();
+    ["image/", "audio/", "video/", "font/"]
+        .iter()
+        .any(|prefix| essence.starts_with(prefix))
+        || matches!(
+            essence.as_str(),
+            "application/octet-stream" | "application/pdf" | "application/zip"
+        )
+}
+
+/// Returns the `Content-Type` of a message, if it is valid text
+fn content_type(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+}
+
+/// Maximum length of request/response bodies that will be logged in full
+/// Bodies larger than this will only have their size logged to avoid excessive logging
+/// Increased from 10KB to 20KB to capture more verbose logging
+///
+/// @deprecated This constant is kept for backward compatibility but is no longer used.
+/// The `log_max_body_size` parameter from Config is used instead, which allows for
+/// configuration via environment variables.
+#[allow(dead_code)]
+pub const MAX_LOG_BODY_LEN: usize = 20 * 1024; // 20KB
+
+/// Logs details of an incoming request in a structured format
+///
+/// This function creates a new logging span and records comprehensive information about
+/// the request, including method, URI, headers (with sensitive values masked), and the
+/// request body (with size limits and JSON formatting when enabled).
+///
+/// # Log Format
+///
+/// This function produces log entries with the following structure:
+///
+/// 1. Basic request information at INFO level:
+///    - `http.method`: HTTP method (GET, POST, etc.)
+///    - `url.full`: Complete request URL
+///
+/// 2. Headers at DEBUG level:
+///    - `http.request.headers`: Map of all headers (sensitive values redacted)
+///
+/// 3. Body logging based on size and configuration:
+///    - If empty: "Request body empty" at INFO level, unless `log_empty_bodies` is false
+///    - If `log_bodies=true` and body size <= `log_max_body_size`:
+///      * Body content logged at DEBUG level with `http.request.body.content` and `http.request.body.size`
+///      * JSON bodies are pretty-printed for readability
+///      * Binary bodies (see [`BodyLogOutcome::Binary`]) get "Request body is binary, not logged"
+///        at DEBUG level with just `http.request.body.size`
+///    - If `log_bodies=false` and body size <= `log_max_body_size`:
+///      * "Request body not logged" at DEBUG level with just `http.request.body.size`
+///    - If body size > `log_max_body_size`:
+///      * "Request body too large to log fully" at INFO level with just `http.request.body.size`
+///    - A `Content-Length` header over `log_max_body_size` takes this path up front,
+///      without inspecting the body at all
+///
+/// # Security Notes
+///
+/// - Sensitive headers like `Authorization` and `x-api-key` are automatically redacted
+/// - Body logging can be disabled entirely via the `log_bodies` parameter
+/// - Body size limits prevent excessive logging with large payloads
+///
+/// # Arguments
+/// * `method` - The HTTP method (GET, POST, etc.)
+/// * `uri` - The request URI including path and query
+/// * `headers` - The request headers map
+/// * `body` - The request body as bytes
+/// * `log_bodies` - Boolean flag indicating whether to include full body content in logs
+/// * `log_max_body_size` - Maximum size in bytes for logged bodies before truncation
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use hyper::{Method, Uri, HeaderMap};
+/// use bytes::Bytes;
+/// use switchboard::http_logging::log_request_details;
+///
+/// // Create request components
+/// let method = Method::POST;
+/// let uri = Uri::from_static("https://example.com/v1/messages");
+/// let headers = HeaderMap::new();
+/// let body = Bytes::from(r#"{"message":"Hello world"}"#);
+///
+/// // Log request details
+/// log_request_details(&method, &uri, &headers, &body, true, 1024);
+/// ```
+#[allow(dead_code)] // ALLOWANCE: Library API used by tests and benches; the proxy uses the options variant
+pub fn log_request_details(
+    method: &hyper::Method,
+    uri: &Uri,
+    headers: &HeaderMap,
+    body: &Bytes,
+    log_bodies: bool,
+    log_max_body_size: usize,
+) {
+    let options = BodyLogOptions {
+        log_bodies,
+        max_body_size: log_max_body_size,
+        ..BodyLogOptions::default()
+    };
+    log_request_details_with_options(method, uri, headers, body, &options);
+}
+
+/// Logs details of an incoming request using the full set of body logging options
+///
+/// Behaves like [`log_request_details`], with additional options such as
+/// schema-only logging. When `options.schema_only` is set, JSON object bodies are
+/// logged as their top-level keys (`http.request.body.keys`) plus the number of
+/// entries in `messages` (`http.request.body.messages_count`) instead of content.
+///
+/// # Arguments
+/// * `method` - The HTTP method (GET, POST, etc.)
+/// * `uri` - The request URI including path and query
+/// * `headers` - The request headers map
+/// * `body` - The request body as bytes
+/// * `options` - Controls whether and how the body is logged
+pub fn log_request_details_with_options(
+    method: &hyper::Method,
+    uri: &Uri,
+    headers: &HeaderMap,
+    body: &Bytes,
+    options: &BodyLogOptions,
+) {
+    let log_bodies = options.log_bodies;
+    let log_max_body_size = options.max_body_size;
+
+    // Create a new span for the request details to keep them separate from the main request span
+    let span = info_span!("request_details");
+    let _enter = span.enter();
+
+    // Log basic request information at the info level
+    info!(
+        http.method = %method,
+        url.full = %redact_url(&uri.to_string(), &options.redact_query_params)
+    );
+
+    // Build a map of header names to values, masking sensitive headers
+    let (headers_log, truncated) =
+        headers_for_log(headers, options.max_span_fields, options.sanitize);
+
+    // Log all headers at debug level (won't show in normal operation)
+    debug!(
+        http.request.headers = ?headers_log,
+        fields_truncated = truncated.then_some(true)
+    );
+
+    // A declared size over the limit settles it before the body is looked at,
+    // sparing large uploads any JSON parsing and formatting
+    if let Some(declared_len) =
+        content_length(headers).filter(|&len| len > log_max_body_size as u64)
+    {
+        info!(
+            http.request.body.size = declared_len,
+            "Request body too large to log fully"
+        );
+        return;
+    }
+
+    // Log the request body with appropriate handling based on size
+    let body_len = body.len();
+
+    if body_len > 0 && log_bodies && body_len <= log_max_body_size && options.schema_only {
+        // Privacy-preserving mode: log the shape of the body, never its values
+        log_request_body_schema(body, options.max_span_fields);
+        return;
+    }
+
+    match format_body_for_log_with_options(body, content_type(headers), options) {
+        BodyLogOutcome::Empty => {
+            if options.log_empty_bodies {
+                info!("Request body empty");
+            }
+        }
+        // Log at DEBUG level even when explicitly enabled
+        BodyLogOutcome::Content { pretty } => debug!(
+            http.request.body.content = %pretty,
+            http.request.body.size = body_len
+        ),
+        // Small enough to log but logging not enabled - put in debug level
+        BodyLogOutcome::SizeOnly => debug!(
+            http.request.body.size = body_len,
+            "Request body not logged (enable LOG_BODIES to see contents)"
+        ),
+        BodyLogOutcome::TooLarge => info!(
+            http.request.body.size = body_len,
+            "Request body too large to log fully"
+        ),
+        BodyLogOutcome::Binary => debug!(
+            http.request.body.size = body_len,
+            "Request body is binary, not logged"
+        ),
+    }
+}
+
+/// Logs details of an API response in a structured format
+///
+/// This function creates a new logging span and records comprehensive information about
+/// the response, including status code, headers, response body (with size limits and
+/// JSON formatting when enabled), and timing metrics.
+///
+/// # Log Format
+///
+/// This function produces log entries with the following structure:
+///
+/// 1. Basic response information at INFO level:
+///    - `http.status_code`: Numeric HTTP status code
+///    - `status_text`: String representation of the status code
+///    - `duration_ms`: Request duration in milliseconds (if provided)
+///
+/// 2. Headers at DEBUG level:
+///    - `http.response.headers`: Map of all headers (sensitive values redacted)
+///
+/// 3. Body logging based on size and configuration:
+///    - If empty: "Response body empty" at INFO level, unless `log_empty_bodies` is false
+///    - If `log_bodies=true` and body size <= `log_max_body_size`:
+///      * Body content logged at DEBUG level with `http.response.body.content` and `http.response.body.size`
+///      * JSON bodies are pretty-printed for readability
+///      * Binary bodies (see [`BodyLogOutcome::Binary`]) get "Response body is binary, not logged"
+///        at DEBUG level with just `http.response.body.size`
+///      * Gzip-encoded bodies are logged decompressed, up to `log_max_body_size` decoded
+///        bytes, with `http.response.body.decompressed_size`
+///    - If `log_bodies=false` and body size <= `log_max_body_size`:
+///      * "Response body not logged" at DEBUG level with just `http.response.body.size`
+///    - If body size > `log_max_body_size`:
+///      * "Response body too large to log fully" at INFO level with just `http.response.body.size`
+///
+/// # Performance Metrics
+///
+/// When the `duration` parameter is provided, this function includes timing metrics in the
+/// log entry, which is useful for monitoring API response times. This timing data is always
+/// included at the INFO level regardless of body logging settings.
+///
+/// # Security Notes
+///
+/// - Sensitive headers like `Authorization` and `x-api-key` are automatically redacted
+/// - Body logging can be disabled entirely via the `log_bodies` parameter
+/// - Body size limits prevent excessive logging with large payloads
+///
+/// # Arguments
+/// * `status` - The HTTP status code
+/// * `headers` - The response headers map
+/// * `body` - The response body as bytes
+/// * `log_bodies` - Boolean flag indicating whether to include full body content in logs
+/// * `log_max_body_size` - Maximum size in bytes for logged bodies before truncation
+/// * `duration` - Optional duration of the request for timing metrics
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use hyper::HeaderMap;
+/// use reqwest::StatusCode;
+/// use bytes::Bytes;
+/// use std::time::Duration;
+/// use switchboard::http_logging::log_response_details;
+///
+/// // Create response components
+/// let status = StatusCode::OK;
+/// let headers = HeaderMap::new();
+/// let body = Bytes::from(r#"{"result":"success","data":{}}"#);
+/// let duration = Duration::from_millis(150);
+///
+/// // Log response details with timing
+/// log_response_details(&status, &headers, &body, true, 1024, Some(duration));
+/// ```
+#[allow(dead_code)] // ALLOWANCE: Library API used by tests and benches; the proxy uses the options variant
+pub fn log_response_details(
+    status: &reqwest::StatusCode,
+    headers: &HeaderMap,
+    body: &Bytes,
+    log_bodies: bool,
+    log_max_body_size: usize,
+    duration: Option<std::time::Duration>,
+) {
+    let options = BodyLogOptions {
+        log_bodies,
+        max_body_size: log_max_body_size,
+        ..BodyLogOptions::default()
+    };
+    log_response_details_with_options(
+        status,
+        headers,
+        body,
+        &options,
+        duration.map(ResponseTimings::from),
+    );
+}
+
+/// Logs details of an API response using the full set of body logging options
+///
+/// Behaves like [`log_response_details`], with additional options such as
+/// JSON field redaction. Schema-only logging applies to request bodies only.
+///
+/// # Arguments
+/// * `status` - The HTTP status code
+/// * `headers` - The response headers map
+/// * `body` - The response body as bytes
+/// * `options` - Controls whether and how the body is logged
+/// * `timings` - Optional total, upstream and body read durations
+pub fn log_response_details_with_options(
+    status: &reqwest::StatusCode,
+    headers: &HeaderMap,
+    body: &Bytes,
+    options: &BodyLogOptions,
+    timings: Option<ResponseTimings>,
+) {
+    let log_bodies = options.log_bodies;
+    let log_max_body_size = options.max_body_size;
+
+    // Create a new span for the response details to keep them separate from the main request span
+    let span = info_span!("response_details");
+    let _enter = span.enter();
+
+    // Log basic response information at the info level, including timing if available
+    if let Some(timings) = timings {
+        info!(
+            http.status_code = %status.as_u16(),
+            status_text = %status.canonical_reason().unwrap_or("Unknown"),
+            duration_ms = %timings.total.as_millis(),
+            upstream_ms = timings.upstream.map(|upstream| upstream.as_millis() as u64),
+            body_read_ms = timings.body_read.map(|body_read| body_read.as_millis() as u64)
+        );
+    } else {
+        info!(
+            http.status_code = %status.as_u16(),
+            status_text = %status.canonical_reason().unwrap_or("Unknown")
+        );
+    }
+
+    // Build a map of header names to values, masking sensitive headers
+    let (headers_log, truncated) =
+        headers_for_log(headers, options.max_span_fields, options.sanitize);
+
+    // Log all headers at debug level (won't show in normal operation)
+    debug!(
+        http.response.headers = ?headers_log,
+        fields_truncated = truncated.then_some(true)
+    );
+
+    // Log the response body with appropriate handling based on size
+    let body_len = body.len();
+
+    if body_len > 0 && log_bodies && body_len <= log_max_body_size && gzip::is_gzip_encoded(headers)
+    {
+        // Compressed bytes are unreadable in logs, so decode a copy for the log only
+        log_gzip_response_body(body, content_type(headers), options);
+        return;
+    }
+
+    match format_body_for_log_with_options(body, content_type(headers), options) {
+        BodyLogOutcome::Empty => {
+            if options.log_empty_bodies {
+                info!("Response body empty");
+            }
+        }
+        // Log at DEBUG level even when explicitly enabled
+        BodyLogOutcome::Content { pretty } => debug!(
+            http.response.body.content = %pretty,
+            http.response.body.size = body_len
+        ),
+        // Small enough to log but logging not enabled - put in debug level
+        BodyLogOutcome::SizeOnly => debug!(
+            http.response.body.size = body_len,
+            "Response body not logged (enable LOG_BODIES to see contents)"
+        ),
+        BodyLogOutcome::TooLarge => info!(
+            http.response.body.size = body_len,
+            "Response body too large to log fully"
+        ),
+        BodyLogOutcome::Binary => debug!(
+            http.response.body.size = body_len,
+            "Response body is binary, not logged"
+        ),
+    }
+}
+
+/// Logs a gzip-encoded response body decompressed, decoding at most the body size cap
+///
+/// `http.response.body.size` stays the size of the compressed body as received.
+fn log_gzip_response_body(body: &Bytes, content_type: Option<&str>, options: &BodyLogOptions) {
+    match gzip::decompress(body, options.max_body_size) {
+        Ok(decoded) => match format_body_for_log_with_options(&decoded, content_type, options) {
+            BodyLogOutcome::Content { pretty } => debug!(
+                http.response.body.decompressed_size = decoded.len(),
+                http.response.body.content = %pretty,
+                http.response.body.size = body.len(),
+                http.response.content_encoding = "gzip"
+            ),
+            // The decoded body is within the limit, so it is either empty or binary
+            _ => debug!(
+                http.response.body.decompressed_size = decoded.len(),
+                http.response.body.size = body.len(),
+                http.response.content_encoding = "gzip",
+                "Decompressed response body is empty or binary, not logged"
+            ),
+        },
+        Err(GzipError::TooLarge(_)) => info!(
+            http.response.body.size = body.len(),
+            http.response.content_encoding = "gzip",
+            "Decompressed response body too large to log fully"
+        ),
+        Err(e) => debug!(
+            error = %e,
+            http.response.body.size = body.len(),
+            "Could not decompress gzip response body for logging"
+        ),
+    }
+}
+
+/// Logs details of response headers for streaming responses
+///
+/// This function creates a new logging span and records the response status and headers,
+/// without attempting to log the body (since the body will be streamed). This is specifically
+/// designed for streaming responses where we want to log headers immediately before
+/// starting to stream the response body.
+///
+/// # Streaming-Specific Behavior
+///
+/// Unlike `log_response_details`, this function:
+/// - Does not attempt to log the response body (which will be streamed later)
+/// - Creates a dedicated span named "streaming_response_details"
+/// - Logs a message at INFO level indicating that streaming is beginning
+/// - Indicates whether full body logging is enabled for subsequent stream chunks
+///
+/// # Log Format
+///
+/// This function produces log entries with the following structure:
+///
+/// 1. Basic response information at INFO level:
+///    - `http.status_code`: Numeric HTTP status code
+///    - `status_text`: String representation of the status code
+///    - `duration_ms`: Request handling duration in milliseconds (if provided)
+///    - Message: "Starting streaming response"
+///
+/// 2. Headers at DEBUG level:
+///    - `http.response.headers`: Map of all headers (sensitive values redacted)
+///
+/// 3. Streaming notification at INFO level:
+///    - Message indicating that streaming is beginning, with logging status
+///
+/// # Use Case
+///
+/// Use this function instead of `log_response_details` when handling streaming responses,
+/// particularly with SSE (Server-Sent Events) or streaming APIs. It allows logging of the
+/// response metadata before the actual streaming begins, which is especially useful for
+/// timing metrics and initial response verification.
+///
+/// # Arguments
+/// * `status` - The HTTP status code of the response
+/// * `headers` - The response headers map
+/// * `log_bodies` - Boolean flag indicating whether to include full body content in logs
+/// * `duration` - Optional duration of the request for timing metrics
+/// * `max_fields` - Maximum number of headers logged (see [`cap_dynamic_fields`])
+/// * `sanitize` - Whether control characters in header values are escaped
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use hyper::HeaderMap;
+/// use reqwest::StatusCode;
+/// use std::time::Duration;
+/// use switchboard::http_logging::log_response_headers;
+///
+/// // Create streaming response components
+/// let status = StatusCode::OK;
+/// let headers = HeaderMap::new();
+/// let duration = Duration::from_millis(120);
+///
+/// // Log streaming response headers with timing
+/// log_response_headers(&status, &headers, true, Some(duration), 64, true);
+///
+/// // Begin streaming chunks...
+/// ```
+pub fn log_response_headers(
+    status: &reqwest::StatusCode,
+    headers: &HeaderMap,
+    log_bodies: bool,
+    duration: Option<std::time::Duration>,
+    max_fields: usize,
+    sanitize: bool,
+) {
+    // Create a new span for the streaming response details
+    let span = info_span!("streaming_response_details");
+    let _enter = span.enter();
+
+    // Log that streaming is starting, with timing if available
+    if let Some(dur) = duration {
+        info!(
+            http.status_code = %status.as_u16(),
+            status_text = %status.canonical_reason().unwrap_or("Unknown"),
+            duration_ms = %dur.as_millis(),
+            "Starting streaming response"
+        );
+    } else {
+        info!(
+            http.status_code = %status.as_u16(),
+            status_text = %status.canonical_reason().unwrap_or("Unknown"),
+            "Starting streaming response"
+        );
+    }
+
+    // Build a map of header names to values, masking sensitive headers
+    let (headers_log, truncated) = headers_for_log(headers, max_fields, sanitize);
+
+    // Log all headers at debug level (won't show in normal operation)
+    debug!(
+        http.response.headers = ?headers_log,
+        fields_truncated = truncated.then_some(true)
+    );
+
+    // Log a message indicating that we're about to start streaming
+    if log_bodies {
+        info!("Headers logged, beginning to stream response body (full logging enabled)");
+    } else {
+        info!("Headers logged, beginning to stream response body (content logging disabled)");
+    }
+}
+
+/// Builds a loggable map of header names to values
+///
+/// Sensitive authentication headers are redacted. Values that aren't valid UTF-8
+/// are shown as `[binary:<N> bytes]` rather than lossily converted, so binary
+/// content is visible as such instead of silently turning into replacement characters.
+/// At most `max_fields` headers are included; the flag reports whether any were dropped.
+/// With `sanitize`, control characters in values are escaped (see [`sanitize_for_log`]).
+fn headers_for_log(
+    headers: &HeaderMap,
+    max_fields: usize,
+    sanitize: bool,
+) -> (HashMap<String, String>, bool) {
+    let (headers_log, truncated) = cap_dynamic_fields(
+        headers.iter().map(|(name, value)| {
+            let value = header_value_for_log(name, value);
+            let value = if sanitize {
+                sanitize_for_log(&value).into_owned()
+            } else {
+                value
+            };
+            (name.to_string(), value)
+        }),
+        max_fields,
+    );
+    (headers_log.into_iter().collect(), truncated)
+}
+
+/// Caps dynamically keyed log fields (header names, JSON keys) at `max` entries
+///
+/// Every recorder of per-key fields goes through here, so a request carrying
+/// thousands of headers or keys cannot blow up log cardinality. Callers log
+/// `fields_truncated = true` alongside the fields when some were dropped.
+///
+/// # Returns
+/// The first `max` fields, and whether any were dropped
+pub fn cap_dynamic_fields<T>(fields: impl IntoIterator<Item = T>, max: usize) -> (Vec<T>, bool) {
+    let mut fields = fields.into_iter();
+    let kept: Vec<T> = fields.by_ref().take(max).collect();
+    let truncated = fields.next().is_some();
+    (kept, truncated)
+}
+
+/// Renders a single header value for logging (see [`headers_for_log`])
+fn header_value_for_log(name: &HeaderName, value: &HeaderValue) -> String {
+    // Mask sensitive authentication headers
+    if name == header::AUTHORIZATION || name == "x-api-key" {
+        return "[REDACTED]".to_string();
+    }
+
+    match std::str::from_utf8(value.as_bytes()) {
+        Ok(text) => text.to_string(),
+        Err(_) => format!("[binary:{} bytes]", value.as_bytes().len()),
+    }
+}
+
+/// Escapes control characters other than newline and tab for safe display in logs
+///
+/// Bodies may carry ANSI escape sequences (or C1 controls, which header values can
+/// also smuggle in as UTF-8) that would drive the terminal showing the logs. Each
+/// such character is replaced by its `\u{..}` escape, so it stays visible.
+pub fn sanitize_for_log(text: &str) -> Cow<'_, str> {
+    let needs_escape = |c: char| c.is_control() && c != '\n' && c != '\t';
+    if !text.contains(needs_escape) {
+        return Cow::Borrowed(text);
+    }
+
+    let mut sanitized = String::with_capacity(text.len() + 8);
+    for c in text.chars() {
+        if needs_escape(c) {
+            sanitized.extend(c.escape_unicode());
+        } else {
+            sanitized.push(c);
+        }
+    }
+    Cow::Owned(sanitized)
+}
+
+/// Parses the Content-Length header, if present and valid
+pub fn content_length(headers: &HeaderMap) -> Option<u64> {
+    headers
+        .get(header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<u64>().ok())
+}
+
+/// Returns `url` with the values of the named query parameters redacted
+///
+/// Only the part after `?` is touched; see [`redact_query`].
+pub fn redact_url<'a>(url: &'a str, params: &[String]) -> Cow<'a, str> {
+    match url.split_once('?') {
+        Some((base, query)) => match redact_query(query, params) {
+            Cow::Borrowed(_) => Cow::Borrowed(url),
+            Cow::Owned(query) => Cow::Owned(format!("{}?{}", base, query)),
+        },
+        None => Cow::Borrowed(url),
+    }
+}
+
+/// Returns a query string with the values of the named parameters redacted
+///
+/// Names are compared case-insensitively and as written (not percent-decoded).
+/// `token=abc` becomes `token=[REDACTED]`; other parameters, their order and
+/// parameters without a value are left unchanged.
+pub fn redact_query<'a>(query: &'a str, params: &[String]) -> Cow<'a, str> {
+    let is_redacted = |pair: &str| {
+        pair.split_once('=')
+            .is_some_and(|(name, _)| params.iter().any(|param| param.eq_ignore_ascii_case(name)))
+    };
+    if !query.split('&').any(is_redacted) {
+        return Cow::Borrowed(query);
+    }
+
+    let pairs: Vec<Cow<str>> = query
+        .split('&')
+        .map(|pair| match pair.split_once('=') {
+            Some((name, _)) if is_redacted(pair) => {
+                Cow::Owned(format!("{}={}", name, REDACTED_VALUE))
+            }
+            _ => Cow::Borrowed(pair),
+        })
+        .collect();
+    Cow::Owned(pairs.join("&"))
+}
+
+/// Renders a body for logging, formatting JSON with configured fields redacted
+///
+/// JSON is indented by `options.json_indent` spaces per level, or compact when 0.
+/// Bodies that aren't valid JSON are logged as (lossy) text, unchanged. With
+/// `options.sanitize`, control characters are escaped either way (serde_json
+/// escapes only those below U+0020).
+fn body_content_for_log(body: &[u8], options: &BodyLogOptions) -> String {
+    let content = match serde_json::from_slice::<Value>(body) {
+        Ok(mut json_val) => {
+            for path in &options.redact_fields {
+                let segments: Vec<&str> = path.split('.').collect();
+                redact_json_path(&mut json_val, &segments);
+            }
+            json_for_log(&json_val, options.json_indent)
+                .unwrap_or_else(|_| String::from_utf8_lossy(body).to_string())
+        }
+        Err(_) => String::from_utf8_lossy(body).to_string(),
+    };
+    if options.sanitize {
+        sanitize_for_log(&content).into_owned()
+    } else {
+        content
+    }
+}
+
+/// Serializes JSON indented by `indent` spaces per level, or on a single line when 0
+fn json_for_log(value: &Value, indent: usize) -> serde_json::Result<String> {
+    if indent == 0 {
+        return serde_json::to_string(value);
+    }
+
+    let indent = " ".repeat(indent);
+    let mut buffer = Vec::new();
+    let formatter = serde_json::ser::PrettyFormatter::with_indent(indent.as_bytes());
+    let mut serializer = serde_json::Serializer::with_formatter(&mut buffer, formatter);
+    value.serialize(&mut serializer)?;
+    // serde_json only writes valid UTF-8
+    Ok(String::from_utf8(buffer).expect("serialized JSON should be valid UTF-8"))
+}
+
+/// Replaces the value at a dotted field path with the redaction marker
+///
+/// Arrays are walked transparently, so `tools.api_key` matches the `api_key` of
+/// every entry in a `tools` array. Missing fields are left alone.
+fn redact_json_path(value: &mut Value, segments: &[&str]) {
+    match value {
+        Value::Array(items) => {
+            for item in items {
+                redact_json_path(item, segments);
+            }
+        }
+        Value::Object(fields) => {
+            let Some((first, rest)) = segments.split_first() else {
+                return;
+            };
+            if let Some(child) = fields.get_mut(*first) {
+                if rest.is_empty() {
+                    *child = Value::String(REDACTED_VALUE.to_string());
+                } else {
+                    redact_json_path(child, rest);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Logs the top-level keys of a JSON request body without any values
+///
+/// Non-JSON bodies (and JSON that isn't an object) are not logged at all in
+/// schema-only mode, since there is no shape to report without exposing content.
+fn log_request_body_schema(body: &Bytes, max_fields: usize) {
+    let body_len = body.len();
+
+    let Ok(Value::Object(fields)) = serde_json::from_slice::<Value>(body) else {
+        debug!(
+            http.request.body.size = body_len,
+            "Request body not logged (schema-only logging applies to JSON objects)"
+        );
+        return;
+    };
+
+    let (keys, truncated) = cap_dynamic_fields(fields.keys().map(String::as_str), max_fields);
+    let fields_truncated = truncated.then_some(true);
+
+    // The message count is the most useful shape signal for Messages API requests
+    match fields.get("messages").and_then(Value::as_array) {
+        Some(messages) => debug!(
+            http.request.body.keys = ?keys,
+            http.request.body.messages_count = messages.len(),
+            http.request.body.size = body_len,
+            fields_truncated,
+            "Request body schema"
+        ),
+        None => debug!(
+            http.request.body.keys = ?keys,
+            http.request.body.size = body_len,
+            fields_truncated,
+            "Request body schema"
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DEFAULT_MAX_SPAN_FIELDS;
+
+    #[test]
+    fn test_non_utf8_header_values_logged_as_binary() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "x-binary",
+            HeaderValue::from_bytes(&[0x66, 0x6f, 0xff, 0xfe]).unwrap(),
+        );
+        headers.insert("x-text", HeaderValue::from_static("plain"));
+        headers.insert(header::AUTHORIZATION, HeaderValue::from_static("Bearer k"));
+
+        let (logged, truncated) = headers_for_log(&headers, DEFAULT_MAX_SPAN_FIELDS, true);
+        assert!(!truncated);
+
+        assert_eq!(logged["x-binary"], "[binary:4 bytes]");
+        assert_eq!(logged["x-text"], "plain");
+        assert_eq!(logged["authorization"], "[REDACTED]");
+    }
+
+    #[test]
+    fn test_cap_dynamic_fields_reports_truncation() {
+        assert_eq!(cap_dynamic_fields(0..3, 3), (vec![0, 1, 2], false));
+        assert_eq!(cap_dynamic_fields(0..10, 3), (vec![0, 1, 2], true));
+        assert_eq!(cap_dynamic_fields(0..10, 0), (vec![], true));
+    }
+
+    #[test]
+    fn test_redact_body_fields_top_level_and_nested() {
+        let body = Bytes::from(
+            r#"{"api_key":"sk-secret","model":"claude","metadata":{"user_id":"u-123","tag":"keep"}}"#,
+        );
+        let options = BodyLogOptions {
+            redact_fields: vec!["api_key".to_string(), "metadata.user_id".to_string()],
+            ..BodyLogOptions::default()
+        };
+
+        let logged: Value = serde_json::from_str(&body_content_for_log(&body, &options)).unwrap();
+
+        assert_eq!(logged["api_key"], REDACTED_VALUE);
+        assert_eq!(logged["metadata"]["user_id"], REDACTED_VALUE);
+        assert_eq!(logged["model"], "claude");
+        assert_eq!(logged["metadata"]["tag"], "keep");
+    }
+
+    #[test]
+    fn test_redact_body_fields_walks_arrays() {
+        let body = Bytes::from(r#"{"tools":[{"name":"a","api_key":"k1"},{"name":"b"}]}"#);
+        let options = BodyLogOptions {
+            redact_fields: vec!["tools.api_key".to_string()],
+            ..BodyLogOptions::default()
+        };
+
+        let logged: Value = serde_json::from_str(&body_content_for_log(&body, &options)).unwrap();
+
+        assert_eq!(logged["tools"][0]["api_key"], REDACTED_VALUE);
+        assert_eq!(logged["tools"][0]["name"], "a");
+        assert!(logged["tools"][1].get("api_key").is_none());
+    }
+
+    #[test]
+    fn test_json_bodies_logged_with_configured_indent() {
+        let body = Bytes::from(r#"{"model":"claude","messages":[{"role":"user"}]}"#);
+        let logged = |json_indent| {
+            let options = BodyLogOptions {
+                json_indent,
+                ..BodyLogOptions::default()
+            };
+            body_content_for_log(&body, &options)
+        };
+
+        // 0 logs the body compactly on one line
+        assert_eq!(
+            logged(0),
+            r#"{"messages":[{"role":"user"}],"model":"claude"}"#
+        );
+
+        // The default indent matches serde_json's pretty printer
+        let expected_two = "{\n  \"messages\": [\n    {\n      \"role\": \"user\"\n    }\n  ],\n  \"model\": \"claude\"\n}";
+        assert_eq!(BodyLogOptions::default().json_indent, 2);
+        assert_eq!(logged(2), expected_two);
+
+        let expected_four = "{\n    \"messages\": [\n        {\n            \"role\": \"user\"\n        }\n    ],\n    \"model\": \"claude\"\n}";
+        assert_eq!(logged(4), expected_four);
+    }
+
+    #[test]
+    fn test_redact_query_replaces_matching_params_only() {
+        let params = vec!["token".to_string(), "SIG".to_string()];
+
+        assert_eq!(
+            redact_query("token=abc&model=claude&sig=x1&flag", &params),
+            "token=[REDACTED]&model=claude&sig=[REDACTED]&flag"
+        );
+        assert_eq!(
+            redact_url("/v1/messages?Token=abc&beta=true", &params),
+            "/v1/messages?Token=[REDACTED]&beta=true"
+        );
+
+        // Nothing to redact leaves the input borrowed and unchanged
+        assert!(matches!(
+            redact_query("model=claude&token_count=3", &params),
+            Cow::Borrowed("model=claude&token_count=3")
+        ));
+        assert_eq!(redact_url("/v1/messages", &params), "/v1/messages");
+    }
+
+    #[test]
+    fn test_sanitize_for_log_escapes_control_characters() {
+        assert!(matches!(
+            sanitize_for_log("line one\n\tindented"),
+            Cow::Borrowed("line one\n\tindented")
+        ));
+        assert_eq!(
+            sanitize_for_log("\x1b[31mred\x1b[0m\r\u{9b}"),
+            "\\u{1b}[31mred\\u{1b}[0m\\u{d}\\u{9b}"
+        );
+    }
+
+    #[test]
+    fn test_format_body_for_log_empty() {
+        assert_eq!(
+            format_body_for_log(b"", true, 10, None),
+            BodyLogOutcome::Empty
+        );
+        // Empty wins over every other setting
+        assert_eq!(
+            format_body_for_log(b"", false, 0, None),
+            BodyLogOutcome::Empty
+        );
+        assert_eq!(
+            format_body_for_log(b"", true, 10, Some("image/png")),
+            BodyLogOutcome::Empty
+        );
+    }
+
+    #[test]
+    fn test_format_body_for_log_size_limit_is_inclusive() {
+        let body = b"0123456789";
+        assert_eq!(
+            format_body_for_log(body, true, 10, None),
+            BodyLogOutcome::Content {
+                pretty: "0123456789".to_string()
+            }
+        );
+        assert_eq!(
+            format_body_for_log(body, true, 9, None),
+            BodyLogOutcome::TooLarge
+        );
+        // Too large is reported even when content would not be logged
+        assert_eq!(
+            format_body_for_log(body, false, 9, None),
+            BodyLogOutcome::TooLarge
+        );
+        assert_eq!(
+            format_body_for_log(b"x", true, 0, None),
+            BodyLogOutcome::TooLarge
+        );
+    }
+
+    #[test]
+    fn test_format_body_for_log_size_only_when_disabled() {
+        assert_eq!(
+            format_body_for_log(br#"{"a":1}"#, false, 100, Some("application/json")),
+            BodyLogOutcome::SizeOnly
+        );
+        assert_eq!(
+            format_body_for_log(&[0xff, 0xfe], false, 100, None),
+            BodyLogOutcome::SizeOnly
+        );
+    }
+
+    #[test]
+    fn test_format_body_for_log_formats_json_and_keeps_other_text() {
+        assert_eq!(
+            format_body_for_log(br#"{"b":1,"a":[true]}"#, true, 100, None),
+            BodyLogOutcome::Content {
+                pretty: "{\n  \"a\": [\n    true\n  ],\n  \"b\": 1\n}".to_string()
+            }
+        );
+        // Invalid JSON, even when declared as JSON, is logged as the text it is
+        assert_eq!(
+            format_body_for_log(b"{\"truncated\":", true, 100, Some("application/json")),
+            BodyLogOutcome::Content {
+                pretty: "{\"truncated\":".to_string()
+            }
+        );
+        assert_eq!(
+            format_body_for_log("héllo wörld".as_bytes(), true, 100, Some("text/plain")),
+            BodyLogOutcome::Content {
+                pretty: "héllo wörld".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_format_body_for_log_sanitizes_content() {
+        assert_eq!(
+            format_body_for_log(b"a\x1b[2Jb", true, 100, None),
+            BodyLogOutcome::Content {
+                pretty: "a\\u{1b}[2Jb".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_format_body_for_log_binary() {
+        // Invalid UTF-8 is never logged lossily
+        assert_eq!(
+            format_body_for_log(&[0x66, 0x6f, 0xff], true, 100, None),
+            BodyLogOutcome::Binary
+        );
+        // A truncated multi-byte character is invalid UTF-8 too
+        assert_eq!(
+            format_body_for_log(&"é".as_bytes()[..1], true, 100, None),
+            BodyLogOutcome::Binary
+        );
+        // Binary media types are skipped even when the bytes happen to be valid UTF-8
+        for content_type in [
+            "image/png",
+            "AUDIO/ogg",
+            "video/mp4",
+            "font/woff2",
+            "application/octet-stream; charset=binary",
+            "application/pdf",
+            "application/zip",
+        ] {
+            assert_eq!(
+                format_body_for_log(b"plain", true, 100, Some(content_type)),
+                BodyLogOutcome::Binary,
+                "{}",
+                content_type
+            );
+        }
+        // Textual types are not
+        for content_type in ["application/json", "text/event-stream", "application/xml"] {
+            assert!(matches!(
+                format_body_for_log(b"plain", true, 100, Some(content_type)),
+                BodyLogOutcome::Content { .. }
+            ));
+        }
+    }
+
+    #[test]
+    fn test_format_body_for_log_with_options_applies_redaction() {
+        let options = BodyLogOptions {
+            redact_fields: vec!["api_key".to_string()],
+            json_indent: 0,
+            ..BodyLogOptions::default()
+        };
+        assert_eq!(
+            format_body_for_log_with_options(br#"{"api_key":"k","n":1}"#, None, &options),
+            BodyLogOutcome::Content {
+                pretty: format!(r#"{{"api_key":"{}","n":1}}"#, REDACTED_VALUE)
+            }
+        );
+    }
+
+    #[test]
+    fn test_format_body_for_log_random_bodies_hold_invariants() {
+        use rand::{Rng, SeedableRng};
+
+        const ALPHABET: &[u8] = b"{}[]\":,0123 abcnulltrue\\";
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0x5eed);
+        for _ in 0..2000 {
+            let len = rng.gen_range(0..64);
+            let body: Vec<u8> = (0..len)
+                .map(|_| {
+                    // Mostly JSON-ish ASCII, with the occasional arbitrary byte
+                    if rng.gen_bool(0.9) {
+                        ALPHABET[rng.gen_range(0..ALPHABET.len())]
+                    } else {
+                        rng.gen()
+                    }
+                })
+                .collect();
+            let max_size = rng.gen_range(0..80);
+            let log_bodies = rng.gen_bool(0.8);
+
+            match format_body_for_log(&body, log_bodies, max_size, None) {
+                BodyLogOutcome::Empty => assert!(body.is_empty()),
+                BodyLogOutcome::TooLarge => assert!(body.len() > max_size),
+                BodyLogOutcome::SizeOnly => assert!(!log_bodies && body.len() <= max_size),
+                BodyLogOutcome::Binary => assert!(std::str::from_utf8(&body).is_err()),
+                BodyLogOutcome::Content { pretty } => {
+                    assert!(log_bodies && !body.is_empty() && body.len() <= max_size);
+                    assert!(!pretty
+                        .chars()
+                        .any(|c| c.is_control() && c != '\n' && c != '\t'));
+                }
+            }
+        }
+    }
+}
@@ -1,8 +1,34 @@
 // Switchboard library entry point
 
 // Re-export modules for use in integration tests and the main binary
+pub mod admin;
+pub mod backoff;
+pub mod build_info;
+pub mod concurrency_limit;
 pub mod config;
+pub mod cors;
+pub mod drain;
+pub mod forwarded;
 pub mod fs_utils;
+pub mod gzip;
+pub mod health;
+pub mod heartbeat;
+pub mod http_logging;
+pub mod listener;
 pub mod log_cleanup;
+pub mod log_level_override;
+pub mod log_naming;
+pub mod log_reorder;
 pub mod logger;
+pub mod memory_budget;
 pub mod proxy_handler;
+pub mod rate_limit;
+pub mod request_id;
+pub mod response_cache;
+pub mod shutdown;
+pub mod sse;
+pub mod tls;
+pub mod trace_context;
+pub mod upstream_ip;
+pub mod warmup;
+pub mod websocket;
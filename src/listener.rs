@@ -0,0 +1,167 @@
+//! Binding the listen socket, with retries while the port is still in use
+//!
+//! In orchestrated environments a replacement instance often starts while the
+//! previous one is still shutting down and holding the port. Rather than failing
+//! immediately, binding is retried a few times on `AddrInUse`. Any other error
+//! (e.g. permission denied) will not go away by waiting, so it fails fast.
+//!
+//! The socket is built with `socket2` so the listen backlog and `TCP_NODELAY`
+//! can be configured; accepted connections inherit `TCP_NODELAY` from it.
+
+use socket2::{Domain, Protocol, Socket, Type};
+use std::io;
+use std::net::SocketAddr;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tracing::{info_span, Instrument};
+
+use crate::backoff::{retry_async, Backoff, RetryPolicy};
+use crate::config::Config;
+
+/// Backlog used when none is configured, the same one tokio's `TcpListener::bind` uses
+pub const FALLBACK_LISTEN_BACKLOG: u32 = 1024;
+
+/// Socket options applied to the listen socket
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ListenOptions {
+    /// Queue length for connections not yet accepted (None = `FALLBACK_LISTEN_BACKLOG`)
+    pub backlog: Option<u32>,
+    /// Whether `TCP_NODELAY` is set
+    pub nodelay: bool,
+}
+
+impl ListenOptions {
+    /// Builds listen options from the application configuration
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            backlog: config.listen_backlog,
+            nodelay: config.tcp_nodelay,
+        }
+    }
+}
+
+/// Returns true if a bind error is worth retrying (the port may be released soon)
+pub fn should_retry_bind(error: &io::Error) -> bool {
+    error.kind() == io::ErrorKind::AddrInUse
+}
+
+/// Creates a listening socket on `addr` with the given options
+///
+/// Mirrors what `TcpListener::bind` does (including `SO_REUSEADDR` on Unix, so a
+/// restart is not blocked by connections in `TIME_WAIT`), plus the options.
+///
+/// # Returns
+/// The listening socket, not yet registered with the tokio runtime
+pub fn bind_socket(addr: SocketAddr, options: ListenOptions) -> io::Result<Socket> {
+    let socket = Socket::new(Domain::for_address(addr), Type::STREAM, Some(Protocol::TCP))?;
+    #[cfg(unix)]
+    socket.set_reuse_address(true)?;
+    socket.set_nodelay(options.nodelay)?;
+    socket.set_nonblocking(true)?;
+    socket.bind(&addr.into())?;
+
+    // The OS silently caps the backlog (e.g. at net.core.somaxconn on Linux)
+    let backlog = options.backlog.unwrap_or(FALLBACK_LISTEN_BACKLOG);
+    socket.listen(i32::try_from(backlog).unwrap_or(i32::MAX))?;
+    Ok(socket)
+}
+
+/// Binds `addr`, retrying up to `retry_attempts` times on `AddrInUse`
+///
+/// Retries are logged by [`retry_async`] within a `bind` span carrying the address.
+///
+/// # Arguments
+/// * `addr` - Address to listen on
+/// * `options` - Backlog and `TCP_NODELAY` for the socket
+/// * `retry_attempts` - Retries after the first failed attempt (0 = no retries)
+/// * `retry_delay` - Wait between attempts
+///
+/// # Returns
+/// The bound listener, or the last bind error
+pub async fn bind_with_retry(
+    addr: SocketAddr,
+    options: ListenOptions,
+    retry_attempts: u32,
+    retry_delay: Duration,
+) -> io::Result<TcpListener> {
+    let policy = RetryPolicy::new(retry_attempts, Backoff::constant(retry_delay));
+    retry_async(&policy, should_retry_bind, || async move {
+        bind_socket(addr, options).and_then(|socket| TcpListener::from_std(socket.into()))
+    })
+    .instrument(info_span!("bind", addr = %addr))
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_addr_in_use_is_retried() {
+        assert!(should_retry_bind(&io::Error::from(
+            io::ErrorKind::AddrInUse
+        )));
+        assert!(!should_retry_bind(&io::Error::from(
+            io::ErrorKind::PermissionDenied
+        )));
+        assert!(!should_retry_bind(&io::Error::from(
+            io::ErrorKind::AddrNotAvailable
+        )));
+    }
+
+    #[tokio::test]
+    async fn test_bind_succeeds_once_port_is_released() {
+        let holder = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = holder.local_addr().unwrap();
+
+        // Release the port while the bind is being retried
+        let release = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            drop(holder);
+        });
+
+        let listener = bind_with_retry(
+            addr,
+            ListenOptions::default(),
+            20,
+            Duration::from_millis(20),
+        )
+        .await
+        .expect("Bind should succeed after the port is released");
+        assert_eq!(listener.local_addr().unwrap(), addr);
+        release.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_bind_gives_up_after_retries() {
+        let holder = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = holder.local_addr().unwrap();
+
+        let err = bind_with_retry(addr, ListenOptions::default(), 2, Duration::from_millis(10))
+            .await
+            .expect_err("Bind should fail while the port is held");
+        assert_eq!(err.kind(), io::ErrorKind::AddrInUse);
+    }
+
+    #[tokio::test]
+    async fn test_socket_applies_nodelay_and_backlog() {
+        let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+
+        let socket = bind_socket(
+            addr,
+            ListenOptions {
+                backlog: Some(16),
+                nodelay: true,
+            },
+        )
+        .unwrap();
+        assert!(socket.nodelay().unwrap());
+
+        let socket = bind_socket(addr, ListenOptions::default()).unwrap();
+        assert!(!socket.nodelay().unwrap());
+
+        // The configured socket is usable as a tokio listener
+        let listener = TcpListener::from_std(socket.into()).unwrap();
+        assert!(listener.local_addr().unwrap().port() > 0);
+    }
+}
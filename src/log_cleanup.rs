@@ -8,12 +8,22 @@
 //! - Handles both app and test log directories
 //! - Can be triggered either at startup or via CLI flag
 //! - Provides detailed reporting on what files were cleaned up
+//! - Removes empty subdirectories left behind after cleanup
+//! - `cleanup_logs_in_dir` cleans any directory, for embedders with their own log layout
+//! - Several directories are cleaned concurrently, with the same aggregate result as one by one
+//! - `directory_usage` reports the disk used by the log directories, for capacity planning
 
 use crate::config::Config;
-use crate::logger::{APP_LOG_SUBDIR, DEFAULT_LOG_DIR, TEST_LOG_SUBDIR};
-use chrono::{DateTime, Duration, Local};
+use crate::log_naming;
+use crate::logger::{LogPathResolver, LogType, APP_LOG_SUBDIR, DEFAULT_LOG_DIR, TEST_LOG_SUBDIR};
+use chrono::{DateTime, Local};
+use serde::Serialize;
+use std::collections::BTreeMap;
 use std::fs;
+use std::panic;
 use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, SystemTime};
 use tracing::{debug, info, warn};
 
 /// Results from the log cleanup operation
@@ -83,34 +93,28 @@ pub fn cleanup_logs(config: &Config) -> CleanupResult {
     };
 
     info!(max_age_days, "Starting log cleanup");
+    let max_age = Duration::from_secs(u64::from(max_age_days) * SECS_PER_DAY);
 
     // Initialize the cleanup result
     let mut result = CleanupResult::new();
 
-    // Clean up app logs
-    let app_dir = PathBuf::from(DEFAULT_LOG_DIR).join(APP_LOG_SUBDIR);
-    if app_dir.exists() {
-        let app_result = cleanup_directory(&app_dir, max_age_days);
-        info!(
-            directory = %app_dir.display(),
-            files_removed = app_result.files_removed,
-            bytes_removed = app_result.bytes_removed,
-            "Cleaned up app logs"
-        );
-        result.merge(app_result);
-    }
-
-    // Clean up test logs
-    let test_dir = PathBuf::from(DEFAULT_LOG_DIR).join(TEST_LOG_SUBDIR);
-    if test_dir.exists() {
-        let test_result = cleanup_directory(&test_dir, max_age_days);
+    // Clean up the app and test logs concurrently
+    let dirs: Vec<PathBuf> = [APP_LOG_SUBDIR, TEST_LOG_SUBDIR]
+        .iter()
+        .map(|subdir| PathBuf::from(DEFAULT_LOG_DIR).join(subdir))
+        .filter(|dir| dir.exists())
+        .collect();
+    for (dir, dir_result) in dirs
+        .iter()
+        .zip(cleanup_dirs_concurrently(&dirs, max_age, false))
+    {
         info!(
-            directory = %test_dir.display(),
-            files_removed = test_result.files_removed,
-            bytes_removed = test_result.bytes_removed,
-            "Cleaned up test logs"
+            directory = %dir.display(),
+            files_removed = dir_result.files_removed,
+            bytes_removed = dir_result.bytes_removed,
+            "Cleaned up log directory"
         );
-        result.merge(test_result);
+        result.merge(dir_result);
     }
 
     // Report any failures
@@ -124,38 +128,175 @@ pub fn cleanup_logs(config: &Config) -> CleanupResult {
         }
     }
 
+    // File removal can leave empty subdirectories behind
+    let dirs_removed = remove_empty_log_dirs(config);
+
     info!(
         files_removed = result.files_removed,
         bytes_removed = result.bytes_removed,
+        dirs_removed,
         "Log cleanup completed"
     );
 
+    if config.log_usage_on_cleanup {
+        let usage = directory_usage(config);
+        info!(
+            total_bytes = usage.total_bytes,
+            file_count = usage.file_count,
+            subdirs = ?usage.subdirs,
+            "Log directory disk usage after cleanup"
+        );
+    }
+
     result
 }
 
-/// Cleans up log files in a specific directory that are older than the max age
+/// Removes empty subdirectories from the app and test log directories
 ///
-/// This function removes log files in the specified directory that are older than
-/// the given maximum age in days. It returns a CleanupResult with statistics about
-/// the operation.
+/// Walks both log base directories depth-first and removes every subdirectory
+/// that is empty (or becomes empty once its own empty children are removed).
+/// The base directories themselves, and the directory of the configured log
+/// file, are never removed.
 ///
 /// # Arguments
-/// * `directory` - Path to the directory to clean up
-/// * `max_age_days` - Maximum age of files in days before deletion
+/// * `config` - The application configuration, used to find the active log directory
 ///
 /// # Returns
-/// A CleanupResult with details about the cleanup operation
-fn cleanup_directory(directory: &Path, max_age_days: u32) -> CleanupResult {
-    let mut result = CleanupResult::new();
-    let cutoff_date = Local::now() - Duration::days(max_age_days as i64);
+/// The number of directories removed
+pub fn remove_empty_log_dirs(config: &Config) -> usize {
+    let app_dir = PathBuf::from(DEFAULT_LOG_DIR).join(APP_LOG_SUBDIR);
+    let test_dir = PathBuf::from(DEFAULT_LOG_DIR).join(TEST_LOG_SUBDIR);
+
+    // The active log directory may be nested below a base directory
+    let mut protected = vec![app_dir.clone(), test_dir.clone()];
+    if let Some(active_dir) = Path::new(&config.log_file_path).parent() {
+        protected.push(active_dir.to_path_buf());
+    }
+    let protected: Vec<PathBuf> = protected
+        .iter()
+        .filter_map(|dir| fs::canonicalize(dir).ok())
+        .collect();
+
+    let mut removed = 0;
+    for base in [&app_dir, &test_dir] {
+        if base.is_dir() {
+            removed += remove_empty_subdirs(base, &protected);
+        }
+    }
 
-    debug!(directory = %directory.display(), max_age_days, "Scanning directory for old log files");
+    if removed > 0 {
+        info!(dirs_removed = removed, "Removed empty log subdirectories");
+    }
 
-    // Read the directory
+    removed
+}
+
+/// Recursively removes empty subdirectories below `directory`
+///
+/// `directory` itself is never removed; protected paths (canonicalized) are kept
+/// even when empty.
+///
+/// # Returns
+/// The number of directories removed
+fn remove_empty_subdirs(directory: &Path, protected: &[PathBuf]) -> usize {
     let dir_entries = match fs::read_dir(directory) {
         Ok(entries) => entries,
         Err(e) => {
             warn!(directory = %directory.display(), error = %e, "Failed to read directory for cleanup");
+            return 0;
+        }
+    };
+
+    let mut removed = 0;
+    for entry in dir_entries.flatten() {
+        let path = entry.path();
+
+        // Only descend into real directories, never through symlinks
+        let is_dir = entry.file_type().map(|t| t.is_dir()).unwrap_or(false);
+        if !is_dir {
+            continue;
+        }
+
+        // Children first, so a directory holding only empty directories becomes empty
+        removed += remove_empty_subdirs(&path, protected);
+
+        let is_protected = fs::canonicalize(&path)
+            .map(|canonical| protected.contains(&canonical))
+            .unwrap_or(true);
+        let is_empty = fs::read_dir(&path)
+            .map(|mut entries| entries.next().is_none())
+            .unwrap_or(false);
+
+        if is_empty && !is_protected {
+            match fs::remove_dir(&path) {
+                Ok(_) => {
+                    debug!(path = %path.display(), "Removed empty log directory");
+                    removed += 1;
+                }
+                Err(e) => {
+                    warn!(path = %path.display(), error = %e, "Failed to remove empty log directory");
+                }
+            }
+        }
+    }
+
+    removed
+}
+
+/// Seconds in a day, for converting `log_max_age_days`
+const SECS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Cleans up log files in any directory that are older than the max age
+///
+/// This is the cleanup algorithm used by [`cleanup_logs`], independent of how the
+/// log directories are derived, so crates embedding Switchboard can clean their own
+/// log directories. Only files that look like logs (`*.log` and rotated
+/// `*.log.YYYY-MM-DD`) directly inside `dir` are considered; subdirectories are
+/// left alone.
+///
+/// # Arguments
+/// * `dir` - Path to the directory to clean up
+/// * `max_age` - Files last modified longer ago than this are removed
+/// * `dry_run` - Only report what would be removed, without deleting anything
+///
+/// # Returns
+/// A CleanupResult with details about the cleanup operation. In a dry run,
+/// `files_removed` and `bytes_removed` count the files that would be removed.
+///
+/// # Examples
+/// ```no_run
+/// use std::path::Path;
+/// use std::time::Duration;
+/// use switchboard::log_cleanup::cleanup_logs_in_dir;
+///
+/// // See what a 30-day retention policy would remove, without removing it
+/// let result = cleanup_logs_in_dir(
+///     Path::new("/var/log/my-app"),
+///     Duration::from_secs(30 * 24 * 60 * 60),
+///     true,
+/// );
+/// println!("Would remove {} files", result.files_removed);
+/// ```
+pub fn cleanup_logs_in_dir(dir: &Path, max_age: Duration, dry_run: bool) -> CleanupResult {
+    let mut result = CleanupResult::new();
+
+    // A max age reaching back before the epoch cannot match any file
+    let Some(cutoff) = SystemTime::now().checked_sub(max_age) else {
+        return result;
+    };
+
+    debug!(
+        directory = %dir.display(),
+        max_age_secs = max_age.as_secs(),
+        dry_run,
+        "Scanning directory for old log files"
+    );
+
+    // Read the directory
+    let dir_entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            warn!(directory = %dir.display(), error = %e, "Failed to read directory for cleanup");
             return result;
         }
     };
@@ -190,7 +331,7 @@ fn cleanup_directory(directory: &Path, max_age_days: u32) -> CleanupResult {
 
         // Get file modification time
         let modified = match metadata.modified() {
-            Ok(time) => DateTime::<Local>::from(time).with_timezone(&Local),
+            Ok(time) => time,
             Err(e) => {
                 result.failed_files.push((
                     path.clone(),
@@ -201,7 +342,15 @@ fn cleanup_directory(directory: &Path, max_age_days: u32) -> CleanupResult {
         };
 
         // Check if file is older than the cutoff date
-        if modified < cutoff_date {
+        if modified < cutoff {
+            let modified = DateTime::<Local>::from(modified);
+            if dry_run {
+                info!(path = %path.display(), modified = %modified, "Would remove old log file");
+                result.files_removed += 1;
+                result.bytes_removed += metadata.len();
+                continue;
+            }
+
             debug!(path = %path.display(), modified = %modified, "Removing old log file");
 
             // Try to remove the file
@@ -222,10 +371,151 @@ fn cleanup_directory(directory: &Path, max_age_days: u32) -> CleanupResult {
     result
 }
 
+/// Cleans up several directories concurrently, returning the merged result
+///
+/// Each directory is cleaned on its own thread with its own `CleanupResult`, so the
+/// threads share no state. Results are merged in the order of `dirs`, which makes the
+/// aggregate (including the order of `failed_files`) identical to cleaning the
+/// directories one after another with [`cleanup_logs_in_dir`].
+///
+/// # Arguments
+/// * `dirs` - Directories to clean; none of them is descended into
+/// * `max_age` - Files modified longer ago than this are removed
+/// * `dry_run` - If true, report what would be removed without removing anything
+#[allow(dead_code)] // ALLOWANCE: Library API for embedders; the binary cleans the fixed log directories
+pub fn cleanup_logs_in_dirs(dirs: &[PathBuf], max_age: Duration, dry_run: bool) -> CleanupResult {
+    let mut result = CleanupResult::new();
+    for dir_result in cleanup_dirs_concurrently(dirs, max_age, dry_run) {
+        result.merge(dir_result);
+    }
+    result
+}
+
+/// Runs [`cleanup_logs_in_dir`] for each directory on its own thread
+///
+/// # Returns
+/// One result per directory, in the order of `dirs`
+fn cleanup_dirs_concurrently(
+    dirs: &[PathBuf],
+    max_age: Duration,
+    dry_run: bool,
+) -> Vec<CleanupResult> {
+    thread::scope(|scope| {
+        let handles: Vec<_> = dirs
+            .iter()
+            .map(|dir| scope.spawn(move || cleanup_logs_in_dir(dir, max_age, dry_run)))
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|panic| panic::resume_unwind(panic))
+            })
+            .collect()
+    })
+}
+
+/// Disk used by the files below one log subdirectory
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct SubdirUsage {
+    /// Total size of the files (in bytes)
+    pub bytes: u64,
+    /// Number of files
+    pub files: u64,
+}
+
+/// Disk used by the app and test log directories
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct DirectoryUsage {
+    /// Total size of all files across the subdirectories (in bytes)
+    pub total_bytes: u64,
+    /// Number of files across the subdirectories
+    pub file_count: u64,
+    /// Usage per subdirectory, keyed by its name (`app`, `test`)
+    pub subdirs: BTreeMap<String, SubdirUsage>,
+}
+
+impl DirectoryUsage {
+    /// Adds a subdirectory's usage to the breakdown and the totals
+    fn add(&mut self, name: &str, usage: SubdirUsage) {
+        self.total_bytes += usage.bytes;
+        self.file_count += usage.files;
+        self.subdirs.insert(name.to_string(), usage);
+    }
+}
+
+/// Computes the disk used by the app and test log directories
+///
+/// The directories are located the same way as the log file itself, so the
+/// environment-specific layouts (development, user, system service) are all
+/// measured correctly. Every file counts, including rotated logs and files in
+/// nested subdirectories; directories that don't exist count as empty.
+///
+/// # Arguments
+/// * `config` - The application configuration, used to locate the log directories
+///
+/// # Returns
+/// Total bytes and file count, with a breakdown per subdirectory
+pub fn directory_usage(config: &Config) -> DirectoryUsage {
+    // The resolved path is <base>/app/<file>, so the base is two levels up
+    let log_path = LogPathResolver::new(config, LogType::Application).resolve_readonly();
+    match log_path
+        .as_deref()
+        .ok()
+        .and_then(|path| path.parent()?.parent())
+    {
+        Some(base) => directory_usage_in(base),
+        None => directory_usage_in(Path::new(DEFAULT_LOG_DIR)),
+    }
+}
+
+/// Computes the disk used by the `app` and `test` subdirectories of `base`
+///
+/// The same as [`directory_usage`], for embedders with their own log base directory.
+pub fn directory_usage_in(base: &Path) -> DirectoryUsage {
+    let mut usage = DirectoryUsage::default();
+    for name in [APP_LOG_SUBDIR, TEST_LOG_SUBDIR] {
+        usage.add(name, subdir_usage(&base.join(name)));
+    }
+    usage
+}
+
+/// Sums the sizes of all files below `directory`, recursing into subdirectories
+fn subdir_usage(directory: &Path) -> SubdirUsage {
+    let mut usage = SubdirUsage::default();
+    let dir_entries = match fs::read_dir(directory) {
+        Ok(entries) => entries,
+        Err(e) => {
+            debug!(directory = %directory.display(), error = %e, "Skipping unreadable log directory");
+            return usage;
+        }
+    };
+
+    for entry in dir_entries.flatten() {
+        // Symlinks are not followed, so a link cannot make files count twice
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        if file_type.is_dir() {
+            let nested = subdir_usage(&entry.path());
+            usage.bytes += nested.bytes;
+            usage.files += nested.files;
+        } else if file_type.is_file() {
+            if let Ok(metadata) = entry.metadata() {
+                usage.bytes += metadata.len();
+                usage.files += 1;
+            }
+        }
+    }
+    usage
+}
+
 /// Checks if a path is a log file based on its extension
 ///
 /// This function determines if a file is a log file by checking its extension.
-/// It supports both .log files and .log.YYYY-MM-DD format (for rotated logs).
+/// It supports .log files (including rotated `<prefix>-YYYY-MM-DD.log` files) and
+/// the .log.YYYY-MM-DD format of rotated logs.
 ///
 /// # Arguments
 /// * `path` - The path to check
@@ -236,21 +526,13 @@ fn is_log_file(path: &Path) -> bool {
     if let Some(file_name) = path.file_name() {
         let file_name = file_name.to_string_lossy();
 
-        // Check for simple .log extension
+        // Check for simple .log extension, which date-in-name rotated files also have
         if file_name.ends_with(".log") {
             return true;
         }
 
         // Check for rotated log files with date suffix (.log.YYYY-MM-DD)
-        if let Some(idx) = file_name.find(".log.") {
-            // Make sure what follows .log. looks like a date (all remaining chars are digits, dashes, or underscores)
-            let suffix = &file_name[(idx + 5)..]; // Skip past ".log."
-            return suffix
-                .chars()
-                .all(|c| c.is_ascii_digit() || c == '-' || c == '_');
-        }
-
-        false
+        log_naming::log_file_date(&file_name).is_some()
     } else {
         false
     }
@@ -269,6 +551,7 @@ mod tests {
         // Test various file paths
         assert!(is_log_file(Path::new("app.log")));
         assert!(is_log_file(Path::new("app.log.2023-01-01")));
+        assert!(is_log_file(Path::new("app-2023-01-01.log")));
         assert!(is_log_file(Path::new("/tmp/logs/app/test.log")));
         assert!(!is_log_file(Path::new("app.txt")));
         assert!(!is_log_file(Path::new("app.log.txt")));
@@ -319,7 +602,8 @@ mod tests {
         .unwrap();
 
         // Run cleanup with 7 days max age
-        let result = cleanup_directory(temp_path, 7);
+        let result =
+            cleanup_logs_in_dir(temp_path, StdDuration::from_secs(7 * SECS_PER_DAY), false);
 
         // Check the results
         assert_eq!(result.files_removed, 2); // Both old and very_old should be removed
@@ -333,6 +617,39 @@ mod tests {
         assert!(non_log_file.exists());
     }
 
+    #[test]
+    fn test_cleanup_handles_both_naming_schemes() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let old_time = SystemTime::now() - StdDuration::from_secs(10 * SECS_PER_DAY);
+
+        let old_files = ["switchboard.log.2023-01-01", "switchboard-2023-01-01.log"];
+        let current_files = ["switchboard.log", "switchboard-2023-01-11.log"];
+        for name in old_files.iter().chain(&current_files) {
+            File::create(temp_dir.path().join(name)).unwrap();
+        }
+        for name in old_files {
+            filetime::set_file_mtime(
+                temp_dir.path().join(name),
+                filetime::FileTime::from_system_time(old_time),
+            )
+            .unwrap();
+        }
+
+        let result = cleanup_logs_in_dir(
+            temp_dir.path(),
+            StdDuration::from_secs(7 * SECS_PER_DAY),
+            false,
+        );
+
+        assert_eq!(result.files_removed, 2);
+        for name in old_files {
+            assert!(!temp_dir.path().join(name).exists(), "{} kept", name);
+        }
+        for name in current_files {
+            assert!(temp_dir.path().join(name).exists(), "{} removed", name);
+        }
+    }
+
     #[test]
     fn test_cleanup_results_merge() {
         // Create two results
@@ -356,4 +673,115 @@ mod tests {
         assert_eq!(result1.bytes_removed, 1500);
         assert_eq!(result1.failed_files.len(), 2);
     }
+
+    #[test]
+    fn test_concurrent_cleanup_matches_sequential() {
+        // Two identical sets of directories, one cleaned each way
+        let create_dirs = |base: &Path| -> Vec<PathBuf> {
+            let old_time = SystemTime::now() - StdDuration::from_secs(10 * SECS_PER_DAY);
+            (0..4)
+                .map(|i| {
+                    let dir = base.join(format!("dir{}", i));
+                    fs::create_dir(&dir).unwrap();
+                    for j in 0..=i {
+                        let old_file = dir.join(format!("old{}.log", j));
+                        fs::write(&old_file, vec![b'x'; 100 * (j + 1)]).unwrap();
+                        filetime::set_file_mtime(
+                            &old_file,
+                            filetime::FileTime::from_system_time(old_time),
+                        )
+                        .unwrap();
+                    }
+                    fs::write(dir.join("recent.log"), b"recent").unwrap();
+                    dir
+                })
+                .collect()
+        };
+        let sequential_base = tempfile::tempdir().unwrap();
+        let concurrent_base = tempfile::tempdir().unwrap();
+        let sequential_dirs = create_dirs(sequential_base.path());
+        let concurrent_dirs = create_dirs(concurrent_base.path());
+        let max_age = StdDuration::from_secs(7 * SECS_PER_DAY);
+
+        let mut sequential = CleanupResult::new();
+        for dir in &sequential_dirs {
+            sequential.merge(cleanup_logs_in_dir(dir, max_age, false));
+        }
+        let concurrent = cleanup_logs_in_dirs(&concurrent_dirs, max_age, false);
+
+        assert_eq!(concurrent.files_removed, 10);
+        assert_eq!(concurrent.files_removed, sequential.files_removed);
+        assert_eq!(concurrent.bytes_removed, sequential.bytes_removed);
+        assert_eq!(concurrent.failed_files, sequential.failed_files);
+        for dir in &concurrent_dirs {
+            assert!(dir.join("recent.log").exists());
+        }
+    }
+
+    #[test]
+    fn test_remove_empty_subdirs_keeps_non_empty_and_protected() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let base = temp_dir.path();
+
+        // Empty nested directories that should be removed
+        let empty_nested = base.join("2023").join("01");
+        fs::create_dir_all(&empty_nested).unwrap();
+
+        // A directory holding a log file that must be kept
+        let non_empty = base.join("2024");
+        fs::create_dir_all(&non_empty).unwrap();
+        File::create(non_empty.join("app.log")).unwrap();
+
+        // An empty directory that is protected (e.g. the active log directory)
+        let active = base.join("active");
+        fs::create_dir_all(&active).unwrap();
+        let protected = vec![fs::canonicalize(&active).unwrap()];
+
+        let removed = remove_empty_subdirs(base, &protected);
+
+        assert_eq!(removed, 2, "Both levels of the empty nested tree should go");
+        assert!(!base.join("2023").exists());
+        assert!(non_empty.exists());
+        assert!(active.exists());
+        assert!(base.exists(), "The base directory is never removed");
+    }
+
+    #[test]
+    fn test_directory_usage_sums_files_per_subdir() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let base = temp_dir.path();
+        let write = |path: PathBuf, size: usize| {
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, vec![b'x'; size]).unwrap();
+        };
+
+        write(base.join(APP_LOG_SUBDIR).join("switchboard.log"), 100);
+        write(
+            base.join(APP_LOG_SUBDIR).join("switchboard.log.2024-01-01"),
+            250,
+        );
+        write(base.join(APP_LOG_SUBDIR).join("2023").join("old.log"), 50);
+        write(base.join(TEST_LOG_SUBDIR).join("test.log"), 7);
+
+        let usage = directory_usage_in(base);
+
+        assert_eq!(usage.total_bytes, 407);
+        assert_eq!(usage.file_count, 4);
+        assert_eq!(
+            usage.subdirs[APP_LOG_SUBDIR],
+            SubdirUsage {
+                bytes: 400,
+                files: 3
+            }
+        );
+        assert_eq!(
+            usage.subdirs[TEST_LOG_SUBDIR],
+            SubdirUsage { bytes: 7, files: 1 }
+        );
+
+        // A base without log directories is reported as empty, not as an error
+        let empty = directory_usage_in(&base.join("missing"));
+        assert_eq!(empty.total_bytes, 0);
+        assert_eq!(empty.subdirs.len(), 2);
+    }
 }
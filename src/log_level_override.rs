@@ -0,0 +1,187 @@
+//! Per-request log level overrides for debugging individual calls
+//!
+//! When a client is allowed to raise the verbosity of its own request, the proxy
+//! records the requested level as the `log.level_override` field of the request span.
+//! `LevelOverrideLayer` notices that field and keeps a per-thread stack of the
+//! overrides of the spans currently entered; `LevelOverrideFilter` wraps a layer's
+//! regular filter and also lets through events the innermost override admits. Other
+//! requests, and the global configuration, are unaffected.
+//!
+//! Key features:
+//! - Applies to the span carrying the override and every span below it
+//! - Wrapped filters are unchanged when overrides are not allowed
+//! - While allowed, disabled callsites are evaluated per event instead of being cached
+//!   as disabled, which costs a little on every suppressed event
+
+use std::cell::RefCell;
+use std::fmt;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{subscriber::Interest, Event, Metadata, Subscriber};
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::layer::{Context, Filter, Layer};
+use tracing_subscriber::registry::LookupSpan;
+
+/// Span field holding a requested log level (e.g. `trace`)
+pub const LEVEL_OVERRIDE_FIELD: &str = "log.level_override";
+
+thread_local! {
+    /// Overrides of the spans entered on this thread, innermost last
+    static ACTIVE_OVERRIDES: RefCell<Vec<(Id, LevelFilter)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Level override stored in the extensions of the span that requested it
+#[derive(Debug, Clone, Copy)]
+struct LevelOverride(LevelFilter);
+
+/// Layer tracking which entered spans carry a level override
+///
+/// Must be added without a filter, so it sees every span.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LevelOverrideLayer;
+
+impl LevelOverrideLayer {
+    /// Stores an override recorded on `id`, applying it at once if the span is current
+    fn store<S>(values: impl FnOnce(&mut LevelVisitor), id: &Id, ctx: &Context<'_, S>)
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        let mut visitor = LevelVisitor(None);
+        values(&mut visitor);
+        let (Some(level), Some(span)) = (visitor.0, ctx.span(id)) else {
+            return;
+        };
+        span.extensions_mut().replace(LevelOverride(level));
+
+        // Recorded inside the span, which was entered before it had the override
+        if ctx.current_span().id() == Some(id) {
+            ACTIVE_OVERRIDES.with(|overrides| overrides.borrow_mut().push((id.clone(), level)));
+        }
+    }
+}
+
+impl<S> Layer<S> for LevelOverrideLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        Self::store(|visitor| attrs.record(visitor), id, &ctx);
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        Self::store(|visitor| values.record(visitor), id, &ctx);
+    }
+
+    fn on_enter(&self, id: &Id, ctx: Context<'_, S>) {
+        // Child spans inherit the override, even when entered on another thread
+        let Some(level) = ctx.span(id).and_then(|span| {
+            span.scope()
+                .find_map(|span| span.extensions().get::<LevelOverride>().copied())
+        }) else {
+            return;
+        };
+        ACTIVE_OVERRIDES.with(|overrides| overrides.borrow_mut().push((id.clone(), level.0)));
+    }
+
+    fn on_exit(&self, id: &Id, _ctx: Context<'_, S>) {
+        ACTIVE_OVERRIDES.with(|overrides| {
+            let mut overrides = overrides.borrow_mut();
+            if let Some(pos) = overrides.iter().rposition(|(entered, _)| entered == id) {
+                overrides.remove(pos);
+            }
+        });
+    }
+}
+
+/// Reads the override field from span values
+struct LevelVisitor(Option<LevelFilter>);
+
+impl Visit for LevelVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == LEVEL_OVERRIDE_FIELD {
+            self.0 = value.parse().ok();
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == LEVEL_OVERRIDE_FIELD {
+            self.0 = format!("{:?}", value).trim_matches('"').parse().ok();
+        }
+    }
+}
+
+/// Filter admitting what `inner` admits, plus events within a span's level override
+#[derive(Debug, Clone)]
+pub struct LevelOverrideFilter<F> {
+    inner: F,
+    active: bool,
+}
+
+impl<F> LevelOverrideFilter<F> {
+    /// Wraps `inner`; with `active` false the wrapper changes nothing
+    pub fn new(inner: F, active: bool) -> Self {
+        Self { inner, active }
+    }
+}
+
+/// Returns true if the innermost override on this thread admits `metadata`
+fn override_admits(metadata: &Metadata<'_>) -> bool {
+    ACTIVE_OVERRIDES.with(|overrides| {
+        overrides
+            .borrow()
+            .last()
+            .is_some_and(|(_, level)| metadata.level() <= level)
+    })
+}
+
+impl<S, F> Filter<S> for LevelOverrideFilter<F>
+where
+    S: Subscriber,
+    F: Filter<S>,
+{
+    fn enabled(&self, metadata: &Metadata<'_>, cx: &Context<'_, S>) -> bool {
+        self.inner.enabled(metadata, cx) || (self.active && override_admits(metadata))
+    }
+
+    fn callsite_enabled(&self, metadata: &'static Metadata<'static>) -> Interest {
+        let interest = self.inner.callsite_enabled(metadata);
+        if self.active && !interest.is_always() {
+            // Whether an override applies is only known per event
+            Interest::sometimes()
+        } else {
+            interest
+        }
+    }
+
+    fn event_enabled(&self, event: &Event<'_>, cx: &Context<'_, S>) -> bool {
+        self.inner.event_enabled(event, cx)
+    }
+
+    fn max_level_hint(&self) -> Option<LevelFilter> {
+        if self.active {
+            Some(LevelFilter::TRACE)
+        } else {
+            self.inner.max_level_hint()
+        }
+    }
+
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        self.inner.on_new_span(attrs, id, ctx);
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        self.inner.on_record(id, values, ctx);
+    }
+
+    fn on_enter(&self, id: &Id, ctx: Context<'_, S>) {
+        self.inner.on_enter(id, ctx);
+    }
+
+    fn on_exit(&self, id: &Id, ctx: Context<'_, S>) {
+        self.inner.on_exit(id, ctx);
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        self.inner.on_close(id, ctx);
+    }
+}
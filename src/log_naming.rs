@@ -0,0 +1,174 @@
+//! Names of the daily log files
+//!
+//! Two naming schemes are supported. By default the date is appended after the
+//! extension (`switchboard.log.2024-01-02`), as `tracing_appender::rolling::daily`
+//! does. With `log_file_date_in_name` the date sits between the prefix and the
+//! extension (`switchboard-2024-01-02.log`), which some log shippers match on.
+//!
+//! Key features:
+//! - The prefix comes from `log_file_prefix`, or from the `log_file_path` file name
+//! - `DatedFileAppender` writes the date-in-name files, switching files at UTC midnight
+//! - `log_file_date` reads the date back from a file name in either scheme
+
+use chrono::{NaiveDate, Utc};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use crate::config::Config;
+
+/// Extension of every log file
+const LOG_EXTENSION: &str = "log";
+
+/// Format of the date in log file names
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// Returns the prefix of the log file names
+///
+/// # Arguments
+/// * `config` - Configuration supplying `log_file_prefix`
+/// * `file_name` - The resolved log file name, used when no prefix is configured
+pub fn log_file_prefix(config: &Config, file_name: &str) -> String {
+    config.log_file_prefix.clone().unwrap_or_else(|| {
+        file_name
+            .strip_suffix(".log")
+            .unwrap_or(file_name)
+            .to_string()
+    })
+}
+
+/// Returns the base name `rolling::daily` appends the date to (date-after-extension scheme)
+///
+/// Without a configured prefix the resolved file name is used unchanged, so a
+/// `LOG_FILE_PATH` of `proxy` still rotates as `proxy.YYYY-MM-DD`.
+pub fn rolling_file_name(config: &Config, file_name: &str) -> String {
+    match &config.log_file_prefix {
+        Some(prefix) => format!("{}.{}", prefix, LOG_EXTENSION),
+        None => file_name.to_string(),
+    }
+}
+
+/// Returns the name of the log file for `date` in the date-in-name scheme
+pub fn dated_file_name(prefix: &str, date: NaiveDate) -> String {
+    format!("{}-{}.{}", prefix, date.format(DATE_FORMAT), LOG_EXTENSION)
+}
+
+/// Returns the date in a rotated log file name, in either naming scheme
+///
+/// Recognizes `<prefix>.log.YYYY-MM-DD` and `<prefix>-YYYY-MM-DD.log`; any other
+/// name (including the undated `<prefix>.log`) yields None.
+pub fn log_file_date(file_name: &str) -> Option<NaiveDate> {
+    let date_after_extension = file_name.rsplit_once(".log.").map(|(_, date)| date);
+    let date_in_name = file_name.strip_suffix(".log").and_then(|stem| {
+        let split = stem.len().checked_sub("YYYY-MM-DD".len())?;
+        let (prefix, date) = (stem.get(..split)?, stem.get(split..)?);
+        (prefix.len() > 1 && prefix.ends_with('-')).then_some(date)
+    });
+    [date_after_extension, date_in_name]
+        .into_iter()
+        .flatten()
+        .find_map(|date| NaiveDate::parse_from_str(date, DATE_FORMAT).ok())
+}
+
+/// Writes log lines to `<prefix>-YYYY-MM-DD.log`, starting a new file each UTC day
+pub struct DatedFileAppender {
+    /// Directory holding the log files
+    directory: PathBuf,
+    /// File name prefix before the date
+    prefix: String,
+    /// Date of the file currently open
+    date: NaiveDate,
+    /// The file for `date`
+    file: File,
+}
+
+impl DatedFileAppender {
+    /// Opens (or creates) today's log file in `directory`
+    pub fn new(directory: impl AsRef<Path>, prefix: impl Into<String>) -> io::Result<Self> {
+        let directory = directory.as_ref().to_path_buf();
+        let prefix = prefix.into();
+        let date = Utc::now().date_naive();
+        let file = open_log_file(&directory, &prefix, date)?;
+        Ok(Self {
+            directory,
+            prefix,
+            date,
+            file,
+        })
+    }
+}
+
+impl Write for DatedFileAppender {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let today = Utc::now().date_naive();
+        if today != self.date {
+            // Keep writing to the old file if the new one cannot be opened
+            if let Ok(file) = open_log_file(&self.directory, &self.prefix, today) {
+                self.file = file;
+                self.date = today;
+            }
+        }
+        self.file.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// Opens the date-in-name log file for `date` for appending
+fn open_log_file(directory: &Path, prefix: &str, date: NaiveDate) -> io::Result<File> {
+    fs::create_dir_all(directory)?;
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(directory.join(dated_file_name(prefix, date)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_file_date_recognizes_both_schemes() {
+        let date = NaiveDate::from_ymd_opt(2024, 1, 2);
+
+        assert_eq!(log_file_date("switchboard.log.2024-01-02"), date);
+        assert_eq!(log_file_date("switchboard-2024-01-02.log"), date);
+        assert_eq!(log_file_date("my-app-2024-01-02.log"), date);
+        assert_eq!(
+            dated_file_name("switchboard", date.unwrap()),
+            "switchboard-2024-01-02.log"
+        );
+
+        assert_eq!(log_file_date("switchboard.log"), None);
+        assert_eq!(log_file_date("2024-01-02.log"), None);
+        assert_eq!(log_file_date("switchboard-2024-13-40.log"), None);
+        assert_eq!(log_file_date("switchboard.log.txt"), None);
+    }
+
+    #[test]
+    fn test_prefix_defaults_to_log_file_name() {
+        let mut config = Config::default();
+        assert_eq!(log_file_prefix(&config, "switchboard.log"), "switchboard");
+        assert_eq!(log_file_prefix(&config, "proxy"), "proxy");
+
+        config.log_file_prefix = Some("edge".to_string());
+        assert_eq!(log_file_prefix(&config, "switchboard.log"), "edge");
+    }
+
+    #[test]
+    fn test_rolling_file_name_keeps_unprefixed_names() {
+        let mut config = Config::default();
+        // Paths without a .log suffix rotate under their own name, as before prefixes existed
+        assert_eq!(rolling_file_name(&config, "proxy"), "proxy");
+        assert_eq!(rolling_file_name(&config, "app.txt"), "app.txt");
+        assert_eq!(
+            rolling_file_name(&config, "switchboard.log"),
+            "switchboard.log"
+        );
+
+        config.log_file_prefix = Some("edge".to_string());
+        assert_eq!(rolling_file_name(&config, "proxy"), "edge.log");
+    }
+}
@@ -0,0 +1,300 @@
+//! Reordering of file log lines by timestamp
+//!
+//! Events are timestamped when they are formatted, but concurrent requests can hand
+//! their lines to the file writer in a different order, so a file may contain a line
+//! stamped slightly earlier than the one before it. `ReorderingWriter` holds each line
+//! for `REORDER_WINDOW` and writes the held lines in timestamp order.
+//!
+//! The cost is latency and memory: every line reaches the file `REORDER_WINDOW` later,
+//! and all lines written within one window are held in memory. Lines delayed by more
+//! than the window are still written, just not in order.
+//!
+//! Key features:
+//! - Sorts on the JSON `timestamp` field, in any of the configured timestamp formats
+//! - Lines without a readable timestamp keep their place after the lines before them
+//! - A background thread writes lines once their window has passed, even when logging goes quiet
+//! - Dropping the writer (when the log worker stops) writes everything still held
+
+use chrono::DateTime;
+use serde::Deserialize;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::io::{self, Write};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// How long each line is held for earlier-stamped lines to catch up
+pub const REORDER_WINDOW: Duration = Duration::from_millis(10);
+
+/// Writer that passes lines on to `W` in timestamp order, `window` after receiving them
+///
+/// Each `write` call must hold one whole line, as the non-blocking log writer delivers them.
+pub struct ReorderingWriter<W: Write + Send + 'static> {
+    shared: Arc<Shared<W>>,
+    flusher: Option<JoinHandle<()>>,
+}
+
+/// State shared with the background flusher
+struct Shared<W> {
+    state: Mutex<State<W>>,
+    /// Signalled when a line arrives in an empty buffer or the writer closes
+    changed: Condvar,
+    window: Duration,
+}
+
+/// Held lines and the writer they go to
+struct State<W> {
+    inner: W,
+    pending: BinaryHeap<Reverse<HeldLine>>,
+    /// Arrival counter, keeping lines with equal timestamps in arrival order
+    next_seq: u64,
+    /// Latest timestamp seen, used for lines without one
+    last_key: i128,
+    closed: bool,
+}
+
+/// One line waiting to be written; ordered by timestamp, then arrival
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+struct HeldLine {
+    key: i128,
+    seq: u64,
+    arrived: Instant,
+    line: Vec<u8>,
+}
+
+/// The only field read from a line
+#[derive(Deserialize)]
+struct Timestamped {
+    timestamp: Option<String>,
+}
+
+impl<W: Write + Send + 'static> ReorderingWriter<W> {
+    /// Wraps `inner`, holding each line for `window` before writing it
+    pub fn new(inner: W, window: Duration) -> Self {
+        let shared = Arc::new(Shared {
+            state: Mutex::new(State {
+                inner,
+                pending: BinaryHeap::new(),
+                next_seq: 0,
+                last_key: i128::MIN,
+                closed: false,
+            }),
+            changed: Condvar::new(),
+            window,
+        });
+        let flusher = {
+            let shared = Arc::clone(&shared);
+            thread::Builder::new()
+                .name("log-reorder".to_string())
+                .spawn(move || shared.run_flusher())
+                .ok()
+        };
+        Self { shared, flusher }
+    }
+}
+
+impl<W: Write> Shared<W> {
+    fn lock(&self) -> MutexGuard<'_, State<W>> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Writes lines as their windows pass, until the writer closes
+    fn run_flusher(&self) {
+        let mut state = self.lock();
+        loop {
+            if state.closed {
+                return;
+            }
+            state.write_held(Some(Instant::now()), self.window);
+            state = match state.pending.peek() {
+                Some(Reverse(oldest)) => {
+                    let due =
+                        (oldest.arrived + self.window).saturating_duration_since(Instant::now());
+                    self.changed
+                        .wait_timeout(state, due)
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .0
+                }
+                None => self
+                    .changed
+                    .wait(state)
+                    .unwrap_or_else(PoisonError::into_inner),
+            };
+        }
+    }
+}
+
+impl<W: Write> State<W> {
+    /// Writes held lines in order, stopping at the first still inside its window
+    ///
+    /// Without a `now`, every held line is written.
+    fn write_held(&mut self, now: Option<Instant>, window: Duration) {
+        while let Some(Reverse(next)) = self.pending.peek() {
+            if now.is_some_and(|now| now.duration_since(next.arrived) < window) {
+                break;
+            }
+            if let Some(Reverse(next)) = self.pending.pop() {
+                // Like the non-blocking worker, a line that cannot be written is dropped
+                let _ = self.inner.write_all(&next.line);
+            }
+        }
+    }
+}
+
+impl<W: Write + Send + 'static> Write for ReorderingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut state = self.shared.lock();
+        let key = timestamp_key(buf).unwrap_or(state.last_key);
+        state.last_key = state.last_key.max(key);
+        let seq = state.next_seq;
+        state.next_seq += 1;
+
+        let was_empty = state.pending.is_empty();
+        state.pending.push(Reverse(HeldLine {
+            key,
+            seq,
+            arrived: Instant::now(),
+            line: buf.to_vec(),
+        }));
+        if was_empty {
+            self.shared.changed.notify_one();
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        // Held lines stay held; only what has already been written is flushed
+        self.shared.lock().inner.flush()
+    }
+}
+
+impl<W: Write + Send + 'static> Drop for ReorderingWriter<W> {
+    fn drop(&mut self) {
+        {
+            let mut state = self.shared.lock();
+            state.closed = true;
+            state.write_held(None, self.shared.window);
+            let _ = state.inner.flush();
+        }
+        self.shared.changed.notify_one();
+        if let Some(flusher) = self.flusher.take() {
+            let _ = flusher.join();
+        }
+    }
+}
+
+/// Reads the `timestamp` of a JSON log line as a sortable number
+///
+/// RFC 3339 timestamps become nanoseconds; epoch timestamps are used as they are,
+/// which sorts correctly as one process writes a single format.
+fn timestamp_key(line: &[u8]) -> Option<i128> {
+    let timestamp = serde_json::from_slice::<Timestamped>(line)
+        .ok()?
+        .timestamp?;
+    if let Ok(epoch) = timestamp.parse::<i128>() {
+        return Some(epoch);
+    }
+    let parsed = DateTime::parse_from_rfc3339(&timestamp).ok()?;
+    Some(
+        i128::from(parsed.timestamp()) * 1_000_000_000
+            + i128::from(parsed.timestamp_subsec_nanos()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writer appending to a buffer the test can still read
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl SharedBuffer {
+        fn lines(&self) -> Vec<String> {
+            String::from_utf8(self.0.lock().unwrap().clone())
+                .unwrap()
+                .lines()
+                .map(String::from)
+                .collect()
+        }
+    }
+
+    fn line(timestamp: &str, message: &str) -> Vec<u8> {
+        format!(
+            "{{\"timestamp\":\"{}\",\"fields\":{{\"message\":\"{}\"}}}}\n",
+            timestamp, message
+        )
+        .into_bytes()
+    }
+
+    #[test]
+    fn test_out_of_order_lines_written_in_timestamp_order() {
+        let buffer = SharedBuffer::default();
+        let mut writer = ReorderingWriter::new(buffer.clone(), Duration::from_secs(60));
+
+        writer
+            .write_all(&line("2024-05-01T10:00:00.300Z", "third"))
+            .unwrap();
+        writer
+            .write_all(&line("2024-05-01T10:00:00.100Z", "first"))
+            .unwrap();
+        writer.write_all(b"not json\n").unwrap();
+        writer
+            .write_all(&line("2024-05-01T10:00:00.200Z", "second"))
+            .unwrap();
+        // Held until the window passes or the writer is dropped
+        assert!(buffer.lines().is_empty());
+
+        drop(writer);
+        let lines = buffer.lines();
+        let order: Vec<_> = ["first", "second", "third", "not json"]
+            .iter()
+            .map(|message| {
+                lines
+                    .iter()
+                    .position(|line| line.contains(message))
+                    .unwrap()
+            })
+            .collect();
+        assert_eq!(order, vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_lines_written_once_window_passes() {
+        let buffer = SharedBuffer::default();
+        let mut writer = ReorderingWriter::new(buffer.clone(), Duration::from_millis(5));
+
+        writer.write_all(&line("1714557600200", "later")).unwrap();
+        writer.write_all(&line("1714557600100", "earlier")).unwrap();
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while buffer.lines().len() < 2 && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(5));
+        }
+        let lines = buffer.lines();
+        assert_eq!(lines.len(), 2, "lines should be written without a drop");
+        assert!(lines[0].contains("earlier") && lines[1].contains("later"));
+    }
+
+    #[test]
+    fn test_timestamp_key_formats() {
+        assert_eq!(timestamp_key(&line("1714557600", "m")), Some(1_714_557_600));
+        assert_eq!(
+            timestamp_key(&line("2024-05-01T10:00:00.000000001+00:00", "m")),
+            Some(1_714_557_600_000_000_001)
+        );
+        assert_eq!(timestamp_key(b"{\"fields\":{}}"), None);
+    }
+}
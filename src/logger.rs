@@ -37,6 +37,31 @@
 //! - `LOG_FORMAT`: Format for stdout logs ("pretty" or "json", default: "pretty")
 //! - `LOG_BODIES`: Whether to log request/response bodies (default: "true")
 //! - `LOG_MAX_BODY_SIZE`: Maximum size for logged bodies in bytes (default: "20480")
+//! - `DEPLOYMENT_ENV`: Environment name attached to every event (default: unset)
+//! - `DEDUPE_REPEATED_LOGS`: Summarize identical consecutive events (default: "false")
+//! - `LOG_SOCKET_PATH`: Unix socket receiving JSON log lines (default: unset)
+//!
+//! # Log Socket
+//!
+//! When `LOG_SOCKET_PATH` is set (Unix only), every event passing the file log level
+//! is also written as a JSON line to that Unix stream socket, e.g. a local log
+//! collector. The connection is opened lazily and re-established on the next event
+//! after a failed write, so a collector restart loses at most the events emitted while
+//! it was down.
+//!
+//! # Deployment Environment Tag
+//!
+//! When `DEPLOYMENT_ENV` is set, every event carries the environment name so logs
+//! shipped from several deployments to one place can be told apart. JSON output gets
+//! a top-level `"deployment.environment"` key; pretty output is prefixed with
+//! `[<environment>]`.
+//!
+//! # Repeated Event Suppression
+//!
+//! When `DEDUPE_REPEATED_LOGS` is enabled, identical consecutive events (same message
+//! and level) are written up to `LOG_REPEAT_THRESHOLD` times per `LOG_REPEAT_WINDOW`.
+//! Further repeats are dropped, and a copy of the last dropped event annotated with
+//! `(repeated N times)` is written once the run ends or the window elapses.
 //!
 //! # JSON Log Format
 //!
@@ -68,18 +93,35 @@
 //! performance under high loads. The `WorkerGuard` returned by `init_tracing()` must be kept
 //! alive for the duration of the application to ensure logs are properly flushed.
 
-use crate::config::{Config, DEFAULT_LOG_DIRECTORY_MODE};
+use crate::build_info::{BUILD_COMMIT, BUILD_COMMIT_FIELD};
+use crate::config::{ColorMode, Config, TimestampFormat, DEFAULT_LOG_DIRECTORY_MODE};
 use crate::fs_utils;
+use crate::log_level_override::{LevelOverrideFilter, LevelOverrideLayer};
+use crate::log_naming::{self, DatedFileAppender};
+use crate::log_reorder::{ReorderingWriter, REORDER_WINDOW};
 use directories::ProjectDirs;
 use std::env;
-use std::io;
+use std::fmt;
+use std::io::{self, IsTerminal};
 #[cfg(target_family = "unix")]
 use std::os::unix::fs::MetadataExt;
+#[cfg(target_family = "unix")]
+use std::os::unix::net::UnixStream;
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use thiserror::Error;
-use tracing::{error, info};
-use tracing_appender::non_blocking::WorkerGuard;
+use tracing::field::{Field, Value, Visit};
+use tracing::{error, info, Event, Level, Subscriber};
+use tracing_appender::non_blocking::{NonBlocking, WorkerGuard};
 use tracing_appender::rolling;
+use tracing_subscriber::field::{RecordFields, VisitOutput};
+use tracing_subscriber::fmt::format::{PrettyVisitor, Writer};
+use tracing_subscriber::fmt::time::FormatTime;
+use tracing_subscriber::fmt::writer::{BoxMakeWriter, MakeWriterExt};
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::fmt::{FmtContext, FormatEvent, FormatFields, FormattedFields};
+use tracing_subscriber::registry::LookupSpan;
 use tracing_subscriber::{fmt as tracing_fmt, prelude::*, registry, EnvFilter};
 
 /// Default base directory for logs
@@ -132,7 +174,7 @@ pub enum LogType {
 /// across different deployment environments, following platform-specific conventions.
 #[derive(Debug, Clone)]
 pub struct LogPathResolver {
-    /// Base directory for logs, determined by environment
+    /// Base directory for logs, from `log_base_dir` or determined by environment
     base_dir: PathBuf,
     /// Type of logs (application or test)
     log_type: LogType,
@@ -145,7 +187,7 @@ impl LogPathResolver {
     ///
     /// Initializes a path resolver based on the current environment and provided configuration.
     /// The resolver will use the appropriate base directory for the detected environment,
-    /// and will be configured for either application or test logs.
+    /// unless `log_base_dir` pins one, and will be configured for either application or test logs.
     ///
     /// # Arguments
     ///
@@ -178,6 +220,7 @@ impl LogPathResolver {
     ///     # log_max_body_size: 20480,
     ///     # log_directory_mode: LogDirectoryMode::Default,
     ///     # log_max_age_days: None,
+    ///     # ..Default::default()
     /// };
     ///
     /// // Create a resolver for application logs
@@ -188,11 +231,11 @@ impl LogPathResolver {
         // The variable is intentionally unused here but the check is useful for debugging
         let _is_legacy = Self::is_legacy_path(&config.log_file_path);
 
-        // Detect the current environment
-        let environment = detect_environment();
-
-        // Get the appropriate base directory for this environment
-        let base_dir = get_environment_log_directory(environment);
+        // An explicit base directory wins; otherwise use the one for the detected environment
+        let base_dir = match &config.log_base_dir {
+            Some(dir) => PathBuf::from(dir),
+            None => get_environment_log_directory(detect_environment()),
+        };
 
         // Extract just the filename from the configured log path
         let file_name = Path::new(&config.log_file_path)
@@ -260,10 +303,33 @@ impl LogPathResolver {
             || path_contains_generic_pattern)
     }
 
+    /// Computes the complete log file path without touching the filesystem
+    ///
+    /// Returns the same path as [`resolve`](Self::resolve), but creates no directories
+    /// and changes no permissions, so it is safe for diagnostics and dry runs. The
+    /// directory may not exist yet.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(PathBuf)` - The path logs would be written to
+    /// * `Err(LogInitError)` - Error details if the path cannot be computed
+    pub fn resolve_readonly(&self) -> Result<PathBuf, LogInitError> {
+        Ok(self.log_dir().join(&self.file_name))
+    }
+
+    /// Directory holding logs of this resolver's type: the base directory plus app/ or test/
+    fn log_dir(&self) -> PathBuf {
+        let subdir = match self.log_type {
+            LogType::Application => APP_LOG_SUBDIR,
+            LogType::Test => TEST_LOG_SUBDIR,
+        };
+        self.base_dir.join(subdir)
+    }
+
     /// Resolves the complete log file path and creates necessary directories
     ///
     /// This method:
-    /// 1. Constructs the full path by combining:
+    /// 1. Computes the full path (see [`resolve_readonly`](Self::resolve_readonly)) by combining:
     ///    - Base directory (determined by environment)
     ///    - Subdirectory (app/ or test/ based on log type)
     ///    - Filename
@@ -298,6 +364,7 @@ impl LogPathResolver {
     ///     # log_max_body_size: 20480,
     ///     # log_directory_mode: LogDirectoryMode::Default,
     ///     # log_max_age_days: None,
+    ///     # ..Default::default()
     /// };
     ///
     /// // Create a resolver for application logs and resolve the path
@@ -309,14 +376,9 @@ impl LogPathResolver {
         // Check if the original path is a legacy path
         let is_legacy = Self::is_legacy_path(&self.file_name);
 
-        // Determine the appropriate subdirectory based on log type
-        let subdir = match self.log_type {
-            LogType::Application => APP_LOG_SUBDIR,
-            LogType::Test => TEST_LOG_SUBDIR,
-        };
-
-        // Construct the directory path by combining base dir and subdirectory
-        let dir_path = self.base_dir.join(subdir);
+        // Compute the path first, then make sure its directory exists
+        let file_path = self.resolve_readonly()?;
+        let dir_path = self.log_dir();
 
         // Create the directory with appropriate permissions if it doesn't exist
         if !dir_path.exists() {
@@ -386,9 +448,6 @@ impl LogPathResolver {
             }
         }
 
-        // Combine directory path with filename to get the full path
-        let file_path = dir_path.join(&self.file_name);
-
         // If we detected a legacy path, log a warning about it
         if is_legacy {
             // We use eprintln here because normal logging might not be initialized yet
@@ -487,6 +546,10 @@ pub enum LogInitError {
     /// Generic I/O error during log initialization
     #[error("I/O error during log initialization: {0}")]
     IoError(#[from] io::Error),
+
+    /// A log socket was configured on a platform without Unix sockets
+    #[error("LOG_SOCKET_PATH is only supported on Unix platforms (got {0})")]
+    UnsupportedLogSocket(String),
 }
 
 /// Validate a log file path for security and usability concerns, creating directories as needed
@@ -823,6 +886,7 @@ pub fn validate_log_path(path_str: &str) -> Result<PathBuf, LogInitError> {
 /// #     log_max_body_size: 20480,
 /// #     log_directory_mode: LogDirectoryMode::Default,
 /// #     log_max_age_days: None,
+/// #     ..Default::default()
 /// # };
 /// // Initialize logging and keep the guard alive
 /// let _guard = logger::init_tracing(&mock_config).expect("Failed to initialize logging");
@@ -850,6 +914,7 @@ pub fn validate_log_path(path_str: &str) -> Result<PathBuf, LogInitError> {
 ///     # log_max_body_size: 20480,
 ///     # log_directory_mode: LogDirectoryMode::Default,
 ///     # log_max_age_days: None,
+///     # ..Default::default()
 /// };
 ///
 /// let _guard = logger::init_tracing(&config).expect("Failed to initialize logging");
@@ -873,6 +938,7 @@ pub fn validate_log_path(path_str: &str) -> Result<PathBuf, LogInitError> {
 ///     # log_max_body_size: 20480,
 ///     # log_directory_mode: LogDirectoryMode::Default,
 ///     # log_max_age_days: None,
+///     # ..Default::default()
 /// };
 ///
 /// let _guard = logger::init_tracing(&config).expect("Failed to initialize logging");
@@ -1069,6 +1135,618 @@ pub fn get_environment_log_directory(environment: LogEnvironment) -> PathBuf {
     }
 }
 
+/// Name of the field carrying the deployment environment in JSON log events
+pub const DEPLOYMENT_ENV_FIELD: &str = "deployment.environment";
+
+/// How the deployment environment is attached to a formatted event
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TagStyle {
+    /// Insert a top-level key into the JSON object
+    Json,
+    /// Prefix the line with `[environment]`
+    Prefix,
+}
+
+/// Length of the build commit prefix in human-readable output
+const PREFIX_COMMIT_LEN: usize = 12;
+
+/// Event formatter that tags every event with the deployment environment
+///
+/// Wraps another formatter (JSON or pretty) and, when an environment is configured,
+/// adds it to each event. The build commit can be added the same way. With no tags
+/// configured, output is untouched.
+#[derive(Debug, Clone)]
+pub struct EnvironmentTaggedFormat<E> {
+    inner: E,
+    environment: Option<String>,
+    build_commit: Option<&'static str>,
+    style: TagStyle,
+}
+
+impl<E> EnvironmentTaggedFormat<E> {
+    /// Wrap a JSON formatter, adding a top-level `deployment.environment` key
+    pub fn json(inner: E, environment: Option<String>) -> Self {
+        Self {
+            inner,
+            environment,
+            build_commit: None,
+            style: TagStyle::Json,
+        }
+    }
+
+    /// Wrap a human-readable formatter, prefixing each event with `[environment]`
+    pub fn prefixed(inner: E, environment: Option<String>) -> Self {
+        Self {
+            inner,
+            environment,
+            build_commit: None,
+            style: TagStyle::Prefix,
+        }
+    }
+
+    /// Also tag each event with the build commit (`build.commit` key or `[commit]` prefix)
+    pub fn with_build_commit(mut self, build_commit: Option<&'static str>) -> Self {
+        self.build_commit = build_commit;
+        self
+    }
+}
+
+impl<S, N, E> FormatEvent<S, N> for EnvironmentTaggedFormat<E>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+    E: FormatEvent<S, N>,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, N>,
+        mut writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        let tags: Vec<(&str, &str)> = [
+            (DEPLOYMENT_ENV_FIELD, self.environment.as_deref()),
+            (BUILD_COMMIT_FIELD, self.build_commit),
+        ]
+        .into_iter()
+        .filter_map(|(key, value)| Some((key, value?)))
+        .collect();
+
+        // Nothing to add - delegate straight to the wrapped formatter
+        if tags.is_empty() {
+            return self.inner.format_event(ctx, writer, event);
+        }
+
+        match self.style {
+            TagStyle::Prefix => {
+                // Writing the prefix first keeps the inner formatter's ANSI handling intact
+                for (key, value) in tags {
+                    let value = match key {
+                        BUILD_COMMIT_FIELD => value.get(..PREFIX_COMMIT_LEN).unwrap_or(value),
+                        _ => value,
+                    };
+                    write!(writer, "[{}] ", value)?;
+                }
+                self.inner.format_event(ctx, writer, event)
+            }
+            TagStyle::Json => {
+                // Format into a buffer so the keys can be spliced into the object
+                let mut buf = String::new();
+                self.inner.format_event(ctx, Writer::new(&mut buf), event)?;
+
+                match buf.strip_prefix('{') {
+                    Some(rest) => {
+                        writer.write_char('{')?;
+                        for (key, value) in tags {
+                            // serde_json handles quoting/escaping of the tag values
+                            let value = serde_json::Value::String(value.to_string());
+                            write!(writer, "\"{}\":{},", key, value)?;
+                        }
+                        writer.write_str(rest)
+                    }
+                    // Not a JSON object - pass the output through unchanged
+                    None => writer.write_str(&buf),
+                }
+            }
+        }
+    }
+}
+
+/// Timer writing event timestamps in the configured [`TimestampFormat`]
+///
+/// RFC 3339 output is delegated to tracing-subscriber's own timer, so the default
+/// format is exactly what the subscriber would write without a custom timer.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LogTimer(pub TimestampFormat);
+
+impl FormatTime for LogTimer {
+    fn format_time(&self, w: &mut Writer<'_>) -> fmt::Result {
+        // A clock before 1970 is treated as the epoch itself
+        let since_epoch = || {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+        };
+
+        match self.0 {
+            TimestampFormat::Rfc3339 => tracing_fmt::time::SystemTime.format_time(w),
+            TimestampFormat::EpochMillis => write!(w, "{}", since_epoch().as_millis()),
+            TimestampFormat::EpochSecs => write!(w, "{}", since_epoch().as_secs()),
+        }
+    }
+}
+
+/// Routes WARN and ERROR events to `errors` and all other events to `others`
+pub fn split_by_level<E, O>(errors: E, others: O) -> impl for<'a> MakeWriter<'a>
+where
+    E: for<'a> MakeWriter<'a>,
+    O: for<'a> MakeWriter<'a>,
+{
+    // A maximum level of WARN admits WARN and the more severe ERROR
+    errors.with_max_level(Level::WARN).or_else(others)
+}
+
+/// Builds the console writer: stdout only, or split with WARN/ERROR on stderr
+fn console_writer(error_log_to_stderr: bool) -> BoxMakeWriter {
+    if error_log_to_stderr {
+        BoxMakeWriter::new(split_by_level(io::stderr, io::stdout))
+    } else {
+        BoxMakeWriter::new(io::stdout)
+    }
+}
+
+/// Decides whether pretty console output uses ANSI colors
+///
+/// In `auto` mode colors are used only on a terminal, so logs captured by CI or
+/// redirected to a file are not garbled by escape codes.
+fn ansi_enabled(mode: ColorMode, is_terminal: bool) -> bool {
+    match mode {
+        ColorMode::Auto => is_terminal,
+        ColorMode::Always => true,
+        ColorMode::Never => false,
+    }
+}
+
+/// How long writing a line to the log socket may block before the line is dropped
+#[cfg(target_family = "unix")]
+pub const LOG_SOCKET_WRITE_TIMEOUT: Duration = Duration::from_millis(250);
+
+/// Writer sending log lines to a Unix stream socket, reconnecting as needed
+///
+/// The connection is opened on the first write. When a write fails (e.g. because the
+/// collector restarted), the writer reconnects once and retries; if that fails too the
+/// line is dropped and the next write tries again. Logging never fails because of it.
+#[cfg(target_family = "unix")]
+#[derive(Debug, Clone)]
+pub struct UnixSocketWriter {
+    path: PathBuf,
+    stream: Arc<Mutex<Option<UnixStream>>>,
+}
+
+#[cfg(target_family = "unix")]
+impl UnixSocketWriter {
+    /// Creates a writer for the socket at `path` without connecting yet
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            stream: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Writes a whole line, reconnecting once if the current connection is broken
+    ///
+    /// # Returns
+    /// True if the line was written
+    pub fn send(&self, line: &[u8]) -> bool {
+        use io::Write as _;
+
+        let mut stream = self.stream.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(connected) = stream.as_mut() {
+            if connected.write_all(line).is_ok() {
+                return true;
+            }
+        }
+
+        // No connection yet, or the collector went away - start afresh
+        *stream = None;
+        let Ok(mut reconnected) = UnixStream::connect(&self.path) else {
+            return false;
+        };
+        if reconnected
+            .set_write_timeout(Some(LOG_SOCKET_WRITE_TIMEOUT))
+            .is_err()
+            || reconnected.write_all(line).is_err()
+        {
+            return false;
+        }
+        *stream = Some(reconnected);
+        true
+    }
+}
+
+#[cfg(target_family = "unix")]
+impl io::Write for &UnixSocketWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // Undeliverable lines are dropped rather than reported as errors
+        self.send(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(target_family = "unix")]
+impl<'a> MakeWriter<'a> for UnixSocketWriter {
+    type Writer = &'a UnixSocketWriter;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        self
+    }
+}
+
+/// Number of identical consecutive events written per window before repeats are suppressed
+pub const LOG_REPEAT_THRESHOLD: u64 = 5;
+
+/// How long a run of identical events is suppressed before a summary is written
+pub const LOG_REPEAT_WINDOW: Duration = Duration::from_secs(30);
+
+/// Event formatter that suppresses identical consecutive events
+///
+/// Events are keyed on level and message. Once a key has been written
+/// `LOG_REPEAT_THRESHOLD` times within `LOG_REPEAT_WINDOW`, further repeats are
+/// dropped. The last dropped event is then written with its message annotated as
+/// `(repeated N times)` when a different event arrives or the window elapses.
+/// Events without a message are never suppressed. When disabled, output is untouched.
+#[derive(Debug, Clone)]
+pub struct RepeatSuppressingFormat<E> {
+    inner: E,
+    enabled: bool,
+    threshold: u64,
+    window: Duration,
+    state: Arc<Mutex<RepeatState>>,
+}
+
+/// Tracks the current run of identical events for [`RepeatSuppressingFormat`]
+#[derive(Debug, Default)]
+struct RepeatState {
+    /// Level and message of the most recent event (None for events without a message)
+    key: Option<(Level, String)>,
+    /// When the current window for `key` started
+    window_start: Option<Instant>,
+    /// Events seen for `key` in the current window
+    seen: u64,
+    /// Events dropped for `key` since the last summary
+    suppressed: u64,
+    /// Plain-text formatting of the most recently dropped event
+    last_suppressed: String,
+}
+
+impl RepeatState {
+    /// Takes the summary line for events dropped since the last summary, if any
+    fn take_summary(&mut self) -> Option<String> {
+        let (_, message) = self.key.as_ref()?;
+        if self.suppressed == 0 {
+            return None;
+        }
+
+        let summary = repeat_summary(&self.last_suppressed, message, self.suppressed);
+        self.suppressed = 0;
+        self.last_suppressed.clear();
+        Some(summary)
+    }
+}
+
+impl<E> RepeatSuppressingFormat<E> {
+    /// Wrap a formatter, suppressing repeated events when `enabled` is true
+    pub fn new(inner: E, enabled: bool) -> Self {
+        Self {
+            inner,
+            enabled,
+            threshold: LOG_REPEAT_THRESHOLD,
+            window: LOG_REPEAT_WINDOW,
+            state: Arc::new(Mutex::new(RepeatState::default())),
+        }
+    }
+}
+
+impl<S, N, E> FormatEvent<S, N> for RepeatSuppressingFormat<E>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+    E: FormatEvent<S, N>,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, N>,
+        mut writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        if !self.enabled {
+            return self.inner.format_event(ctx, writer, event);
+        }
+
+        let key = event_message(event).map(|message| (*event.metadata().level(), message));
+        let now = Instant::now();
+
+        let summary = {
+            let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+
+            if key.is_some() && state.key == key {
+                state.seen += 1;
+                let window_elapsed = state
+                    .window_start
+                    .is_none_or(|start| now.duration_since(start) >= self.window);
+
+                if window_elapsed {
+                    // Start a new window, reporting what the previous one dropped
+                    state.window_start = Some(now);
+                    state.seen = 1;
+                    state.take_summary()
+                } else if state.seen > self.threshold {
+                    // Keep the formatted event so the summary can reuse it
+                    let mut buf = String::new();
+                    self.inner.format_event(ctx, Writer::new(&mut buf), event)?;
+                    state.last_suppressed = buf;
+                    state.suppressed += 1;
+                    return Ok(());
+                } else {
+                    None
+                }
+            } else {
+                // A different event ends the current run
+                let summary = state.take_summary();
+                state.key = key;
+                state.window_start = Some(now);
+                state.seen = 1;
+                summary
+            }
+        };
+
+        if let Some(summary) = summary {
+            writer.write_str(&summary)?;
+        }
+        self.inner.format_event(ctx, writer, event)
+    }
+}
+
+/// Extracts the `message` field of an event, if it has one
+fn event_message(event: &Event<'_>) -> Option<String> {
+    struct MessageVisitor(Option<String>);
+
+    impl Visit for MessageVisitor {
+        fn record_str(&mut self, field: &Field, value: &str) {
+            if field.name() == "message" {
+                self.0 = Some(value.to_string());
+            }
+        }
+
+        fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+            if field.name() == "message" {
+                self.0 = Some(format!("{:?}", value));
+            }
+        }
+    }
+
+    let mut visitor = MessageVisitor(None);
+    event.record(&mut visitor);
+    visitor.0
+}
+
+/// Builds the summary line for a run of suppressed events
+///
+/// JSON output gets its `fields.message` annotated and a `fields.repeated_count`
+/// key; any other output has the message annotated in place.
+///
+/// # Arguments
+/// * `formatted` - The formatted output of the last suppressed event
+/// * `message` - The message shared by the suppressed events
+/// * `count` - How many events were suppressed
+fn repeat_summary(formatted: &str, message: &str, count: u64) -> String {
+    let annotated = format!("{} (repeated {} times)", message, count);
+
+    match serde_json::from_str::<serde_json::Value>(formatted.trim()) {
+        Ok(serde_json::Value::Object(mut object)) => {
+            if let Some(serde_json::Value::Object(fields)) = object.get_mut("fields") {
+                fields.insert("message".to_string(), annotated.into());
+                fields.insert("repeated_count".to_string(), count.into());
+            }
+            format!("{}\n", serde_json::Value::Object(object))
+        }
+        _ => formatted.replacen(message, &annotated, 1),
+    }
+}
+
+/// Most fields a single event can carry; `tracing` builds value sets from fixed-size arrays
+const MAX_EVENT_FIELDS: usize = 32;
+
+/// Suffix marking a field value that was truncated
+pub const TRUNCATION_MARKER: char = '…';
+
+/// Field formatter for pretty output that truncates long field values
+///
+/// Values longer than the limit are cut to `max_len` characters and end in
+/// [`TRUNCATION_MARKER`]. Only the pretty console layer uses it, so JSON output
+/// and the log file keep full values. With no limit, output is untouched.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TruncatingFields {
+    max_len: Option<usize>,
+}
+
+impl TruncatingFields {
+    /// Create a formatter truncating values beyond `max_len` characters (None = unlimited)
+    pub fn new(max_len: Option<usize>) -> Self {
+        Self { max_len }
+    }
+
+    /// Collects the values of `fields`, truncated to the limit
+    fn truncated_values<R: RecordFields>(&self, fields: R) -> Vec<(Field, Box<dyn Value>)> {
+        let mut visitor = TruncatingVisitor {
+            max_len: self.max_len,
+            values: Vec::new(),
+        };
+        fields.record(&mut visitor);
+        visitor.values
+    }
+
+    /// Writes `fields` in pretty style, continuing a list when `is_empty` is false
+    fn write_pretty<R: RecordFields>(
+        &self,
+        writer: Writer<'_>,
+        fields: R,
+        is_empty: bool,
+    ) -> fmt::Result {
+        let mut visitor = PrettyVisitor::new(writer, is_empty);
+        for (field, value) in self.truncated_values(fields) {
+            value.record(&field, &mut visitor);
+        }
+        visitor.finish()
+    }
+}
+
+impl<'writer> FormatFields<'writer> for TruncatingFields {
+    fn format_fields<R: RecordFields>(&self, writer: Writer<'writer>, fields: R) -> fmt::Result {
+        self.write_pretty(writer, fields, true)
+    }
+
+    fn add_fields(
+        &self,
+        current: &'writer mut FormattedFields<Self>,
+        fields: &tracing::span::Record<'_>,
+    ) -> fmt::Result {
+        let is_empty = current.is_empty();
+        self.write_pretty(current.as_writer(), fields, is_empty)
+    }
+}
+
+/// Collects field values for [`TruncatingFields`], truncating strings and Debug output
+struct TruncatingVisitor {
+    max_len: Option<usize>,
+    values: Vec<(Field, Box<dyn Value>)>,
+}
+
+impl TruncatingVisitor {
+    /// Returns `value` cut to the limit and marked, or unchanged if it fits
+    fn truncate(&self, mut value: String) -> String {
+        if let Some((cut, _)) = self
+            .max_len
+            .and_then(|max_len| value.char_indices().nth(max_len))
+        {
+            value.truncate(cut);
+            value.push(TRUNCATION_MARKER);
+        }
+        value
+    }
+}
+
+impl Visit for TruncatingVisitor {
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.values.push((field.clone(), Box::new(value)));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.values.push((field.clone(), Box::new(value)));
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.values.push((field.clone(), Box::new(value)));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.values.push((field.clone(), Box::new(value)));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        // Stays a string value, so the pretty visitor still quotes it
+        let value = self.truncate(value.to_string());
+        self.values.push((field.clone(), Box::new(value)));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        let value = self.truncate(format!("{:?}", value));
+        self.values
+            .push((field.clone(), Box::new(tracing::field::display(value))));
+    }
+}
+
+/// Event formatter that applies [`TruncatingFields`] to event fields
+///
+/// The pretty event formatter records event fields itself rather than through the
+/// layer's field formatter, so the event is rebuilt with truncated values before
+/// being handed to the wrapped formatter. Span fields are covered by using
+/// [`TruncatingFields`] as the layer's field formatter.
+#[derive(Debug, Clone)]
+pub struct TruncatingFormat<E> {
+    inner: E,
+    fields: TruncatingFields,
+}
+
+impl<E> TruncatingFormat<E> {
+    /// Wrap a formatter, truncating event field values beyond `max_len` characters
+    pub fn new(inner: E, max_len: Option<usize>) -> Self {
+        Self {
+            inner,
+            fields: TruncatingFields::new(max_len),
+        }
+    }
+}
+
+impl<S, N, E> FormatEvent<S, N> for TruncatingFormat<E>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+    E: FormatEvent<S, N>,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, N>,
+        writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        let metadata = event.metadata();
+        let filler = metadata.fields().iter().next();
+        let (Some(_), Some(filler)) = (self.fields.max_len, filler) else {
+            return self.inner.format_event(ctx, writer, event);
+        };
+
+        let truncated = self.fields.truncated_values(event);
+        if truncated.len() > MAX_EVENT_FIELDS {
+            return self.inner.format_event(ctx, writer, event);
+        }
+
+        // Unused slots repeat a field without a value, which recording skips
+        let mut entries: [(&Field, Option<&dyn Value>); MAX_EVENT_FIELDS] =
+            [(&filler, None); MAX_EVENT_FIELDS];
+        for (entry, (field, value)) in entries.iter_mut().zip(&truncated) {
+            *entry = (field, Some(value.as_ref()));
+        }
+        let values = metadata.fields().value_set(&entries);
+
+        let rebuilt = if event.is_contextual() {
+            Event::new(metadata, &values)
+        } else {
+            Event::new_child_of(event.parent().cloned(), metadata, &values)
+        };
+        self.inner.format_event(ctx, writer, &rebuilt)
+    }
+}
+
+/// Starts the non-blocking writer for a file appender
+///
+/// With `order_lines`, lines pass through a [`ReorderingWriter`] on the worker side,
+/// so they reach the file in timestamp order and any still held are written when
+/// the guard stops the worker.
+fn non_blocking_file_writer<W: io::Write + Send + 'static>(
+    appender: W,
+    order_lines: bool,
+) -> (NonBlocking, WorkerGuard) {
+    if order_lines {
+        tracing_appender::non_blocking(ReorderingWriter::new(appender, REORDER_WINDOW))
+    } else {
+        tracing_appender::non_blocking(appender)
+    }
+}
+
 pub fn init_tracing(config: &Config) -> Result<WorkerGuard, LogInitError> {
     // Check for empty path before creating resolver
     if config.log_file_path.is_empty() {
@@ -1086,13 +1764,21 @@ pub fn init_tracing(config: &Config) -> Result<WorkerGuard, LogInitError> {
 
     // Extract directory and filename from the resolved path
     let log_dir = resolved_path.parent().unwrap_or_else(|| Path::new("."));
-    let log_file_name = resolved_path.file_name().unwrap();
-
-    // Create daily rotating file appender
-    let file_appender = rolling::daily(log_dir, log_file_name);
-
-    // Create non-blocking writer and get the guard
-    let (non_blocking_writer, guard) = tracing_appender::non_blocking(file_appender);
+    let log_file_name = resolved_path.file_name().unwrap().to_string_lossy();
+    let prefix = log_naming::log_file_prefix(config, &log_file_name);
+
+    // Create the daily rotating file appender for the configured naming scheme, with
+    // a non-blocking writer and its guard
+    let (non_blocking_writer, guard) = if config.log_file_date_in_name {
+        let file_appender = DatedFileAppender::new(log_dir, prefix)?;
+        non_blocking_file_writer(file_appender, config.order_file_logs)
+    } else {
+        let file_appender = rolling::daily(
+            log_dir,
+            log_naming::rolling_file_name(config, &log_file_name),
+        );
+        non_blocking_file_writer(file_appender, config.order_file_logs)
+    };
 
     // Create file filter based on config.log_file_level
     let file_filter = match EnvFilter::try_new(&config.log_file_level) {
@@ -1106,11 +1792,25 @@ pub fn init_tracing(config: &Config) -> Result<WorkerGuard, LogInitError> {
         }
     };
 
+    // Every layer writes timestamps in the same configured format
+    let timer = LogTimer(config.log_timestamp_format);
+    // Every filter also admits events of requests that asked for a higher level
+    let allow_level_override = config.allow_client_log_level;
+    let build_commit = config.log_build_info.then_some(BUILD_COMMIT);
+
     // Create file layer with JSON formatting
     let file_layer = tracing_fmt::layer()
         .json()
+        .event_format(RepeatSuppressingFormat::new(
+            EnvironmentTaggedFormat::json(
+                tracing_fmt::format().json().with_timer(timer),
+                config.deployment_env.clone(),
+            )
+            .with_build_commit(build_commit),
+            config.dedupe_repeated_logs,
+        ))
         .with_writer(non_blocking_writer)
-        .with_filter(file_filter);
+        .with_filter(LevelOverrideFilter::new(file_filter, allow_level_override));
 
     // Create stdout filter based on RUST_LOG or config.log_stdout_level
     let stdout_filter = match EnvFilter::try_from_default_env() {
@@ -1127,21 +1827,89 @@ pub fn init_tracing(config: &Config) -> Result<WorkerGuard, LogInitError> {
         },
     };
 
-    // Create registry and add file layer
-    let subscriber = registry().with(file_layer);
+    // Send the same JSON lines as the file to the log socket, if one is configured
+    #[cfg(target_family = "unix")]
+    let socket_layer = match &config.log_socket_path {
+        Some(path) => {
+            let socket_filter = EnvFilter::try_new(&config.log_file_level).map_err(|e| {
+                LogInitError::FilterParseError(format!(
+                    "Failed to parse socket log level filter '{}': {}",
+                    config.log_file_level, e
+                ))
+            })?;
+            Some(
+                tracing_fmt::layer()
+                    .json()
+                    .event_format(RepeatSuppressingFormat::new(
+                        EnvironmentTaggedFormat::json(
+                            tracing_fmt::format().json().with_timer(timer),
+                            config.deployment_env.clone(),
+                        )
+                        .with_build_commit(build_commit),
+                        config.dedupe_repeated_logs,
+                    ))
+                    .with_writer(UnixSocketWriter::new(path))
+                    .with_filter(LevelOverrideFilter::new(
+                        socket_filter,
+                        allow_level_override,
+                    )),
+            )
+        }
+        None => None,
+    };
+    #[cfg(not(target_family = "unix"))]
+    let socket_layer: Option<tracing_subscriber::layer::Identity> = match &config.log_socket_path {
+        Some(path) => return Err(LogInitError::UnsupportedLogSocket(path.clone())),
+        None => None,
+    };
+
+    // Create registry and add file and socket layers, plus the tracking of per-request
+    // level overrides if clients may request them
+    let subscriber = registry()
+        .with(allow_level_override.then_some(LevelOverrideLayer))
+        .with(file_layer)
+        .with(socket_layer);
 
     // Add the appropriate stdout layer based on format
     if config.log_format == "json" {
         let json_layer = tracing_fmt::layer()
             .json()
-            .with_writer(io::stdout)
-            .with_filter(stdout_filter);
+            .event_format(RepeatSuppressingFormat::new(
+                EnvironmentTaggedFormat::json(
+                    tracing_fmt::format().json().with_timer(timer),
+                    config.deployment_env.clone(),
+                )
+                .with_build_commit(build_commit),
+                config.dedupe_repeated_logs,
+            ))
+            .with_writer(console_writer(config.error_log_to_stderr))
+            .with_filter(LevelOverrideFilter::new(
+                stdout_filter,
+                allow_level_override,
+            ));
         subscriber.with(json_layer).init();
     } else {
+        // Long field values are truncated here only; the file layer logs them in full
         let pretty_layer = tracing_fmt::layer()
             .pretty()
-            .with_writer(io::stdout)
-            .with_filter(stdout_filter);
+            .fmt_fields(TruncatingFields::new(config.stdout_max_field_len))
+            .event_format(TruncatingFormat::new(
+                RepeatSuppressingFormat::new(
+                    EnvironmentTaggedFormat::prefixed(
+                        tracing_fmt::format().pretty().with_timer(timer),
+                        config.deployment_env.clone(),
+                    )
+                    .with_build_commit(build_commit),
+                    config.dedupe_repeated_logs,
+                ),
+                config.stdout_max_field_len,
+            ))
+            .with_ansi(ansi_enabled(config.log_color, io::stdout().is_terminal()))
+            .with_writer(console_writer(config.error_log_to_stderr))
+            .with_filter(LevelOverrideFilter::new(
+                stdout_filter,
+                allow_level_override,
+            ));
         subscriber.with(pretty_layer).init();
     }
 
@@ -1154,6 +1922,12 @@ pub fn init_tracing(config: &Config) -> Result<WorkerGuard, LogInitError> {
             resolved_path = %resolved_path.display(),
             log_file_level = %config.log_file_level,
             log_directory_mode = ?config.log_directory_mode,
+            deployment_env = ?config.deployment_env,
+            dedupe_repeated_logs = config.dedupe_repeated_logs,
+            log_timestamp_format = ?config.log_timestamp_format,
+            error_log_to_stderr = config.error_log_to_stderr,
+            stdout_max_field_len = ?config.stdout_max_field_len,
+            log_socket_path = ?config.log_socket_path,
             "Dual logging initialized with legacy path adaptation"
         );
     } else {
@@ -1163,6 +1937,12 @@ pub fn init_tracing(config: &Config) -> Result<WorkerGuard, LogInitError> {
             log_file_path = %resolved_path.display(),
             log_file_level = %config.log_file_level,
             log_directory_mode = ?config.log_directory_mode,
+            deployment_env = ?config.deployment_env,
+            dedupe_repeated_logs = config.dedupe_repeated_logs,
+            log_timestamp_format = ?config.log_timestamp_format,
+            error_log_to_stderr = config.error_log_to_stderr,
+            stdout_max_field_len = ?config.stdout_max_field_len,
+            log_socket_path = ?config.log_socket_path,
             "Dual logging initialized"
         );
     }
@@ -1325,6 +2105,7 @@ mod tests {
             log_max_body_size: 1024,
             log_directory_mode: crate::config::LogDirectoryMode::Default,
             log_max_age_days: None,
+            ..Default::default()
         };
 
         // Initialize logging using our mock function
@@ -1399,6 +2180,7 @@ mod tests {
                 log_max_body_size: 1024,
                 log_directory_mode: crate::config::LogDirectoryMode::Default,
                 log_max_age_days: None,
+                ..Default::default()
             };
 
             // Initialize logging using our mock function - should return an error
@@ -1430,6 +2212,7 @@ mod tests {
                 log_max_body_size: 1024,
                 log_directory_mode: crate::config::LogDirectoryMode::Default,
                 log_max_age_days: None,
+                ..Default::default()
             };
 
             let result = mock_init_tracing(&config);
@@ -1461,6 +2244,7 @@ mod tests {
             log_max_body_size: 1024,
             log_directory_mode: crate::config::LogDirectoryMode::Default,
             log_max_age_days: None,
+            ..Default::default()
         };
 
         // Initialize logging using our mock function - should return an error
@@ -1663,6 +2447,7 @@ mod tests {
             log_max_body_size: 1024,
             log_directory_mode: crate::config::LogDirectoryMode::Default,
             log_max_age_days: None,
+            ..Default::default()
         };
 
         // Create resolvers for both application and test logs
@@ -1699,6 +2484,7 @@ mod tests {
             log_max_body_size: 1024,
             log_directory_mode: crate::config::LogDirectoryMode::Default,
             log_max_age_days: None,
+            ..Default::default()
         };
 
         // Create a resolver
@@ -1724,6 +2510,7 @@ mod tests {
             log_max_body_size: 1024,
             log_directory_mode: crate::config::LogDirectoryMode::Default,
             log_max_age_days: None,
+            ..Default::default()
         };
 
         // Create a resolver
@@ -1748,6 +2535,7 @@ mod tests {
             log_max_body_size: 1024,
             log_directory_mode: crate::config::LogDirectoryMode::Default,
             log_max_age_days: None,
+            ..Default::default()
         };
 
         // Test app log resolution
@@ -1872,6 +2660,7 @@ mod tests {
             log_max_body_size: 1024,
             log_directory_mode: crate::config::LogDirectoryMode::Default,
             log_max_age_days: None,
+            ..Default::default()
         };
 
         // Create custom resolvers with our test paths
@@ -1913,6 +2702,45 @@ mod tests {
         std::fs::remove_dir_all(temp_dir).ok();
     }
 
+    #[test]
+    fn test_log_path_resolver_resolve_readonly_creates_nothing() {
+        let temp_dir = tempfile::TempDir::new().expect("Failed to create temp dir");
+        let base_dir = temp_dir.path().join("not_yet_created");
+
+        let app_resolver = LogPathResolver {
+            base_dir: base_dir.clone(),
+            log_type: LogType::Application,
+            file_name: "readonly.log".to_string(),
+        };
+        let test_resolver = LogPathResolver {
+            base_dir: base_dir.clone(),
+            log_type: LogType::Test,
+            file_name: "readonly.log".to_string(),
+        };
+
+        let app_path = app_resolver
+            .resolve_readonly()
+            .expect("Failed to compute app path");
+        let test_path = test_resolver
+            .resolve_readonly()
+            .expect("Failed to compute test path");
+
+        // Same paths resolve() would produce, but nothing exists on disk
+        assert_eq!(app_path, base_dir.join(APP_LOG_SUBDIR).join("readonly.log"));
+        assert_eq!(
+            test_path,
+            base_dir.join(TEST_LOG_SUBDIR).join("readonly.log")
+        );
+        assert!(
+            !base_dir.exists(),
+            "resolve_readonly must not create directories"
+        );
+
+        // resolve() agrees on the path and creates the directory
+        assert_eq!(app_resolver.resolve().expect("Failed to resolve"), app_path);
+        assert!(app_path.parent().unwrap().is_dir());
+    }
+
     #[test]
     fn test_log_path_resolver_permission_error() {
         // This test only makes sense on Unix systems where we can set restricted permissions
@@ -2038,6 +2866,7 @@ mod tests {
             log_max_body_size: 1024,
             log_directory_mode: crate::config::LogDirectoryMode::Default,
             log_max_age_days: None,
+            ..Default::default()
         };
 
         // Initialize logging with the legacy path
@@ -2067,4 +2896,317 @@ mod tests {
         // We can't easily test the warning message since it goes to stdout/stderr
         // and we'd need to capture that output, but we can verify the function succeeds
     }
+
+    /// Runs `emit` under a subscriber using `format` and returns everything written
+    fn capture_formatted<E>(format: E, emit: impl FnOnce()) -> String
+    where
+        E: FormatEvent<registry::Registry, tracing_fmt::format::DefaultFields>
+            + Send
+            + Sync
+            + 'static,
+    {
+        use std::sync::{Arc, Mutex};
+
+        let buffer = Arc::new(Mutex::new(Vec::new()));
+        let writer_buffer = buffer.clone();
+        let layer = tracing_fmt::layer()
+            .event_format(format)
+            .with_ansi(false)
+            .with_writer(move || SharedBuffer(writer_buffer.clone()));
+
+        tracing::subscriber::with_default(registry().with(layer), emit);
+
+        let bytes = buffer.lock().unwrap().clone();
+        String::from_utf8(bytes).expect("Log output should be valid UTF-8")
+    }
+
+    /// Writer handing log output to a shared in-memory buffer
+    struct SharedBuffer(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl io::Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_environment_tag_json_and_pretty() {
+        // JSON output gets a top-level deployment.environment key
+        let json_output = capture_formatted(
+            EnvironmentTaggedFormat::json(tracing_fmt::format().json(), Some("staging".into())),
+            || info!(answer = 42, "tagged event"),
+        );
+        let parsed: serde_json::Value =
+            serde_json::from_str(json_output.trim()).expect("Tagged output should be valid JSON");
+        assert_eq!(parsed[DEPLOYMENT_ENV_FIELD], "staging");
+        assert_eq!(parsed["fields"]["message"], "tagged event");
+        assert_eq!(parsed["fields"]["answer"], 42);
+
+        // Pretty output is prefixed with the environment name
+        let pretty_output = capture_formatted(
+            EnvironmentTaggedFormat::prefixed(tracing_fmt::format().pretty(), Some("prod".into())),
+            || info!("tagged event"),
+        );
+        assert!(
+            pretty_output.starts_with("[prod] "),
+            "Pretty output should be prefixed with the environment: {}",
+            pretty_output
+        );
+
+        // Without an environment the output carries no tag
+        let untagged_output = capture_formatted(
+            EnvironmentTaggedFormat::json(tracing_fmt::format().json(), None),
+            || info!("untagged event"),
+        );
+        let parsed: serde_json::Value = serde_json::from_str(untagged_output.trim()).unwrap();
+        assert!(parsed.get(DEPLOYMENT_ENV_FIELD).is_none());
+    }
+
+    #[test]
+    fn test_ansi_colors_follow_color_mode() {
+        // Auto follows the terminal; the explicit modes ignore it
+        assert!(ansi_enabled(ColorMode::Auto, true));
+        assert!(!ansi_enabled(ColorMode::Auto, false));
+        assert!(ansi_enabled(ColorMode::Always, false));
+        assert!(!ansi_enabled(ColorMode::Never, true));
+    }
+
+    #[test]
+    fn test_build_commit_tag_alongside_environment() {
+        let commit = "0123456789abcdef0123456789abcdef01234567";
+        let json_output = capture_formatted(
+            EnvironmentTaggedFormat::json(tracing_fmt::format().json(), Some("staging".into()))
+                .with_build_commit(Some(commit)),
+            || info!("tagged event"),
+        );
+        let parsed: serde_json::Value =
+            serde_json::from_str(json_output.trim()).expect("Tagged output should be valid JSON");
+        assert_eq!(parsed[DEPLOYMENT_ENV_FIELD], "staging");
+        assert_eq!(parsed[BUILD_COMMIT_FIELD], commit);
+
+        // Pretty output gets a shortened commit after the environment
+        let pretty_output = capture_formatted(
+            EnvironmentTaggedFormat::prefixed(tracing_fmt::format().pretty(), Some("prod".into()))
+                .with_build_commit(Some(commit)),
+            || info!("tagged event"),
+        );
+        assert!(
+            pretty_output.starts_with("[prod] [0123456789ab] "),
+            "Pretty output should be prefixed with the environment and commit: {}",
+            pretty_output
+        );
+    }
+
+    #[test]
+    fn test_pretty_fields_truncated_while_json_keeps_full_values() {
+        use std::sync::{Arc, Mutex};
+
+        let pretty = Arc::new(Mutex::new(Vec::new()));
+        let json = Arc::new(Mutex::new(Vec::new()));
+        let (pretty_buffer, json_buffer) = (pretty.clone(), json.clone());
+        let pretty_layer = tracing_fmt::layer()
+            .pretty()
+            .fmt_fields(TruncatingFields::new(Some(10)))
+            .event_format(TruncatingFormat::new(
+                tracing_fmt::format().pretty(),
+                Some(10),
+            ))
+            .with_ansi(false)
+            .with_writer(move || SharedBuffer(pretty_buffer.clone()));
+        let json_layer = tracing_fmt::layer()
+            .json()
+            .with_writer(move || SharedBuffer(json_buffer.clone()));
+
+        let long_value = "x".repeat(50);
+        let subscriber = registry().with(pretty_layer).with(json_layer);
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("request", body = %long_value);
+            let _entered = span.enter();
+            info!(payload = %long_value, name = "short", "logged");
+        });
+
+        let pretty = String::from_utf8(pretty.lock().unwrap().clone()).unwrap();
+        let truncated = format!("{}{}", "x".repeat(10), TRUNCATION_MARKER);
+        assert!(
+            !pretty.contains(&long_value),
+            "Pretty output should not contain the full value: {}",
+            pretty
+        );
+        assert!(pretty.contains(&format!("payload: {}", truncated)));
+        assert!(pretty.contains(&format!("body: {}", truncated)));
+        // Values within the limit are left alone
+        assert!(pretty.contains("logged"));
+        assert!(pretty.contains("name: \"short\""));
+
+        let json = String::from_utf8(json.lock().unwrap().clone()).unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(json.trim()).unwrap();
+        assert_eq!(parsed["fields"]["payload"], long_value.as_str());
+        assert_eq!(parsed["span"]["body"], long_value.as_str());
+    }
+
+    #[test]
+    fn test_epoch_millis_timestamps_are_numeric() {
+        let before = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+        let output = capture_formatted(
+            tracing_fmt::format()
+                .json()
+                .with_timer(LogTimer(TimestampFormat::EpochMillis)),
+            || info!("timestamped event"),
+        );
+        let after = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+
+        let parsed: serde_json::Value = serde_json::from_str(output.trim()).unwrap();
+        let millis: u128 = parsed["timestamp"]
+            .as_str()
+            .expect("Event should carry a timestamp")
+            .parse()
+            .expect("Epoch millis timestamp should be numeric");
+        assert!((before.as_millis()..=after.as_millis()).contains(&millis));
+    }
+
+    #[test]
+    fn test_split_by_level_routes_warnings_to_error_writer() {
+        use std::sync::{Arc, Mutex};
+
+        let errors = Arc::new(Mutex::new(Vec::new()));
+        let others = Arc::new(Mutex::new(Vec::new()));
+        let (error_buffer, other_buffer) = (errors.clone(), others.clone());
+        let layer = tracing_fmt::layer().json().with_writer(split_by_level(
+            move || SharedBuffer(error_buffer.clone()),
+            move || SharedBuffer(other_buffer.clone()),
+        ));
+
+        tracing::subscriber::with_default(registry().with(layer), || {
+            warn!("disk nearly full");
+            error!("disk full");
+            info!("request handled");
+            debug!("request details");
+        });
+
+        let errors = String::from_utf8(errors.lock().unwrap().clone()).unwrap();
+        let others = String::from_utf8(others.lock().unwrap().clone()).unwrap();
+        assert!(errors.contains("disk nearly full") && errors.contains("disk full"));
+        assert!(!errors.contains("request handled"));
+        assert!(others.contains("request handled") && others.contains("request details"));
+        assert!(!others.contains("disk"));
+    }
+
+    #[test]
+    fn test_repeated_events_suppressed_with_summary() {
+        let output = capture_formatted(
+            RepeatSuppressingFormat::new(tracing_fmt::format().json(), true),
+            || {
+                for _ in 0..20 {
+                    error!(status = 529, "Upstream overloaded");
+                }
+                info!("Upstream recovered");
+            },
+        );
+
+        let events: Vec<serde_json::Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).expect("Each line should be valid JSON"))
+            .collect();
+
+        // Threshold events, then one summary, then the distinct event
+        assert_eq!(events.len(), LOG_REPEAT_THRESHOLD as usize + 2);
+        let summary = &events[LOG_REPEAT_THRESHOLD as usize];
+        assert_eq!(
+            summary["fields"]["message"],
+            format!(
+                "Upstream overloaded (repeated {} times)",
+                20 - LOG_REPEAT_THRESHOLD
+            )
+        );
+        assert_eq!(
+            summary["fields"]["repeated_count"],
+            20 - LOG_REPEAT_THRESHOLD
+        );
+        assert_eq!(summary["level"], "ERROR");
+        assert_eq!(
+            events.last().unwrap()["fields"]["message"],
+            "Upstream recovered"
+        );
+    }
+
+    #[test]
+    fn test_repeated_events_summarized_when_window_elapses() {
+        let format = RepeatSuppressingFormat {
+            window: Duration::from_millis(50),
+            ..RepeatSuppressingFormat::new(tracing_fmt::format().json(), true)
+        };
+        let output = capture_formatted(format, || {
+            for _ in 0..(LOG_REPEAT_THRESHOLD + 3) {
+                warn!("Same warning");
+            }
+            std::thread::sleep(Duration::from_millis(60));
+            warn!("Same warning");
+        });
+
+        let messages: Vec<String> = output
+            .lines()
+            .map(|line| {
+                let event: serde_json::Value = serde_json::from_str(line).unwrap();
+                event["fields"]["message"].as_str().unwrap().to_string()
+            })
+            .collect();
+
+        // The new window reports the dropped repeats before resuming output
+        assert_eq!(messages.len(), LOG_REPEAT_THRESHOLD as usize + 2);
+        assert_eq!(
+            messages[LOG_REPEAT_THRESHOLD as usize],
+            "Same warning (repeated 3 times)"
+        );
+        assert_eq!(messages.last().unwrap(), "Same warning");
+    }
+
+    #[test]
+    fn test_repeated_events_untouched_when_disabled() {
+        let output = capture_formatted(
+            RepeatSuppressingFormat::new(tracing_fmt::format().json(), false),
+            || {
+                for _ in 0..20 {
+                    error!("Upstream overloaded");
+                }
+            },
+        );
+        assert_eq!(output.lines().count(), 20);
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn test_socket_writer_reconnects_after_collector_restart() {
+        use std::io::{BufRead, BufReader};
+        use std::os::unix::net::UnixListener;
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let socket_path = temp_dir.path().join("collector.sock");
+        let read_line = |listener: &UnixListener| {
+            let (stream, _) = listener.accept().unwrap();
+            let mut line = String::new();
+            BufReader::new(stream).read_line(&mut line).unwrap();
+            line
+        };
+
+        // Nothing is listening yet, so the line is dropped
+        let writer = UnixSocketWriter::new(&socket_path);
+        assert!(!writer.send(b"dropped\n"));
+
+        let listener = UnixListener::bind(&socket_path).unwrap();
+        assert!(writer.send(b"first\n"));
+        assert_eq!(read_line(&listener), "first\n");
+
+        // Restart the collector at the same path
+        drop(listener);
+        fs::remove_file(&socket_path).unwrap();
+        let listener = UnixListener::bind(&socket_path).unwrap();
+
+        assert!(writer.send(b"second\n"));
+        assert_eq!(read_line(&listener), "second\n");
+    }
 }
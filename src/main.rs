@@ -1,19 +1,47 @@
+mod admin;
+mod backoff;
+mod build_info;
+mod concurrency_limit;
 mod config;
+mod cors;
+mod drain;
+mod forwarded;
 mod fs_utils;
+mod gzip;
+mod health;
+mod heartbeat;
+mod http_logging;
+mod listener;
 mod log_cleanup;
+mod log_level_override;
+mod log_naming;
+mod log_reorder;
 mod logger;
+mod memory_budget;
 mod proxy_handler;
+mod rate_limit;
+mod request_id;
+mod response_cache;
+mod shutdown;
+mod sse;
+mod tls;
+mod trace_context;
+mod upstream_ip;
+mod warmup;
+mod websocket;
 
 use axum::Server;
 use clap::{Arg, Command};
 use std::net::SocketAddr;
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::net::TcpListener;
 use tokio::signal;
+use tokio::sync::watch;
 use tracing::{error, info};
 
-use proxy_handler::create_router;
+use drain::SHUTDOWN_REQUEST_GRACE;
+use proxy_handler::{create_router_with_state, ProxyState};
+use shutdown::Shutdown;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -27,8 +55,20 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 .action(clap::ArgAction::SetTrue)
                 .help("Clean old log files based on configured max age and exit"),
         )
+        .arg(
+            Arg::new("generate-env-template")
+                .long("generate-env-template")
+                .action(clap::ArgAction::SetTrue)
+                .help("Print a commented .env template of all supported variables and exit"),
+        )
         .get_matches();
 
+    // Print the template before loading config, which would require ANTHROPIC_API_KEY
+    if matches.get_flag("generate-env-template") {
+        print!("{}", config::env_template());
+        return Ok(());
+    }
+
     // Main application entry point
     println!("Starting switchboard...");
 
@@ -43,7 +83,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         e
     })?;
 
-    info!("switchboard initialized");
+    info!(commit = build_info::BUILD_COMMIT, "switchboard initialized");
 
     // Check if we should just clean logs and exit
     if matches.get_flag("clean-logs") {
@@ -89,9 +129,21 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let config_owned = config.clone();
     let config_arc = Arc::new(config_owned);
 
+    // Subsystems holding in-memory data register flush hooks here
+    let shutdown = Shutdown::default();
+
     // Create the router with the HTTP client and config
     // Clone the Arc to preserve ownership for later use
-    let app = create_router(client, config_arc.clone());
+    let state = Arc::new(ProxyState::new(&config_arc));
+    let app = create_router_with_state(client.clone(), config_arc.clone(), Arc::clone(&state));
+    let in_flight = Arc::clone(&state.in_flight);
+    let stream_grace = Duration::from_secs(config_arc.shutdown_stream_grace_secs);
+
+    // Log a periodic heartbeat until shutdown, if configured
+    if let Some(interval_secs) = config_arc.heartbeat_interval_secs.filter(|&secs| secs > 0) {
+        let heartbeat = heartbeat::spawn_heartbeat(Duration::from_secs(interval_secs), state);
+        shutdown.register("heartbeat", move || async move { heartbeat.abort() });
+    }
 
     // Parse and bind to the configured address
     let addr_str = format!("0.0.0.0:{}", config_arc.port);
@@ -103,9 +155,16 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     };
 
-    // Bind to the configured port
+    // Bind to the configured port, waiting briefly if a previous instance still holds it
     info!("Binding server to {}", addr);
-    let listener = match TcpListener::bind(addr).await {
+    let listener = match listener::bind_with_retry(
+        addr,
+        listener::ListenOptions::from_config(&config_arc),
+        config_arc.bind_retry_attempts,
+        Duration::from_millis(config_arc.bind_retry_delay_ms),
+    )
+    .await
+    {
         Ok(listener) => listener,
         Err(e) => {
             error!(error = %e, addr = %addr, "Failed to bind to address");
@@ -113,23 +172,98 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     };
 
-    // Start the server with graceful shutdown
-    info!("Starting Axum server, listening for requests");
-    let server = Server::from_tcp(listener.into_std()?)?;
+    // Prime the upstream connection pool in the background; the result is only logged
+    warmup::spawn_warmup(client, &config_arc);
 
-    if let Err(e) = server
-        .serve(app.into_make_service())
-        .with_graceful_shutdown(shutdown_signal())
-        .await
-    {
-        error!(error = %e, "Server error");
-        return Err(e.into());
+    // Load TLS settings up front so misconfiguration fails startup instead of serving HTTP
+    let tls_config = tls::load_rustls_config(&config_arc).await.map_err(|e| {
+        error!(error = %e, "Invalid TLS configuration");
+        e
+    })?;
+
+    // Shutdown signals stop new connections at once; the drain decides when to force-close
+    let (stop_tx, stop_rx) = watch::channel(false);
+    tokio::spawn(async move {
+        shutdown_signal().await;
+        stop_tx.send_replace(true);
+    });
+
+    if let Some(tls_config) = tls_config {
+        // Serve HTTPS, terminating TLS in the proxy
+        info!("Starting Axum server with TLS, listening for HTTPS requests");
+        let handle = axum_server::Handle::new();
+
+        // Translate shutdown signals into a graceful shutdown of the TLS server
+        let shutdown_handle = handle.clone();
+        tokio::spawn(async move {
+            stopped(stop_rx).await;
+            shutdown_handle.graceful_shutdown(None);
+            let outcome = drain::drain(&in_flight, SHUTDOWN_REQUEST_GRACE, stream_grace).await;
+            if !outcome.is_complete() {
+                shutdown_handle.shutdown();
+            }
+        });
+
+        // The server sets TCP_NODELAY on each accepted connection, so pass it on
+        let incoming_config = axum_server::AddrIncomingConfig::new()
+            .tcp_nodelay(config_arc.tcp_nodelay)
+            .build();
+        if let Err(e) = axum_server::from_tcp_rustls(listener.into_std()?, tls_config)
+            .addr_incoming_config(incoming_config)
+            .handle(handle)
+            .serve(app.into_make_service_with_connect_info::<SocketAddr>())
+            .await
+        {
+            error!(error = %e, "Server error");
+            return Err(e.into());
+        }
+    } else {
+        // Start the server with graceful shutdown
+        info!("Starting Axum server, listening for requests");
+        // Connection info gives the handler the client address for X-Forwarded-For
+        let server = Server::from_tcp(listener.into_std()?)?
+            .tcp_nodelay(config_arc.tcp_nodelay)
+            .serve(app.into_make_service_with_connect_info::<SocketAddr>())
+            .with_graceful_shutdown(stopped(stop_rx.clone()));
+        tokio::pin!(server);
+
+        // Once stopped, wait for in-flight work within the graces; dropping the server
+        // afterwards force-closes any connections still open
+        let drained = async {
+            stopped(stop_rx).await;
+            drain::drain(&in_flight, SHUTDOWN_REQUEST_GRACE, stream_grace).await
+        };
+        let result = tokio::select! {
+            biased;
+            result = &mut server => result,
+            outcome = drained => {
+                if outcome.is_complete() {
+                    server.await
+                } else {
+                    Ok(())
+                }
+            }
+        };
+
+        if let Err(e) = result {
+            error!(error = %e, "Server error");
+            return Err(e.into());
+        }
     }
 
+    // The server has stopped accepting requests; flush in-memory data before exiting
+    shutdown.flush().await;
+
     info!("Server shutdown complete");
     Ok(())
 }
 
+/// Resolves once a shutdown signal has been received
+async fn stopped(mut stop_rx: watch::Receiver<bool>) {
+    // The sender only goes away after sending, so an error also means "stopped"
+    let _ = stop_rx.wait_for(|&stopped| stopped).await;
+}
+
 /// Handles graceful shutdown signals by waiting for either Ctrl+C or SIGTERM
 /// This allows the application to properly close resources and finish ongoing requests
 /// before shutting down.
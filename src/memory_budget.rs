@@ -0,0 +1,231 @@
+//! Global accounting for buffered request/response bodies
+//!
+//! The proxy buffers full request bodies (and non-streaming response bodies) in memory.
+//! Under high concurrency those buffers add up, so this module tracks the total number
+//! of bytes held across all in-flight requests against a configurable budget.
+//!
+//! Key features:
+//! - Lock-free reservation using an atomic counter
+//! - RAII reservations that release their bytes when dropped
+//! - Reservations can grow once the real body size is known
+//! - An unset budget (None) never rejects anything
+
+use axum::{
+    body::{boxed, Empty},
+    http::StatusCode,
+    response::Response,
+};
+use hyper::header;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use crate::config::BUFFER_BUDGET_RETRY_AFTER_SECS;
+
+/// Shared budget for body bytes buffered across all in-flight requests
+#[derive(Debug)]
+pub struct MemoryBudget {
+    /// Maximum number of bytes that may be reserved at once (None = unlimited)
+    limit: Option<u64>,
+    /// Bytes currently reserved by in-flight requests
+    in_use: AtomicU64,
+}
+
+impl MemoryBudget {
+    /// Creates a new budget with the given limit (None = unlimited)
+    pub fn new(limit: Option<u64>) -> Self {
+        Self {
+            limit,
+            in_use: AtomicU64::new(0),
+        }
+    }
+
+    /// Returns the number of bytes currently reserved
+    pub fn in_use(&self) -> u64 {
+        self.in_use.load(Ordering::Acquire)
+    }
+
+    /// Attempts to reserve `bytes` against the budget
+    ///
+    /// # Arguments
+    /// * `bytes` - Number of bytes the caller is about to buffer
+    ///
+    /// # Returns
+    /// A reservation that releases the bytes when dropped, or None if the
+    /// reservation would push the total over the limit
+    pub fn try_reserve(self: &Arc<Self>, bytes: u64) -> Option<BudgetReservation> {
+        if !self.try_add(bytes) {
+            return None;
+        }
+
+        Some(BudgetReservation {
+            budget: Arc::clone(self),
+            bytes,
+        })
+    }
+
+    /// Adds `bytes` to the in-use counter if the limit allows it
+    fn try_add(&self, bytes: u64) -> bool {
+        // Without a limit there is nothing to enforce, but keep counting so
+        // in_use() stays meaningful for observability
+        let Some(limit) = self.limit else {
+            self.in_use.fetch_add(bytes, Ordering::AcqRel);
+            return true;
+        };
+
+        // Compare-and-swap loop so concurrent reservations never overshoot the limit
+        self.in_use
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |current| {
+                current.checked_add(bytes).filter(|total| *total <= limit)
+            })
+            .is_ok()
+    }
+
+    /// Returns `bytes` to the budget
+    fn release(&self, bytes: u64) {
+        self.in_use.fetch_sub(bytes, Ordering::AcqRel);
+    }
+}
+
+/// Bytes reserved against a [`MemoryBudget`], released on drop
+#[derive(Debug)]
+pub struct BudgetReservation {
+    budget: Arc<MemoryBudget>,
+    bytes: u64,
+}
+
+impl BudgetReservation {
+    /// Returns the number of bytes held by this reservation
+    pub fn bytes(&self) -> u64 {
+        self.bytes
+    }
+
+    /// Grows the reservation to at least `total` bytes
+    ///
+    /// Used when the actual body turns out larger than the declared size.
+    ///
+    /// # Returns
+    /// `true` if the reservation now covers `total` bytes, `false` if the
+    /// budget could not accommodate the extra bytes (the reservation is unchanged)
+    pub fn try_grow_to(&mut self, total: u64) -> bool {
+        if total <= self.bytes {
+            return true;
+        }
+
+        let additional = total - self.bytes;
+        if !self.budget.try_add(additional) {
+            return false;
+        }
+
+        self.bytes = total;
+        true
+    }
+}
+
+impl Drop for BudgetReservation {
+    fn drop(&mut self) {
+        self.budget.release(self.bytes);
+    }
+}
+
+/// Builds the 503 response returned when the buffering budget is exhausted
+///
+/// The Retry-After header tells well-behaved clients to back off briefly,
+/// since budget pressure clears as soon as in-flight requests complete.
+pub fn budget_exceeded_response() -> Response {
+    Response::builder()
+        .status(StatusCode::SERVICE_UNAVAILABLE)
+        .header(
+            header::RETRY_AFTER,
+            BUFFER_BUDGET_RETRY_AFTER_SECS.to_string(),
+        )
+        .body(boxed(Empty::new()))
+        // Static status and header values cannot fail to build
+        .expect("budget exceeded response should always build")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Barrier;
+    use std::thread;
+
+    #[test]
+    fn test_reservations_rejected_when_budget_exceeded_and_recover_after_release() {
+        let budget = Arc::new(MemoryBudget::new(Some(100)));
+
+        // Fill the budget exactly
+        let first = budget
+            .try_reserve(60)
+            .expect("First reservation should fit");
+        let second = budget
+            .try_reserve(40)
+            .expect("Second reservation should fit");
+        assert_eq!(budget.in_use(), 100);
+
+        // Any further reservation must be rejected without changing the total
+        assert!(budget.try_reserve(1).is_none());
+        assert_eq!(budget.in_use(), 100);
+
+        // Releasing a reservation frees its bytes for new requests
+        drop(first);
+        assert_eq!(budget.in_use(), 40);
+        let third = budget
+            .try_reserve(60)
+            .expect("Released bytes should be reusable");
+
+        drop(second);
+        drop(third);
+        assert_eq!(budget.in_use(), 0);
+    }
+
+    #[test]
+    fn test_concurrent_reservations_never_exceed_limit() {
+        // 8 threads each try to reserve 30 bytes from a 100 byte budget at the same time;
+        // exactly 3 can succeed
+        let budget = Arc::new(MemoryBudget::new(Some(100)));
+        let barrier = Arc::new(Barrier::new(8));
+
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let budget = Arc::clone(&budget);
+                let barrier = Arc::clone(&barrier);
+                thread::spawn(move || {
+                    barrier.wait();
+                    let reservation = budget.try_reserve(30);
+                    // Hold reservations until every thread has tried
+                    barrier.wait();
+                    reservation.is_some()
+                })
+            })
+            .collect();
+
+        let granted = handles
+            .into_iter()
+            .map(|h| h.join().unwrap())
+            .filter(|ok| *ok)
+            .count();
+
+        assert_eq!(granted, 3, "Only 3 reservations of 30 bytes fit in 100");
+        assert_eq!(budget.in_use(), 0, "All reservations should be released");
+    }
+
+    #[test]
+    fn test_grow_and_unlimited_budget() {
+        let budget = Arc::new(MemoryBudget::new(Some(50)));
+        let mut reservation = budget.try_reserve(10).unwrap();
+
+        // Growing within the limit succeeds; beyond it fails and leaves the reservation intact
+        assert!(reservation.try_grow_to(50));
+        assert!(!reservation.try_grow_to(51));
+        assert_eq!(reservation.bytes(), 50);
+        drop(reservation);
+        assert_eq!(budget.in_use(), 0);
+
+        // An unlimited budget accepts everything but still tracks usage
+        let unlimited = Arc::new(MemoryBudget::new(None));
+        let big = unlimited.try_reserve(u64::MAX / 2).unwrap();
+        assert_eq!(unlimited.in_use(), u64::MAX / 2);
+        drop(big);
+        assert_eq!(unlimited.in_use(), 0);
+    }
+}
@@ -1,68 +1,216 @@
 // This module will contain the proxy handler implementation
 // Core proxy functionality for intercepting and forwarding API requests
 
+use arc_swap::ArcSwap;
 use axum::{
-    body::{boxed, Body, Full},
+    body::{boxed, Body, Empty, Full},
+    extract::ConnectInfo,
     http::StatusCode,
     response::Response,
     routing::any,
     Router,
 };
-use bytes::Bytes;
-use futures_util::StreamExt;
-use hyper::{header, HeaderMap, Request, Uri};
+use bytes::{Bytes, BytesMut};
+use futures_util::{future, stream, Stream, StreamExt};
+use hyper::body::HttpBody;
+use hyper::http::uri::InvalidUri;
+use hyper::{header, header::HeaderName, header::HeaderValue, HeaderMap, Method, Request, Uri};
 use reqwest::{header::HeaderValue as ReqHeaderValue, Client};
-use serde::Deserialize;
+use serde::{de::IgnoredAny, Deserialize};
 use serde_json::Value;
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::convert::Infallible;
+use std::fmt;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
-use std::time::Instant;
-use tracing::{debug, error, field, info, info_span, instrument, Span};
-use uuid::Uuid;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use tracing::{debug, error, field, info, instrument, warn, Span};
+use tracing_subscriber::filter::LevelFilter;
+
+use crate::admin::admin_router;
+use crate::backoff::{retry_async, Backoff, RetryPolicy};
+use crate::concurrency_limit::{concurrency_limit_response, ConcurrencyLimiter, ConcurrencyPermit};
+use crate::config::{key_fingerprint, AuthMode, Config, EmptyBodyPolicy};
+use crate::cors;
+use crate::drain::InFlight;
+use crate::forwarded::add_forwarded_headers;
+use crate::health::health_router;
+use crate::http_logging::{content_length, redact_query, redact_url, sanitize_for_log};
+use crate::log_level_override::LEVEL_OVERRIDE_FIELD;
+use crate::memory_budget::{budget_exceeded_response, BudgetReservation, MemoryBudget};
+use crate::rate_limit::{rate_limited_response, ModelRateLimiter};
+use crate::request_id::RequestId;
+use crate::response_cache::{CachedResponse, ResponseCache, CACHE_STATUS_HEADER};
+use crate::sse::TerminalEventWatch;
+use crate::trace_context::{TraceParent, TRACEPARENT_HEADER};
+use crate::upstream_ip::{ip_for_log, UpstreamIpCache, RESOLVED_IP_TTL};
+use crate::websocket;
+
+// Logging helpers are re-exported so existing `proxy_handler::log_*` paths keep working
+#[allow(unused_imports)]
+// ALLOWANCE: Part of the library API; the binary only uses some of them
+pub use crate::http_logging::{
+    log_request_details, log_request_details_with_options, log_response_details,
+    log_response_details_with_options, log_response_headers, BodyLogOptions, ResponseTimings,
+};
+
+/// Path of the Messages API, whose request bodies the proxy may rewrite
+const MESSAGES_PATH: &str = "/v1/messages";
 
-use crate::config::Config;
+/// Upstream response headers announcing that the requested API or model is deprecated
+const DEPRECATION_HEADERS: [&str; 2] = ["anthropic-deprecation", "deprecation"];
+
+/// Status the Anthropic API answers with when it is overloaded (not in `StatusCode`)
+const UPSTREAM_OVERLOADED_STATUS: u16 = 529;
 
 /// Minimal representation of an Anthropic Messages API request
 ///
-/// This struct is used only for logging context, not for processing.
-/// It extracts only the essential fields needed for logging identification.
-///
-/// # Note
-/// Currently not actively used but prepared for future logging enhancements.
-/// The fields will be used to provide better contextual information in logs.
+/// This struct is never used to modify requests. It extracts only the essential
+/// fields needed to identify a request, such as the model for per-model rate limits,
+/// the conversation length for logging and whether the client asked for a stream.
 #[derive(Deserialize, Debug)]
-#[allow(dead_code)] // Explicitly suppressed as this struct is prepared for future use
 struct AnthropicMessagesRequestMinimal {
     /// The model being requested (claude-3-opus, claude-3-sonnet, etc.)
     model: Option<String>,
 
     /// Whether the request is for a streaming response
     stream: Option<bool>,
-    // Other fields could be added if needed for better logging context
-    // For example, the number of messages in the conversation
-    // messages: Option<Vec<Value>>, // Not included by default as it would be verbose
+
+    /// The conversation messages; only counted, so their content is skipped
+    messages: Option<Vec<IgnoredAny>>,
+}
+
+/// Minimal representation of an Anthropic API error response body
+///
+/// Error responses look like `{"type":"error","error":{"type":"rate_limit_error",...}}`.
+/// Only the error type is extracted, so alerts can key on the upstream error class.
+#[derive(Deserialize, Debug)]
+struct AnthropicErrorResponseMinimal {
+    /// The error details
+    error: AnthropicErrorMinimal,
+}
+
+/// The `error` object of an Anthropic API error response
+#[derive(Deserialize, Debug)]
+struct AnthropicErrorMinimal {
+    /// The error class, e.g. `rate_limit_error` or `overloaded_error`
+    #[serde(rename = "type")]
+    error_type: String,
+}
+
+/// State shared by every request handled by one router
+///
+/// Each piece is created once from the startup configuration; only `Config` itself
+/// can change at runtime (via admin reloads).
+#[derive(Debug)]
+pub struct ProxyState {
+    /// Global budget for buffered body bytes shared across requests
+    pub budget: Arc<MemoryBudget>,
+    /// Global limit on requests handled at once
+    pub limiter: ConcurrencyLimiter,
+    /// Limit on streaming responses open at once, separate from `limiter`
+    pub stream_limiter: ConcurrencyLimiter,
+    /// Cached upstream resolutions, used when `log_resolved_ip` is enabled
+    pub ip_cache: UpstreamIpCache,
+    /// Per-model request rate limits
+    pub rate_limiter: ModelRateLimiter,
+    /// Number of requests received so far, used for `req_seq` and the heartbeat
+    pub request_seq: AtomicU64,
+    /// Responses to deterministic requests (disabled unless a TTL is configured)
+    pub response_cache: ResponseCache,
+    /// Requests and streams in flight, drained on shutdown
+    pub in_flight: Arc<InFlight>,
+}
+
+impl ProxyState {
+    /// Creates the shared state for a router from the startup configuration
+    pub fn new(config: &Config) -> Self {
+        Self {
+            budget: Arc::new(MemoryBudget::new(config.max_total_buffered_bytes)),
+            limiter: ConcurrencyLimiter::new(
+                config.max_concurrent_requests,
+                config.queue_timeout_ms.map(Duration::from_millis),
+            ),
+            // Streams never queue: the upstream is already generating by the time one is seen
+            stream_limiter: ConcurrencyLimiter::new(config.max_concurrent_streams, None),
+            // Upstream resolutions are only logged, but cached so most requests skip the lookup
+            ip_cache: UpstreamIpCache::new(RESOLVED_IP_TTL),
+            rate_limiter: ModelRateLimiter::new(&config.model_rate_limits),
+            request_seq: AtomicU64::new(0),
+            // Client credentials reach the upstream in passthrough mode, so they key the cache
+            response_cache: ResponseCache::new(
+                config.response_cache_ttl_secs.map(Duration::from_secs),
+            )
+            .with_credentials_in_key(config.auth_mode == AuthMode::Passthrough),
+            in_flight: Arc::new(InFlight::default()),
+        }
+    }
+
+    /// Returns the next request sequence number (the first request gets 1)
+    pub fn next_request_seq(&self) -> u64 {
+        self.request_seq.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    /// Returns how many requests this router has received so far
+    pub fn requests_served(&self) -> u64 {
+        self.request_seq.load(Ordering::Relaxed)
+    }
 }
 
 /// Creates the Axum router with routes for the application
 ///
 /// Sets up an Axum router with a catch-all route that forwards all
 /// incoming requests to the proxy_handler function regardless of
-/// HTTP method (GET, POST, etc.)
+/// HTTP method (GET, POST, etc.), plus the `/healthz` and `/admin/*` endpoints
 ///
 /// # Arguments
 ///
 /// * `client` - The HTTP client used to make requests to the upstream API
 /// * `config` - Configuration wrapped in an Arc for thread-safe sharing
+#[allow(dead_code)] // ALLOWANCE: Library API used by tests; the binary keeps a handle on the state
 pub fn create_router(client: Client, config: Arc<Config>) -> Router {
+    // Budget, limits and counters shared by every request handled by this router
+    let state = Arc::new(ProxyState::new(&config));
+    create_router_with_state(client, config, state)
+}
+
+/// Creates the Axum router around existing shared state
+///
+/// Like [`create_router`], but the caller keeps a handle on the state, e.g. to
+/// report the number of requests served.
+///
+/// # Arguments
+///
+/// * `client` - The HTTP client used to make requests to the upstream API
+/// * `config` - Configuration wrapped in an Arc for thread-safe sharing
+/// * `state` - Budget, limits and counters shared by every request
+pub fn create_router_with_state(
+    client: Client,
+    config: Arc<Config>,
+    state: Arc<ProxyState>,
+) -> Router {
     info!("Creating Axum router with catch-all route to proxy_handler");
 
-    Router::new().route(
-        "/*path", // Catch-all route
-        any(move |req: Request<Body>| {
-            let config = Arc::clone(&config);
-            proxy_handler(req, client.clone(), config)
-        }),
-    )
+    // Live configuration: admin reloads swap it, each request takes a snapshot
+    let live_config = Arc::new(ArcSwap::new(config));
+
+    Router::new()
+        .route(
+            "/*path", // Catch-all route
+            any({
+                let live_config = Arc::clone(&live_config);
+                move |req: Request<Body>| {
+                    let config = live_config.load_full();
+                    proxy_handler(req, client.clone(), config, Arc::clone(&state))
+                }
+            }),
+        )
+        // Health and admin routes are more specific, so they win over the catch-all
+        .merge(health_router(Arc::clone(&live_config)))
+        .merge(admin_router(live_config))
 }
 
 /// The main proxy handler function that processes incoming requests
@@ -72,12 +220,14 @@ pub fn create_router(client: Client, config: Arc<Config>) -> Router {
 /// 2. Assigns a unique request ID for tracing
 /// 3. Records basic request information in the tracing span
 /// 4. Forwards the request to the Anthropic API and returns the response
+/// 5. Adds the request ID to JSON error bodies, when enabled
 ///
 /// # Arguments
 ///
 /// * `req` - The incoming HTTP request to be proxied
 /// * `client` - The HTTP client used to make requests to the upstream API
 /// * `config` - Configuration wrapped in an Arc for thread-safe sharing
+/// * `state` - Budget, limits and counters shared across requests
 ///
 /// The `#[instrument]` attribute macro automatically creates a tracing span for this function,
 /// with empty fields that will be filled in during processing.
@@ -86,23 +236,64 @@ pub fn create_router(client: Client, config: Arc<Config>) -> Router {
     name = "proxy_request",                    // Name the span 'proxy_request'
     fields(
         req_id = field::Empty,                 // Unique ID for this request
+        req_seq = field::Empty,                // Per-process request number (when enabled)
+        key_fingerprint = field::Empty,        // Fingerprint of the API key used (when enabled)
         http.method = field::Empty,            // HTTP method (GET, POST, etc.)
         url.path = field::Empty,               // Request path
         url.query = field::Empty,              // Query parameters
         http.status_code = field::Empty,       // Response status code
-        duration_ms = field::Empty             // Total request duration
+        duration_ms = field::Empty,            // Total request duration
+        upstream_ms = field::Empty,            // Time until upstream status and headers arrived
+        body_read_ms = field::Empty,           // Time reading the upstream response body
+        request_body_read_ms = field::Empty,   // Time reading the client request body (when enabled)
+        queue_wait_ms = field::Empty,          // Time spent waiting for a concurrency slot
+        ttfb_ms = field::Empty,                // Time to first streamed chunk
+        trace_id = field::Empty,               // W3C trace ID (incoming or generated)
+        span_id = field::Empty,                // W3C parent span ID sent upstream
+        upstream.url = field::Empty,           // Fully resolved upstream URL, query redacted
+        upstream.ip = field::Empty,            // Resolved upstream IP (when enabled)
+        anthropic.message_count = field::Empty, // Number of messages in a Messages API request
+        anthropic.max_tokens_clamped = field::Empty, // Whether max_tokens was capped (when enforced)
+        request.stream_requested = field::Empty, // Whether the request body asked for a stream
+        response.is_streaming = field::Empty,  // Whether the upstream response is an event stream
+        anthropic.error_type = field::Empty,   // Error type from an upstream error response body
+        anthropic.request_id = field::Empty,   // Anthropic's request ID, for support tickets
+        client.required_headers = field::Empty, // Values of REQUIRE_HEADERS, for attribution
+        log.opted_out = field::Empty,          // Whether the client opted out of detail logging
+        log.level_override = field::Empty      // Log level requested by the client (when allowed)
     )
 )]
 pub async fn proxy_handler(
     req: Request<Body>,
     client: Client,
     config: Arc<Config>,
+    state: Arc<ProxyState>,
+) -> Result<Response, StatusCode> {
+    // Generate a unique ID for this request
+    let req_id = RequestId::generate(config.request_id_format);
+    let inject_request_id = config.inject_request_id_in_errors;
+
+    let response = handle_request(req, client, config, state, req_id).await?;
+    if inject_request_id {
+        Ok(inject_request_id_into_error(response, req_id).await)
+    } else {
+        Ok(response)
+    }
+}
+
+/// Processes one request inside the span opened by [`proxy_handler`]
+async fn handle_request(
+    req: Request<Body>,
+    client: Client,
+    config: Arc<Config>,
+    state: Arc<ProxyState>,
+    req_id: RequestId,
 ) -> Result<Response, StatusCode> {
     // Start timing the request processing
     let start = Instant::now();
 
-    // Generate a unique ID for this request
-    let req_id = Uuid::new_v4();
+    // Counted until the handler returns, so shutdown can wait for the request to finish
+    let _in_flight = state.in_flight.track_request();
 
     // Get the current span created by the #[instrument] macro
     let span = Span::current();
@@ -110,20 +301,128 @@ pub async fn proxy_handler(
     // Record the request ID in the span
     span.record("req_id", req_id.to_string());
 
+    // Number requests in arrival order, for reading logs of one process chronologically
+    let req_seq = state.next_request_seq();
+    if config.log_request_sequence {
+        span.record("req_seq", req_seq);
+    }
+
     info!(request_id = %req_id, "Starting request processing");
 
+    // While in maintenance nothing is proxied; the health check is routed elsewhere
+    if config.maintenance_mode {
+        return Ok(reject_during_maintenance(
+            &span,
+            config.maintenance_retry_after_secs,
+        ));
+    }
+
+    // Browsers preflight cross-origin requests; with CORS enabled they are answered here
+    let cors_origin = cors::allowed_origin(&config, req.headers());
+    if config.cors_enabled && req.method() == Method::OPTIONS {
+        return Ok(answer_cors_preflight(&span, &config, cors_origin.as_ref()));
+    }
+
+    // This is an API proxy, not a forward proxy: never open tunnels for CONNECT
+    if req.method() == Method::CONNECT {
+        return Ok(reject_connect(&span, req.uri()));
+    }
+
+    // An upgrade cannot be forwarded as an ordinary request, so it needs a tunnel
+    let websocket_upgrade = websocket::is_websocket_upgrade(req.headers());
+    if websocket_upgrade && !config.allow_websocket {
+        return Ok(reject_websocket_upgrade(&span));
+    }
+
+    // Hold a concurrency slot until the handler returns (or a tunnel closes), waiting for
+    // one if queuing is enabled
+    let Some(permit) = acquire_concurrency_permit(&span, &state.limiter).await else {
+        return Ok(reject_over_concurrency_limit(&span));
+    };
+
     // Extract and clone the essential request information
+    let client_ip = req
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip());
     let original_uri = req.uri().clone();
     let method = req.method().clone();
-    let original_headers = req.headers().clone();
+    let mut original_headers = req.headers().clone();
+
+    // Clients may skip detail logging, but only when allowed; otherwise the header is
+    // stripped below like any other internal header
+    let log_opted_out = config.allow_client_log_optout && take_log_optout(&mut original_headers);
+    if log_opted_out {
+        span.record("log.opted_out", true);
+    }
+
+    // Likewise, a client may ask for its request to be logged more verbosely
+    if config.allow_client_log_level {
+        if let Some(level) = take_log_level(&mut original_headers) {
+            span.record(LEVEL_OVERRIDE_FIELD, level.to_string().as_str());
+            debug!(level = %level, "Logging request at the client's requested level");
+        }
+    }
+
+    // Clients must not be able to spoof headers the proxy itself sets
+    let stripped = strip_internal_headers(&mut original_headers);
+    if stripped > 0 {
+        warn!(
+            stripped,
+            prefix = INTERNAL_HEADER_PREFIX,
+            "Stripped proxy-internal headers from client request"
+        );
+    }
 
     // Record basic request information in the tracing span
     span.record("http.method", method.to_string());
     span.record("url.path", original_uri.path());
 
+    // Deployments may require attribution headers; nothing is forwarded without them
+    if !config.require_headers.is_empty() {
+        match required_header_values(
+            &original_headers,
+            &config.require_headers,
+            config.sanitize_log_output,
+        ) {
+            Ok(values) => {
+                span.record("client.required_headers", field::debug(&values));
+            }
+            Err(missing) => return Ok(reject_missing_required_header(&span, missing)),
+        }
+    }
+
+    // The Messages API only takes JSON; anything else usually means a misconfigured client
+    if config.warn_on_content_type_anomaly
+        && method == Method::POST
+        && original_uri.path() == MESSAGES_PATH
+        && !is_json_content_type(&original_headers)
+    {
+        warn!(
+            http.request.content_type = ?original_headers.get(header::CONTENT_TYPE),
+            "Messages request does not have a JSON content type"
+        );
+    }
+
+    // Overlong URLs are refused before anything is built from them
+    if let Some(max_length) = config.max_path_length {
+        let length = original_uri
+            .path_and_query()
+            .map_or(0, |pq| pq.as_str().len());
+        if length > max_length {
+            return Ok(reject_overlong_path(&span, length, max_length));
+        }
+    }
+
+    // Query strings may carry tokens, so configured parameters are redacted wherever they're logged
+    let redact_params = &config.redact_query_params;
+    let logged_query = original_uri
+        .query()
+        .map(|query| redact_query(query, redact_params));
+
     // If there's a query string, record it in the span
-    if let Some(query) = original_uri.query() {
-        span.record("url.query", query);
+    if let Some(query) = &logged_query {
+        span.record("url.query", query.as_ref());
     }
 
     // Extract the path and query, defaulting to "/" if none
@@ -131,29 +430,38 @@ pub async fn proxy_handler(
         .path_and_query()
         .map(|pq| pq.as_str())
         .unwrap_or("/");
+    let path_and_query = if config.normalize_path {
+        collapse_path_slashes(path_and_query)
+    } else {
+        Cow::Borrowed(path_and_query)
+    };
 
     info!(
         method = %method,
         path = %original_uri.path(),
-        query = %original_uri.query().unwrap_or(""),
+        query = %logged_query.as_deref().unwrap_or(""),
         "Processing request"
     );
 
-    // Construct the target Anthropic API URL
-    let target_url_str = format!("{}{}", config.anthropic_target_url, path_and_query);
+    // API key routes match the client's path, as the target URL may add a base path
+    let route_path = path_and_query.split('?').next().unwrap_or("/");
 
-    // Parse the constructed URL into a Uri
-    let target_url = match target_url_str.parse::<Uri>() {
+    // Construct the target Anthropic API URL
+    let target_url = match build_target_url(&config.anthropic_target_url, &path_and_query) {
         Ok(uri) => {
-            info!(target_url = %uri, "Target URL constructed successfully");
+            // Path rewrites make the final URL differ from the configured base, so record it
+            let logged_url = redact_url(&uri.to_string(), redact_params).into_owned();
+            span.record("upstream.url", logged_url.as_str());
+            info!(target_url = %logged_url, "Target URL constructed successfully");
             uri
         }
         Err(e) => {
             // Log the error with context and return an error status
+            let attempted_url = format!("{}{}", config.anthropic_target_url, path_and_query);
             error!(
                 error = %e,
-                attempted_url = %target_url_str,
-                "Failed to parse target URL"
+                attempted_url = %redact_url(&attempted_url, redact_params),
+                "Failed to build target URL"
             );
 
             // Record the error status in the span
@@ -166,37 +474,190 @@ pub async fn proxy_handler(
         }
     };
 
-    // Convert the request body to bytes for processing
-    // The usize::MAX parameter means we'll read the entire body, no matter how large
-    let body_bytes_result = hyper::body::to_bytes(req.into_body()).await;
+    // Never forward to a host outside the allowlist, whatever built the target URL
+    let target_host = target_url.host().unwrap_or("");
+    if !config.upstream_host_allowed(target_host) {
+        return Ok(reject_disallowed_upstream(&span, target_host));
+    }
 
-    // Handle any errors that might occur during body extraction
-    // The extracted body bytes will be used in future implementations
-    let body_bytes = match body_bytes_result {
-        Ok(bytes) => {
-            info!(body_size = bytes.len(), "Request body read successfully");
-            bytes
-        }
-        Err(e) => {
-            // Log the error and return a BAD_REQUEST status
-            error!(error = %e, "Failed to read request body");
+    // Record where the upstream host currently resolves to, for DNS/routing debugging
+    if config.log_resolved_ip {
+        let upstream_ip = ip_for_log(state.ip_cache.resolve_uri(&target_url).await);
+        span.record("upstream.ip", upstream_ip.as_str());
+        debug!(upstream.ip = %upstream_ip, "Resolved upstream address");
+    }
 
-            // Record the error status in the span
-            span.record("http.status_code", StatusCode::BAD_REQUEST.as_u16());
+    // A WebSocket handshake has no body to process; the connection is relayed as is
+    if websocket_upgrade {
+        return open_websocket_tunnel(
+            req,
+            &target_url,
+            route_path,
+            original_headers,
+            &config,
+            &span,
+            permit,
+        )
+        .await;
+    }
 
-            return Err(StatusCode::BAD_REQUEST);
-        }
-    };
+    // Whether the proxy changed the request body, so its length had to be recomputed
+    let mut body_rewritten = false;
 
-    // Log detailed request information including headers and body
-    log_request_details(
-        &method,
-        &original_uri,
-        &original_headers,
-        &body_bytes,
-        config.log_bodies,
-        config.log_max_body_size,
-    );
+    // Large uploads can be streamed straight through when nothing needs to inspect them.
+    // A streamed body is never held in memory, so it is not reserved against the budget.
+    let (body_bytes, streamed_body, _request_reservation, request_model, stream_requested) =
+        if config.streams_request_body() {
+            info!(
+                http.method = %method,
+                url.full = %redact_url(&original_uri.to_string(), redact_params),
+                http.request.body.size = ?content_length(&original_headers),
+                "Streaming request body to upstream without buffering"
+            );
+            (Bytes::new(), Some(req.into_body()), None, None, None)
+        } else {
+            // Reserve the declared body size before buffering anything, so a burst of large
+            // requests is turned away instead of exhausting memory
+            let declared_body_size = content_length(&original_headers).unwrap_or(0);
+            let Some(mut request_reservation) = state.budget.try_reserve(declared_body_size) else {
+                return Ok(reject_over_budget(&span, declared_body_size, &state.budget));
+            };
+
+            // Read the body, holding it to the budget chunk by chunk
+            let body_read_start = Instant::now();
+            let body_bytes_result =
+                read_request_body(req.into_body(), &mut request_reservation).await;
+
+            // A slow read means a slow client, not a slow upstream
+            if config.log_body_read_timing {
+                span.record(
+                    "request_body_read_ms",
+                    duration_ms(body_read_start.elapsed()),
+                );
+            }
+
+            // Handle any errors that might occur during body extraction
+            // The extracted body bytes will be used in future implementations
+            let body_bytes = match body_bytes_result {
+                Ok(bytes) => {
+                    info!(body_size = bytes.len(), "Request body read successfully");
+                    bytes
+                }
+                Err(RequestBodyError::OverBudget(received)) => {
+                    return Ok(reject_over_budget(&span, received, &state.budget));
+                }
+                Err(RequestBodyError::Read(e)) => {
+                    // Log the error and return a BAD_REQUEST status
+                    error!(error = %e, "Failed to read request body");
+
+                    // Record the error status in the span
+                    span.record("http.status_code", StatusCode::BAD_REQUEST.as_u16());
+
+                    return Err(StatusCode::BAD_REQUEST);
+                }
+            };
+
+            debug!(
+                reserved_bytes = request_reservation.bytes(),
+                in_use_bytes = state.budget.in_use(),
+                "Request body reserved against buffer budget"
+            );
+
+            // Cap max_tokens for cost control, before anything else reads or logs the body
+            let body_bytes = match config.enforce_max_tokens {
+                Some(ceiling) if method == Method::POST && original_uri.path() == MESSAGES_PATH => {
+                    match clamp_max_tokens(&body_bytes, ceiling) {
+                        Some(clamped) => {
+                            info!(ceiling, "Capped max_tokens of Messages request");
+                            span.record("anthropic.max_tokens_clamped", true);
+                            body_rewritten = true;
+                            original_headers
+                                .insert(header::CONTENT_LENGTH, HeaderValue::from(clamped.len()));
+                            clamped
+                        }
+                        None => {
+                            span.record("anthropic.max_tokens_clamped", false);
+                            body_bytes
+                        }
+                    }
+                }
+                _ => body_bytes,
+            };
+
+            // Best-effort parse as a Messages request; other bodies are simply not described
+            let anthropic_request = parse_messages_request(&body_bytes);
+            if let Some(messages) = anthropic_request
+                .as_ref()
+                .and_then(|request| request.messages.as_ref())
+            {
+                span.record("anthropic.message_count", messages.len());
+            }
+
+            // A body that parses says whether the client wants a stream, even if it omits `stream`
+            let stream_requested = anthropic_request
+                .as_ref()
+                .map(|request| request.stream.unwrap_or(false));
+            if let Some(stream_requested) = stream_requested {
+                span.record("request.stream_requested", stream_requested);
+            }
+
+            let request_model = anthropic_request.and_then(|request| request.model);
+
+            // Enforce per-model limits (requests without a model are never limited)
+            if !state.rate_limiter.is_empty() {
+                if let Some(model) = &request_model {
+                    if let Err(retry_after) = state.rate_limiter.try_acquire(model) {
+                        warn!(
+                            model = %model,
+                            retry_after_ms = retry_after.as_millis() as u64,
+                            "Model rate limit exceeded, rejecting request"
+                        );
+                        span.record("http.status_code", StatusCode::TOO_MANY_REQUESTS.as_u16());
+                        return Ok(rate_limited_response(retry_after));
+                    }
+                }
+            }
+
+            // Log detailed request information including headers and body
+            if !log_opted_out {
+                log_request_details_with_options(
+                    &method,
+                    &original_uri,
+                    &original_headers,
+                    &body_bytes,
+                    &BodyLogOptions::from_config(&config),
+                );
+            }
+
+            (
+                body_bytes,
+                None,
+                Some(request_reservation),
+                request_model,
+                stream_requested,
+            )
+        };
+
+    // In dry-run mode the request has been logged in full, so report where it would have gone
+    if config.dry_run_proxy {
+        return Ok(dry_run_response(&span, &target_url));
+    }
+
+    // Deterministic requests may be answered from the cache without going upstream
+    let cache_key =
+        state
+            .response_cache
+            .key_for(&method, original_uri.path(), &original_headers, &body_bytes);
+    if let Some(cached) = cache_key
+        .as_deref()
+        .and_then(|key| state.response_cache.get(key))
+    {
+        let mut response = cached_response(&span, cached, start);
+        if let Some(origin) = &cors_origin {
+            cors::apply_allow_origin(response.headers_mut(), origin);
+        }
+        return Ok(response);
+    }
 
     // Create the request builder for forwarding to Anthropic API
     info!("Setting up request forwarding to Anthropic API");
@@ -234,22 +695,103 @@ pub async fn proxy_handler(
         }
     }
 
-    // Set the Anthropic API key as x-api-key header
-    match ReqHeaderValue::from_str(&config.anthropic_api_key) {
-        Ok(api_key_value) => {
-            // Add the API key header
-            forward_headers.insert(header::HeaderName::from_static("x-api-key"), api_key_value);
+    // Tell the upstream who the client is and how it reached us
+    if config.add_forwarded_headers {
+        let proto = if config.tls_cert_path.is_some() {
+            "https"
+        } else {
+            "http"
+        };
+        add_forwarded_headers(
+            &mut forward_headers,
+            &original_headers,
+            &original_uri,
+            client_ip,
+            proto,
+        );
+    }
+
+    // Set the API key for this route as x-api-key header, unless clients bring their own
+    if config.auth_mode == AuthMode::Passthrough {
+        debug!("Passthrough auth mode, forwarding client credentials unchanged");
+    } else {
+        match config.api_key_header_value(route_path) {
+            Ok(api_key_value) => {
+                // Add the API key header
+                forward_headers.insert(header::HeaderName::from_static("x-api-key"), api_key_value);
+                if config.log_key_fingerprint {
+                    span.record(
+                        "key_fingerprint",
+                        key_fingerprint(config.api_key_for_path(route_path)),
+                    );
+                }
 
-            // Remove Authorization header if it exists (x-api-key is preferred by Anthropic)
-            forward_headers.remove(header::AUTHORIZATION);
+                // Remove Authorization header if it exists (x-api-key is preferred by Anthropic)
+                forward_headers.remove(header::AUTHORIZATION);
+            }
+            Err(e) => {
+                error!(error = %e, "Failed to create header value for Anthropic API key");
+                span.record(
+                    "http.status_code",
+                    StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+                );
+                return Err(StatusCode::INTERNAL_SERVER_ERROR);
+            }
         }
-        Err(e) => {
-            error!(error = %e, "Failed to create header value for Anthropic API key");
-            span.record(
-                "http.status_code",
-                StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
-            );
-            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    // Propagate W3C trace context, starting a new trace when configured to
+    match TraceParent::from_headers(&original_headers) {
+        Some(trace_parent) => record_trace_parent(&span, &trace_parent),
+        None if config.generate_traceparent => {
+            let trace_parent = TraceParent::generate();
+            forward_headers.insert(TRACEPARENT_HEADER, trace_parent.to_header_value());
+            record_trace_parent(&span, &trace_parent);
+            debug!(traceparent = %trace_parent, "Generated traceparent for upstream request");
+        }
+        None => {}
+    }
+
+    // Some upstream endpoints reject a POST without a body, so optionally send `{}` instead
+    let body_bytes = if method == Method::POST
+        && streamed_body.is_none()
+        && body_bytes.is_empty()
+        && config.empty_post_body == EmptyBodyPolicy::EmptyJson
+    {
+        let empty_json = Bytes::from_static(b"{}");
+        forward_headers.insert(
+            header::CONTENT_TYPE,
+            ReqHeaderValue::from_static("application/json"),
+        );
+        forward_headers.insert(
+            header::CONTENT_LENGTH,
+            ReqHeaderValue::from(empty_json.len()),
+        );
+        debug!("Substituted empty JSON object for empty POST body");
+        body_rewritten = true;
+        empty_json
+    } else {
+        body_bytes
+    };
+
+    // Without Content-Length, a body of unknown size (a stream) is sent chunked
+    let send_chunked = body_rewritten && config.force_chunked_on_rewrite;
+    if send_chunked {
+        forward_headers.remove(header::CONTENT_LENGTH);
+        debug!("Sending rewritten request body chunked, without Content-Length");
+    }
+
+    // The upstream only gets what is left of the deadline, and is told so when configured
+    if let Some(deadline_ms) = config.request_deadline_ms {
+        let remaining = Duration::from_millis(deadline_ms).saturating_sub(start.elapsed());
+        forward_req_builder = forward_req_builder.timeout(remaining);
+        if let Some(name) = &config.upstream_timeout_header {
+            // Checked when the configuration loaded
+            let name = HeaderName::from_bytes(name.as_bytes())
+                .expect("upstream timeout header name should be valid");
+            let remaining_ms = remaining.as_millis() as u64;
+            forward_headers.insert(name, ReqHeaderValue::from(remaining_ms));
+            debug!(remaining_ms, "Sending remaining request deadline upstream");
         }
     }
 
@@ -257,17 +799,27 @@ pub async fn proxy_handler(
     forward_req_builder = forward_req_builder.headers(forward_headers);
 
     // Add the request body to the builder
-    forward_req_builder = forward_req_builder.body(body_bytes);
+    forward_req_builder = match streamed_body {
+        Some(body) => forward_req_builder.body(reqwest::Body::wrap_stream(body)),
+        None if send_chunked => forward_req_builder.body(reqwest::Body::wrap_stream(stream::once(
+            future::ready(Ok::<_, Infallible>(body_bytes)),
+        ))),
+        None => forward_req_builder.body(body_bytes),
+    };
 
     // Store the builder for the next step (actually sending the request)
     info!("Request forwarding setup complete");
 
     // Send the request to the Anthropic API
     info!("Sending request to Anthropic API");
-    let forward_resp_result = forward_req_builder.send().await;
+    // Time spent waiting on upstream is tracked so Server-Timing can report proxy overhead only
+    let upstream_start = Instant::now();
+    let forward_resp_result = send_upstream(forward_req_builder, &method, &config).await;
+    let upstream_elapsed = upstream_start.elapsed();
+    span.record("upstream_ms", upstream_elapsed.as_millis());
 
     // Check if the request was successful
-    let forward_resp = match forward_resp_result {
+    let mut forward_resp = match forward_resp_result {
         Ok(resp) => {
             info!(
                 status = %resp.status(),
@@ -276,11 +828,19 @@ pub async fn proxy_handler(
             resp
         }
         Err(e) => {
-            // Log the error with context
-            error!(
-                error = %e,
-                "Failed to send request to Anthropic API"
-            );
+            // Log the error with context, telling unreachable upstreams apart from other failures
+            if e.is_connect() {
+                error!(
+                    error = %e,
+                    failure = "connection failed",
+                    "Failed to connect to Anthropic API"
+                );
+            } else {
+                error!(
+                    error = %e,
+                    "Failed to send request to Anthropic API"
+                );
+            }
 
             // Record the error status in the span
             span.record("http.status_code", StatusCode::BAD_GATEWAY.as_u16());
@@ -296,6 +856,16 @@ pub async fn proxy_handler(
     // Record the response status code in the span for observability
     span.record("http.status_code", resp_status.as_u16());
 
+    // Anthropic's own ID for the request is what its support asks for
+    if config.log_upstream_request_id {
+        if let Some(upstream_id) = resp_headers
+            .get(UPSTREAM_REQUEST_ID_HEADER)
+            .and_then(|value| value.to_str().ok())
+        {
+            span.record("anthropic.request_id", upstream_id);
+        }
+    }
+
     // Log detailed information about the response
     info!(
         request_id = %req_id,
@@ -312,13 +882,59 @@ pub async fn proxy_handler(
         "Response headers from Anthropic API"
     );
 
+    if config.warn_on_deprecation {
+        warn_on_deprecation_headers(&resp_headers, request_model.as_deref());
+    }
+
+    // Rate limiting gets its own event, so alerting does not have to parse response logs
+    if config.log_rate_limit_events && is_upstream_rate_limit(resp_status) {
+        // The key is only the proxy's own in inject mode
+        let key_fingerprint = (config.auth_mode != AuthMode::Passthrough)
+            .then(|| key_fingerprint(config.api_key_for_path(route_path)));
+        log_upstream_rate_limit(
+            resp_status,
+            &resp_headers,
+            request_model.as_deref(),
+            key_fingerprint.as_deref(),
+        );
+    }
+
     // Check if this is a streaming response by examining Content-Type header
     let is_streaming = resp_headers
         .get(header::CONTENT_TYPE)
         .map(|ct| ct.to_str().unwrap_or("").contains("text/event-stream"))
         .unwrap_or(false);
+    span.record("response.is_streaming", is_streaming);
+
+    // Error responses are never streamed, so only successful ones can contradict the request
+    if config.warn_on_stream_mismatch && resp_status.is_success() {
+        if let Some(stream_requested) = stream_requested.filter(|&wanted| wanted != is_streaming) {
+            warn!(
+                request.stream_requested = stream_requested,
+                response.is_streaming = is_streaming,
+                "Response streaming does not match the request's stream field"
+            );
+        }
+    }
+
+    // A successful API response is JSON or an event stream; anything else suggests a wrong upstream
+    if config.warn_on_content_type_anomaly
+        && resp_status == StatusCode::OK
+        && !is_streaming
+        && !is_json_content_type(&resp_headers)
+    {
+        warn!(
+            http.response.content_type = ?resp_headers.get(header::CONTENT_TYPE),
+            "Successful response is neither JSON nor an event stream"
+        );
+    }
 
     if is_streaming {
+        // Streams hold their own slot until the client body is dropped
+        let Some(stream_permit) = state.stream_limiter.acquire().await else {
+            return Ok(reject_over_stream_limit(&span));
+        };
+
         // Log headers for streaming response
         info!(
             request_id = %req_id,
@@ -326,12 +942,16 @@ pub async fn proxy_handler(
         );
 
         // Call the header logging helper to log status and headers
-        log_response_headers(
-            &resp_status,
-            &resp_headers,
-            config.log_bodies,
-            Some(start.elapsed()),
-        );
+        if !log_opted_out {
+            log_response_headers(
+                &resp_status,
+                &resp_headers,
+                config.log_bodies,
+                Some(start.elapsed()),
+                config.max_span_fields,
+                config.sanitize_log_output,
+            );
+        }
 
         // Create a stream from the reqwest response
         info!(
@@ -339,24 +959,51 @@ pub async fn proxy_handler(
             "Creating stream from Anthropic API response"
         );
 
-        // Get the bytes stream from the reqwest response
+        // Get the bytes stream from the reqwest response. HTTP trailers are not carried
+        // over: reqwest 0.11 discards them on every response body, so the upstream's
+        // trailers never reach the proxy. Forwarding them needs a client exposing trailers.
         let reqwest_stream = forward_resp.bytes_stream();
 
         // Convert reqwest stream to axum stream by mapping each chunk
         // and handling errors appropriately
         let log_bodies = config.log_bodies;
+        let sanitize_chunks = config.sanitize_log_output;
+        let log_chunks = !log_opted_out;
+        let mut timing = StreamTiming::new(start, span.clone(), req_id);
+        let mut terminal_watch = config
+            .warn_on_sse_trailing_data
+            .then(TerminalEventWatch::new);
         let axum_stream = reqwest_stream.map(move |result| match result {
             Ok(bytes) => {
+                timing.on_chunk();
+
+                // Data after the terminal event is forwarded, but worth knowing about
+                if let Some(trailing_bytes) = terminal_watch
+                    .as_mut()
+                    .and_then(|watch| watch.on_chunk(&bytes))
+                {
+                    warn!(
+                        request_id = %req_id,
+                        trailing_bytes,
+                        "Upstream sent data after the terminal SSE event"
+                    );
+                }
+
                 // Log the chunk content at DEBUG level if LOG_BODIES is enabled
-                if log_bodies {
+                if log_chunks && log_bodies {
                     let chunk_str = String::from_utf8_lossy(&bytes);
+                    let chunk_str = if sanitize_chunks {
+                        sanitize_for_log(&chunk_str).into_owned()
+                    } else {
+                        chunk_str.into_owned()
+                    };
                     debug!(
                         request_id = %req_id,
                         chunk_size = bytes.len(),
                         chunk_content = %chunk_str,
                         "Received stream chunk from Anthropic API"
                     );
-                } else {
+                } else if log_chunks {
                     // Otherwise just log the chunk size at debug level
                     debug!(
                         request_id = %req_id,
@@ -377,8 +1024,21 @@ pub async fn proxy_handler(
             }
         });
 
-        // Create the Axum body from the stream
-        let stream_body = Body::wrap_stream(axum_stream);
+        // The slot is released (and the stream stops counting as in flight) on completion
+        // or client disconnect, as both drop the body
+        let axum_stream =
+            hold_while_alive(axum_stream, (stream_permit, state.in_flight.track_stream()));
+
+        // Create the Axum body from the stream, cut short if it may only run so long
+        let stream_body = match config.max_stream_duration_secs {
+            Some(max_secs) => Body::wrap_stream(limit_stream_duration(
+                axum_stream,
+                Duration::from_secs(max_secs),
+                span.clone(),
+                req_id,
+            )),
+            None => Body::wrap_stream(axum_stream),
+        };
 
         info!(
             request_id = %req_id,
@@ -392,24 +1052,15 @@ pub async fn proxy_handler(
             "Forwarding streaming response to client"
         );
 
-        // Start building the response with the same status code
-        let mut response_builder = Response::builder().status(resp_status);
+        // Start building the response with the upstream status code (or its mapping)
+        let mut response_builder = client_response_builder(resp_status, &config);
 
         // Copy the headers from the Anthropic API response, excluding hop-by-hop headers
         // For streaming responses, we also exclude Content-Length as it's not applicable
         for (name, value) in resp_headers.iter() {
             // Filter out hop-by-hop headers that shouldn't be forwarded back
             // and Content-Length which doesn't apply to streaming responses
-            if name != header::CONNECTION
-                && name != header::PROXY_AUTHENTICATE
-                && name != header::PROXY_AUTHORIZATION
-                && name != header::TE
-                && name != header::TRAILER
-                && name != header::TRANSFER_ENCODING
-                && name != header::UPGRADE
-                && name != header::HOST
-                && name != header::CONTENT_LENGTH
-            {
+            if !is_hop_by_hop_response_header(name) && name != header::CONTENT_LENGTH {
                 // Add the header to our response
                 response_builder = response_builder.header(name.clone(), value.clone());
             }
@@ -421,6 +1072,19 @@ pub async fn proxy_handler(
             response_builder = response_builder.header(header::CONTENT_TYPE, "text/event-stream");
         }
 
+        // Browsers only hand the response to the page when CORS allows its origin
+        if let (Some(origin), Some(headers)) = (&cors_origin, response_builder.headers_mut()) {
+            cors::apply_allow_origin(headers, origin);
+        }
+
+        // For streams only the overhead up to the first byte can be known at this point
+        if config.server_timing {
+            response_builder = response_builder.header(
+                SERVER_TIMING_HEADER,
+                server_timing_value(start.elapsed(), upstream_elapsed),
+            );
+        }
+
         // Build the final streaming response with the body
         match response_builder.body(boxed(stream_body)) {
             Ok(response) => {
@@ -454,75 +1118,243 @@ pub async fn proxy_handler(
                 Err(StatusCode::INTERNAL_SERVER_ERROR)
             }
         }
-    } else {
-        // Handle non-streaming response by reading the full body
+    } else if method == Method::HEAD {
+        // HEAD responses have no body, but the upstream Content-Length describes the
+        // body a GET would return, so it is forwarded untouched and nothing is read
         info!(
             request_id = %req_id,
-            "Handling non-streaming response from Anthropic API"
+            "Handling HEAD response from Anthropic API without reading a body"
         );
 
-        // Read the full response body
-        let resp_body_bytes_result = forward_resp.bytes().await;
+        if !log_opted_out {
+            log_response_headers(
+                &resp_status,
+                &resp_headers,
+                config.log_bodies,
+                Some(start.elapsed()),
+                config.max_span_fields,
+                config.sanitize_log_output,
+            );
+        }
 
-        // Handle any errors that might occur during body extraction
-        let resp_body_bytes = match resp_body_bytes_result {
-            Ok(bytes) => {
+        // Start building the response with the upstream status code (or its mapping)
+        let mut response_builder = client_response_builder(resp_status, &config);
+
+        // Copy the headers, including Content-Length, excluding hop-by-hop headers
+        for (name, value) in resp_headers.iter() {
+            if !is_hop_by_hop_response_header(name) {
+                response_builder = response_builder.header(name.clone(), value.clone());
+            }
+        }
+
+        if let (Some(origin), Some(headers)) = (&cors_origin, response_builder.headers_mut()) {
+            cors::apply_allow_origin(headers, origin);
+        }
+
+        if config.server_timing {
+            response_builder = response_builder.header(
+                SERVER_TIMING_HEADER,
+                server_timing_value(start.elapsed(), upstream_elapsed),
+            );
+        }
+
+        match response_builder.body(boxed(Empty::new())) {
+            Ok(response) => {
+                let duration = start.elapsed();
+                span.record("duration_ms", duration.as_millis());
                 info!(
                     request_id = %req_id,
-                    body_size = bytes.len(),
-                    "Response body read successfully"
+                    duration_ms = %duration.as_millis(),
+                    "Successfully built HEAD client response"
                 );
-                bytes
+                Ok(response)
             }
             Err(e) => {
-                // Log the error with context
                 error!(
                     request_id = %req_id,
                     error = %e,
-                    "Failed to read response body from Anthropic API"
+                    "Failed to build HEAD response"
                 );
-
-                // Record the error status in the span
-                span.record("http.status_code", StatusCode::BAD_GATEWAY.as_u16());
-
-                return Err(StatusCode::BAD_GATEWAY);
+                span.record(
+                    "http.status_code",
+                    StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+                );
+                Err(StatusCode::INTERNAL_SERVER_ERROR)
             }
-        };
-
-        // Log detailed response information including headers and body
-        log_response_details(
-            &resp_status,
-            &resp_headers,
-            &resp_body_bytes,
-            config.log_bodies,
-            config.log_max_body_size,
-            Some(start.elapsed()),
-        );
-
-        // Build the response to return to the client
+        }
+    } else {
+        // Handle non-streaming response by reading the full body
         info!(
             request_id = %req_id,
-            status = %resp_status,
-            body_size = resp_body_bytes.len(),
-            "Forwarding non-streaming response to client"
+            "Handling non-streaming response from Anthropic API"
         );
 
-        // Start building the response with the same status code
-        let mut response_builder = Response::builder().status(resp_status);
+        // Reserve the declared response size before buffering it (at most the buffering limit)
+        let buffer_limit = config.response_buffer_limit_bytes;
+        let declared_resp_size = forward_resp.content_length().unwrap_or(0);
+        let reserved_size = buffer_limit.map_or(declared_resp_size, |limit| {
+            declared_resp_size.min(limit as u64)
+        });
+        let Some(mut response_reservation) = state.budget.try_reserve(reserved_size) else {
+            return Ok(reject_over_budget(&span, reserved_size, &state.budget));
+        };
 
-        // Copy the headers from the Anthropic API response, excluding hop-by-hop headers
-        for (name, value) in resp_headers.iter() {
+        // Read the full response body, keeping what arrived if the upstream stops early
+        let body_read_start = Instant::now();
+        let resp_body_bytes_result =
+            read_full_body(&mut forward_resp, buffer_limit, &mut response_reservation).await;
+        let body_read_elapsed = body_read_start.elapsed();
+        span.record("body_read_ms", body_read_elapsed.as_millis());
+
+        // Handle any errors that might occur during body extraction
+        let (resp_body_bytes, body_complete) = match resp_body_bytes_result {
+            Ok(BufferedBody::Overflowed(buffered)) => {
+                // Too large to hold: forward what was read, then stream the rest
+                drop(response_reservation);
+                info!(
+                    request_id = %req_id,
+                    buffered_bytes = buffered.len(),
+                    buffer_limit = ?buffer_limit,
+                    "Response exceeds the buffering limit, streaming the rest to the client"
+                );
+                if !log_opted_out {
+                    log_response_headers(
+                        &resp_status,
+                        &resp_headers,
+                        config.log_bodies,
+                        Some(start.elapsed()),
+                        config.max_span_fields,
+                        config.sanitize_log_output,
+                    );
+                }
+
+                let rest = forward_resp.bytes_stream().map(move |result| {
+                    result.map_err(|e| {
+                        error!(
+                            request_id = %req_id,
+                            error = %e,
+                            "Error reading response body from Anthropic API after the buffering limit"
+                        );
+                        axum::BoxError::from(format!("Stream error: {}", e))
+                    })
+                });
+                let body_stream = stream::once(future::ready(Ok(buffered))).chain(rest);
+                let body_stream = hold_while_alive(body_stream, state.in_flight.track_stream());
+
+                // The whole upstream body is forwarded, so its Content-Length still holds
+                let mut response_builder = client_response_builder(resp_status, &config);
+                for (name, value) in resp_headers.iter() {
+                    if !is_hop_by_hop_response_header(name) {
+                        response_builder = response_builder.header(name.clone(), value.clone());
+                    }
+                }
+                if let (Some(origin), Some(headers)) =
+                    (&cors_origin, response_builder.headers_mut())
+                {
+                    cors::apply_allow_origin(headers, origin);
+                }
+                if config.server_timing {
+                    response_builder = response_builder.header(
+                        SERVER_TIMING_HEADER,
+                        server_timing_value(start.elapsed(), upstream_elapsed + body_read_elapsed),
+                    );
+                }
+
+                span.record("duration_ms", start.elapsed().as_millis());
+                return response_builder
+                    .body(boxed(Body::wrap_stream(body_stream)))
+                    .map_err(|e| {
+                        error!(
+                            request_id = %req_id,
+                            error = %e,
+                            "Failed to build response"
+                        );
+                        span.record(
+                            "http.status_code",
+                            StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+                        );
+                        StatusCode::INTERNAL_SERVER_ERROR
+                    });
+            }
+            Ok(BufferedBody::OverBudget(received)) => {
+                return Ok(reject_over_budget(&span, received as u64, &state.budget));
+            }
+            Ok(BufferedBody::Complete(bytes)) => {
+                info!(
+                    request_id = %req_id,
+                    body_size = bytes.len(),
+                    "Response body read successfully"
+                );
+                (bytes, true)
+            }
+            Err((received, e)) if config.forward_partial_bodies => {
+                warn!(
+                    request_id = %req_id,
+                    error = %e,
+                    failure = "incomplete body",
+                    received_bytes = received.len(),
+                    expected_bytes = declared_resp_size,
+                    "Upstream response body incomplete, forwarding the partial body"
+                );
+                (received, false)
+            }
+            Err((received, e)) => {
+                // Log the error with context
+                error!(
+                    request_id = %req_id,
+                    error = %e,
+                    failure = "incomplete body",
+                    received_bytes = received.len(),
+                    expected_bytes = declared_resp_size,
+                    "Failed to read response body from Anthropic API"
+                );
+
+                // Record the error status in the span
+                span.record("http.status_code", StatusCode::BAD_GATEWAY.as_u16());
+
+                return Err(StatusCode::BAD_GATEWAY);
+            }
+        };
+
+        // Surface the upstream error class for alerting (best effort; other bodies are skipped)
+        if resp_status.as_u16() >= 400 {
+            if let Some(error_type) = parse_error_type(&resp_body_bytes) {
+                span.record("anthropic.error_type", error_type.as_str());
+            }
+        }
+
+        // Log detailed response information including headers and body
+        if !log_opted_out {
+            log_response_details_with_options(
+                &resp_status,
+                &resp_headers,
+                &resp_body_bytes,
+                &BodyLogOptions::from_config(&config),
+                Some(ResponseTimings {
+                    total: start.elapsed(),
+                    upstream: Some(upstream_elapsed),
+                    body_read: Some(body_read_elapsed),
+                }),
+            );
+        }
+
+        // Build the response to return to the client
+        info!(
+            request_id = %req_id,
+            status = %resp_status,
+            body_size = resp_body_bytes.len(),
+            "Forwarding non-streaming response to client"
+        );
+
+        // Start building the response with the upstream status code (or its mapping)
+        let mut response_builder = client_response_builder(resp_status, &config);
+
+        // Copy the headers from the Anthropic API response, excluding hop-by-hop headers.
+        // Content-Length is set below from the body actually forwarded, which is shorter
+        // than the upstream's declared length when a partial body is forwarded.
+        for (name, value) in resp_headers.iter() {
             // Filter out hop-by-hop headers that shouldn't be forwarded back
-            if name != header::CONNECTION
-                && name != header::PROXY_AUTHENTICATE
-                && name != header::PROXY_AUTHORIZATION
-                && name != header::TE
-                && name != header::TRAILER
-                && name != header::TRANSFER_ENCODING
-                && name != header::UPGRADE
-                // Also don't forward the host header in the response
-                && name != header::HOST
-            {
+            if !is_hop_by_hop_response_header(name) && name != header::CONTENT_LENGTH {
                 // Add the header to our response
                 response_builder = response_builder.header(name.clone(), value.clone());
             }
@@ -532,6 +1364,32 @@ pub async fn proxy_handler(
         response_builder =
             response_builder.header(header::CONTENT_LENGTH, resp_body_bytes.len().to_string());
 
+        // Cacheable requests that got here missed the cache; store complete successful responses
+        if let Some(key) = cache_key.filter(|_| body_complete) {
+            response_builder = response_builder.header(CACHE_STATUS_HEADER, "MISS");
+            if state.response_cache.insert(
+                key,
+                resp_status,
+                resp_headers.clone(),
+                resp_body_bytes.clone(),
+            ) {
+                debug!(request_id = %req_id, "Stored response in cache");
+            }
+        }
+
+        // Browsers only hand the response to the page when CORS allows its origin
+        if let (Some(origin), Some(headers)) = (&cors_origin, response_builder.headers_mut()) {
+            cors::apply_allow_origin(headers, origin);
+        }
+
+        // Report how much latency the proxy itself added
+        if config.server_timing {
+            response_builder = response_builder.header(
+                SERVER_TIMING_HEADER,
+                server_timing_value(start.elapsed(), upstream_elapsed + body_read_elapsed),
+            );
+        }
+
         // Build the final response with the body
         // Converting the body to a boxed body to make it compatible with axum's expectations
         match response_builder.body(boxed(Full::from(resp_body_bytes))) {
@@ -569,415 +1427,814 @@ pub async fn proxy_handler(
     }
 }
 
-/// Maximum length of request/response bodies that will be logged in full
-/// Bodies larger than this will only have their size logged to avoid excessive logging
-/// Increased from 10KB to 20KB to capture more verbose logging
-///
-/// @deprecated This constant is kept for backward compatibility but is no longer used.
-/// The `log_max_body_size` parameter from Config is used instead, which allows for
-/// configuration via environment variables.
-#[allow(dead_code)]
-pub const MAX_LOG_BODY_LEN: usize = 20 * 1024; // 20KB
+/// Why a client request body could not be buffered
+enum RequestBodyError {
+    /// The body could not be read from the client
+    Read(hyper::Error),
+    /// Reading stopped once this many bytes no longer fit the memory budget
+    OverBudget(u64),
+}
 
-/// Logs details of an incoming request in a structured format
-///
-/// This function creates a new logging span and records comprehensive information about
-/// the request, including method, URI, headers (with sensitive values masked), and the
-/// request body (with size limits and JSON formatting when enabled).
-///
-/// # Log Format
-///
-/// This function produces log entries with the following structure:
-///
-/// 1. Basic request information at INFO level:
-///    - `http.method`: HTTP method (GET, POST, etc.)
-///    - `url.full`: Complete request URL
-///
-/// 2. Headers at DEBUG level:
-///    - `http.request.headers`: Map of all headers (sensitive values redacted)
-///
-/// 3. Body logging based on size and configuration:
-///    - If empty: "Request body empty" at INFO level
-///    - If `log_bodies=true` and body size <= `log_max_body_size`:
-///      * Body content logged at DEBUG level with `http.request.body.content` and `http.request.body.size`
-///      * JSON bodies are pretty-printed for readability
-///    - If `log_bodies=false` and body size <= `log_max_body_size`:
-///      * "Request body not logged" at DEBUG level with just `http.request.body.size`
-///    - If body size > `log_max_body_size`:
-///      * "Request body too large to log fully" at INFO level with just `http.request.body.size`
-///
-/// # Security Notes
-///
-/// - Sensitive headers like `Authorization` and `x-api-key` are automatically redacted
-/// - Body logging can be disabled entirely via the `log_bodies` parameter
-/// - Body size limits prevent excessive logging with large payloads
-///
-/// # Arguments
-/// * `method` - The HTTP method (GET, POST, etc.)
-/// * `uri` - The request URI including path and query
-/// * `headers` - The request headers map
-/// * `body` - The request body as bytes
-/// * `log_bodies` - Boolean flag indicating whether to include full body content in logs
-/// * `log_max_body_size` - Maximum size in bytes for logged bodies before truncation
+/// Reads a client request body chunk by chunk, growing `reservation` with every chunk
 ///
-/// # Examples
-///
-/// Basic usage:
-///
-/// ```
-/// use hyper::{Method, Uri, HeaderMap};
-/// use bytes::Bytes;
-/// use switchboard::proxy_handler::log_request_details;
+/// A body sent without Content-Length (or larger than declared) reserved too little
+/// up front; growing the reservation as it arrives stops the read as soon as the
+/// budget is exceeded, rather than after the whole body is in memory.
+async fn read_request_body(
+    mut body: Body,
+    reservation: &mut BudgetReservation,
+) -> Result<Bytes, RequestBodyError> {
+    let mut buffered = BytesMut::new();
+    while let Some(chunk) = body.data().await {
+        buffered.extend_from_slice(&chunk.map_err(RequestBodyError::Read)?);
+        if !reservation.try_grow_to(buffered.len() as u64) {
+            return Err(RequestBodyError::OverBudget(buffered.len() as u64));
+        }
+    }
+    Ok(buffered.freeze())
+}
+
+/// Upstream response body read by [`read_full_body`]
+enum BufferedBody {
+    /// The whole body
+    Complete(Bytes),
+    /// The start of a body that outgrew the buffering limit; the rest is unread
+    Overflowed(Bytes),
+    /// Reading stopped once this many bytes no longer fit the memory budget
+    OverBudget(usize),
+}
+
+/// Reads a whole upstream response body chunk by chunk, up to an optional limit
 ///
-/// // Create request components
-/// let method = Method::POST;
-/// let uri = Uri::from_static("https://example.com/v1/messages");
-/// let headers = HeaderMap::new();
-/// let body = Bytes::from(r#"{"message":"Hello world"}"#);
+/// Unlike `Response::bytes`, a failure part way (e.g. the upstream closing the
+/// connection early) does not lose what was already received. Reading stops as soon
+/// as more than `limit` bytes have arrived, leaving the rest of the body to stream.
+/// Otherwise `reservation` grows with every chunk, so a body larger than declared
+/// (or undeclared) stops being read as soon as it exceeds the budget.
 ///
-/// // Log request details
-/// log_request_details(&method, &uri, &headers, &body, true, 1024);
-/// ```
-pub fn log_request_details(
-    method: &hyper::Method,
-    uri: &Uri,
-    headers: &HeaderMap,
-    body: &Bytes,
-    log_bodies: bool,
-    log_max_body_size: usize,
-) {
-    // Create a new span for the request details to keep them separate from the main request span
-    let span = info_span!("request_details");
-    let _enter = span.enter();
-
-    // Log basic request information at the info level
-    info!(http.method = %method, url.full = %uri);
-
-    // Build a map of header names to values, masking sensitive headers
-    let mut headers_log: HashMap<String, String> = HashMap::new();
-    for (name, value) in headers.iter() {
-        let name_str = name.to_string();
-        // Mask sensitive authentication headers
-        let value_str = if name == header::AUTHORIZATION || name == "x-api-key" {
-            "[REDACTED]".to_string()
-        } else {
-            // Convert header value to string (lossy UTF-8 conversion if needed)
-            String::from_utf8_lossy(value.as_bytes()).to_string()
-        };
-        headers_log.insert(name_str, value_str);
-    }
-
-    // Log all headers at debug level (won't show in normal operation)
-    debug!(http.request.headers = ?headers_log);
-
-    // Log the request body with appropriate handling based on size
-    let body_len = body.len();
-
-    if body_len == 0 {
-        // Empty body
-        info!("Request body empty");
-    } else if log_bodies && body_len <= log_max_body_size {
-        // Body is small enough to log fully and logging is enabled
-        // Try to parse as JSON first for pretty formatting
-        match serde_json::from_slice::<Value>(body) {
-            Ok(json_val) => {
-                // Successfully parsed as JSON, pretty print it
-                let pretty_json = serde_json::to_string_pretty(&json_val)
-                    .unwrap_or_else(|_| String::from_utf8_lossy(body).to_string());
-                // Log at DEBUG level even when explicitly enabled
-                debug!(
-                    http.request.body.content = %pretty_json,
-                    http.request.body.size = body_len
-                );
-            }
-            Err(_) => {
-                // Not valid JSON, log as regular string
-                debug!(
-                    http.request.body.content = %String::from_utf8_lossy(body),
-                    http.request.body.size = body_len
-                );
+/// # Returns
+/// The body read, or the bytes received before the error together with the error
+async fn read_full_body(
+    resp: &mut reqwest::Response,
+    limit: Option<usize>,
+    reservation: &mut BudgetReservation,
+) -> Result<BufferedBody, (Bytes, reqwest::Error)> {
+    let mut body = BytesMut::new();
+    loop {
+        match resp.chunk().await {
+            Ok(Some(chunk)) => {
+                body.extend_from_slice(&chunk);
+                if limit.is_some_and(|limit| body.len() > limit) {
+                    return Ok(BufferedBody::Overflowed(body.freeze()));
+                }
+                if !reservation.try_grow_to(body.len() as u64) {
+                    return Ok(BufferedBody::OverBudget(body.len()));
+                }
             }
+            Ok(None) => return Ok(BufferedBody::Complete(body.freeze())),
+            Err(e) => return Err((body.freeze(), e)),
+        }
+    }
+}
+
+/// Tracks timing of a streamed response body as it is forwarded
+///
+/// Lives inside the stream adapter, so it sees every chunk and is dropped once
+/// the stream finishes (or the client goes away). Time to first byte is recorded
+/// as `ttfb_ms` on the request span, since total duration says little about
+/// how responsive a stream felt.
+struct StreamTiming {
+    /// When the proxy started handling the request
+    start: Instant,
+    /// The request span, which outlives the handler while the body streams
+    span: Span,
+    /// Request ID for correlating the timing events
+    req_id: RequestId,
+    /// Time from `start` until the first chunk arrived
+    ttfb: Option<Duration>,
+}
+
+impl StreamTiming {
+    fn new(start: Instant, span: Span, req_id: RequestId) -> Self {
+        Self {
+            start,
+            span,
+            req_id,
+            ttfb: None,
         }
-    } else if body_len <= log_max_body_size {
-        // Small enough to log but logging not enabled - put in debug level
-        debug!(
-            http.request.body.size = body_len,
-            "Request body not logged (enable LOG_BODIES to see contents)"
+    }
+
+    /// Records time to first byte when the first chunk arrives
+    fn on_chunk(&mut self) {
+        if self.ttfb.is_some() {
+            return;
+        }
+
+        let ttfb = self.start.elapsed();
+        self.ttfb = Some(ttfb);
+        let ttfb_ms = duration_ms(ttfb);
+        self.span.record("ttfb_ms", ttfb_ms);
+        info!(
+            parent: &self.span,
+            request_id = %self.req_id,
+            ttfb_ms,
+            "First streaming chunk received from Anthropic API"
         );
-    } else {
-        // Body too large to log fully
+    }
+}
+
+impl Drop for StreamTiming {
+    fn drop(&mut self) {
         info!(
-            http.request.body.size = body_len,
-            "Request body too large to log fully"
+            parent: &self.span,
+            request_id = %self.req_id,
+            ttfb_ms = self.ttfb.map(duration_ms),
+            stream_duration_ms = duration_ms(self.start.elapsed()),
+            "Streaming response finished"
         );
     }
 }
 
-/// Logs details of an API response in a structured format
-///
-/// This function creates a new logging span and records comprehensive information about
-/// the response, including status code, headers, response body (with size limits and
-/// JSON formatting when enabled), and timing metrics.
-///
-/// # Log Format
-///
-/// This function produces log entries with the following structure:
-///
-/// 1. Basic response information at INFO level:
-///    - `http.status_code`: Numeric HTTP status code
-///    - `status_text`: String representation of the status code
-///    - `duration_ms`: Request duration in milliseconds (if provided)
-///
-/// 2. Headers at DEBUG level:
-///    - `http.response.headers`: Map of all headers (sensitive values redacted)
+/// Ends `stream` once `max` has elapsed, logging a truncation warning if it was still running
 ///
-/// 3. Body logging based on size and configuration:
-///    - If empty: "Response body empty" at INFO level
-///    - If `log_bodies=true` and body size <= `log_max_body_size`:
-///      * Body content logged at DEBUG level with `http.response.body.content` and `http.response.body.size`
-///      * JSON bodies are pretty-printed for readability
-///    - If `log_bodies=false` and body size <= `log_max_body_size`:
-///      * "Response body not logged" at DEBUG level with just `http.response.body.size`
-///    - If body size > `log_max_body_size`:
-///      * "Response body too large to log fully" at INFO level with just `http.response.body.size`
-///
-/// # Performance Metrics
-///
-/// When the `duration` parameter is provided, this function includes timing metrics in the
-/// log entry, which is useful for monitoring API response times. This timing data is always
-/// included at the INFO level regardless of body logging settings.
-///
-/// # Security Notes
-///
-/// - Sensitive headers like `Authorization` and `x-api-key` are automatically redacted
-/// - Body logging can be disabled entirely via the `log_bodies` parameter
-/// - Body size limits prevent excessive logging with large payloads
-///
-/// # Arguments
-/// * `status` - The HTTP status code
-/// * `headers` - The response headers map
-/// * `body` - The response body as bytes
-/// * `log_bodies` - Boolean flag indicating whether to include full body content in logs
-/// * `log_max_body_size` - Maximum size in bytes for logged bodies before truncation
-/// * `duration` - Optional duration of the request for timing metrics
-///
-/// # Examples
-///
-/// Basic usage:
-///
-/// ```
-/// use hyper::HeaderMap;
-/// use reqwest::StatusCode;
-/// use bytes::Bytes;
-/// use std::time::Duration;
-/// use switchboard::proxy_handler::log_response_details;
-///
-/// // Create response components
-/// let status = StatusCode::OK;
-/// let headers = HeaderMap::new();
-/// let body = Bytes::from(r#"{"result":"success","data":{}}"#);
-/// let duration = Duration::from_millis(150);
-///
-/// // Log response details with timing
-/// log_response_details(&status, &headers, &body, true, 1024, Some(duration));
-/// ```
-pub fn log_response_details(
-    status: &reqwest::StatusCode,
+/// Guards against upstreams that never finish a stream. The client sees the body end
+/// early; the upstream connection is dropped along with the stream.
+fn limit_stream_duration<S>(
+    stream: S,
+    max: Duration,
+    span: Span,
+    req_id: RequestId,
+) -> impl Stream<Item = S::Item>
+where
+    S: Stream,
+{
+    let deadline = tokio::time::sleep(max);
+    stream.take_until(async move {
+        deadline.await;
+        warn!(
+            parent: &span,
+            request_id = %req_id,
+            max_stream_duration_secs = max.as_secs(),
+            "Streaming response exceeded maximum duration, truncating"
+        );
+    })
+}
+
+/// Keeps `held` (e.g. a permit or guard) alive for as long as `stream` is
+fn hold_while_alive<S, T>(stream: S, held: T) -> impl Stream<Item = S::Item>
+where
+    S: Stream,
+{
+    stream.map(move |item| {
+        let _held = &held;
+        item
+    })
+}
+
+/// Logs a warning for every deprecation notice on an upstream response
+fn warn_on_deprecation_headers(headers: &HeaderMap, model: Option<&str>) {
+    for name in DEPRECATION_HEADERS {
+        for value in headers.get_all(name) {
+            warn!(
+                header = name,
+                value = %String::from_utf8_lossy(value.as_bytes()),
+                model = model.unwrap_or("unknown"),
+                "Upstream response carries a deprecation notice"
+            );
+        }
+    }
+}
+
+/// Returns true if the upstream status means it is rate limiting (429) or overloaded (529)
+fn is_upstream_rate_limit(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.as_u16() == UPSTREAM_OVERLOADED_STATUS
+}
+
+/// Logs the dedicated warning for an upstream rate limiting response
+fn log_upstream_rate_limit(
+    status: StatusCode,
     headers: &HeaderMap,
-    body: &Bytes,
-    log_bodies: bool,
-    log_max_body_size: usize,
-    duration: Option<std::time::Duration>,
+    model: Option<&str>,
+    key_fingerprint: Option<&str>,
 ) {
-    // Create a new span for the response details to keep them separate from the main request span
-    let span = info_span!("response_details");
-    let _enter = span.enter();
+    let retry_after = headers
+        .get(header::RETRY_AFTER)
+        .map(|value| String::from_utf8_lossy(value.as_bytes()).into_owned());
+    warn!(
+        http.status_code = status.as_u16(),
+        retry_after,
+        model = model.unwrap_or("unknown"),
+        key_fingerprint,
+        "Upstream is rate limiting requests"
+    );
+}
 
-    // Log basic response information at the info level, including timing if available
-    if let Some(dur) = duration {
-        info!(
-            http.status_code = %status.as_u16(),
-            status_text = %status.canonical_reason().unwrap_or("Unknown"),
-            duration_ms = %dur.as_millis()
-        );
-    } else {
-        info!(
-            http.status_code = %status.as_u16(),
-            status_text = %status.canonical_reason().unwrap_or("Unknown")
-        );
+/// Collapses runs of slashes in the path part of `path_and_query`, leaving the query as is
+fn collapse_path_slashes(path_and_query: &str) -> Cow<'_, str> {
+    let (path, query) = match path_and_query.find('?') {
+        Some(index) => path_and_query.split_at(index),
+        None => (path_and_query, ""),
+    };
+    if !path.contains("//") {
+        return Cow::Borrowed(path_and_query);
     }
 
-    // Build a map of header names to values, masking sensitive headers
-    let mut headers_log: HashMap<String, String> = HashMap::new();
-    for (name, value) in headers.iter() {
-        let name_str = name.to_string();
-        // Mask sensitive headers if any (similar to request handling)
-        let value_str = if name == header::AUTHORIZATION || name == "x-api-key" {
-            "[REDACTED]".to_string()
-        } else {
-            // Convert header value to string (lossy UTF-8 conversion if needed)
-            String::from_utf8_lossy(value.as_bytes()).to_string()
-        };
-        headers_log.insert(name_str, value_str);
-    }
-
-    // Log all headers at debug level (won't show in normal operation)
-    debug!(http.response.headers = ?headers_log);
-
-    // Log the response body with appropriate handling based on size
-    let body_len = body.len();
-
-    if body_len == 0 {
-        // Empty body
-        info!("Response body empty");
-    } else if log_bodies && body_len <= log_max_body_size {
-        // Body is small enough to log fully and logging is enabled
-        // Try to parse as JSON first for pretty formatting
-        match serde_json::from_slice::<Value>(body) {
-            Ok(json_val) => {
-                // Successfully parsed as JSON, pretty print it
-                let pretty_json = serde_json::to_string_pretty(&json_val)
-                    .unwrap_or_else(|_| String::from_utf8_lossy(body).to_string());
-                // Log at DEBUG level even when explicitly enabled
-                debug!(
-                    http.response.body.content = %pretty_json,
-                    http.response.body.size = body_len
-                );
-            }
-            Err(_) => {
-                // Not valid JSON, log as regular string
-                debug!(
-                    http.response.body.content = %String::from_utf8_lossy(body),
-                    http.response.body.size = body_len
-                );
-            }
+    let mut collapsed = String::with_capacity(path_and_query.len());
+    for c in path.chars() {
+        if c != '/' || !collapsed.ends_with('/') {
+            collapsed.push(c);
         }
-    } else if body_len <= log_max_body_size {
-        // Small enough to log but logging not enabled - put in debug level
-        debug!(
-            http.response.body.size = body_len,
-            "Response body not logged (enable LOG_BODIES to see contents)"
-        );
-    } else {
-        // Body too large to log fully
-        info!(
-            http.response.body.size = body_len,
-            "Response body too large to log fully"
-        );
     }
+    collapsed.push_str(query);
+    Cow::Owned(collapsed)
 }
 
-/// Logs details of response headers for streaming responses
-///
-/// This function creates a new logging span and records the response status and headers,
-/// without attempting to log the body (since the body will be streamed). This is specifically
-/// designed for streaming responses where we want to log headers immediately before
-/// starting to stream the response body.
-///
-/// # Streaming-Specific Behavior
-///
-/// Unlike `log_response_details`, this function:
-/// - Does not attempt to log the response body (which will be streamed later)
-/// - Creates a dedicated span named "streaming_response_details"
-/// - Logs a message at INFO level indicating that streaming is beginning
-/// - Indicates whether full body logging is enabled for subsequent stream chunks
-///
-/// # Log Format
-///
-/// This function produces log entries with the following structure:
-///
-/// 1. Basic response information at INFO level:
-///    - `http.status_code`: Numeric HTTP status code
-///    - `status_text`: String representation of the status code
-///    - `duration_ms`: Request handling duration in milliseconds (if provided)
-///    - Message: "Starting streaming response"
-///
-/// 2. Headers at DEBUG level:
-///    - `http.response.headers`: Map of all headers (sensitive values redacted)
+/// Converts a duration to fractional milliseconds
+fn duration_ms(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/// Returns true for headers that must not be copied from the upstream response
 ///
-/// 3. Streaming notification at INFO level:
-///    - Message indicating that streaming is beginning, with logging status
+/// Covers hop-by-hop headers (RFC 7230 §6.1) plus Host, which is meaningless in a response.
+fn is_hop_by_hop_response_header(name: &HeaderName) -> bool {
+    name == header::CONNECTION
+        || name == header::PROXY_AUTHENTICATE
+        || name == header::PROXY_AUTHORIZATION
+        || name == header::TE
+        || name == header::TRAILER
+        || name == header::TRANSFER_ENCODING
+        || name == header::UPGRADE
+        || name == header::HOST
+}
+
+/// Prefix reserved for headers set by the proxy itself (e.g. `x-switchboard-upstream`)
+const INTERNAL_HEADER_PREFIX: &str = "x-switchboard-";
+
+/// Removes every header with the proxy-internal prefix, returning how many were removed
 ///
-/// # Use Case
+/// Header names are always lowercase in `HeaderMap`, so a plain prefix check suffices.
+fn strip_internal_headers(headers: &mut HeaderMap) -> usize {
+    let internal: Vec<HeaderName> = headers
+        .keys()
+        .filter(|name| name.as_str().starts_with(INTERNAL_HEADER_PREFIX))
+        .cloned()
+        .collect();
+    for name in &internal {
+        headers.remove(name);
+    }
+    internal.len()
+}
+
+/// Response header in which Anthropic identifies the request
+const UPSTREAM_REQUEST_ID_HEADER: &str = "request-id";
+
+/// Response header carrying the upstream's status when `map_upstream_5xx_to` replaced it
+pub const ORIGINAL_STATUS_HEADER: &str = "x-switchboard-original-status";
+
+/// Starts the client response for an upstream status, applying `map_upstream_5xx_to`
 ///
-/// Use this function instead of `log_response_details` when handling streaming responses,
-/// particularly with SSE (Server-Sent Events) or streaming APIs. It allows logging of the
-/// response metadata before the actual streaming begins, which is especially useful for
-/// timing metrics and initial response verification.
+/// A replaced status is reported in [`ORIGINAL_STATUS_HEADER`].
+fn client_response_builder(
+    upstream_status: StatusCode,
+    config: &Config,
+) -> axum::http::response::Builder {
+    let mapped = config
+        .map_upstream_5xx_to
+        .filter(|_| upstream_status.is_server_error())
+        .and_then(|status| StatusCode::from_u16(status).ok());
+    match mapped {
+        Some(status) => {
+            debug!(
+                upstream_status = upstream_status.as_u16(),
+                client_status = status.as_u16(),
+                "Reporting upstream server error under the configured status"
+            );
+            Response::builder()
+                .status(status)
+                .header(ORIGINAL_STATUS_HEADER, upstream_status.as_u16())
+        }
+        None => Response::builder().status(upstream_status),
+    }
+}
+
+/// Request header with which a client opts out of detail logging (when allowed)
+pub const LOG_OPTOUT_HEADER: &str = "x-switchboard-no-log";
+
+/// Removes the opt-out header, returning true if it asked to skip detail logging
+fn take_log_optout(headers: &mut HeaderMap) -> bool {
+    headers
+        .remove(LOG_OPTOUT_HEADER)
+        .and_then(|value| {
+            value
+                .to_str()
+                .ok()
+                .map(|value| value.eq_ignore_ascii_case("true"))
+        })
+        .unwrap_or(false)
+}
+
+/// Request header with which a client raises the log level of its request (when allowed)
+pub const LOG_LEVEL_HEADER: &str = "x-switchboard-log-level";
+
+/// Removes the log level header, returning the level it asked for if it names one
+fn take_log_level(headers: &mut HeaderMap) -> Option<LevelFilter> {
+    headers
+        .remove(LOG_LEVEL_HEADER)
+        .and_then(|value| value.to_str().ok()?.trim().parse().ok())
+}
+
+/// Name of the response header carrying proxy overhead timing
+const SERVER_TIMING_HEADER: &str = "server-timing";
+
+/// Formats a Server-Timing value for the time the proxy added on top of upstream
 ///
 /// # Arguments
-/// * `status` - The HTTP status code of the response
-/// * `headers` - The response headers map
-/// * `log_bodies` - Boolean flag indicating whether to include full body content in logs
-/// * `duration` - Optional duration of the request for timing metrics
+/// * `total` - Time since the request arrived
+/// * `upstream` - Time spent waiting on the upstream API
 ///
-/// # Examples
+/// # Returns
+/// A value like `proxy;dur=1.234` (milliseconds, per the Server-Timing spec)
+fn server_timing_value(total: Duration, upstream: Duration) -> String {
+    let overhead = total.saturating_sub(upstream);
+    format!("proxy;dur={:.3}", overhead.as_secs_f64() * 1000.0)
+}
+
+/// Caps `max_tokens` of a Messages API request body at `ceiling`, adding it if absent
 ///
-/// Basic usage:
+/// # Returns
+/// The rewritten body, or None if it already complies or is not a JSON object
+fn clamp_max_tokens(body: &[u8], ceiling: u64) -> Option<Bytes> {
+    let Ok(Value::Object(mut request)) = serde_json::from_slice::<Value>(body) else {
+        return None;
+    };
+    if request
+        .get("max_tokens")
+        .and_then(Value::as_u64)
+        .is_some_and(|max_tokens| max_tokens <= ceiling)
+    {
+        return None;
+    }
+
+    request.insert("max_tokens".to_string(), Value::from(ceiling));
+    serde_json::to_vec(&request).ok().map(Bytes::from)
+}
+
+/// Parses the fields of interest from a Messages API request body, if it is one
+fn parse_messages_request(body: &[u8]) -> Option<AnthropicMessagesRequestMinimal> {
+    serde_json::from_slice(body).ok()
+}
+
+/// Extracts the error type from an Anthropic API error response body, if it is one
+fn parse_error_type(body: &[u8]) -> Option<String> {
+    serde_json::from_slice::<AnthropicErrorResponseMinimal>(body)
+        .ok()
+        .map(|response| response.error.error_type)
+}
+
+/// Records the trace and parent IDs of a trace context on the request span
+fn record_trace_parent(span: &Span, trace_parent: &TraceParent) {
+    span.record("trace_id", trace_parent.trace_id_hex());
+    span.record("span_id", trace_parent.parent_id_hex());
+}
+
+/// Acquires a concurrency slot, recording any time spent queued as `queue_wait_ms`
+async fn acquire_concurrency_permit(
+    span: &Span,
+    limiter: &ConcurrencyLimiter,
+) -> Option<ConcurrencyPermit> {
+    let permit = limiter.acquire().await?;
+    let queue_wait = permit.queue_wait();
+    if !queue_wait.is_zero() {
+        let queue_wait_ms = duration_ms(queue_wait);
+        span.record("queue_wait_ms", queue_wait_ms);
+        info!(queue_wait_ms, "Concurrency slot acquired after queuing");
+    }
+    Some(permit)
+}
+
+/// Adds `request_id` to the body of an error response whose body is a JSON object
 ///
-/// ```
-/// use hyper::HeaderMap;
-/// use reqwest::StatusCode;
-/// use std::time::Duration;
-/// use switchboard::proxy_handler::log_response_headers;
+/// Covers both errors the proxy generates and those forwarded from the upstream.
+/// Other responses, non-JSON bodies and bodies that already carry a `request_id`
+/// are returned unchanged.
+async fn inject_request_id_into_error(response: Response, req_id: RequestId) -> Response {
+    let status = response.status();
+    if !(status.is_client_error() || status.is_server_error())
+        || !is_json_content_type(response.headers())
+    {
+        return response;
+    }
+
+    // Error bodies are small and already buffered, whether generated or forwarded
+    let (mut parts, body) = response.into_parts();
+    let bytes = match hyper::body::to_bytes(body).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            warn!(error = %e, "Failed to read error body to add the request ID");
+            return Response::from_parts(parts, boxed(Empty::new()));
+        }
+    };
+
+    let Ok(Value::Object(mut fields)) = serde_json::from_slice::<Value>(&bytes) else {
+        return Response::from_parts(parts, boxed(Full::from(bytes)));
+    };
+    if fields.contains_key("request_id") {
+        return Response::from_parts(parts, boxed(Full::from(bytes)));
+    }
+    fields.insert("request_id".to_string(), Value::from(req_id.to_string()));
+    let body = Value::Object(fields).to_string();
+    parts
+        .headers
+        .insert(header::CONTENT_LENGTH, HeaderValue::from(body.len()));
+    Response::from_parts(parts, boxed(Full::from(body)))
+}
+
+/// Returns true if a message's `Content-Type` is JSON (`application/json` or a `+json` type)
+fn is_json_content_type(headers: &HeaderMap) -> bool {
+    let Some(content_type) = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+    else {
+        return false;
+    };
+    let essence = content_type
+        .split(';')
+        .next()
+        .unwrap_or("")
+        .trim()
+        .to_ascii_lowercase();
+    essence == "application/json" || essence.ends_with("+json")
+}
+
+/// Reasons the upstream URL for a request cannot be built
+#[derive(Debug, Error)]
+enum TargetUrlError {
+    /// The joined URL is not a valid URI
+    #[error("invalid target URL: {0}")]
+    Invalid(#[from] InvalidUri),
+    /// The URL has no host to connect to (e.g. `https://`)
+    #[error("target URL has no host")]
+    MissingHost,
+}
+
+/// Joins the configured upstream base URL with a request's path and query
 ///
-/// // Create streaming response components
-/// let status = StatusCode::OK;
-/// let headers = HeaderMap::new();
-/// let duration = Duration::from_millis(120);
+/// `Config::from_env_map` already rejects a base URL without a host; checking again
+/// here keeps a request from ever being sent without a Host header.
+fn build_target_url(base: &str, path_and_query: &str) -> Result<Uri, TargetUrlError> {
+    let uri = format!("{}{}", base, path_and_query).parse::<Uri>()?;
+    if uri.host().is_none_or(str::is_empty) {
+        return Err(TargetUrlError::MissingHost);
+    }
+    Ok(uri)
+}
+
+/// Delay before the first retry of a retryable upstream status, doubling per retry
+const UPSTREAM_RETRY_BASE_DELAY: Duration = Duration::from_millis(100);
+
+/// Longest delay between retries of a retryable upstream status
+const UPSTREAM_RETRY_MAX_DELAY: Duration = Duration::from_secs(2);
+
+/// Outcome of one upstream attempt that may be retried
+enum UpstreamAttemptError {
+    /// The request could not be sent or no response arrived; not retried
+    Send(reqwest::Error),
+    /// The upstream answered with a status in `retryable_statuses`
+    RetryableStatus(reqwest::Response),
+}
+
+impl fmt::Display for UpstreamAttemptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Send(e) => e.fmt(f),
+            Self::RetryableStatus(response) => {
+                write!(f, "upstream answered {}", response.status())
+            }
+        }
+    }
+}
+
+/// Methods that are safe to send twice, so may be retried
+const IDEMPOTENT_METHODS: [Method; 5] = [
+    Method::GET,
+    Method::HEAD,
+    Method::PUT,
+    Method::DELETE,
+    Method::OPTIONS,
+];
+
+/// Sends the upstream request, resending it after statuses in `retryable_statuses`
 ///
-/// // Log streaming response headers with timing
-/// log_response_headers(&status, &headers, true, Some(duration));
+/// Only idempotent requests are sent again: resending a POST could create (and bill)
+/// a second message. Requests with a streamed body get one attempt too, as the body
+/// cannot be replayed. Once the retries are used up, the last response is returned
+/// to be relayed.
+async fn send_upstream(
+    builder: reqwest::RequestBuilder,
+    method: &Method,
+    config: &Config,
+) -> reqwest::Result<reqwest::Response> {
+    if config.upstream_retries == 0
+        || !IDEMPOTENT_METHODS.contains(method)
+        || builder.try_clone().is_none()
+    {
+        return builder.send().await;
+    }
+
+    let policy = RetryPolicy::new(
+        config.upstream_retries,
+        Backoff::new(UPSTREAM_RETRY_BASE_DELAY, UPSTREAM_RETRY_MAX_DELAY).with_jitter(),
+    );
+    let retryable_statuses = &config.retryable_statuses;
+    let result = retry_async(
+        &policy,
+        |e| matches!(e, UpstreamAttemptError::RetryableStatus(_)),
+        || {
+            // Checked above: a buffered body can always be cloned
+            let request = builder
+                .try_clone()
+                .expect("request with a buffered body should be cloneable");
+            async move {
+                let response = request.send().await.map_err(UpstreamAttemptError::Send)?;
+                if retryable_statuses.contains(&response.status().as_u16()) {
+                    Err(UpstreamAttemptError::RetryableStatus(response))
+                } else {
+                    Ok(response)
+                }
+            }
+        },
+    )
+    .await;
+
+    match result {
+        Ok(response) | Err(UpstreamAttemptError::RetryableStatus(response)) => Ok(response),
+        Err(UpstreamAttemptError::Send(e)) => Err(e),
+    }
+}
+
+/// Methods the proxy forwards, advertised in the Allow header of a 405
+const ALLOWED_METHODS: &str = "GET, HEAD, POST, PUT, PATCH, DELETE, OPTIONS";
+
+/// Builds the client response for a cache hit, without contacting the upstream
+fn cached_response(span: &Span, cached: CachedResponse, start: Instant) -> Response {
+    span.record("http.status_code", cached.status.as_u16());
+
+    let mut response_builder = Response::builder().status(cached.status);
+    for (name, value) in cached.headers.iter() {
+        if !is_hop_by_hop_response_header(name) {
+            response_builder = response_builder.header(name.clone(), value.clone());
+        }
+    }
+    let response = response_builder
+        .header(header::CONTENT_LENGTH, cached.body.len().to_string())
+        .header(CACHE_STATUS_HEADER, "HIT")
+        .body(boxed(Full::from(cached.body)))
+        // The headers were valid when received from upstream, so rebuilding cannot fail
+        .expect("cached response should always build");
+
+    let duration = start.elapsed();
+    span.record("duration_ms", duration.as_millis());
+    info!(
+        status = %cached.status,
+        duration_ms = %duration.as_millis(),
+        "Served response from cache"
+    );
+    response
+}
+
+/// Logs a dry-run request and builds the canned response returned instead of forwarding it
+fn dry_run_response(span: &Span, target_url: &Uri) -> Response {
+    info!(
+        would_forward_to = %target_url,
+        "Dry-run mode, returning canned response instead of forwarding"
+    );
+    span.record("http.status_code", StatusCode::OK.as_u16());
+
+    let body = serde_json::json!({
+        "dry_run": true,
+        "would_forward_to": target_url.to_string(),
+    });
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(boxed(Full::from(body.to_string())))
+        // Static status and header values cannot fail to build
+        .expect("dry-run response should always build")
+}
+
+/// Logs a maintenance rejection and builds the 503 response asking the client to retry later
+fn reject_during_maintenance(span: &Span, retry_after_secs: u64) -> Response {
+    info!(retry_after_secs, "Maintenance mode on, rejecting request");
+    span.record("http.status_code", StatusCode::SERVICE_UNAVAILABLE.as_u16());
+
+    let body = serde_json::json!({ "error": "maintenance" });
+    Response::builder()
+        .status(StatusCode::SERVICE_UNAVAILABLE)
+        .header(header::CONTENT_TYPE, "application/json")
+        .header(header::RETRY_AFTER, retry_after_secs.to_string())
+        .body(boxed(Full::from(body.to_string())))
+        // A numeric Retry-After and static values cannot fail to build
+        .expect("maintenance response should always build")
+}
+
+/// Logs a CONNECT rejection and builds the 405 response for it
+fn reject_connect(span: &Span, uri: &Uri) -> Response {
+    warn!(target_uri = %uri, "CONNECT is not supported, rejecting request");
+    span.record("http.method", Method::CONNECT.as_str());
+    span.record("http.status_code", StatusCode::METHOD_NOT_ALLOWED.as_u16());
+
+    let body = serde_json::json!({
+        "error": "CONNECT is not supported: this proxy only forwards Anthropic API requests"
+    });
+    Response::builder()
+        .status(StatusCode::METHOD_NOT_ALLOWED)
+        .header(header::ALLOW, ALLOWED_METHODS)
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(boxed(Full::from(body.to_string())))
+        // Static status and header values cannot fail to build
+        .expect("CONNECT rejection response should always build")
+}
+
+/// Returns the values of the `required` headers for logging, or the name of the first one missing
 ///
-/// // Begin streaming chunks...
-/// ```
-pub fn log_response_headers(
-    status: &reqwest::StatusCode,
+/// Values that are not valid UTF-8 are converted lossily, as they are only logged,
+/// and control characters are escaped when `sanitize` is set.
+fn required_header_values<'a>(
     headers: &HeaderMap,
-    log_bodies: bool,
-    duration: Option<std::time::Duration>,
-) {
-    // Create a new span for the streaming response details
-    let span = info_span!("streaming_response_details");
-    let _enter = span.enter();
+    required: &'a [String],
+    sanitize: bool,
+) -> Result<BTreeMap<&'a str, String>, &'a str> {
+    required
+        .iter()
+        .map(|name| {
+            let value = headers.get(name.as_str()).ok_or(name.as_str())?;
+            let value = String::from_utf8_lossy(value.as_bytes());
+            let value = if sanitize {
+                sanitize_for_log(&value).into_owned()
+            } else {
+                value.into_owned()
+            };
+            Ok((name.as_str(), value))
+        })
+        .collect()
+}
 
-    // Log that streaming is starting, with timing if available
-    if let Some(dur) = duration {
-        info!(
-            http.status_code = %status.as_u16(),
-            status_text = %status.canonical_reason().unwrap_or("Unknown"),
-            duration_ms = %dur.as_millis(),
-            "Starting streaming response"
-        );
-    } else {
-        info!(
-            http.status_code = %status.as_u16(),
-            status_text = %status.canonical_reason().unwrap_or("Unknown"),
-            "Starting streaming response"
-        );
-    }
+/// Logs a request missing a required header and builds the 400 response for it
+fn reject_missing_required_header(span: &Span, missing: &str) -> Response {
+    warn!(
+        missing_header = %missing,
+        "Request is missing a required header, rejecting request"
+    );
+    span.record("http.status_code", StatusCode::BAD_REQUEST.as_u16());
+
+    let body = serde_json::json!({
+        "error": format!("Missing required header: {}", missing),
+        "missing_header": missing
+    });
+    Response::builder()
+        .status(StatusCode::BAD_REQUEST)
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(boxed(Full::from(body.to_string())))
+        // Static status and header values cannot fail to build
+        .expect("missing header rejection response should always build")
+}
 
-    // Build a map of header names to values, masking sensitive headers
-    let mut headers_log: HashMap<String, String> = HashMap::new();
-    for (name, value) in headers.iter() {
-        let name_str = name.to_string();
-        // Mask sensitive authentication headers
-        let value_str = if name == header::AUTHORIZATION || name == "x-api-key" {
-            "[REDACTED]".to_string()
-        } else {
-            // Convert header value to string (lossy UTF-8 conversion if needed)
-            String::from_utf8_lossy(value.as_bytes()).to_string()
+/// Logs a CORS preflight and builds the local response to it
+fn answer_cors_preflight(span: &Span, config: &Config, origin: Option<&HeaderValue>) -> Response {
+    info!(
+        origin_allowed = origin.is_some(),
+        "Answered CORS preflight without contacting upstream"
+    );
+    span.record("http.method", Method::OPTIONS.as_str());
+    span.record("http.status_code", StatusCode::OK.as_u16());
+    cors::preflight_response(config, origin)
+}
+
+/// Logs a refused overlong request URL and builds the 414 response for it
+fn reject_overlong_path(span: &Span, length: usize, max_length: usize) -> Response {
+    warn!(
+        path_length = length,
+        max_path_length = max_length,
+        "Request path exceeds the maximum length, rejecting request"
+    );
+    span.record("http.status_code", StatusCode::URI_TOO_LONG.as_u16());
+
+    let body = serde_json::json!({
+        "error": format!("Request path and query exceed {} bytes", max_length)
+    });
+    Response::builder()
+        .status(StatusCode::URI_TOO_LONG)
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(boxed(Full::from(body.to_string())))
+        // Static status and header values cannot fail to build
+        .expect("overlong path rejection response should always build")
+}
+
+/// Logs a refused WebSocket upgrade and builds the 501 response for it
+fn reject_websocket_upgrade(span: &Span) -> Response {
+    warn!("WebSocket upgrade requested but ALLOW_WEBSOCKET is disabled, rejecting request");
+    span.record("http.status_code", StatusCode::NOT_IMPLEMENTED.as_u16());
+
+    let body = serde_json::json!({
+        "error": "WebSocket upgrades are not enabled on this proxy"
+    });
+    Response::builder()
+        .status(StatusCode::NOT_IMPLEMENTED)
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(boxed(Full::from(body.to_string())))
+        // Static status and header values cannot fail to build
+        .expect("WebSocket rejection response should always build")
+}
+
+/// Forwards a WebSocket handshake upstream with the proxy's credentials and relays it
+///
+/// `route_path` is the client's request path, which selects the API key. The open
+/// tunnel keeps `permit`, so it counts against `MAX_CONCURRENT_REQUESTS` until it closes.
+///
+/// # Returns
+/// The upstream's handshake response, or 502 if the upstream could not be reached
+async fn open_websocket_tunnel(
+    req: Request<Body>,
+    target_url: &Uri,
+    route_path: &str,
+    mut headers: HeaderMap,
+    config: &Config,
+    span: &Span,
+    permit: ConcurrencyPermit,
+) -> Result<Response, StatusCode> {
+    if let Some(host) = target_url.host() {
+        if let Ok(host_value) = HeaderValue::from_str(host) {
+            headers.insert(header::HOST, host_value);
+        }
+    }
+    if config.auth_mode != AuthMode::Passthrough {
+        let Ok(api_key_value) = config.api_key_header_value(route_path) else {
+            error!("Failed to create header value for Anthropic API key");
+            span.record(
+                "http.status_code",
+                StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+            );
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
         };
-        headers_log.insert(name_str, value_str);
+        headers.insert(HeaderName::from_static("x-api-key"), api_key_value);
+        headers.remove(header::AUTHORIZATION);
     }
 
-    // Log all headers at debug level (won't show in normal operation)
-    debug!(http.response.headers = ?headers_log);
-
-    // Log a message indicating that we're about to start streaming
-    if log_bodies {
-        info!("Headers logged, beginning to stream response body (full logging enabled)");
-    } else {
-        info!("Headers logged, beginning to stream response body (content logging disabled)");
+    info!("Forwarding WebSocket handshake to upstream");
+    match websocket::tunnel(req, target_url, headers, span.clone(), permit).await {
+        Ok(response) => {
+            span.record("http.status_code", response.status().as_u16());
+            Ok(response)
+        }
+        Err(e) => {
+            error!(error = %e, "Failed to open WebSocket tunnel");
+            span.record("http.status_code", StatusCode::BAD_GATEWAY.as_u16());
+            Err(StatusCode::BAD_GATEWAY)
+        }
     }
 }
+
+/// Logs a request to a host outside the upstream allowlist and builds the 403 response for it
+fn reject_disallowed_upstream(span: &Span, host: &str) -> Response {
+    warn!(
+        upstream.host = %host,
+        "Upstream host is not in the allowlist, rejecting request"
+    );
+    span.record("http.status_code", StatusCode::FORBIDDEN.as_u16());
+
+    let body = serde_json::json!({
+        "error": "Upstream host is not allowed"
+    });
+    Response::builder()
+        .status(StatusCode::FORBIDDEN)
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(boxed(Full::from(body.to_string())))
+        // Static status and header values cannot fail to build
+        .expect("upstream allowlist rejection response should always build")
+}
+
+/// Logs a concurrency limit rejection and builds the 503 response for it
+fn reject_over_concurrency_limit(span: &Span) -> Response {
+    warn!("Concurrency limit reached, rejecting request");
+    span.record("http.status_code", StatusCode::SERVICE_UNAVAILABLE.as_u16());
+    concurrency_limit_response()
+}
+
+/// Logs a stream limit rejection and builds the 503 response for it
+fn reject_over_stream_limit(span: &Span) -> Response {
+    warn!("Stream concurrency limit reached, rejecting streaming response");
+    span.record("http.status_code", StatusCode::SERVICE_UNAVAILABLE.as_u16());
+    concurrency_limit_response()
+}
+
+/// Logs a buffering budget rejection and builds the 503 response for it
+fn reject_over_budget(span: &Span, requested_bytes: u64, budget: &MemoryBudget) -> Response {
+    warn!(
+        requested_bytes,
+        in_use_bytes = budget.in_use(),
+        "Buffered body budget exhausted, rejecting request"
+    );
+    span.record("http.status_code", StatusCode::SERVICE_UNAVAILABLE.as_u16());
+    budget_exceeded_response()
+}
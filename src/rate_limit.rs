@@ -0,0 +1,183 @@
+//! Per-model request rate limiting
+//!
+//! Upstream rate limits differ per model, so requests are limited separately for
+//! each configured model using token buckets. Each bucket holds up to one minute's
+//! allowance and refills continuously, allowing short bursts while enforcing the
+//! per-minute rate over time.
+//!
+//! Key features:
+//! - Models without a configured limit are never limited
+//! - An empty bucket yields how long until the next request would be allowed
+//! - Limits apply per proxy instance; they are not shared across replicas
+
+use axum::{
+    body::{boxed, Empty},
+    http::StatusCode,
+    response::Response,
+};
+use hyper::header;
+use std::collections::HashMap;
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+/// Token buckets for every model with a configured limit
+#[derive(Debug)]
+pub struct ModelRateLimiter {
+    buckets: HashMap<String, Mutex<TokenBucket>>,
+}
+
+/// A bucket refilled continuously at a fixed per-minute rate
+#[derive(Debug)]
+struct TokenBucket {
+    /// Maximum tokens held (one minute's allowance)
+    capacity: f64,
+    /// Tokens currently available
+    tokens: f64,
+    /// Tokens added per second
+    refill_per_sec: f64,
+    /// When tokens were last added
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    /// Creates a full bucket allowing `per_minute` requests per minute
+    fn new(per_minute: u32, now: Instant) -> Self {
+        let capacity = f64::from(per_minute);
+        Self {
+            capacity,
+            tokens: capacity,
+            refill_per_sec: capacity / 60.0,
+            last_refill: now,
+        }
+    }
+
+    /// Takes one token, or returns how long until one becomes available
+    fn try_take(&mut self, now: Instant) -> Result<(), Duration> {
+        let elapsed = now.saturating_duration_since(self.last_refill);
+        self.tokens =
+            (self.tokens + elapsed.as_secs_f64() * self.refill_per_sec).min(self.capacity);
+        self.last_refill = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            return Ok(());
+        }
+
+        // A zero limit never refills, so the best advice is to wait a full minute
+        if self.refill_per_sec <= 0.0 {
+            return Err(Duration::from_secs(60));
+        }
+        Err(Duration::from_secs_f64(
+            (1.0 - self.tokens) / self.refill_per_sec,
+        ))
+    }
+}
+
+impl ModelRateLimiter {
+    /// Creates a limiter with a full bucket per model
+    ///
+    /// # Arguments
+    /// * `limits` - Requests allowed per minute, keyed by model name
+    pub fn new(limits: &HashMap<String, u32>) -> Self {
+        let now = Instant::now();
+        Self {
+            buckets: limits
+                .iter()
+                .map(|(model, &per_minute)| {
+                    (model.clone(), Mutex::new(TokenBucket::new(per_minute, now)))
+                })
+                .collect(),
+        }
+    }
+
+    /// Returns true if no model has a limit (so requests need not be inspected)
+    pub fn is_empty(&self) -> bool {
+        self.buckets.is_empty()
+    }
+
+    /// Admits one request for `model`
+    ///
+    /// # Returns
+    /// Ok if the request may proceed (always for unlimited models), or the time
+    /// until the model's bucket has a token again
+    pub fn try_acquire(&self, model: &str) -> Result<(), Duration> {
+        self.try_acquire_at(model, Instant::now())
+    }
+
+    fn try_acquire_at(&self, model: &str, now: Instant) -> Result<(), Duration> {
+        match self.buckets.get(model) {
+            Some(bucket) => bucket
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .try_take(now),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Builds the 429 response returned when a model's bucket is empty
+///
+/// Retry-After is rounded up to whole seconds (at least 1), as the header requires.
+pub fn rate_limited_response(retry_after: Duration) -> Response {
+    let retry_after_secs = retry_after.as_secs_f64().ceil().max(1.0) as u64;
+    Response::builder()
+        .status(StatusCode::TOO_MANY_REQUESTS)
+        .header(header::RETRY_AFTER, retry_after_secs.to_string())
+        .body(boxed(Empty::new()))
+        // Static status and header values cannot fail to build
+        .expect("rate limited response should always build")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limiter(limits: &[(&str, u32)]) -> ModelRateLimiter {
+        ModelRateLimiter::new(
+            &limits
+                .iter()
+                .map(|(model, limit)| (model.to_string(), *limit))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_bucket_empties_then_refills() {
+        let limiter = limiter(&[("claude-3-opus", 2)]);
+        let start = Instant::now();
+
+        assert!(limiter.try_acquire_at("claude-3-opus", start).is_ok());
+        assert!(limiter.try_acquire_at("claude-3-opus", start).is_ok());
+        let retry_after = limiter
+            .try_acquire_at("claude-3-opus", start)
+            .expect_err("Bucket should be empty");
+        // Two per minute refills one token every 30 seconds
+        assert!(retry_after <= Duration::from_secs(30) && retry_after > Duration::from_secs(29));
+
+        assert!(limiter
+            .try_acquire_at("claude-3-opus", start + Duration::from_secs(30))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_models_are_limited_independently() {
+        let limiter = limiter(&[("claude-3-opus", 1), ("claude-3-haiku", 1)]);
+
+        assert!(limiter.try_acquire("claude-3-opus").is_ok());
+        assert!(limiter.try_acquire("claude-3-opus").is_err());
+        assert!(limiter.try_acquire("claude-3-haiku").is_ok());
+
+        // Unknown models are unlimited
+        for _ in 0..100 {
+            assert!(limiter.try_acquire("claude-3-sonnet").is_ok());
+        }
+    }
+
+    #[test]
+    fn test_rate_limited_response_rounds_retry_after_up() {
+        let response = rate_limited_response(Duration::from_millis(1500));
+
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "2");
+    }
+}
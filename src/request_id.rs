@@ -0,0 +1,83 @@
+//! Per-request IDs correlating every log line of a request
+//!
+//! IDs are full UUID v4s by default. The short format is an 11-character base62
+//! encoding of 64 random bits: far less verbose in logs and still unique enough
+//! to tell the requests of any realistic log window apart.
+
+use rand::Rng;
+use std::fmt;
+use uuid::Uuid;
+
+use crate::config::RequestIdFormat;
+
+/// Length of a short request ID; 11 base62 digits cover every 64-bit value
+pub const SHORT_ID_LEN: usize = 11;
+
+/// Digits of the base62 alphabet, in value order
+const BASE62_DIGITS: &[u8; 62] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
+/// The ID of one proxied request, displayed in the configured format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RequestId {
+    /// A random UUID v4, displayed hyphenated
+    Uuid(Uuid),
+    /// Base62 digits of a random 64-bit value, zero-padded
+    Short([u8; SHORT_ID_LEN]),
+}
+
+impl RequestId {
+    /// Generates a fresh random ID in the given format
+    pub fn generate(format: RequestIdFormat) -> Self {
+        match format {
+            RequestIdFormat::Uuid => RequestId::Uuid(Uuid::new_v4()),
+            RequestIdFormat::Short => RequestId::Short(base62(rand::thread_rng().gen())),
+        }
+    }
+}
+
+impl fmt::Display for RequestId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RequestId::Uuid(uuid) => uuid.fmt(f),
+            // Only ASCII digits from the alphabet are ever stored
+            RequestId::Short(digits) => {
+                f.write_str(std::str::from_utf8(digits).map_err(|_| fmt::Error)?)
+            }
+        }
+    }
+}
+
+/// Encodes `value` as zero-padded base62, most significant digit first
+fn base62(mut value: u64) -> [u8; SHORT_ID_LEN] {
+    let mut digits = [BASE62_DIGITS[0]; SHORT_ID_LEN];
+    for digit in digits.iter_mut().rev() {
+        *digit = BASE62_DIGITS[(value % 62) as usize];
+        value /= 62;
+    }
+    digits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_short_ids_are_base62_of_fixed_length() {
+        for _ in 0..100 {
+            let id = RequestId::generate(RequestIdFormat::Short).to_string();
+            assert_eq!(id.len(), SHORT_ID_LEN);
+            assert!(id.chars().all(|c| c.is_ascii_alphanumeric()), "{}", id);
+        }
+
+        assert_eq!(base62(0), *b"00000000000");
+        assert_eq!(base62(61), *b"0000000000z");
+        assert_eq!(base62(u64::MAX), *b"LygHa16AHYF");
+    }
+
+    #[test]
+    fn test_uuid_ids_are_valid_uuids() {
+        let id = RequestId::generate(RequestIdFormat::Uuid).to_string();
+        let parsed = Uuid::parse_str(&id).expect("Default request IDs should be UUIDs");
+        assert_eq!(parsed.get_version_num(), 4);
+    }
+}
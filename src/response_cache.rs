@@ -0,0 +1,279 @@
+//! Cache of upstream responses to deterministic Messages API requests
+//!
+//! A `POST /v1/messages` request with `temperature` 0 and no streaming yields the
+//! same response for the same body, so repeating it upstream only costs money.
+//! Such responses are cached for a configurable TTL, keyed by a hash of the
+//! normalized request body (JSON keys sorted, whitespace removed) together with
+//! the path and the headers that select API behaviour.
+//!
+//! Key features:
+//! - Disabled unless a TTL is configured
+//! - Non-deterministic or streaming requests always bypass the cache
+//! - Only successful (200) responses are stored
+//! - Responses carry `x-switchboard-cache: HIT` or `MISS` while caching is enabled
+//! - When clients bring their own credentials, they are part of the key, so one
+//!   client's response is never served to another
+
+use bytes::Bytes;
+use hyper::{HeaderMap, Method, StatusCode};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+/// Response header reporting whether a cacheable request was served from the cache
+pub const CACHE_STATUS_HEADER: &str = "x-switchboard-cache";
+
+/// Maximum number of cached responses held at once
+///
+/// When full, expired entries are dropped first; if none have expired, new
+/// responses are simply not cached until space frees up.
+pub const RESPONSE_CACHE_MAX_ENTRIES: usize = 1024;
+
+/// Path of the only endpoint whose responses are cached
+const CACHEABLE_PATH: &str = "/v1/messages";
+
+/// Request headers that change the upstream response and so are part of the key
+const KEYED_HEADERS: [&str; 2] = ["anthropic-version", "anthropic-beta"];
+
+/// Request headers carrying client credentials, keyed when they are forwarded upstream
+const CREDENTIAL_HEADERS: [&str; 2] = ["x-api-key", "authorization"];
+
+/// Cached upstream responses shared across requests
+#[derive(Debug)]
+pub struct ResponseCache {
+    /// How long responses stay valid (None = caching disabled)
+    ttl: Option<Duration>,
+    /// Cached responses by request key
+    entries: Mutex<HashMap<String, CachedResponse>>,
+    /// Whether the client's credential headers are part of the key
+    key_credentials: bool,
+}
+
+/// An upstream response as stored in the cache
+#[derive(Debug, Clone)]
+pub struct CachedResponse {
+    /// Status of the upstream response
+    pub status: StatusCode,
+    /// Headers of the upstream response
+    pub headers: HeaderMap,
+    /// Full body of the upstream response
+    pub body: Bytes,
+    /// When the response was stored
+    stored_at: Instant,
+}
+
+impl ResponseCache {
+    /// Creates an empty cache whose entries expire after `ttl` (None = disabled)
+    pub fn new(ttl: Option<Duration>) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+            key_credentials: false,
+        }
+    }
+
+    /// Makes the client's `x-api-key`/`Authorization` part of every key
+    ///
+    /// Needed whenever client credentials reach the upstream (passthrough auth):
+    /// otherwise a client without a valid key would be served responses paid for,
+    /// and requested by, another client.
+    pub fn with_credentials_in_key(mut self, key_credentials: bool) -> Self {
+        self.key_credentials = key_credentials;
+        self
+    }
+
+    /// Returns true if responses are cached at all
+    pub fn is_enabled(&self) -> bool {
+        self.ttl.is_some()
+    }
+
+    /// Computes the cache key for a request, or None if it must bypass the cache
+    ///
+    /// Only deterministic requests are cacheable: `POST /v1/messages` with a JSON
+    /// body whose `temperature` is 0 and whose `stream` is not true.
+    pub fn key_for(
+        &self,
+        method: &Method,
+        path: &str,
+        headers: &HeaderMap,
+        body: &[u8],
+    ) -> Option<String> {
+        if !self.is_enabled() || method != Method::POST || path != CACHEABLE_PATH {
+            return None;
+        }
+
+        let request: Value = serde_json::from_slice(body).ok()?;
+        if !is_deterministic(&request) {
+            return None;
+        }
+
+        // serde_json orders object keys, so serializing normalizes key order and spacing
+        let mut keyed = format!("{}\n{}", path, request);
+        for name in KEYED_HEADERS {
+            for value in headers.get_all(name) {
+                keyed.push_str(&format!("\n{}: {}", name, value.to_str().unwrap_or("")));
+            }
+        }
+        // Only the digest below is stored, never the credentials themselves
+        if self.key_credentials {
+            for name in CREDENTIAL_HEADERS {
+                for value in headers.get_all(name) {
+                    keyed.push_str(&format!("\n{}: {}", name, value.to_str().unwrap_or("")));
+                }
+            }
+        }
+
+        let digest = ring::digest::digest(&ring::digest::SHA256, keyed.as_bytes());
+        Some(
+            digest
+                .as_ref()
+                .iter()
+                .map(|byte| format!("{:02x}", byte))
+                .collect(),
+        )
+    }
+
+    /// Returns the cached response for `key` if it has not expired
+    pub fn get(&self, key: &str) -> Option<CachedResponse> {
+        let ttl = self.ttl?;
+        let entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+        entries
+            .get(key)
+            .filter(|entry| entry.stored_at.elapsed() < ttl)
+            .cloned()
+    }
+
+    /// Stores a successful response under `key`; other statuses are never cached
+    ///
+    /// # Returns
+    /// True if the response was stored
+    pub fn insert(&self, key: String, status: StatusCode, headers: HeaderMap, body: Bytes) -> bool {
+        let Some(ttl) = self.ttl else {
+            return false;
+        };
+        if status != StatusCode::OK {
+            return false;
+        }
+
+        let mut entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+        if entries.len() >= RESPONSE_CACHE_MAX_ENTRIES && !entries.contains_key(&key) {
+            entries.retain(|_, entry| entry.stored_at.elapsed() < ttl);
+            if entries.len() >= RESPONSE_CACHE_MAX_ENTRIES {
+                return false;
+            }
+        }
+
+        entries.insert(
+            key,
+            CachedResponse {
+                status,
+                headers,
+                body,
+                stored_at: Instant::now(),
+            },
+        );
+        true
+    }
+}
+
+/// Returns true if a Messages request has temperature 0 and does not stream
+fn is_deterministic(request: &Value) -> bool {
+    let zero_temperature = request
+        .get("temperature")
+        .and_then(Value::as_f64)
+        .is_some_and(|temperature| temperature == 0.0);
+    let streaming = request.get("stream").and_then(Value::as_bool) == Some(true);
+    zero_temperature && !streaming
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn key(cache: &ResponseCache, body: Value) -> Option<String> {
+        cache.key_for(
+            &Method::POST,
+            CACHEABLE_PATH,
+            &HeaderMap::new(),
+            body.to_string().as_bytes(),
+        )
+    }
+
+    #[test]
+    fn test_only_deterministic_requests_are_keyed() {
+        let cache = ResponseCache::new(Some(Duration::from_secs(60)));
+
+        assert!(key(&cache, json!({"model": "m", "temperature": 0})).is_some());
+        assert!(key(&cache, json!({"model": "m", "temperature": 0.0})).is_some());
+        assert!(key(&cache, json!({"model": "m", "temperature": 0.7})).is_none());
+        assert!(key(&cache, json!({"model": "m"})).is_none());
+        assert!(key(&cache, json!({"temperature": 0, "stream": true})).is_none());
+
+        // Disabled caches never key anything
+        let disabled = ResponseCache::new(None);
+        assert!(key(&disabled, json!({"temperature": 0})).is_none());
+    }
+
+    #[test]
+    fn test_key_ignores_key_order_and_whitespace() {
+        let cache = ResponseCache::new(Some(Duration::from_secs(60)));
+        let compact = br#"{"model":"m","temperature":0}"#;
+        let spaced = br#"{ "temperature": 0,  "model": "m" }"#;
+        let headers = HeaderMap::new();
+
+        assert_eq!(
+            cache.key_for(&Method::POST, CACHEABLE_PATH, &headers, compact),
+            cache.key_for(&Method::POST, CACHEABLE_PATH, &headers, spaced)
+        );
+    }
+
+    #[test]
+    fn test_credentials_keyed_only_when_enabled() {
+        let body = br#"{"model":"m","temperature":0}"#;
+        let with_key = |api_key: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert("x-api-key", api_key.parse().unwrap());
+            headers
+        };
+
+        let shared = ResponseCache::new(Some(Duration::from_secs(60)));
+        assert_eq!(
+            shared.key_for(&Method::POST, CACHEABLE_PATH, &with_key("a"), body),
+            shared.key_for(&Method::POST, CACHEABLE_PATH, &with_key("b"), body)
+        );
+
+        let per_client =
+            ResponseCache::new(Some(Duration::from_secs(60))).with_credentials_in_key(true);
+        assert_ne!(
+            per_client.key_for(&Method::POST, CACHEABLE_PATH, &with_key("a"), body),
+            per_client.key_for(&Method::POST, CACHEABLE_PATH, &with_key("b"), body)
+        );
+        assert_ne!(
+            per_client.key_for(&Method::POST, CACHEABLE_PATH, &with_key("a"), body),
+            per_client.key_for(&Method::POST, CACHEABLE_PATH, &HeaderMap::new(), body)
+        );
+    }
+
+    #[test]
+    fn test_entries_expire_and_errors_are_not_stored() {
+        let cache = ResponseCache::new(Some(Duration::ZERO));
+        assert!(cache.insert(
+            "k".to_string(),
+            StatusCode::OK,
+            HeaderMap::new(),
+            Bytes::from_static(b"{}")
+        ));
+        assert!(cache.get("k").is_none());
+
+        let cache = ResponseCache::new(Some(Duration::from_secs(60)));
+        assert!(!cache.insert(
+            "k".to_string(),
+            StatusCode::TOO_MANY_REQUESTS,
+            HeaderMap::new(),
+            Bytes::new()
+        ));
+        assert!(cache.get("k").is_none());
+    }
+}
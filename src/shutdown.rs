@@ -0,0 +1,133 @@
+//! Coordination of work that must finish before the process exits
+//!
+//! Subsystems holding data in memory register a flush hook. Once the server has
+//! stopped accepting requests, `main` calls `Shutdown::flush`, which runs every hook
+//! so a SIGTERM does not lose that data.
+//!
+//! Key features:
+//! - Hooks run once, in registration order; flushing again is a no-op
+//! - Each hook is bounded by a timeout, so a stuck hook cannot block exit
+//! - Registration is thread-safe, so hooks can be added from anywhere
+
+use futures_util::future::BoxFuture;
+use std::future::Future;
+use std::sync::{Mutex, PoisonError};
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+/// How long a single flush hook may run before it is abandoned
+pub const SHUTDOWN_FLUSH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A named hook producing the flush work
+type FlushHook = (String, Box<dyn FnOnce() -> BoxFuture<'static, ()> + Send>);
+
+/// Flush hooks to run once the server has stopped
+pub struct Shutdown {
+    /// Upper bound on each hook's run time
+    timeout: Duration,
+    /// Hooks not yet run
+    hooks: Mutex<Vec<FlushHook>>,
+}
+
+impl Shutdown {
+    /// Creates a coordinator whose hooks may each run for up to `timeout`
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            timeout,
+            hooks: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Registers a hook to run during `flush`
+    ///
+    /// # Arguments
+    /// * `name` - Identifies the subsystem in shutdown logs
+    /// * `hook` - Produces the flush work; called at most once
+    pub fn register<F, Fut>(&self, name: &str, hook: F)
+    where
+        F: FnOnce() -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.hooks
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push((name.to_string(), Box::new(move || Box::pin(hook()))));
+    }
+
+    /// Runs every registered hook, each bounded by the timeout
+    ///
+    /// # Returns
+    /// The number of hooks that completed in time
+    pub async fn flush(&self) -> usize {
+        // Take the hooks so they run once even if flush is called again
+        let hooks = std::mem::take(&mut *self.hooks.lock().unwrap_or_else(PoisonError::into_inner));
+        let total = hooks.len();
+        info!(hooks = total, "Flushing subsystems before exit");
+
+        let mut completed = 0;
+        for (name, hook) in hooks {
+            match tokio::time::timeout(self.timeout, hook()).await {
+                Ok(()) => {
+                    debug!(hook = %name, "Shutdown flush hook completed");
+                    completed += 1;
+                }
+                Err(_) => warn!(
+                    hook = %name,
+                    timeout_ms = self.timeout.as_millis() as u64,
+                    "Shutdown flush hook timed out, abandoning it"
+                ),
+            }
+        }
+
+        info!(completed, total, "Shutdown flush finished");
+        completed
+    }
+}
+
+impl Default for Shutdown {
+    fn default() -> Self {
+        Self::new(SHUTDOWN_FLUSH_TIMEOUT)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_registered_hooks_run_once() {
+        let shutdown = Shutdown::default();
+        let runs = Arc::new(AtomicUsize::new(0));
+
+        for name in ["metrics", "capture"] {
+            let runs = Arc::clone(&runs);
+            shutdown.register(name, move || async move {
+                runs.fetch_add(1, Ordering::SeqCst);
+            });
+        }
+
+        assert_eq!(shutdown.flush().await, 2);
+        assert_eq!(runs.load(Ordering::SeqCst), 2);
+
+        // A second flush has nothing left to run
+        assert_eq!(shutdown.flush().await, 0);
+        assert_eq!(runs.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_stuck_hook_does_not_block_others() {
+        let shutdown = Shutdown::new(Duration::from_millis(20));
+        let flushed = Arc::new(AtomicUsize::new(0));
+
+        shutdown.register("stuck", || tokio::time::sleep(Duration::from_secs(60)));
+        let counter = Arc::clone(&flushed);
+        shutdown.register("capture", move || async move {
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+
+        assert_eq!(shutdown.flush().await, 1);
+        assert_eq!(flushed.load(Ordering::SeqCst), 1);
+    }
+}
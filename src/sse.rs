@@ -0,0 +1,141 @@
+//! Detection of data an upstream sends after the end of an SSE stream
+//!
+//! A Messages stream ends with an `event: message_stop` event, and OpenAI-style
+//! streams with `data: [DONE]`. Anything but whitespace after that event has
+//! finished points at a misbehaving upstream or an intermediary appending to the
+//! body. Chunks are only inspected, never changed: the bytes are still forwarded.
+//!
+//! Key features:
+//! - Events are tracked line by line across chunk boundaries
+//! - Only lines short enough to be a terminal marker are buffered, so long `data:` lines cost nothing
+//! - Trailing data is reported once per stream
+
+/// Lines marking the terminal event of a stream
+const TERMINAL_LINES: [&[u8]; 2] = [b"event: message_stop", b"data: [DONE]"];
+
+/// Longest line kept for comparison with the terminal markers
+const MAX_MARKER_LEN: usize = 32;
+
+/// Watches a streamed SSE body for bytes arriving after its terminal event
+#[derive(Debug, Default)]
+pub struct TerminalEventWatch {
+    /// Start of the current line, if it is short enough to be a marker
+    line: Vec<u8>,
+    /// Whether the current line outgrew `MAX_MARKER_LEN`
+    line_too_long: bool,
+    /// Whether the current event has a terminal marker line
+    terminal_pending: bool,
+    /// Whether the terminal event has ended (with a blank line)
+    finished: bool,
+    /// Whether trailing data has already been reported
+    reported: bool,
+}
+
+impl TerminalEventWatch {
+    /// Creates a watch for a new stream
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Inspects the next chunk of the stream
+    ///
+    /// # Returns
+    /// The number of bytes of this chunk after the terminal event, the first time
+    /// any of them is not whitespace; None otherwise
+    pub fn on_chunk(&mut self, chunk: &[u8]) -> Option<usize> {
+        if self.reported {
+            return None;
+        }
+
+        let mut rest = chunk;
+        while !self.finished {
+            let Some(newline) = rest.iter().position(|&byte| byte == b'\n') else {
+                self.push_partial_line(rest);
+                return None;
+            };
+            self.push_partial_line(&rest[..newline]);
+            self.end_line();
+            rest = &rest[newline + 1..];
+        }
+
+        if rest.iter().all(u8::is_ascii_whitespace) {
+            return None;
+        }
+        self.reported = true;
+        Some(rest.len())
+    }
+
+    /// Adds bytes of the current line, keeping only what could still be a marker
+    fn push_partial_line(&mut self, bytes: &[u8]) {
+        if self.line_too_long {
+            return;
+        }
+        if self.line.len() + bytes.len() > MAX_MARKER_LEN {
+            self.line_too_long = true;
+            self.line.clear();
+        } else {
+            self.line.extend_from_slice(bytes);
+        }
+    }
+
+    /// Handles a complete line: a marker flags the event, a blank line ends it
+    fn end_line(&mut self) {
+        let line = self.line.strip_suffix(b"\r").unwrap_or(&self.line);
+        if line.is_empty() && !self.line_too_long {
+            self.finished = self.terminal_pending;
+            self.terminal_pending = false;
+        } else if TERMINAL_LINES.contains(&line) {
+            self.terminal_pending = true;
+        }
+        self.line.clear();
+        self.line_too_long = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STREAM: &[u8] = b"event: content_block_delta\ndata: {\"delta\":\"hi\"}\n\n\
+        event: message_stop\ndata: {\"type\":\"message_stop\"}\n\n";
+
+    #[test]
+    fn test_clean_stream_reports_nothing() {
+        let mut watch = TerminalEventWatch::new();
+        assert_eq!(watch.on_chunk(STREAM), None);
+        // Whitespace after the end is harmless
+        assert_eq!(watch.on_chunk(b"\r\n\n"), None);
+    }
+
+    #[test]
+    fn test_trailing_data_reported_once() {
+        let mut watch = TerminalEventWatch::new();
+        let mut body = STREAM.to_vec();
+        body.extend_from_slice(b"garbage");
+
+        assert_eq!(watch.on_chunk(&body), Some(7));
+        assert_eq!(watch.on_chunk(b"more garbage"), None);
+    }
+
+    #[test]
+    fn test_terminal_event_split_across_chunks() {
+        let mut watch = TerminalEventWatch::new();
+        for chunk in [&b"data: [DO"[..], b"NE]\r", b"\n\r\n", b"x"] {
+            let reported = watch.on_chunk(chunk);
+            if chunk == b"x" {
+                assert_eq!(reported, Some(1));
+            } else {
+                assert_eq!(reported, None);
+            }
+        }
+    }
+
+    #[test]
+    fn test_marker_inside_long_line_is_not_terminal() {
+        let mut watch = TerminalEventWatch::new();
+        let long_line = format!("data: {}event: message_stop\n\n", "x".repeat(100));
+
+        assert_eq!(watch.on_chunk(long_line.as_bytes()), None);
+        assert_eq!(watch.on_chunk(b"event: ping\n\n"), None);
+    }
+}
@@ -0,0 +1,136 @@
+//! TLS termination for serving HTTPS directly to clients
+//!
+//! Most deployments terminate TLS at a load balancer, so the proxy serves plain
+//! HTTP by default. When both `TLS_CERT_PATH` and `TLS_KEY_PATH` are configured,
+//! the PEM certificate chain and private key are loaded here at startup and the
+//! server switches to HTTPS using rustls.
+//!
+//! Misconfiguration (only one path set, missing files, unparseable PEM) is reported
+//! as a [`TlsConfigError`] so startup fails with a clear message instead of
+//! silently falling back to HTTP.
+
+use axum_server::tls_rustls::RustlsConfig;
+use std::io;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use tracing::info;
+
+use crate::config::Config;
+
+/// Errors that can occur while preparing TLS at startup
+#[derive(Error, Debug)]
+pub enum TlsConfigError {
+    /// Only one of the certificate/key paths was configured
+    #[error("TLS_CERT_PATH and TLS_KEY_PATH must be set together, but only {0} is set")]
+    Incomplete(&'static str),
+
+    /// A configured certificate or key file does not exist
+    #[error("TLS {kind} file not found: {path}")]
+    FileNotFound {
+        /// Which file is missing ("certificate" or "key")
+        kind: &'static str,
+        /// The configured path
+        path: PathBuf,
+    },
+
+    /// The certificate or key could not be read or parsed as PEM
+    #[error("Failed to load TLS certificate/key: {0}")]
+    Load(#[source] io::Error),
+}
+
+/// Returns the certificate and key paths when TLS is configured
+///
+/// # Arguments
+/// * `config` - The application configuration
+///
+/// # Returns
+/// * `Ok(Some((cert, key)))` - Both paths set and both files exist
+/// * `Ok(None)` - Neither path set; serve plain HTTP
+/// * `Err(TlsConfigError)` - Only one path set, or a file is missing
+pub fn tls_paths(config: &Config) -> Result<Option<(PathBuf, PathBuf)>, TlsConfigError> {
+    let (cert_path, key_path) = match (&config.tls_cert_path, &config.tls_key_path) {
+        (Some(cert), Some(key)) => (PathBuf::from(cert), PathBuf::from(key)),
+        (None, None) => return Ok(None),
+        (Some(_), None) => return Err(TlsConfigError::Incomplete("TLS_CERT_PATH")),
+        (None, Some(_)) => return Err(TlsConfigError::Incomplete("TLS_KEY_PATH")),
+    };
+
+    ensure_file_exists("certificate", &cert_path)?;
+    ensure_file_exists("key", &key_path)?;
+
+    Ok(Some((cert_path, key_path)))
+}
+
+/// Loads the rustls server configuration when TLS is configured
+///
+/// # Arguments
+/// * `config` - The application configuration
+///
+/// # Returns
+/// * `Ok(Some(RustlsConfig))` - TLS configured and the PEM files parsed
+/// * `Ok(None)` - TLS not configured
+/// * `Err(TlsConfigError)` - TLS misconfigured or the PEM files are invalid
+pub async fn load_rustls_config(config: &Config) -> Result<Option<RustlsConfig>, TlsConfigError> {
+    let Some((cert_path, key_path)) = tls_paths(config)? else {
+        return Ok(None);
+    };
+
+    let rustls_config = RustlsConfig::from_pem_file(&cert_path, &key_path)
+        .await
+        .map_err(TlsConfigError::Load)?;
+
+    info!(
+        cert_path = %cert_path.display(),
+        key_path = %key_path.display(),
+        "TLS certificate and key loaded"
+    );
+
+    Ok(Some(rustls_config))
+}
+
+/// Checks that a configured TLS file exists and is a regular file
+fn ensure_file_exists(kind: &'static str, path: &Path) -> Result<(), TlsConfigError> {
+    if path.is_file() {
+        Ok(())
+    } else {
+        Err(TlsConfigError::FileNotFound {
+            kind,
+            path: path.to_path_buf(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tls_paths_require_both_files() {
+        // Neither set: plain HTTP
+        assert!(tls_paths(&Config::default()).unwrap().is_none());
+
+        // Only one set: clear error naming the one that is set
+        let cert_only = Config {
+            tls_cert_path: Some("cert.pem".to_string()),
+            ..Default::default()
+        };
+        assert!(matches!(
+            tls_paths(&cert_only),
+            Err(TlsConfigError::Incomplete("TLS_CERT_PATH"))
+        ));
+
+        // Both set but missing on disk
+        let missing = Config {
+            tls_cert_path: Some("/nonexistent/cert.pem".to_string()),
+            tls_key_path: Some("/nonexistent/key.pem".to_string()),
+            ..Default::default()
+        };
+        assert!(matches!(
+            tls_paths(&missing),
+            Err(TlsConfigError::FileNotFound {
+                kind: "certificate",
+                ..
+            })
+        ));
+    }
+}
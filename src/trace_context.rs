@@ -0,0 +1,172 @@
+//! W3C Trace Context (`traceparent`) handling for distributed tracing
+//!
+//! Incoming `traceparent` headers are forwarded upstream unchanged; this module
+//! parses them so the trace and parent IDs can be attached to the request span.
+//! When a request carries no (valid) `traceparent` and generation is enabled, a
+//! fresh one is created so the upstream can correlate its logs with ours.
+//!
+//! Only version `00` of the header format is understood; see
+//! <https://www.w3.org/TR/trace-context/#traceparent-header>.
+
+use hyper::header::HeaderValue;
+use hyper::HeaderMap;
+use std::fmt;
+
+/// Name of the W3C trace context header
+pub const TRACEPARENT_HEADER: &str = "traceparent";
+
+/// Trace flags used for generated headers (sampled)
+const SAMPLED_FLAGS: u8 = 0x01;
+
+/// A parsed `traceparent` header value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TraceParent {
+    /// 16-byte trace ID shared by every span in the trace
+    pub trace_id: u128,
+    /// 8-byte ID of the caller's span (the parent of ours)
+    pub parent_id: u64,
+    /// Trace flags (bit 0 = sampled)
+    pub flags: u8,
+}
+
+impl TraceParent {
+    /// Parses a `traceparent` value such as `00-<32 hex>-<16 hex>-<2 hex>`
+    ///
+    /// Returns None for anything malformed, an unsupported version, or the
+    /// all-zero trace/parent IDs the spec declares invalid.
+    pub fn parse(value: &str) -> Option<Self> {
+        let mut parts = value.trim().split('-');
+        let (version, trace_id, parent_id, flags) =
+            (parts.next()?, parts.next()?, parts.next()?, parts.next()?);
+        if parts.next().is_some() || version != "00" {
+            return None;
+        }
+
+        let trace_id = parse_lower_hex(trace_id, 32)?;
+        let parent_id = parse_lower_hex(parent_id, 16).and_then(|id| u64::try_from(id).ok())?;
+        let flags = parse_lower_hex(flags, 2).and_then(|id| u8::try_from(id).ok())?;
+
+        if trace_id == 0 || parent_id == 0 {
+            return None;
+        }
+
+        Some(Self {
+            trace_id,
+            parent_id,
+            flags,
+        })
+    }
+
+    /// Reads and parses the `traceparent` header, if present and valid
+    pub fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        headers
+            .get(TRACEPARENT_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(Self::parse)
+    }
+
+    /// Creates a new sampled trace context with random, non-zero IDs
+    pub fn generate() -> Self {
+        Self {
+            trace_id: random_non_zero(rand::random::<u128>),
+            parent_id: random_non_zero(rand::random::<u64>),
+            flags: SAMPLED_FLAGS,
+        }
+    }
+
+    /// Formats the trace ID as 32 lowercase hex digits
+    pub fn trace_id_hex(self) -> String {
+        format!("{:032x}", self.trace_id)
+    }
+
+    /// Formats the parent ID as 16 lowercase hex digits
+    pub fn parent_id_hex(self) -> String {
+        format!("{:016x}", self.parent_id)
+    }
+
+    /// Renders this context as a header value
+    pub fn to_header_value(self) -> HeaderValue {
+        HeaderValue::from_str(&self.to_string())
+            .expect("traceparent values are always valid header characters")
+    }
+}
+
+impl fmt::Display for TraceParent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "00-{}-{}-{:02x}",
+            self.trace_id_hex(),
+            self.parent_id_hex(),
+            self.flags
+        )
+    }
+}
+
+/// Parses exactly `len` lowercase hex digits (the spec forbids uppercase)
+fn parse_lower_hex(digits: &str, len: usize) -> Option<u128> {
+    if digits.len() != len
+        || !digits
+            .bytes()
+            .all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b))
+    {
+        return None;
+    }
+    u128::from_str_radix(digits, 16).ok()
+}
+
+/// Draws random values until a non-zero one comes up (zero IDs are invalid)
+fn random_non_zero<T: PartialEq + Default>(mut draw: impl FnMut() -> T) -> T {
+    loop {
+        let value = draw();
+        if value != T::default() {
+            return value;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+
+    #[test]
+    fn test_parse_valid_traceparent() {
+        let parsed = TraceParent::parse(EXAMPLE).expect("Example header should parse");
+
+        assert_eq!(parsed.trace_id_hex(), "4bf92f3577b34da6a3ce929d0e0e4736");
+        assert_eq!(parsed.parent_id_hex(), "00f067aa0ba902b7");
+        assert_eq!(parsed.flags, 0x01);
+        assert_eq!(parsed.to_string(), EXAMPLE);
+    }
+
+    #[test]
+    fn test_parse_rejects_malformed_traceparent() {
+        for invalid in [
+            "",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7",
+            "01-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+            "00-4BF92F3577B34DA6A3CE929D0E0E4736-00f067aa0ba902b7-01",
+            "00-00000000000000000000000000000000-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-0000000000000000-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e473-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01-extra",
+        ] {
+            assert!(
+                TraceParent::parse(invalid).is_none(),
+                "Should reject: {:?}",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn test_generated_traceparent_round_trips() {
+        let generated = TraceParent::generate();
+
+        assert_ne!(generated.trace_id, 0);
+        assert_ne!(generated.parent_id, 0);
+        assert_eq!(TraceParent::parse(&generated.to_string()), Some(generated));
+    }
+}
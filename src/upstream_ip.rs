@@ -0,0 +1,143 @@
+//! Cached resolution of the upstream host to an IP address
+//!
+//! When debugging DNS or routing issues it helps to know which address a request
+//! was sent to. The HTTP client resolves hosts internally without exposing the
+//! result, so the proxy resolves the upstream host itself, purely for logging.
+//!
+//! Key features:
+//! - Resolutions are cached per host and port for `RESOLVED_IP_TTL`
+//! - Failed resolutions are cached too, so a broken resolver is not hammered
+//! - Resolution never affects forwarding; failures are reported as `UNRESOLVED`
+
+use hyper::Uri;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+use tokio::net::lookup_host;
+
+/// How long a resolved (or failed) lookup is reused before resolving again
+pub const RESOLVED_IP_TTL: Duration = Duration::from_secs(30);
+
+/// Value recorded when the upstream host could not be resolved
+pub const UNRESOLVED: &str = "unresolved";
+
+/// Cache of upstream host resolutions shared across requests
+#[derive(Debug)]
+pub struct UpstreamIpCache {
+    /// How long entries stay valid
+    ttl: Duration,
+    /// Last resolution per `host:port`
+    entries: Mutex<HashMap<String, CachedIp>>,
+}
+
+/// A cached resolution and when it was made
+#[derive(Debug, Clone, Copy)]
+struct CachedIp {
+    ip: Option<IpAddr>,
+    resolved_at: Instant,
+}
+
+impl UpstreamIpCache {
+    /// Creates an empty cache whose entries expire after `ttl`
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Resolves the host of `uri` to its first IP address, using the cache when fresh
+    ///
+    /// # Returns
+    /// The address, or None if the URI has no host or resolution failed
+    pub async fn resolve_uri(&self, uri: &Uri) -> Option<IpAddr> {
+        let host = uri.host()?;
+        let port = uri
+            .port_u16()
+            .unwrap_or(if uri.scheme_str() == Some("https") {
+                443
+            } else {
+                80
+            });
+        self.resolve(host, port).await
+    }
+
+    /// Resolves `host` to its first IP address, using the cache when fresh
+    pub async fn resolve(&self, host: &str, port: u16) -> Option<IpAddr> {
+        let key = format!("{}:{}", host, port);
+        if let Some(cached) = self.cached(&key) {
+            return cached;
+        }
+
+        // The lock is not held across the lookup; concurrent misses may both resolve
+        let ip = match lookup_host(key.as_str()).await {
+            Ok(mut addrs) => addrs.next().map(|addr| addr.ip()),
+            Err(_) => None,
+        };
+
+        self.entries
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(
+                key,
+                CachedIp {
+                    ip,
+                    resolved_at: Instant::now(),
+                },
+            );
+        ip
+    }
+
+    /// Returns the cached resolution for `key` if it has not expired
+    fn cached(&self, key: &str) -> Option<Option<IpAddr>> {
+        let entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+        entries
+            .get(key)
+            .filter(|entry| entry.resolved_at.elapsed() < self.ttl)
+            .map(|entry| entry.ip)
+    }
+}
+
+/// Formats a resolution for logging, using `UNRESOLVED` for failures
+pub fn ip_for_log(ip: Option<IpAddr>) -> String {
+    ip.map_or_else(|| UNRESOLVED.to_string(), |ip| ip.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_localhost_resolves_to_loopback() {
+        let cache = UpstreamIpCache::new(RESOLVED_IP_TTL);
+        let uri: Uri = "http://localhost:8080/v1/messages".parse().unwrap();
+
+        let ip = cache
+            .resolve_uri(&uri)
+            .await
+            .expect("localhost should resolve");
+        assert!(ip.is_loopback(), "Expected a loopback address, got {}", ip);
+
+        // The second lookup is served from the cache
+        assert!(cache.cached("localhost:8080").is_some());
+        assert_eq!(cache.resolve_uri(&uri).await, Some(ip));
+    }
+
+    #[tokio::test]
+    async fn test_unresolvable_host_logged_as_unresolved() {
+        let cache = UpstreamIpCache::new(RESOLVED_IP_TTL);
+
+        let ip = cache.resolve("switchboard-test.invalid", 443).await;
+        assert_eq!(ip, None);
+        assert_eq!(ip_for_log(ip), UNRESOLVED);
+    }
+
+    #[tokio::test]
+    async fn test_expired_entries_are_not_reused() {
+        let cache = UpstreamIpCache::new(Duration::ZERO);
+
+        cache.resolve("localhost", 80).await;
+        assert!(cache.cached("localhost:80").is_none());
+    }
+}
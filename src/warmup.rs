@@ -0,0 +1,57 @@
+//! Priming the upstream connection pool at startup
+//!
+//! The first request to the upstream pays for DNS resolution, the TCP connect and
+//! the TLS handshake. With warm-up enabled, a single background request is sent to
+//! the upstream base URL right after the listener is bound, so the pooled
+//! connection is already established when real traffic arrives.
+//!
+//! Key features:
+//! - Runs in the background and never delays serving
+//! - Any response status counts as success; only the connection matters
+//! - Failures are logged and otherwise ignored
+
+use reqwest::Client;
+use tokio::task::JoinHandle;
+use tracing::debug;
+
+use crate::config::Config;
+
+/// Starts the warm-up request in the background if `config.warmup_on_start` is set
+///
+/// # Returns
+/// The warm-up task, resolving to whether the upstream answered, or None if disabled
+pub fn spawn_warmup(client: Client, config: &Config) -> Option<JoinHandle<bool>> {
+    if !config.warmup_on_start {
+        return None;
+    }
+
+    let target_url = config.anthropic_target_url.clone();
+    Some(tokio::spawn(
+        async move { warm_up(&client, &target_url).await },
+    ))
+}
+
+/// Sends one GET to `target_url` so the client pools a connection to it
+///
+/// # Returns
+/// True if the upstream answered with any status
+pub async fn warm_up(client: &Client, target_url: &str) -> bool {
+    match client.get(target_url).send().await {
+        Ok(response) => {
+            debug!(
+                target_url = %target_url,
+                status = %response.status(),
+                "Upstream connection warm-up succeeded"
+            );
+            true
+        }
+        Err(e) => {
+            debug!(
+                target_url = %target_url,
+                error = %e,
+                "Upstream connection warm-up failed, continuing without it"
+            );
+            false
+        }
+    }
+}
@@ -0,0 +1,249 @@
+//! Transparent tunnelling of WebSocket upgrade requests
+//!
+//! A WebSocket connection starts as an HTTP/1.1 request carrying
+//! `Upgrade: websocket`. With `allow_websocket`, the proxy forwards that handshake
+//! upstream over a dedicated connection and, once the upstream switches protocols,
+//! relays raw bytes in both directions until either side closes. Frames are not
+//! parsed, so any subprotocol or extension the two ends agree on works unchanged.
+//!
+//! Key features:
+//! - The handshake carries the headers the caller prepared (credentials, Host)
+//! - An upstream answer other than `101 Switching Protocols` is relayed as is
+//! - Tunnels to `https` upstreams use TLS with the same public roots as reqwest
+//! - Tunnel open and close are logged within the request's span
+
+use axum::body::{boxed, Body};
+use axum::response::Response;
+use hyper::{header, HeaderMap, Request, StatusCode, Uri};
+use std::io;
+use std::sync::{Arc, OnceLock};
+use std::time::Instant;
+use thiserror::Error;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpStream;
+use tokio_rustls::rustls::{self, OwnedTrustAnchor, RootCertStore, ServerName};
+use tokio_rustls::TlsConnector;
+use tracing::{debug, info, warn, Instrument, Span};
+
+/// Errors that prevent a tunnel from being opened
+#[derive(Error, Debug)]
+pub enum TunnelError {
+    /// The target URL has no host to connect to
+    #[error("WebSocket target URL has no host: {0}")]
+    InvalidTarget(String),
+
+    /// The upstream could not be reached, or its TLS handshake failed
+    #[error("Failed to connect to WebSocket upstream: {0}")]
+    Connect(#[source] io::Error),
+
+    /// The upgrade request could not be sent or its response not read
+    #[error("WebSocket handshake with upstream failed: {0}")]
+    Handshake(#[source] hyper::Error),
+}
+
+/// A byte stream to the upstream, plain or TLS
+trait UpstreamIo: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> UpstreamIo for T {}
+
+/// Returns true if the request asks to switch to the WebSocket protocol
+pub fn is_websocket_upgrade(headers: &HeaderMap) -> bool {
+    let has_token = |name: header::HeaderName, token: &str| {
+        headers
+            .get_all(name)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .any(|part| part.trim().eq_ignore_ascii_case(token))
+    };
+    has_token(header::UPGRADE, "websocket") && has_token(header::CONNECTION, "upgrade")
+}
+
+/// Forwards a WebSocket handshake to `target_url` and relays the connection
+///
+/// When the upstream accepts the upgrade, its `101` response is returned for the
+/// client and a background task relays bytes between the two upgraded connections
+/// until either side closes.
+///
+/// # Arguments
+/// * `req` - The client's upgrade request
+/// * `target_url` - Upstream URL of the handshake
+/// * `headers` - Headers for the upstream handshake, including `Upgrade` and `Connection`
+/// * `span` - The request span, which tunnel logs are recorded in
+/// * `held` - Kept until the tunnel closes (e.g. a concurrency permit), or dropped
+///   with the handshake if the upstream declines the upgrade
+pub async fn tunnel(
+    mut req: Request<Body>,
+    target_url: &Uri,
+    headers: HeaderMap,
+    span: Span,
+    held: impl Send + 'static,
+) -> Result<Response, TunnelError> {
+    let client_upgrade = hyper::upgrade::on(&mut req);
+
+    let io = connect(target_url).await?;
+    let (mut sender, connection) = hyper::client::conn::handshake(io)
+        .await
+        .map_err(TunnelError::Handshake)?;
+    // Drives the connection, then hands it over to the upgrade once the upstream switches
+    tokio::spawn(async move {
+        if let Err(e) = connection.await {
+            debug!(error = %e, "WebSocket upstream connection ended with error");
+        }
+    });
+
+    let path_and_query = target_url
+        .path_and_query()
+        .map(|path| path.as_str())
+        .unwrap_or("/");
+    let mut upstream_req = Request::builder()
+        .method(req.method())
+        .uri(path_and_query)
+        .body(Body::empty())
+        .map_err(|e| TunnelError::InvalidTarget(e.to_string()))?;
+    *upstream_req.headers_mut() = headers;
+
+    let mut upstream_resp = sender
+        .send_request(upstream_req)
+        .await
+        .map_err(TunnelError::Handshake)?;
+    if upstream_resp.status() != StatusCode::SWITCHING_PROTOCOLS {
+        warn!(
+            status = upstream_resp.status().as_u16(),
+            "Upstream declined WebSocket upgrade"
+        );
+        return Ok(upstream_resp.map(boxed));
+    }
+
+    let upstream_upgrade = hyper::upgrade::on(&mut upstream_resp);
+    tokio::spawn(
+        async move {
+            let _held = held;
+            let (mut client_io, mut upstream_io) =
+                match tokio::try_join!(client_upgrade, upstream_upgrade) {
+                    Ok(upgraded) => upgraded,
+                    Err(e) => {
+                        warn!(error = %e, "WebSocket upgrade failed after handshake");
+                        return;
+                    }
+                };
+
+            info!("WebSocket tunnel opened");
+            let opened = Instant::now();
+            match tokio::io::copy_bidirectional(&mut client_io, &mut upstream_io).await {
+                Ok((to_upstream, to_client)) => info!(
+                    bytes_to_upstream = to_upstream,
+                    bytes_to_client = to_client,
+                    duration_ms = opened.elapsed().as_millis() as u64,
+                    "WebSocket tunnel closed"
+                ),
+                Err(e) => info!(
+                    error = %e,
+                    duration_ms = opened.elapsed().as_millis() as u64,
+                    "WebSocket tunnel closed with error"
+                ),
+            }
+        }
+        .instrument(span),
+    );
+
+    // The client gets the upstream's handshake response; the body is empty for a 101
+    Ok(upstream_resp.map(|_| boxed(Body::empty())))
+}
+
+/// Opens a connection to the host of `target_url`, with TLS for `https`
+async fn connect(target_url: &Uri) -> Result<Box<dyn UpstreamIo>, TunnelError> {
+    let host = connect_host(target_url)
+        .ok_or_else(|| TunnelError::InvalidTarget(target_url.to_string()))?;
+    let is_tls = target_url.scheme_str() == Some("https");
+    let port = target_url
+        .port_u16()
+        .unwrap_or(if is_tls { 443 } else { 80 });
+
+    let stream = TcpStream::connect((host, port))
+        .await
+        .map_err(TunnelError::Connect)?;
+    if !is_tls {
+        return Ok(Box::new(stream));
+    }
+
+    let server_name = ServerName::try_from(host)
+        .map_err(|_| TunnelError::InvalidTarget(target_url.to_string()))?;
+    let tls_stream = TlsConnector::from(tls_client_config())
+        .connect(server_name, stream)
+        .await
+        .map_err(TunnelError::Connect)?;
+    Ok(Box::new(tls_stream))
+}
+
+/// Returns the host of `target_url` to connect to, without the brackets of an IPv6 literal
+fn connect_host(target_url: &Uri) -> Option<&str> {
+    let host = target_url.host()?;
+    Some(
+        host.strip_prefix('[')
+            .and_then(|host| host.strip_suffix(']'))
+            .unwrap_or(host),
+    )
+}
+
+/// Returns the TLS client configuration trusting the public web PKI roots
+///
+/// Built on first use and shared by every tunnel after that.
+fn tls_client_config() -> Arc<rustls::ClientConfig> {
+    static CONFIG: OnceLock<Arc<rustls::ClientConfig>> = OnceLock::new();
+    CONFIG
+        .get_or_init(|| {
+            let mut roots = RootCertStore::empty();
+            roots.add_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.iter().map(|anchor| {
+                OwnedTrustAnchor::from_subject_spki_name_constraints(
+                    anchor.subject,
+                    anchor.spki,
+                    anchor.name_constraints,
+                )
+            }));
+            Arc::new(
+                rustls::ClientConfig::builder()
+                    .with_safe_defaults()
+                    .with_root_certificates(roots)
+                    .with_no_client_auth(),
+            )
+        })
+        .clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hyper::header::HeaderValue;
+
+    #[test]
+    fn test_detects_websocket_upgrades() {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::UPGRADE, HeaderValue::from_static("WebSocket"));
+        headers.insert(
+            header::CONNECTION,
+            HeaderValue::from_static("keep-alive, Upgrade"),
+        );
+        assert!(is_websocket_upgrade(&headers));
+
+        headers.insert(header::UPGRADE, HeaderValue::from_static("h2c"));
+        assert!(!is_websocket_upgrade(&headers));
+
+        // Upgrade without Connection: upgrade is not a request to switch protocols
+        let mut headers = HeaderMap::new();
+        headers.insert(header::UPGRADE, HeaderValue::from_static("websocket"));
+        assert!(!is_websocket_upgrade(&headers));
+    }
+
+    #[test]
+    fn test_connect_host_strips_ipv6_brackets() {
+        let host = |url: &str| connect_host(&url.parse().unwrap()).map(str::to_string);
+        assert_eq!(host("http://[::1]:8080/v1"), Some("::1".to_string()));
+        assert_eq!(
+            host("https://api.anthropic.com"),
+            Some("api.anthropic.com".to_string())
+        );
+        assert_eq!(host("http://127.0.0.1:80"), Some("127.0.0.1".to_string()));
+        assert_eq!(host("/v1/realtime"), None);
+    }
+}
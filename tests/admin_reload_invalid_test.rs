@@ -0,0 +1,68 @@
+// Integration tests for reloading an invalid configuration
+//
+// Kept apart from the other reload tests, as the invalid value is set in the
+// process environment.
+mod common;
+
+use axum::body::Body;
+use axum::http::{header, Request, StatusCode};
+use serde_json::Value;
+use std::env;
+use tower::ServiceExt;
+
+const ADMIN_TOKEN: &str = "test-admin-token";
+
+/// Tests that an invalid environment is refused with 422 and leaves the live config alone
+#[tokio::test]
+async fn test_reload_rejects_invalid_configuration() {
+    let test_setup = common::setup_test_environment_with_config(|config| {
+        config.admin_token = Some(ADMIN_TOKEN.to_string());
+        config.admin_enabled = true;
+        config.server_timing = false;
+    })
+    .await;
+
+    env::set_var("SERVER_TIMING", "true");
+    env::set_var("RETRYABLE_STATUSES", "503,oops");
+    let reload_request = || {
+        Request::builder()
+            .method("POST")
+            .uri("/admin/reload")
+            .header(header::AUTHORIZATION, format!("Bearer {}", ADMIN_TOKEN))
+            .body(Body::empty())
+            .unwrap()
+    };
+    let response = test_setup
+        .app
+        .clone()
+        .oneshot(reload_request())
+        .await
+        .unwrap();
+    env::remove_var("RETRYABLE_STATUSES");
+
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    let body_json: Value = serde_json::from_slice(&body).expect("Rejection should be JSON");
+    assert!(
+        body_json["error"]
+            .as_str()
+            .is_some_and(|error| error.contains("RETRYABLE_STATUSES")),
+        "{}",
+        body_json
+    );
+
+    // Once the environment is valid again, the reload goes through
+    let response = test_setup.app.oneshot(reload_request()).await.unwrap();
+    env::remove_var("SERVER_TIMING");
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    let body_json: Value = serde_json::from_slice(&body).unwrap();
+    let changes = body_json["changes"].as_array().unwrap();
+    assert!(
+        changes
+            .iter()
+            .any(|change| change["field"] == "server_timing"),
+        "The rejected reload should not have applied SERVER_TIMING: {:?}",
+        changes
+    );
+}
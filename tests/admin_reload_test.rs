@@ -0,0 +1,103 @@
+// Integration tests for the admin reload endpoint
+mod common;
+
+use axum::body::Body;
+use axum::http::{header, Request, StatusCode};
+use serde_json::{json, Value};
+use std::env;
+use tower::ServiceExt;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+const ADMIN_TOKEN: &str = "test-admin-token";
+
+/// Builds an authorized reload request
+fn reload_request(token: &str) -> Request<Body> {
+    Request::builder()
+        .method("POST")
+        .uri("/admin/reload")
+        .header(header::AUTHORIZATION, format!("Bearer {}", token))
+        .body(Body::empty())
+        .unwrap()
+}
+
+/// Tests that changing an env var and hitting reload changes proxy behavior,
+/// and that the response contains the redacted config.
+#[tokio::test]
+async fn test_reload_applies_changed_env_var() {
+    // Make sure the reloaded value starts from a known state
+    env::remove_var("SERVER_TIMING");
+
+    let test_setup = common::setup_test_environment_with_config(|config| {
+        config.admin_token = Some(ADMIN_TOKEN.to_string());
+        config.admin_enabled = true;
+        config.server_timing = false;
+    })
+    .await;
+
+    Mock::given(method("POST"))
+        .and(path("/v1/messages"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"status": "ok"})))
+        .mount(&test_setup.mock_server)
+        .await;
+
+    let proxy_request = || {
+        Request::builder()
+            .method("POST")
+            .uri("/v1/messages")
+            .body(Body::from("{}"))
+            .unwrap()
+    };
+
+    // Before the reload the header is off
+    let response = test_setup
+        .app
+        .clone()
+        .oneshot(proxy_request())
+        .await
+        .unwrap();
+    assert!(response.headers().get("server-timing").is_none());
+
+    // An unauthorized reload is refused and changes nothing
+    let response = test_setup
+        .app
+        .clone()
+        .oneshot(reload_request("wrong-token"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    // Flip the setting in the environment and reload
+    env::set_var("SERVER_TIMING", "true");
+    let response = test_setup
+        .app
+        .clone()
+        .oneshot(reload_request(ADMIN_TOKEN))
+        .await
+        .unwrap();
+    env::remove_var("SERVER_TIMING");
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    let body_json: Value = serde_json::from_slice(&body).expect("Reload should return JSON");
+    assert_eq!(body_json["config"]["server_timing"], true);
+    assert_eq!(body_json["config"]["anthropic_api_key"], "[REDACTED]");
+    assert_eq!(body_json["config"]["admin_token"], "[REDACTED]");
+
+    // The changed field is reported, old and new value alike
+    let changes = body_json["changes"]
+        .as_array()
+        .expect("Reload should list changes");
+    assert!(
+        changes.contains(&json!({"field": "server_timing", "old": false, "new": true})),
+        "{:?}",
+        changes
+    );
+
+    // The new value is in effect for subsequent proxied requests
+    let response = test_setup.app.oneshot(proxy_request()).await.unwrap();
+    assert!(
+        response.headers().get("server-timing").is_some(),
+        "Reloaded SERVER_TIMING=true should add the header"
+    );
+}
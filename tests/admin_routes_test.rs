@@ -0,0 +1,54 @@
+// Integration tests for the admin route discovery endpoint
+mod common;
+
+use axum::body::Body;
+use axum::http::{header, Request, StatusCode};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+const ADMIN_TOKEN: &str = "test-admin-token";
+
+/// Tests that the routing table lists the default route and configured routes, without keys
+#[tokio::test]
+async fn test_routes_lists_default_and_custom_routes() {
+    let test_setup = common::setup_test_environment_with_config(|config| {
+        config.admin_token = Some(ADMIN_TOKEN.to_string());
+        config.admin_enabled = true;
+        config.route_api_keys = vec![("/v1/messages/batches".to_string(), "BATCH_KEY".to_string())];
+        config
+            .route_api_key_values
+            .insert("BATCH_KEY".to_string(), "batch-secret-key".to_string());
+    })
+    .await;
+    let upstream = test_setup.mock_server.uri();
+
+    let request = Request::builder()
+        .uri("/admin/routes")
+        .header(header::AUTHORIZATION, format!("Bearer {}", ADMIN_TOKEN))
+        .body(Body::empty())
+        .unwrap();
+    let response = test_setup.app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    let routes: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(
+        routes,
+        json!({
+            "routes": [
+                {"prefix": "/", "upstream": upstream, "api_key": "ANTHROPIC_API_KEY"},
+                {"prefix": "/v1/messages/batches", "upstream": upstream, "api_key": "BATCH_KEY"},
+            ]
+        })
+    );
+    let body = String::from_utf8_lossy(&body);
+    assert!(!body.contains("batch-secret-key") && !body.contains("test-api-key"));
+
+    // Like every admin endpoint, routes require the token
+    let unauthorized = Request::builder()
+        .uri("/admin/routes")
+        .body(Body::empty())
+        .unwrap();
+    let response = test_setup.app.oneshot(unauthorized).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
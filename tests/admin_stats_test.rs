@@ -0,0 +1,52 @@
+// Integration tests for the admin stats endpoint
+mod common;
+
+use axum::body::Body;
+use axum::http::{header, Request, StatusCode};
+use serde_json::Value;
+use tower::ServiceExt;
+
+const ADMIN_TOKEN: &str = "test-admin-token";
+
+/// Tests that the stats endpoint reports log usage consistent with its breakdown
+#[tokio::test]
+async fn test_stats_reports_log_directory_usage() {
+    let test_setup = common::setup_test_environment_with_config(|config| {
+        config.admin_token = Some(ADMIN_TOKEN.to_string());
+        config.admin_enabled = true;
+    })
+    .await;
+
+    let request = Request::builder()
+        .uri("/admin/stats")
+        .header(header::AUTHORIZATION, format!("Bearer {}", ADMIN_TOKEN))
+        .body(Body::empty())
+        .unwrap();
+    let response = test_setup.app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    let stats: Value = serde_json::from_slice(&body).unwrap();
+    let usage = &stats["log_usage"];
+    let subdirs = usage["subdirs"]
+        .as_object()
+        .expect("subdirs should be an object");
+    assert_eq!(
+        subdirs.keys().collect::<Vec<_>>(),
+        vec!["app", "test"],
+        "Both log subdirectories should be reported"
+    );
+    let summed: u64 = subdirs
+        .values()
+        .map(|subdir| subdir["bytes"].as_u64().unwrap())
+        .sum();
+    assert_eq!(usage["total_bytes"].as_u64(), Some(summed));
+
+    // Like every admin endpoint, stats require the token
+    let unauthorized = Request::builder()
+        .uri("/admin/stats")
+        .body(Body::empty())
+        .unwrap();
+    let response = test_setup.app.oneshot(unauthorized).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
@@ -0,0 +1,57 @@
+// Integration tests for injecting the proxy's API key versus passing client keys through
+mod common;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use switchboard::config::AuthMode;
+use tower::ServiceExt;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+/// Sends a request carrying a client key and returns the headers the upstream received
+async fn forwarded_headers(auth_mode: AuthMode) -> (String, wiremock::Request) {
+    let test_setup = common::setup_test_environment_with_config(|config| {
+        config.auth_mode = auth_mode;
+    })
+    .await;
+    Mock::given(method("POST"))
+        .and(path("/v1/messages"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&test_setup.mock_server)
+        .await;
+
+    let request = Request::builder()
+        .method("POST")
+        .uri("/v1/messages")
+        .header("x-api-key", "client-api-key")
+        .header("authorization", "Bearer client-token")
+        .body(Body::from("{}"))
+        .unwrap();
+    let response = test_setup.app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let mut received = test_setup.mock_server.received_requests().await.unwrap();
+    assert_eq!(received.len(), 1);
+    (
+        test_setup.config.anthropic_api_key.clone(),
+        received.remove(0),
+    )
+}
+
+/// Tests that inject mode replaces the client's credentials with the proxy's key
+#[tokio::test]
+async fn test_inject_overrides_client_key() {
+    let (proxy_key, received) = forwarded_headers(AuthMode::Inject).await;
+
+    assert_eq!(received.headers["x-api-key"], proxy_key.as_str());
+    assert!(received.headers.get("authorization").is_none());
+}
+
+/// Tests that passthrough mode forwards the client's credentials unchanged
+#[tokio::test]
+async fn test_passthrough_forwards_client_key() {
+    let (_, received) = forwarded_headers(AuthMode::Passthrough).await;
+
+    assert_eq!(received.headers["x-api-key"], "client-api-key");
+    assert_eq!(received.headers["authorization"], "Bearer client-token");
+}
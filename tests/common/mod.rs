@@ -9,6 +9,7 @@ use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::Duration;
 use switchboard::config::Config;
+use switchboard::log_naming;
 use switchboard::logger::{self, LogPathResolver, LogType};
 use switchboard::proxy_handler::create_router;
 use tracing_appender::non_blocking::WorkerGuard;
@@ -61,13 +62,22 @@ pub struct TestLoggingSetup {
 /// Returns a TestSetup instance containing all components needed for testing.
 #[allow(dead_code)] // ALLOWANCE: Used by tests/proxy_integration_tests.rs
 pub async fn setup_test_environment() -> TestSetup {
+    setup_test_environment_with_config(|_| {}).await
+}
+
+/// Sets up the test environment like `setup_test_environment`, letting the caller
+/// adjust the configuration before the router is built.
+///
+/// The target URL already points at the mock server when `customize` runs.
+#[allow(dead_code)] // ALLOWANCE: Used by tests/proxy_integration_tests.rs
+pub async fn setup_test_environment_with_config(customize: impl FnOnce(&mut Config)) -> TestSetup {
     // Start a WireMock server on a random available port
     // This will be used to mock the Anthropic API during tests
     let mock_server = MockServer::start().await;
 
     // Create a test-specific configuration pointing to the mock server
     // Use dummy values for fields that are suitable for testing
-    let config = Config {
+    let mut config = Config {
         port: "0".to_string(), // Use 0 to let OS assign a random port if needed
         anthropic_api_key: "test-api-key".to_string(), // Dummy API key for testing
         anthropic_target_url: mock_server.uri(), // Point to the mock server
@@ -79,8 +89,12 @@ pub async fn setup_test_environment() -> TestSetup {
         log_max_body_size: 20480, // Default size for tests
         log_directory_mode: switchboard::config::LogDirectoryMode::Default, // Use automatic detection for tests
         log_max_age_days: None,
+        ..Default::default()
     };
 
+    // Apply test-specific configuration overrides
+    customize(&mut config);
+
     // Create a reqwest client with appropriate timeouts for testing
     // Using shorter timeouts than production to avoid long-running tests
     let client = Client::builder()
@@ -137,6 +151,7 @@ pub fn setup_test_logging(test_name: &str) -> TestLoggingSetup {
         log_max_body_size: 1024,
         log_directory_mode: switchboard::config::LogDirectoryMode::Default,
         log_max_age_days: None,
+        ..Default::default()
     };
 
     // Use LogPathResolver to get the correct path for test logs
@@ -186,6 +201,7 @@ pub fn generate_test_log_path(test_name: &str) -> PathBuf {
         log_max_body_size: 1024,
         log_directory_mode: switchboard::config::LogDirectoryMode::Default,
         log_max_age_days: None,
+        ..Default::default()
     };
 
     // Use LogPathResolver to get the correct path for test logs
@@ -239,6 +255,7 @@ pub fn verify_log_directory() -> bool {
         log_max_body_size: 1024,
         log_directory_mode: switchboard::config::LogDirectoryMode::Default,
         log_max_age_days: None,
+        ..Default::default()
     };
 
     // Create resolvers for both app and test logs
@@ -273,8 +290,10 @@ pub fn verify_log_directory() -> bool {
 
 /// Helper function to find a log file, accounting for date suffixes.
 ///
-/// Log files are often created with date suffixes for rotation purposes.
-/// This function helps find the actual log file when given a base path.
+/// Log files are often created with date suffixes for rotation purposes, either
+/// after the extension (`app.log.2024-01-02`) or in the name (`app-2024-01-02.log`,
+/// with `log_file_date_in_name`). This function helps find the actual log file
+/// when given a base path.
 ///
 /// # Arguments
 /// * `base_path` - The base path of the log file (without date suffix)
@@ -288,21 +307,34 @@ pub fn find_log_file(base_path: &Path) -> Option<PathBuf> {
         return Some(base_path.to_path_buf());
     }
 
-    // Check for the base path with today's date suffix
-    let date_suffix = chrono::Local::now().format(".%Y-%m-%d").to_string();
+    // Check for the base path with today's date, in both naming schemes (the
+    // appenders date files in UTC)
+    let today = chrono::Utc::now().date_naive();
+    let date_suffix = today.format(".%Y-%m-%d").to_string();
     let dated_path = PathBuf::from(format!("{}{}", base_path.display(), date_suffix));
 
     if dated_path.exists() {
         return Some(dated_path);
     }
 
-    // If not found, check the directory for files with similar names
+    let base_name = base_path.file_name().unwrap().to_string_lossy();
+    let prefix = base_name.strip_suffix(".log").unwrap_or(&base_name);
+    let dated_in_name_path = base_path.with_file_name(log_naming::dated_file_name(prefix, today));
+
+    if dated_in_name_path.exists() {
+        return Some(dated_in_name_path);
+    }
+
+    // If not found, check the directory for files with similar names from other days
     if let Some(parent) = base_path.parent() {
         if let Ok(entries) = fs::read_dir(parent) {
-            let base_name = base_path.file_name().unwrap().to_string_lossy();
             for entry in entries.flatten() {
                 let file_name = entry.file_name().to_string_lossy().to_string();
-                if file_name.starts_with(base_name.as_ref()) {
+                let dated_in_name = file_name
+                    .strip_prefix(prefix)
+                    .is_some_and(|rest| rest.starts_with('-'))
+                    && log_naming::log_file_date(&file_name).is_some();
+                if file_name.starts_with(base_name.as_ref()) || dated_in_name {
                     return Some(entry.path());
                 }
             }
@@ -450,3 +482,130 @@ pub fn cleanup_test_log_file(log_path: &Path) {
         }
     }
 }
+
+/// Fields of one captured event, keyed by field name
+#[allow(dead_code)] // ALLOWANCE: Used by tests that assert on logged fields
+pub type CapturedEvent = std::collections::HashMap<String, String>;
+
+/// Tracing layer that keeps the fields of every event in memory
+#[allow(dead_code)] // ALLOWANCE: Used by tests that assert on logged fields
+#[derive(Clone, Default)]
+pub struct EventCapture {
+    pub events: Arc<std::sync::Mutex<Vec<CapturedEvent>>>,
+}
+
+impl<S> tracing_subscriber::Layer<S> for EventCapture
+where
+    S: tracing::Subscriber + for<'a> tracing_subscriber::registry::LookupSpan<'a>,
+{
+    fn on_event(
+        &self,
+        event: &tracing::Event<'_>,
+        _ctx: tracing_subscriber::layer::Context<'_, S>,
+    ) {
+        let mut visitor = FieldVisitor::default();
+        event.record(&mut visitor);
+        self.events.lock().unwrap().push(visitor.0);
+    }
+}
+
+/// Collects event fields as strings (floats unquoted, everything else via Debug)
+#[derive(Default)]
+struct FieldVisitor(CapturedEvent);
+
+impl tracing::field::Visit for FieldVisitor {
+    fn record_f64(&mut self, field: &tracing::field::Field, value: f64) {
+        self.0.insert(field.name().to_string(), value.to_string());
+    }
+
+    fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+        self.0
+            .insert(field.name().to_string(), format!("{:?}", value));
+    }
+}
+
+/// Finds the captured event with the given message
+#[allow(dead_code)] // ALLOWANCE: Used by tests that assert on logged fields
+pub fn find_event<'a>(events: &'a [CapturedEvent], message: &str) -> &'a CapturedEvent {
+    events
+        .iter()
+        .find(|event| event.get("message").map(String::as_str) == Some(message))
+        .unwrap_or_else(|| panic!("Expected an event with message {:?}", message))
+}
+
+/// Tracing layer that keeps the fields of every value recorded on a span after creation
+///
+/// Captures fields filled in with `Span::record`, such as those declared
+/// `field::Empty` on the proxy request span.
+#[allow(dead_code)] // ALLOWANCE: Used by tests that assert on recorded span fields
+#[derive(Clone, Default)]
+pub struct SpanRecordCapture {
+    pub records: Arc<std::sync::Mutex<Vec<CapturedEvent>>>,
+}
+
+#[allow(dead_code)] // ALLOWANCE: Used by tests that assert on recorded span fields
+impl SpanRecordCapture {
+    /// Returns every value recorded for `field`, in recording order
+    pub fn values(&self, field: &str) -> Vec<String> {
+        self.records
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(|record| record.get(field).cloned())
+            .collect()
+    }
+}
+
+impl<S> tracing_subscriber::Layer<S> for SpanRecordCapture
+where
+    S: tracing::Subscriber,
+{
+    fn on_record(
+        &self,
+        _id: &tracing::span::Id,
+        values: &tracing::span::Record<'_>,
+        _ctx: tracing_subscriber::layer::Context<'_, S>,
+    ) {
+        let mut visitor = FieldVisitor::default();
+        values.record(&mut visitor);
+        self.records.lock().unwrap().push(visitor.0);
+    }
+}
+
+/// Runs `f` with in-memory log capture installed and returns what was logged
+///
+/// Captures both the events logged while `f` runs and the fields recorded on
+/// spans (such as `http.status_code` and `duration_ms` on the proxy request span),
+/// so a test can drive a full request through the router and assert on the proxy's
+/// own log output. Events come first, in logging order; span records follow as
+/// entries without a `message`.
+#[allow(dead_code)] // ALLOWANCE: Used by tests that assert on end-to-end log output
+pub async fn capture_logs_during<F, Fut>(f: F) -> Vec<CapturedEvent>
+where
+    F: FnOnce() -> Fut,
+    Fut: std::future::Future<Output = ()>,
+{
+    use tracing_subscriber::layer::SubscriberExt;
+
+    let events = EventCapture::default();
+    let records = SpanRecordCapture::default();
+    let subscriber = tracing_subscriber::registry()
+        .with(events.clone())
+        .with(records.clone());
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    f().await;
+
+    let mut captured = events.events.lock().unwrap().clone();
+    captured.extend(records.records.lock().unwrap().iter().cloned());
+    captured
+}
+
+/// Returns the first captured value of `field`, from an event or a span record
+#[allow(dead_code)] // ALLOWANCE: Used by tests that assert on end-to-end log output
+pub fn captured_value<'a>(captured: &'a [CapturedEvent], field: &str) -> Option<&'a str> {
+    captured
+        .iter()
+        .find_map(|entry| entry.get(field))
+        .map(String::as_str)
+}
@@ -0,0 +1,76 @@
+// Integration tests for the concurrency limit and its bounded request queue
+mod common;
+
+use axum::body::Body;
+use axum::http::{header, Request, StatusCode};
+use std::time::Duration;
+use tower::ServiceExt;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+/// How long the mock upstream takes to answer, keeping the single slot busy
+const UPSTREAM_DELAY: Duration = Duration::from_millis(300);
+
+fn messages_request() -> Request<Body> {
+    Request::builder()
+        .method("POST")
+        .uri("/v1/messages")
+        .body(Body::from("{}"))
+        .unwrap()
+}
+
+/// Sends two requests at once through a router allowing one request at a time
+async fn send_two_concurrent(queue_timeout_ms: Option<u64>) -> (StatusCode, StatusCode) {
+    let test_setup = common::setup_test_environment_with_config(|config| {
+        config.max_concurrent_requests = Some(1);
+        config.queue_timeout_ms = queue_timeout_ms;
+    })
+    .await;
+
+    Mock::given(method("POST"))
+        .and(path("/v1/messages"))
+        .respond_with(ResponseTemplate::new(200).set_delay(UPSTREAM_DELAY))
+        .mount(&test_setup.mock_server)
+        .await;
+
+    let first = tokio::spawn(test_setup.app.clone().oneshot(messages_request()));
+    // Let the first request take the only slot before the second arrives
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    let second = test_setup.app.oneshot(messages_request()).await.unwrap();
+
+    let first = first.await.unwrap().unwrap();
+    if second.status() == StatusCode::SERVICE_UNAVAILABLE {
+        assert!(
+            second.headers().contains_key(header::RETRY_AFTER),
+            "Concurrency rejection should include Retry-After"
+        );
+    }
+    (first.status(), second.status())
+}
+
+/// Tests that a queued request waits for the busy slot and then succeeds
+#[tokio::test]
+async fn test_queued_request_succeeds_when_slot_frees_in_time() {
+    let (first, second) = send_two_concurrent(Some(5_000)).await;
+
+    assert_eq!(first, StatusCode::OK);
+    assert_eq!(second, StatusCode::OK, "Queued request should be forwarded");
+}
+
+/// Tests that a queued request gives up with 503 when the slot stays busy too long
+#[tokio::test]
+async fn test_queued_request_rejected_when_queue_timeout_elapses() {
+    let (first, second) = send_two_concurrent(Some(50)).await;
+
+    assert_eq!(first, StatusCode::OK);
+    assert_eq!(second, StatusCode::SERVICE_UNAVAILABLE);
+}
+
+/// Tests that without a queue timeout a saturated proxy rejects immediately
+#[tokio::test]
+async fn test_saturated_request_rejected_immediately_without_queue() {
+    let (first, second) = send_two_concurrent(None).await;
+
+    assert_eq!(first, StatusCode::OK);
+    assert_eq!(second, StatusCode::SERVICE_UNAVAILABLE);
+}
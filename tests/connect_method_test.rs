@@ -0,0 +1,37 @@
+// Integration tests for rejecting CONNECT requests
+mod common;
+
+use axum::body::Body;
+use axum::http::{header, Method, Request, StatusCode};
+use serde_json::Value;
+use tower::ServiceExt;
+
+/// Tests that CONNECT is rejected with 405 and never reaches the upstream
+#[tokio::test]
+async fn test_connect_rejected_without_forwarding() {
+    let test_setup = common::setup_test_environment().await;
+
+    let request = Request::builder()
+        .method(Method::CONNECT)
+        .uri("/v1/messages")
+        .body(Body::empty())
+        .unwrap();
+    let response = test_setup.app.oneshot(request).await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+    assert!(!response.headers()[header::ALLOW]
+        .to_str()
+        .unwrap()
+        .contains("CONNECT"));
+
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    let error: Value = serde_json::from_slice(&body).unwrap();
+    assert!(error["error"].as_str().unwrap().contains("CONNECT"));
+
+    assert!(test_setup
+        .mock_server
+        .received_requests()
+        .await
+        .unwrap()
+        .is_empty());
+}
@@ -0,0 +1,99 @@
+// Integration tests for warnings about unexpected request and response content types
+mod common;
+
+use axum::body::Body;
+use axum::http::{header, Request, StatusCode};
+use common::{CapturedEvent, EventCapture};
+use tower::ServiceExt;
+use tracing_subscriber::layer::SubscriberExt;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+/// Message of the warning about a non-JSON Messages request
+const REQUEST_ANOMALY: &str = "Messages request does not have a JSON content type";
+
+/// Message of the warning about a 200 response that is neither JSON nor SSE
+const RESPONSE_ANOMALY: &str = "Successful response is neither JSON nor an event stream";
+
+/// Proxies a Messages request sent as `request_type` to an upstream answering
+/// `upstream`, returning the logged events
+async fn proxy(request_type: &str, body: &str, upstream: ResponseTemplate) -> Vec<CapturedEvent> {
+    let capture = EventCapture::default();
+    let _guard =
+        tracing::subscriber::set_default(tracing_subscriber::registry().with(capture.clone()));
+
+    let test_setup = common::setup_test_environment_with_config(|config| {
+        config.warn_on_content_type_anomaly = true;
+    })
+    .await;
+    Mock::given(method("POST"))
+        .and(path("/v1/messages"))
+        .respond_with(upstream)
+        .mount(&test_setup.mock_server)
+        .await;
+
+    let request = Request::builder()
+        .method("POST")
+        .uri("/v1/messages")
+        .header(header::CONTENT_TYPE, request_type)
+        .body(Body::from(body.to_string()))
+        .unwrap();
+    let response = test_setup.app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let events = capture.events.lock().unwrap().clone();
+    events
+}
+
+/// Returns true if an event with `message` was logged
+fn logged(events: &[CapturedEvent], message: &str) -> bool {
+    events
+        .iter()
+        .any(|event| event.get("message").map(String::as_str) == Some(message))
+}
+
+/// Tests that a form-encoded POST to the Messages endpoint triggers a warning
+#[tokio::test]
+async fn test_form_encoded_messages_request_warns() {
+    let events = proxy(
+        "application/x-www-form-urlencoded",
+        "model=claude-3-haiku",
+        ResponseTemplate::new(200).set_body_raw("{}", "application/json"),
+    )
+    .await;
+
+    let event = common::find_event(&events, REQUEST_ANOMALY);
+    assert_eq!(
+        event["http.request.content_type"],
+        "Some(\"application/x-www-form-urlencoded\")"
+    );
+    assert!(!logged(&events, RESPONSE_ANOMALY));
+}
+
+/// Tests that a 200 answered with HTML triggers a warning
+#[tokio::test]
+async fn test_html_success_response_warns() {
+    let events = proxy(
+        "application/json",
+        "{}",
+        ResponseTemplate::new(200).set_body_raw("<html></html>", "text/html"),
+    )
+    .await;
+
+    assert!(logged(&events, RESPONSE_ANOMALY));
+    assert!(!logged(&events, REQUEST_ANOMALY));
+}
+
+/// Tests that a normal JSON request and response produce no warnings
+#[tokio::test]
+async fn test_json_request_and_response_do_not_warn() {
+    let events = proxy(
+        "application/json; charset=utf-8",
+        "{}",
+        ResponseTemplate::new(200).set_body_raw("{}", "application/json"),
+    )
+    .await;
+
+    assert!(!logged(&events, REQUEST_ANOMALY));
+    assert!(!logged(&events, RESPONSE_ANOMALY));
+}
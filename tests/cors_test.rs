@@ -0,0 +1,102 @@
+// Integration tests for answering CORS preflights and allowing origins on responses
+mod common;
+
+use axum::body::Body;
+use axum::http::{header, Request, StatusCode};
+use tower::ServiceExt;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+/// Origin of the web app calling the proxy in these tests
+const ORIGIN: &str = "https://app.example";
+
+/// Tests that a preflight is answered locally with the configured CORS headers
+#[tokio::test]
+async fn test_preflight_answered_locally() {
+    let test_setup = common::setup_test_environment_with_config(|config| {
+        config.cors_enabled = true;
+        config.cors_allow_origins = vec![ORIGIN.to_string()];
+    })
+    .await;
+
+    let request = Request::builder()
+        .method("OPTIONS")
+        .uri("/v1/messages")
+        .header(header::ORIGIN, ORIGIN)
+        .header(header::ACCESS_CONTROL_REQUEST_METHOD, "POST")
+        .header(
+            header::ACCESS_CONTROL_REQUEST_HEADERS,
+            "content-type, x-api-key",
+        )
+        .body(Body::empty())
+        .unwrap();
+    let response = test_setup.app.oneshot(request).await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let headers = response.headers();
+    assert_eq!(headers[header::ACCESS_CONTROL_ALLOW_ORIGIN], ORIGIN);
+    assert_eq!(
+        headers[header::ACCESS_CONTROL_ALLOW_METHODS],
+        "GET, POST, OPTIONS"
+    );
+    assert_eq!(
+        headers[header::ACCESS_CONTROL_ALLOW_HEADERS],
+        "content-type, anthropic-version, anthropic-beta, x-api-key, authorization"
+    );
+    assert_eq!(headers[header::VARY], "origin");
+
+    // Nothing reached the upstream
+    let received = test_setup.mock_server.received_requests().await.unwrap();
+    assert!(received.is_empty());
+}
+
+/// Tests that proxied responses carry the allow-origin header
+#[tokio::test]
+async fn test_proxied_response_gets_allow_origin() {
+    let test_setup = common::setup_test_environment_with_config(|config| {
+        config.cors_enabled = true;
+    })
+    .await;
+    Mock::given(method("POST"))
+        .and(path("/v1/messages"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("{}"))
+        .mount(&test_setup.mock_server)
+        .await;
+
+    let request = Request::builder()
+        .method("POST")
+        .uri("/v1/messages")
+        .header(header::ORIGIN, ORIGIN)
+        .body(Body::from("{}"))
+        .unwrap();
+    let response = test_setup.app.oneshot(request).await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[header::ACCESS_CONTROL_ALLOW_ORIGIN], "*");
+}
+
+/// Tests that OPTIONS is proxied, and no CORS headers added, when CORS is disabled
+#[tokio::test]
+async fn test_options_proxied_when_disabled() {
+    let test_setup = common::setup_test_environment().await;
+    Mock::given(method("OPTIONS"))
+        .and(path("/v1/messages"))
+        .respond_with(ResponseTemplate::new(204))
+        .mount(&test_setup.mock_server)
+        .await;
+
+    let request = Request::builder()
+        .method("OPTIONS")
+        .uri("/v1/messages")
+        .header(header::ORIGIN, ORIGIN)
+        .body(Body::empty())
+        .unwrap();
+    let response = test_setup.app.oneshot(request).await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    assert!(!response
+        .headers()
+        .contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN));
+    let received = test_setup.mock_server.received_requests().await.unwrap();
+    assert_eq!(received.len(), 1);
+}
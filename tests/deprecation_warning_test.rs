@@ -0,0 +1,65 @@
+// Integration tests for warning about deprecation headers on upstream responses
+mod common;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use common::{find_event, EventCapture};
+use tower::ServiceExt;
+use tracing_subscriber::layer::SubscriberExt;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+const DEPRECATION_MESSAGE: &str = "Upstream response carries a deprecation notice";
+
+/// Sends a messages request answered with a deprecation header and returns the captured events
+async fn request_with_deprecation_header(warn_on_deprecation: bool) -> Vec<common::CapturedEvent> {
+    let capture = EventCapture::default();
+    let _guard =
+        tracing::subscriber::set_default(tracing_subscriber::registry().with(capture.clone()));
+
+    let test_setup = common::setup_test_environment_with_config(|config| {
+        config.warn_on_deprecation = warn_on_deprecation;
+    })
+    .await;
+    Mock::given(method("POST"))
+        .and(path("/v1/messages"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("anthropic-deprecation", "claude-2.0 retires on 2025-01-01")
+                .set_body_string("{}"),
+        )
+        .mount(&test_setup.mock_server)
+        .await;
+
+    let request = Request::builder()
+        .method("POST")
+        .uri("/v1/messages")
+        .body(Body::from(r#"{"model":"claude-2.0","messages":[]}"#))
+        .unwrap();
+    let response = test_setup.app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let events = capture.events.lock().unwrap().clone();
+    events
+}
+
+/// Tests that a deprecation header is logged with its value and the request's model
+#[tokio::test]
+async fn test_deprecation_header_logs_warning() {
+    let events = request_with_deprecation_header(true).await;
+    let warning = find_event(&events, DEPRECATION_MESSAGE);
+
+    assert_eq!(warning["header"], "\"anthropic-deprecation\"");
+    assert_eq!(warning["value"], "claude-2.0 retires on 2025-01-01");
+    assert_eq!(warning["model"], "\"claude-2.0\"");
+}
+
+/// Tests that no warning is logged when disabled
+#[tokio::test]
+async fn test_deprecation_warning_can_be_disabled() {
+    let events = request_with_deprecation_header(false).await;
+
+    assert!(events
+        .iter()
+        .all(|event| event.get("message").map(String::as_str) != Some(DEPRECATION_MESSAGE)));
+}
@@ -24,6 +24,7 @@ fn create_test_config(log_file_path: &str, log_directory_mode: LogDirectoryMode)
         log_max_body_size: 1024,
         log_directory_mode,
         log_max_age_days: None,
+        ..Default::default()
     }
 }
 
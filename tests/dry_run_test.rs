@@ -0,0 +1,47 @@
+// Integration tests for dry-run mode, which logs requests without forwarding them
+mod common;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use tower::ServiceExt;
+use wiremock::matchers::any;
+use wiremock::{Mock, ResponseTemplate};
+
+/// Tests that dry-run mode answers with a canned response and never calls upstream
+#[tokio::test]
+async fn test_dry_run_returns_canned_response_without_forwarding() {
+    let test_setup = common::setup_test_environment_with_config(|config| {
+        config.dry_run_proxy = true;
+    })
+    .await;
+    Mock::given(any())
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&test_setup.mock_server)
+        .await;
+
+    let request = Request::builder()
+        .method("POST")
+        .uri("/v1/messages?beta=true")
+        .body(Body::from(r#"{"model": "claude-3-haiku"}"#))
+        .unwrap();
+    let response = test_setup.app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(
+        body,
+        serde_json::json!({
+            "dry_run": true,
+            "would_forward_to": format!("{}/v1/messages?beta=true", test_setup.mock_server.uri()),
+        })
+    );
+
+    assert!(test_setup
+        .mock_server
+        .received_requests()
+        .await
+        .unwrap()
+        .is_empty());
+}
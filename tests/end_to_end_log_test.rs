@@ -0,0 +1,46 @@
+// Integration tests asserting on the proxy's own log output for a full request
+mod common;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use common::{capture_logs_during, captured_value, find_event};
+use tower::ServiceExt;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+/// Tests that a proxied request logs its method, path, status and duration
+#[tokio::test]
+async fn test_proxied_request_logs_expected_fields() {
+    let test_setup = common::setup_test_environment().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/messages"))
+        .respond_with(ResponseTemplate::new(201).set_body_string("{}"))
+        .mount(&test_setup.mock_server)
+        .await;
+
+    let logs = capture_logs_during(|| async {
+        let request = Request::builder()
+            .method("POST")
+            .uri("/v1/messages")
+            .body(Body::from(r#"{"model":"claude-3-haiku","messages":[]}"#))
+            .unwrap();
+        let response = test_setup.app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+    })
+    .await;
+
+    // Fields recorded on the request span
+    assert_eq!(captured_value(&logs, "http.method"), Some("POST"));
+    assert_eq!(captured_value(&logs, "url.path"), Some("\"/v1/messages\""));
+    assert_eq!(captured_value(&logs, "http.status_code"), Some("201"));
+    assert_eq!(captured_value(&logs, "anthropic.message_count"), Some("0"));
+    let duration_ms: u64 = captured_value(&logs, "duration_ms")
+        .expect("duration_ms should be recorded")
+        .parse()
+        .expect("duration_ms should be numeric");
+    assert!(duration_ms < 60_000);
+
+    // Events logged along the way
+    find_event(&logs, "Starting request processing");
+    find_event(&logs, "Received response from Anthropic API");
+}
@@ -0,0 +1,98 @@
+// Integration tests for capping max_tokens of Messages API requests
+mod common;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use common::SpanRecordCapture;
+use serde_json::{json, Value};
+use tower::ServiceExt;
+use tracing_subscriber::layer::SubscriberExt;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+/// Ceiling configured for every test
+const CEILING: u64 = 1024;
+
+/// Sends `body` through a proxy enforcing `CEILING`, returning what the upstream received
+///
+/// Also returns the values recorded for `anthropic.max_tokens_clamped`.
+async fn forward(body: &str) -> (Vec<u8>, Option<String>, Vec<String>) {
+    let capture = SpanRecordCapture::default();
+    let _guard =
+        tracing::subscriber::set_default(tracing_subscriber::registry().with(capture.clone()));
+
+    let test_setup = common::setup_test_environment_with_config(|config| {
+        config.enforce_max_tokens = Some(CEILING);
+    })
+    .await;
+    Mock::given(method("POST"))
+        .and(path("/v1/messages"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&test_setup.mock_server)
+        .await;
+
+    let request = Request::builder()
+        .method("POST")
+        .uri("/v1/messages")
+        .header("content-type", "application/json")
+        .header("content-length", body.len())
+        .body(Body::from(body.to_string()))
+        .unwrap();
+    let response = test_setup.app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let received = test_setup.mock_server.received_requests().await.unwrap();
+    assert_eq!(received.len(), 1);
+    let content_length = received[0]
+        .headers
+        .get("content-length")
+        .map(|value| value.to_str().unwrap().to_string());
+    (
+        received[0].body.clone(),
+        content_length,
+        capture.values("anthropic.max_tokens_clamped"),
+    )
+}
+
+/// Tests that a max_tokens above the ceiling is lowered to it
+#[tokio::test]
+async fn test_too_high_max_tokens_is_clamped() {
+    let (body, content_length, clamped) =
+        forward(r#"{"model":"claude-3-haiku","max_tokens":100000,"messages":[]}"#).await;
+
+    let forwarded: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(forwarded["max_tokens"], json!(CEILING));
+    assert_eq!(forwarded["model"], "claude-3-haiku");
+    assert_eq!(content_length, Some(body.len().to_string()));
+    assert_eq!(clamped, vec!["true"]);
+}
+
+/// Tests that a missing max_tokens is set to the ceiling
+#[tokio::test]
+async fn test_missing_max_tokens_is_injected() {
+    let (body, content_length, clamped) =
+        forward(r#"{"model":"claude-3-haiku","messages":[]}"#).await;
+
+    let forwarded: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(forwarded["max_tokens"], json!(CEILING));
+    assert_eq!(content_length, Some(body.len().to_string()));
+    assert_eq!(clamped, vec!["true"]);
+}
+
+/// Tests that a max_tokens within the ceiling leaves the body byte-for-byte unchanged
+#[tokio::test]
+async fn test_lower_max_tokens_is_left_alone() {
+    let original = r#"{"model": "claude-3-haiku", "max_tokens": 256, "messages": []}"#;
+    let (body, _, clamped) = forward(original).await;
+
+    assert_eq!(body, original.as_bytes());
+    assert_eq!(clamped, vec!["false"]);
+}
+
+/// Tests that bodies that are not JSON objects are forwarded untouched
+#[tokio::test]
+async fn test_non_json_body_is_untouched() {
+    let (body, _, _) = forward("max_tokens=100000").await;
+
+    assert_eq!(body, b"max_tokens=100000");
+}
@@ -0,0 +1,92 @@
+// Integration tests for adding the request ID to JSON error bodies
+mod common;
+
+use axum::body::Body;
+use axum::http::{header, Request, StatusCode};
+use axum::response::Response;
+use serde_json::Value;
+use switchboard::config::Config;
+use tower::ServiceExt;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+/// Proxies a Messages request to an upstream answering `upstream`
+async fn proxy_to(upstream: ResponseTemplate, configure: impl FnOnce(&mut Config)) -> Response {
+    let test_setup = common::setup_test_environment_with_config(|config| {
+        config.inject_request_id_in_errors = true;
+        configure(config);
+    })
+    .await;
+    Mock::given(method("POST"))
+        .and(path("/v1/messages"))
+        .respond_with(upstream)
+        .mount(&test_setup.mock_server)
+        .await;
+
+    let request = Request::builder()
+        .method("POST")
+        .uri("/v1/messages")
+        .body(Body::from("{}"))
+        .unwrap();
+    test_setup.app.oneshot(request).await.unwrap()
+}
+
+/// Reads a response body, checking Content-Length matches it
+async fn body_of(response: Response) -> Vec<u8> {
+    let declared = response.headers().get(header::CONTENT_LENGTH).cloned();
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    if let Some(declared) = declared {
+        assert_eq!(declared.to_str().unwrap(), body.len().to_string());
+    }
+    body.to_vec()
+}
+
+/// Tests that an error the proxy generates gets the request ID
+#[tokio::test]
+async fn test_request_id_added_to_generated_error() {
+    let response = proxy_to(ResponseTemplate::new(200), |config| {
+        config.require_headers = vec!["x-team-id".to_string()];
+    })
+    .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let body: Value = serde_json::from_slice(&body_of(response).await).unwrap();
+    assert_eq!(body["missing_header"], "x-team-id");
+    assert!(!body["request_id"].as_str().unwrap().is_empty());
+}
+
+/// Tests that a forwarded upstream JSON error gets the request ID, keeping its fields
+#[tokio::test]
+async fn test_request_id_added_to_upstream_json_error() {
+    let upstream_error =
+        r#"{"type":"error","error":{"type":"invalid_request_error","message":"bad"}}"#;
+    let response = proxy_to(
+        ResponseTemplate::new(400).set_body_raw(upstream_error, "application/json"),
+        |_| {},
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let body: Value = serde_json::from_slice(&body_of(response).await).unwrap();
+    assert_eq!(body["error"]["type"], "invalid_request_error");
+    assert!(!body["request_id"].as_str().unwrap().is_empty());
+}
+
+/// Tests that non-JSON error bodies and disabled injection leave bodies unchanged
+#[tokio::test]
+async fn test_body_unchanged_when_not_json_or_disabled() {
+    let response = proxy_to(
+        ResponseTemplate::new(502).set_body_raw("Bad Gateway", "text/plain"),
+        |_| {},
+    )
+    .await;
+    assert_eq!(body_of(response).await, b"Bad Gateway");
+
+    let upstream_error = r#"{"type":"error"}"#;
+    let response = proxy_to(
+        ResponseTemplate::new(500).set_body_raw(upstream_error, "application/json"),
+        |config| config.inject_request_id_in_errors = false,
+    )
+    .await;
+    assert_eq!(body_of(response).await, upstream_error.as_bytes());
+}
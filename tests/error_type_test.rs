@@ -0,0 +1,65 @@
+// Integration tests for recording the upstream error type on the request span
+mod common;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use common::SpanRecordCapture;
+use tower::ServiceExt;
+use tracing_subscriber::layer::SubscriberExt;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+/// Sends a messages request answered with `response` and returns the recorded error types
+async fn recorded_error_types(response: ResponseTemplate) -> Vec<String> {
+    let capture = SpanRecordCapture::default();
+    let _guard =
+        tracing::subscriber::set_default(tracing_subscriber::registry().with(capture.clone()));
+
+    let test_setup = common::setup_test_environment().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/messages"))
+        .respond_with(response)
+        .mount(&test_setup.mock_server)
+        .await;
+
+    let request = Request::builder()
+        .method("POST")
+        .uri("/v1/messages")
+        .body(Body::from("{}"))
+        .unwrap();
+    test_setup.app.oneshot(request).await.unwrap();
+
+    capture.values("anthropic.error_type")
+}
+
+/// Tests that the error type of a typed error body is recorded
+#[tokio::test]
+async fn test_rate_limit_error_type_recorded() {
+    let error_body =
+        r#"{"type":"error","error":{"type":"rate_limit_error","message":"Slow down"}}"#;
+    let error_types = recorded_error_types(
+        ResponseTemplate::new(StatusCode::TOO_MANY_REQUESTS.as_u16())
+            .set_body_raw(error_body, "application/json"),
+    )
+    .await;
+
+    // String fields are captured in Debug form, i.e. quoted
+    assert_eq!(error_types, vec![format!("{:?}", "rate_limit_error")]);
+}
+
+/// Tests that successful and untyped error responses record nothing
+#[tokio::test]
+async fn test_no_error_type_without_typed_error_body() {
+    let typed_body = r#"{"type":"error","error":{"type":"api_error"}}"#;
+    assert!(recorded_error_types(
+        ResponseTemplate::new(200).set_body_raw(typed_body, "application/json")
+    )
+    .await
+    .is_empty());
+
+    assert!(
+        recorded_error_types(ResponseTemplate::new(502).set_body_string("Bad Gateway"))
+            .await
+            .is_empty()
+    );
+}
@@ -0,0 +1,96 @@
+// Integration tests for sending rewritten request bodies chunked
+mod common;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use serde_json::{json, Value};
+use switchboard::config::EmptyBodyPolicy;
+use tower::ServiceExt;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+/// What the upstream received for one forwarded request
+struct Received {
+    body: Vec<u8>,
+    content_length: Option<String>,
+    transfer_encoding: Option<String>,
+}
+
+/// Sends `body` to `/v1/messages` through a proxy that caps max_tokens at 1024
+async fn forward(body: &str, force_chunked_on_rewrite: bool) -> Received {
+    let test_setup = common::setup_test_environment_with_config(|config| {
+        config.enforce_max_tokens = Some(1024);
+        config.empty_post_body = EmptyBodyPolicy::EmptyJson;
+        config.force_chunked_on_rewrite = force_chunked_on_rewrite;
+    })
+    .await;
+    Mock::given(method("POST"))
+        .and(path("/v1/messages"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&test_setup.mock_server)
+        .await;
+
+    let request = Request::builder()
+        .method("POST")
+        .uri("/v1/messages")
+        .header("content-type", "application/json")
+        .header("content-length", body.len())
+        .body(Body::from(body.to_string()))
+        .unwrap();
+    let response = test_setup.app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let received = test_setup.mock_server.received_requests().await.unwrap();
+    assert_eq!(received.len(), 1);
+    let header = |name: &str| {
+        received[0]
+            .headers
+            .get(name)
+            .map(|value| value.to_str().unwrap().to_string())
+    };
+    Received {
+        body: received[0].body.clone(),
+        content_length: header("content-length"),
+        transfer_encoding: header("transfer-encoding"),
+    }
+}
+
+/// Tests that by default a rewritten body carries its exact new length
+#[tokio::test]
+async fn test_rewritten_body_has_exact_content_length_by_default() {
+    let received = forward(r#"{"model":"m","max_tokens":100000,"messages":[]}"#, false).await;
+
+    let forwarded: Value = serde_json::from_slice(&received.body).unwrap();
+    assert_eq!(forwarded["max_tokens"], json!(1024));
+    assert_eq!(
+        received.content_length,
+        Some(received.body.len().to_string())
+    );
+    assert_eq!(received.transfer_encoding, None);
+}
+
+/// Tests that with the flag a rewritten body is sent chunked, without Content-Length
+#[tokio::test]
+async fn test_rewritten_body_is_chunked_when_forced() {
+    let received = forward(r#"{"model":"m","max_tokens":100000,"messages":[]}"#, true).await;
+
+    let forwarded: Value = serde_json::from_slice(&received.body).unwrap();
+    assert_eq!(forwarded["max_tokens"], json!(1024));
+    assert_eq!(received.content_length, None);
+    assert_eq!(received.transfer_encoding.as_deref(), Some("chunked"));
+
+    // The empty JSON substitution is a rewrite too
+    let received = forward("", true).await;
+    assert_eq!(received.body, b"{}");
+    assert_eq!(received.content_length, None);
+}
+
+/// Tests that bodies the proxy leaves alone keep their Content-Length
+#[tokio::test]
+async fn test_untouched_body_keeps_content_length_when_forced() {
+    let body = r#"{"model":"m","max_tokens":10,"messages":[]}"#;
+    let received = forward(body, true).await;
+
+    assert_eq!(received.body, body.as_bytes());
+    assert_eq!(received.content_length, Some(body.len().to_string()));
+}
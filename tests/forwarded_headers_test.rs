@@ -0,0 +1,73 @@
+// Integration tests for adding X-Forwarded-* headers to forwarded requests
+mod common;
+
+use axum::body::Body;
+use axum::extract::ConnectInfo;
+use axum::http::{header, Request, StatusCode};
+use std::net::SocketAddr;
+use tower::ServiceExt;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+/// Sends a request from 203.0.113.7 through an existing proxy hop and returns
+/// the X-Forwarded-* headers the upstream received
+async fn forwarded_headers(add_forwarded_headers: bool) -> Vec<(String, String)> {
+    let test_setup = common::setup_test_environment_with_config(|config| {
+        config.add_forwarded_headers = add_forwarded_headers;
+    })
+    .await;
+    Mock::given(method("GET"))
+        .and(path("/v1/models"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&test_setup.mock_server)
+        .await;
+
+    let mut request = Request::builder()
+        .uri("/v1/models")
+        .header(header::HOST, "proxy.example.com")
+        .header("x-forwarded-for", "198.51.100.1")
+        .body(Body::empty())
+        .unwrap();
+    let client: SocketAddr = "203.0.113.7:54321".parse().unwrap();
+    request.extensions_mut().insert(ConnectInfo(client));
+    let response = test_setup.app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let received = test_setup.mock_server.received_requests().await.unwrap();
+    let mut headers: Vec<(String, String)> = received[0]
+        .headers
+        .iter()
+        .filter(|(name, _)| name.as_str().starts_with("x-forwarded-"))
+        .map(|(name, value)| (name.to_string(), value.to_str().unwrap().to_string()))
+        .collect();
+    headers.sort();
+    headers
+}
+
+/// Tests that the client IP is appended and the original host and scheme are added
+#[tokio::test]
+async fn test_forwarded_headers_added_when_enabled() {
+    assert_eq!(
+        forwarded_headers(true).await,
+        vec![
+            (
+                "x-forwarded-for".to_string(),
+                "198.51.100.1, 203.0.113.7".to_string()
+            ),
+            (
+                "x-forwarded-host".to_string(),
+                "proxy.example.com".to_string()
+            ),
+            ("x-forwarded-proto".to_string(), "http".to_string()),
+        ]
+    );
+}
+
+/// Tests that incoming forwarded headers pass through untouched when disabled
+#[tokio::test]
+async fn test_forwarded_headers_untouched_when_disabled() {
+    assert_eq!(
+        forwarded_headers(false).await,
+        vec![("x-forwarded-for".to_string(), "198.51.100.1".to_string())]
+    );
+}
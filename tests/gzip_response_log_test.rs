@@ -0,0 +1,72 @@
+// Integration tests for logging gzip-encoded response bodies
+mod common;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use common::EventCapture;
+use serde_json::{json, Value};
+use tower::ServiceExt;
+use tracing_subscriber::layer::SubscriberExt;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+/// A Messages API response, gzip-compressed with a dynamic Huffman block
+const GZIPPED_RESPONSE: [u8; 140] = [
+    0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x03, 0xcd, 0x8e, 0x51, 0x0a, 0x02, 0x31,
+    0x0c, 0x44, 0xaf, 0xa2, 0xf9, 0xde, 0x05, 0xc5, 0xbf, 0x9e, 0xc0, 0x3b, 0x88, 0x94, 0xb0, 0x0d,
+    0x6b, 0x31, 0xdb, 0x2c, 0x4d, 0x0a, 0xca, 0xb2, 0x77, 0x37, 0x15, 0xbc, 0x83, 0x5f, 0x33, 0xf3,
+    0x66, 0x3e, 0x66, 0x83, 0x9c, 0x20, 0xc0, 0xa2, 0x73, 0x3c, 0x9d, 0x61, 0x00, 0x7b, 0xaf, 0xd4,
+    0x33, 0xa9, 0xe2, 0x4c, 0x0e, 0xaa, 0x70, 0x07, 0xa8, 0x9a, 0xd5, 0xb0, 0x98, 0xa3, 0x49, 0x8a,
+    0x91, 0xbb, 0x70, 0xdb, 0x7e, 0x7b, 0xa3, 0x57, 0x6f, 0xbe, 0x12, 0xe0, 0x4a, 0xcc, 0x72, 0x3c,
+    0xfc, 0x85, 0xc0, 0x7e, 0x1f, 0x60, 0x91, 0x44, 0xec, 0xc7, 0x26, 0xc6, 0x96, 0x68, 0xbc, 0x8c,
+    0x0f, 0xcc, 0xcf, 0xe6, 0x87, 0xd5, 0x64, 0x8d, 0x95, 0x50, 0xa5, 0x78, 0x4d, 0x25, 0x45, 0x6b,
+    0xb5, 0xc0, 0xfe, 0x01, 0xf0, 0x65, 0x16, 0xea, 0x17, 0x01, 0x00, 0x00,
+];
+
+/// Tests that a gzip response is logged decompressed but forwarded compressed
+#[tokio::test]
+async fn test_gzip_response_body_logged_decompressed() {
+    let capture = EventCapture::default();
+    let _guard =
+        tracing::subscriber::set_default(tracing_subscriber::registry().with(capture.clone()));
+
+    let test_setup = common::setup_test_environment_with_config(|config| {
+        config.log_bodies = true;
+    })
+    .await;
+    Mock::given(method("POST"))
+        .and(path("/v1/messages"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("content-encoding", "gzip")
+                .insert_header("content-type", "application/json")
+                .set_body_bytes(GZIPPED_RESPONSE.to_vec()),
+        )
+        .mount(&test_setup.mock_server)
+        .await;
+
+    let request = Request::builder()
+        .method("POST")
+        .uri("/v1/messages")
+        .body(Body::from(r#"{"model":"claude-3-haiku","messages":[]}"#))
+        .unwrap();
+    let response = test_setup.app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["content-encoding"], "gzip");
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    assert_eq!(body, GZIPPED_RESPONSE.as_slice());
+
+    let events = capture.events.lock().unwrap().clone();
+    let event = events
+        .iter()
+        .find(|event| event.contains_key("http.response.body.content"))
+        .expect("Response body should be logged");
+    let logged: Value = serde_json::from_str(&event["http.response.body.content"]).unwrap();
+    assert_eq!(logged["model"], json!("claude-3-haiku"));
+    assert_eq!(logged["content"][0]["text"], json!("Hello! ".repeat(20)));
+    assert_eq!(
+        event["http.response.body.size"],
+        GZIPPED_RESPONSE.len().to_string()
+    );
+    assert_eq!(event["http.response.body.decompressed_size"], "279");
+}
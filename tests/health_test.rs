@@ -0,0 +1,67 @@
+// Integration tests for the /healthz endpoint
+mod common;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use serde_json::Value;
+use tower::ServiceExt;
+
+async fn get_health(app: axum::Router, uri: &str) -> Value {
+    let request = Request::builder().uri(uri).body(Body::empty()).unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    serde_json::from_slice(&body).unwrap()
+}
+
+/// Tests that the basic probe is answered locally and omits logging details
+#[tokio::test]
+async fn test_basic_health_check_omits_logging() {
+    let test_setup = common::setup_test_environment().await;
+
+    let health = get_health(test_setup.app, "/healthz").await;
+
+    assert_eq!(health["status"], "ok");
+    assert!(health.get("logging").is_none());
+    // The upstream mock has no routes, so any forwarded request would have been logged there
+    assert!(test_setup
+        .mock_server
+        .received_requests()
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+/// Tests that the verbose health check reports the log path and writability
+#[tokio::test]
+async fn test_verbose_health_check_reports_logging_status() {
+    let test_setup = common::setup_test_environment().await;
+
+    let health = get_health(test_setup.app, "/healthz?verbose=true").await;
+
+    assert_eq!(health["status"], "ok");
+    let log_path = health["logging"]["log_path"]
+        .as_str()
+        .expect("log_path should be a string");
+    assert!(
+        log_path.ends_with("test-switchboard.log"),
+        "Unexpected log path {}",
+        log_path
+    );
+    assert!(health["logging"]["writable"].is_boolean());
+}
+
+/// Tests that the health check reports the commit the binary was built from
+#[tokio::test]
+async fn test_health_check_reports_build_commit() {
+    let test_setup = common::setup_test_environment().await;
+
+    let health = get_health(test_setup.app, "/healthz").await;
+
+    assert_eq!(
+        health["build"]["commit"],
+        switchboard::build_info::BUILD_COMMIT
+    );
+    assert!(!switchboard::build_info::BUILD_COMMIT.is_empty());
+}
@@ -0,0 +1,44 @@
+// Integration tests for the periodic heartbeat log event
+mod common;
+
+use common::EventCapture;
+use std::sync::Arc;
+use std::time::Duration;
+use switchboard::config::Config;
+use switchboard::heartbeat::spawn_heartbeat;
+use switchboard::proxy_handler::ProxyState;
+use tracing_subscriber::layer::SubscriberExt;
+
+/// Tests that heartbeats are logged at the interval and stop once the task is cancelled
+#[tokio::test]
+async fn test_heartbeat_emits_until_cancelled() {
+    let capture = EventCapture::default();
+    let _guard =
+        tracing::subscriber::set_default(tracing_subscriber::registry().with(capture.clone()));
+
+    let state = Arc::new(ProxyState::new(&Config::default()));
+    state.next_request_seq();
+    state.next_request_seq();
+
+    let heartbeat = spawn_heartbeat(Duration::from_millis(20), state);
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    heartbeat.abort();
+    assert!(heartbeat.await.unwrap_err().is_cancelled());
+
+    let heartbeats: Vec<_> = capture
+        .events
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|event| event.get("message").map(String::as_str) == Some("heartbeat"))
+        .cloned()
+        .collect();
+    assert!(!heartbeats.is_empty(), "Expected at least one heartbeat");
+    assert_eq!(heartbeats[0]["requests_served"], "2");
+    assert!(heartbeats[0].contains_key("uptime_secs"));
+
+    // Nothing more is logged after cancellation
+    let count = capture.events.lock().unwrap().len();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert_eq!(capture.events.lock().unwrap().len(), count);
+}
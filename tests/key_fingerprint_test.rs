@@ -0,0 +1,50 @@
+// Integration tests for recording the API key fingerprint on the request span
+mod common;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use common::SpanRecordCapture;
+use switchboard::config::key_fingerprint;
+use tower::ServiceExt;
+use tracing_subscriber::layer::SubscriberExt;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+/// Tests that the fingerprint, never the key, is recorded when enabled
+#[tokio::test]
+async fn test_key_fingerprint_recorded_without_key() {
+    let capture = SpanRecordCapture::default();
+    let _guard =
+        tracing::subscriber::set_default(tracing_subscriber::registry().with(capture.clone()));
+
+    let test_setup = common::setup_test_environment_with_config(|config| {
+        config.log_key_fingerprint = true;
+    })
+    .await;
+    Mock::given(method("GET"))
+        .and(path("/v1/models"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&test_setup.mock_server)
+        .await;
+
+    let request = Request::builder()
+        .uri("/v1/models")
+        .body(Body::empty())
+        .unwrap();
+    let response = test_setup.app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let key = &test_setup.config.anthropic_api_key;
+    // String fields are captured in Debug form, i.e. quoted
+    assert_eq!(
+        capture.values("key_fingerprint"),
+        vec![format!("{:?}", key_fingerprint(key))]
+    );
+
+    // The key itself is never recorded on the span
+    let records = capture.records.lock().unwrap();
+    assert!(records
+        .iter()
+        .flat_map(|record| record.values())
+        .all(|value| !value.contains(key.as_str())));
+}
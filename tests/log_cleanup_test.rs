@@ -5,7 +5,7 @@ use std::io::Write;
 use std::path::Path;
 use std::time::{Duration, SystemTime};
 use switchboard::config::{Config, LogDirectoryMode};
-use switchboard::log_cleanup::cleanup_logs;
+use switchboard::log_cleanup::{cleanup_logs, cleanup_logs_in_dir};
 use switchboard::logger::{APP_LOG_SUBDIR, DEFAULT_LOG_DIR, TEST_LOG_SUBDIR};
 
 // Utility function to create a log file with a specific age
@@ -77,6 +77,7 @@ fn test_log_cleanup_with_max_age() {
         log_max_body_size: 1024,
         log_directory_mode: LogDirectoryMode::Default,
         log_max_age_days: Some(7),
+        ..Default::default()
     };
 
     // Run the cleanup
@@ -133,6 +134,7 @@ fn test_log_cleanup_with_disabled_max_age() {
         log_max_body_size: 1024,
         log_directory_mode: LogDirectoryMode::Default,
         log_max_age_days: None,
+        ..Default::default()
     };
 
     // Run the cleanup
@@ -179,6 +181,7 @@ fn test_log_cleanup_with_zero_max_age() {
         log_max_body_size: 1024,
         log_directory_mode: LogDirectoryMode::Default,
         log_max_age_days: Some(0),
+        ..Default::default()
     };
 
     // Run the cleanup
@@ -197,3 +200,39 @@ fn test_log_cleanup_with_zero_max_age() {
         fs::remove_file(old_log).ok();
     }
 }
+
+#[test]
+fn test_cleanup_logs_in_custom_dir_removes_only_old_files() {
+    // Any directory can be cleaned, not just the derived app/test log directories
+    let temp_dir = tempfile::tempdir().unwrap();
+    let dir = temp_dir.path();
+
+    let recent = dir.join("recent.log");
+    let old = dir.join("old.log");
+    let old_rotated = dir.join("service.log.2023-01-01");
+    create_test_log_file(&recent, "recent log", 1).unwrap();
+    create_test_log_file(&old, "old log", 10).unwrap();
+    create_test_log_file(&old_rotated, "old rotated log", 20).unwrap();
+
+    let result = cleanup_logs_in_dir(dir, Duration::from_secs(7 * 24 * 60 * 60), false);
+
+    assert_eq!(result.files_removed, 2);
+    assert!(result.failed_files.is_empty());
+    assert!(recent.exists());
+    assert!(!old.exists());
+    assert!(!old_rotated.exists());
+}
+
+#[test]
+fn test_cleanup_logs_in_dir_dry_run_keeps_files() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let old = temp_dir.path().join("old.log");
+    create_test_log_file(&old, "old log", 10).unwrap();
+
+    let result = cleanup_logs_in_dir(temp_dir.path(), Duration::from_secs(7 * 24 * 60 * 60), true);
+
+    // The file is reported as removable but left in place
+    assert_eq!(result.files_removed, 1);
+    assert_eq!(result.bytes_removed, "old log".len() as u64);
+    assert!(old.exists());
+}
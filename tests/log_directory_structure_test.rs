@@ -30,6 +30,7 @@ fn generate_log_paths(base_name: &str) -> LogPaths {
         log_max_body_size: 1024,
         log_directory_mode: switchboard::config::LogDirectoryMode::Default,
         log_max_age_days: None,
+        ..Default::default()
     };
 
     // Create resolvers for both app and test logs
@@ -228,6 +229,7 @@ fn test_all_log_types() {
         log_max_body_size: 1024,
         log_directory_mode: switchboard::config::LogDirectoryMode::Default,
         log_max_age_days: None,
+        ..Default::default()
     };
 
     // Get app log path
@@ -250,6 +252,7 @@ fn test_all_log_types() {
         log_max_body_size: 1024,
         log_directory_mode: switchboard::config::LogDirectoryMode::Default,
         log_max_age_days: None,
+        ..Default::default()
     };
 
     // Get test log path
@@ -290,3 +293,37 @@ fn test_all_log_types() {
     cleanup_test_log_file(&app_log_path);
     cleanup_test_log_file(&test_log_path);
 }
+
+#[test]
+fn test_log_base_dir_override() {
+    let base_dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let config = Config {
+        log_file_path: "./elsewhere/base_dir_test.log".to_string(),
+        log_base_dir: Some(base_dir.path().to_string_lossy().to_string()),
+        ..Default::default()
+    };
+
+    // The override replaces the environment's base, keeping the subdirectory and file name
+    let app_path = LogPathResolver::new(&config, LogType::Application)
+        .resolve()
+        .expect("Failed to resolve app log path");
+    assert_eq!(
+        app_path,
+        base_dir
+            .path()
+            .join(APP_LOG_SUBDIR)
+            .join("base_dir_test.log")
+    );
+    assert!(base_dir.path().join(APP_LOG_SUBDIR).is_dir());
+
+    let test_path = LogPathResolver::new(&config, LogType::Test)
+        .resolve_readonly()
+        .expect("Failed to resolve test log path");
+    assert_eq!(
+        test_path,
+        base_dir
+            .path()
+            .join(TEST_LOG_SUBDIR)
+            .join("base_dir_test.log")
+    );
+}
@@ -0,0 +1,58 @@
+use crate::common::find_log_file;
+use std::io::Write;
+use switchboard::log_naming::{self, DatedFileAppender};
+use tracing_appender::rolling;
+
+mod common;
+
+#[test]
+fn test_finds_files_with_date_after_extension() {
+    let temp_dir = tempfile::tempdir().unwrap();
+
+    // The default scheme, as written by the daily rolling appender
+    let mut appender = rolling::daily(temp_dir.path(), "switchboard.log");
+    appender
+        .write_all(b"{\"message\":\"dated suffix\"}\n")
+        .unwrap();
+    appender.flush().unwrap();
+
+    let found = find_log_file(&temp_dir.path().join("switchboard.log"))
+        .expect("Log file with the date after the extension should be found");
+    let name = found.file_name().unwrap().to_string_lossy().to_string();
+    assert!(name.starts_with("switchboard.log."), "{}", name);
+    assert!(log_naming::log_file_date(&name).is_some(), "{}", name);
+}
+
+#[test]
+fn test_finds_files_with_date_in_name() {
+    let temp_dir = tempfile::tempdir().unwrap();
+
+    let mut appender = DatedFileAppender::new(temp_dir.path(), "switchboard").unwrap();
+    appender
+        .write_all(b"{\"message\":\"date in name\"}\n")
+        .unwrap();
+    appender.flush().unwrap();
+
+    let found = find_log_file(&temp_dir.path().join("switchboard.log"))
+        .expect("Log file with the date in its name should be found");
+    let today = chrono::Utc::now().date_naive();
+    assert_eq!(
+        found.file_name().unwrap().to_string_lossy(),
+        log_naming::dated_file_name("switchboard", today)
+    );
+    assert_eq!(
+        std::fs::read_to_string(found).unwrap(),
+        "{\"message\":\"date in name\"}\n"
+    );
+}
+
+#[test]
+fn test_finds_date_in_name_files_from_other_days() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    std::fs::write(temp_dir.path().join("switchboard-2024-01-02.log"), "{}\n").unwrap();
+    // Other apps' files sharing the directory are not mistaken for ours
+    std::fs::write(temp_dir.path().join("other-2024-01-02.log"), "{}\n").unwrap();
+
+    let found = find_log_file(&temp_dir.path().join("switchboard.log")).unwrap();
+    assert_eq!(found.file_name().unwrap(), "switchboard-2024-01-02.log");
+}
@@ -0,0 +1,119 @@
+// Integration tests for the per-request log level override header
+mod common;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use common::{CapturedEvent, EventCapture};
+use switchboard::log_level_override::{LevelOverrideFilter, LevelOverrideLayer};
+use switchboard::proxy_handler::LOG_LEVEL_HEADER;
+use tower::ServiceExt;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::{EnvFilter, Layer};
+use wiremock::matchers::{header_exists, method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+/// A DEBUG event the proxy logs for every upstream response
+const DEBUG_EVENT: &str = "Response headers from Anthropic API";
+
+/// Proxies one request, optionally with the override header, returning its INFO-filtered events
+async fn proxy_events(
+    app: &axum::Router,
+    capture: &EventCapture,
+    level: Option<&str>,
+) -> Vec<CapturedEvent> {
+    capture.events.lock().unwrap().clear();
+
+    let mut request = Request::builder().method("POST").uri("/v1/messages");
+    if let Some(level) = level {
+        request = request.header(LOG_LEVEL_HEADER, level);
+    }
+    let response = app
+        .clone()
+        .oneshot(request.body(Body::from("{}")).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let events = capture.events.lock().unwrap().clone();
+    events
+}
+
+fn has_event(events: &[CapturedEvent], message: &str) -> bool {
+    events
+        .iter()
+        .any(|event| event.get("message").map(String::as_str) == Some(message))
+}
+
+/// Tests that only the request asking for `trace` gets its DEBUG events logged
+#[tokio::test]
+async fn test_override_raises_level_for_that_request_only() {
+    let capture = EventCapture::default();
+    let subscriber = tracing_subscriber::registry()
+        .with(LevelOverrideLayer)
+        .with(
+            capture
+                .clone()
+                .with_filter(LevelOverrideFilter::new(EnvFilter::new("info"), true)),
+        );
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let test_setup = common::setup_test_environment_with_config(|config| {
+        config.allow_client_log_level = true;
+    })
+    .await;
+    // The header is consumed by the proxy, never forwarded
+    Mock::given(method("POST"))
+        .and(path("/v1/messages"))
+        .and(header_exists(LOG_LEVEL_HEADER))
+        .respond_with(ResponseTemplate::new(500))
+        .mount(&test_setup.mock_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/v1/messages"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&test_setup.mock_server)
+        .await;
+
+    let before = proxy_events(&test_setup.app, &capture, None).await;
+    let traced = proxy_events(&test_setup.app, &capture, Some("trace")).await;
+    let after = proxy_events(&test_setup.app, &capture, None).await;
+
+    assert!(
+        has_event(&traced, DEBUG_EVENT),
+        "override should admit DEBUG events"
+    );
+    for events in [&before, &after] {
+        assert!(
+            !has_event(events, DEBUG_EVENT),
+            "other requests stay at INFO"
+        );
+        // INFO events are logged either way
+        assert!(has_event(events, "Processing request"));
+    }
+}
+
+/// Tests that the header changes nothing unless overrides are allowed
+#[tokio::test]
+async fn test_override_ignored_when_not_allowed() {
+    let capture = EventCapture::default();
+    let subscriber = tracing_subscriber::registry()
+        .with(LevelOverrideLayer)
+        .with(
+            capture
+                .clone()
+                .with_filter(LevelOverrideFilter::new(EnvFilter::new("info"), true)),
+        );
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let test_setup = common::setup_test_environment_with_config(|config| {
+        config.allow_client_log_level = false;
+    })
+    .await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&test_setup.mock_server)
+        .await;
+
+    let events = proxy_events(&test_setup.app, &capture, Some("trace")).await;
+    assert!(!has_event(&events, DEBUG_EVENT));
+}
@@ -0,0 +1,70 @@
+// Integration tests for the client opt-out from detail logging
+mod common;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use common::{CapturedEvent, EventCapture};
+use switchboard::proxy_handler::LOG_OPTOUT_HEADER;
+use tower::ServiceExt;
+use tracing_subscriber::layer::SubscriberExt;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+/// Sends a Messages request carrying the opt-out header and returns the logged events
+async fn send_opted_out_request(allow_client_log_optout: bool) -> Vec<CapturedEvent> {
+    let capture = EventCapture::default();
+    let _guard =
+        tracing::subscriber::set_default(tracing_subscriber::registry().with(capture.clone()));
+
+    let test_setup = common::setup_test_environment_with_config(|config| {
+        config.allow_client_log_optout = allow_client_log_optout;
+    })
+    .await;
+    Mock::given(method("POST"))
+        .and(path("/v1/messages"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({"id": "msg_1"})))
+        .mount(&test_setup.mock_server)
+        .await;
+
+    let request = Request::builder()
+        .method("POST")
+        .uri("/v1/messages")
+        .header(LOG_OPTOUT_HEADER, "true")
+        .body(Body::from(r#"{"model": "claude-3-haiku"}"#))
+        .unwrap();
+    let response = test_setup.app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // The header is meant for the proxy only, whether or not it is honoured
+    let received = test_setup.mock_server.received_requests().await.unwrap();
+    assert_eq!(received.len(), 1);
+    assert!(!received[0].headers.contains_key(LOG_OPTOUT_HEADER));
+
+    let events = capture.events.lock().unwrap().clone();
+    events
+}
+
+/// Returns true if any event is a request or response detail log
+fn has_detail_events(events: &[CapturedEvent]) -> bool {
+    events
+        .iter()
+        .any(|event| event.contains_key("url.full") || event.contains_key("status_text"))
+}
+
+/// Tests that an allowed opt-out skips detail logging but keeps the summary
+#[tokio::test]
+async fn test_optout_suppresses_detail_logging_when_allowed() {
+    let events = send_opted_out_request(true).await;
+
+    assert!(!has_detail_events(&events));
+    common::find_event(&events, "Processing request");
+    common::find_event(&events, "Forwarding non-streaming response to client");
+}
+
+/// Tests that the opt-out header is ignored unless the feature is enabled
+#[tokio::test]
+async fn test_optout_ignored_when_disabled() {
+    let events = send_opted_out_request(false).await;
+
+    assert!(has_detail_events(&events));
+}
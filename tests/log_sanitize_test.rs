@@ -0,0 +1,80 @@
+// Integration tests for escaping control characters in logged bodies
+mod common;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use common::{CapturedEvent, EventCapture};
+use tower::ServiceExt;
+use tracing_subscriber::layer::SubscriberExt;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+/// A request body that clears the screen and recolors the terminal when printed raw
+const REQUEST_BODY: &str = "hello \x1b[2J\x1b[31mworld\x1b[0m";
+
+/// A response body smuggling a one-byte C1 CSI, which some terminals also obey
+const RESPONSE_BODY: &str = "done \u{9b}1A\x07";
+
+/// Proxies one request with body logging on, returning the logged events
+///
+/// Also checks that the bodies reach the upstream and the client unchanged.
+async fn proxy_with_escape_sequences(sanitize_log_output: bool) -> Vec<CapturedEvent> {
+    let capture = EventCapture::default();
+    let _guard =
+        tracing::subscriber::set_default(tracing_subscriber::registry().with(capture.clone()));
+
+    let test_setup = common::setup_test_environment_with_config(|config| {
+        config.log_bodies = true;
+        config.sanitize_log_output = sanitize_log_output;
+    })
+    .await;
+    Mock::given(method("POST"))
+        .and(path("/v1/messages"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(RESPONSE_BODY))
+        .mount(&test_setup.mock_server)
+        .await;
+
+    let request = Request::builder()
+        .method("POST")
+        .uri("/v1/messages")
+        .body(Body::from(REQUEST_BODY))
+        .unwrap();
+    let response = test_setup.app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    assert_eq!(body, RESPONSE_BODY.as_bytes());
+
+    let received = test_setup.mock_server.received_requests().await.unwrap();
+    assert_eq!(received[0].body, REQUEST_BODY.as_bytes());
+
+    let events = capture.events.lock().unwrap().clone();
+    events
+}
+
+/// Returns the logged value of `field` from the first event that has it
+fn logged(events: &[CapturedEvent], field: &str) -> String {
+    events
+        .iter()
+        .find_map(|event| event.get(field).cloned())
+        .unwrap_or_else(|| panic!("No event with {}", field))
+}
+
+/// Tests that escape sequences are neutralized in logs but forwarded unchanged
+#[tokio::test]
+async fn test_control_characters_escaped_in_logged_bodies() {
+    let events = proxy_with_escape_sequences(true).await;
+
+    let request_body = logged(&events, "http.request.body.content");
+    assert_eq!(request_body, "hello \\u{1b}[2J\\u{1b}[31mworld\\u{1b}[0m");
+    let response_body = logged(&events, "http.response.body.content");
+    assert_eq!(response_body, "done \\u{9b}1A\\u{7}");
+}
+
+/// Tests that sanitizing can be turned off, logging bodies verbatim
+#[tokio::test]
+async fn test_bodies_logged_verbatim_when_sanitizing_disabled() {
+    let events = proxy_with_escape_sequences(false).await;
+
+    assert_eq!(logged(&events, "http.request.body.content"), REQUEST_BODY);
+    assert_eq!(logged(&events, "http.response.body.content"), RESPONSE_BODY);
+}
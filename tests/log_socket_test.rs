@@ -0,0 +1,46 @@
+// Integration test for sending JSON log lines to a Unix socket
+#![cfg(unix)]
+
+mod common;
+
+use std::io::{BufRead, BufReader};
+use std::os::unix::net::UnixListener;
+use std::time::Duration;
+use switchboard::config::Config;
+use switchboard::logger;
+use tracing::info;
+
+/// Tests that events reach a listening collector as JSON lines
+///
+/// init_tracing installs a global subscriber, so this is the only test in this binary.
+/// Reconnection is covered by the UnixSocketWriter unit test in logger.rs.
+#[test]
+fn test_init_tracing_sends_json_lines_to_socket() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let socket_path = temp_dir.path().join("collector.sock");
+    let listener = UnixListener::bind(&socket_path).unwrap();
+
+    let config = Config {
+        log_file_path: common::generate_test_log_path("log_socket")
+            .to_string_lossy()
+            .into_owned(),
+        log_socket_path: Some(socket_path.to_string_lossy().into_owned()),
+        ..Default::default()
+    };
+    let _guard = logger::init_tracing(&config).expect("Logging should initialize with a socket");
+
+    info!(answer = 42, "Sent to the collector");
+
+    let (stream, _) = listener.accept().unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+    let event = BufReader::new(stream)
+        .lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(&line.unwrap()).unwrap())
+        .find(|event| event["fields"]["message"] == "Sent to the collector")
+        .expect("The event should be received as a JSON line");
+
+    assert_eq!(event["fields"]["answer"], 42);
+    assert_eq!(event["level"], "INFO");
+}
@@ -3,6 +3,9 @@ use hyper::{header::HeaderMap, http::Method, Uri};
 use reqwest::StatusCode;
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
+use switchboard::http_logging::{
+    log_request_details_with_options, log_response_details_with_options, BodyLogOptions,
+};
 use switchboard::proxy_handler::{log_request_details, log_response_details};
 use tracing::{info, Level, Subscriber};
 use tracing_subscriber::{layer::SubscriberExt, registry::LookupSpan, Layer};
@@ -283,6 +286,55 @@ fn test_request_body_size_limit() {
     assert!(logs_contain_body_size(&logs), "Body size should be logged");
 }
 
+#[test]
+fn test_request_body_schema_only_logs_keys_not_values() {
+    // Set up the test subscriber with debug level
+    let (subscriber, buffer) = create_test_subscriber(Level::DEBUG);
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    // Create a request whose values must never reach the logs
+    let method = Method::POST;
+    let uri = Uri::from_static("https://example.com/v1/messages");
+    let headers = HeaderMap::new();
+    let body = Bytes::from(
+        r#"{"model":"claude-3-opus-20240229","max_tokens":256,"messages":[{"role":"user","content":"top secret prompt"},{"role":"assistant","content":"hidden reply"}]}"#,
+    );
+    let options = BodyLogOptions {
+        log_bodies: true,
+        max_body_size: 1000,
+        schema_only: true,
+        ..BodyLogOptions::default()
+    };
+
+    log_request_details_with_options(&method, &uri, &headers, &body, &options);
+
+    // Get the captured logs
+    let logs: Vec<String> = buffer.lock().unwrap().clone();
+    for log in &logs {
+        println!(" -> {}", log);
+    }
+
+    // The keys and message count are logged
+    assert!(
+        logs_contain(&logs, r#"["max_tokens", "messages", "model"]"#),
+        "Top-level keys should be logged"
+    );
+    assert!(
+        logs_contain(&logs, "messages_count=2"),
+        "Message count should be logged"
+    );
+
+    // No values or content are logged
+    assert!(!logs_contain_body_content(&logs));
+    for secret in ["top secret prompt", "hidden reply", "claude-3-opus", "256"] {
+        assert!(
+            !logs_contain(&logs, secret),
+            "Value '{}' must not appear in schema-only logs",
+            secret
+        );
+    }
+}
+
 // Tests for response body logging behavior
 
 #[test]
@@ -570,3 +622,117 @@ fn test_empty_body_logging() {
         "No body content should be logged for empty bodies"
     );
 }
+
+#[test]
+fn test_empty_body_events_suppressed_when_disabled() {
+    // Set up the test subscriber with debug level
+    let (subscriber, buffer) = create_test_subscriber(Level::DEBUG);
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let method = Method::GET;
+    let uri = Uri::from_static("https://example.com/v1/models");
+    let headers = HeaderMap::new();
+    let options = BodyLogOptions {
+        log_empty_bodies: false,
+        ..BodyLogOptions::default()
+    };
+
+    log_request_details_with_options(&method, &uri, &headers, &Bytes::new(), &options);
+    log_response_details_with_options(&StatusCode::OK, &headers, &Bytes::new(), &options, None);
+
+    let logs: Vec<String> = buffer.lock().unwrap().clone();
+    for log in &logs {
+        println!(" -> {}", log);
+    }
+
+    // Nothing at all is logged about the empty bodies
+    assert!(!logs_contain(&logs, "body empty"));
+    assert!(!logs_contain_body_content(&logs));
+    // The rest of the request is still logged
+    assert!(logs_contain(&logs, "/v1/models"));
+}
+
+#[test]
+fn test_large_declared_content_length_skips_body_formatting() {
+    // Set up the test subscriber with debug level
+    let (subscriber, buffer) = create_test_subscriber(Level::DEBUG);
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    // The body itself is small and valid JSON, but the declared size is over the limit
+    let method = Method::POST;
+    let uri = Uri::from_static("https://example.com/v1/messages");
+    let mut headers = HeaderMap::new();
+    headers.insert(hyper::header::CONTENT_LENGTH, "5000000".parse().unwrap());
+    let body = Bytes::from(r#"{"model":"claude-3-opus-20240229","secret":"do not format"}"#);
+    let options = BodyLogOptions {
+        log_bodies: true,
+        max_body_size: 1000,
+        schema_only: true,
+        ..BodyLogOptions::default()
+    };
+
+    log_request_details_with_options(&method, &uri, &headers, &body, &options);
+
+    let logs: Vec<String> = buffer.lock().unwrap().clone();
+    for log in &logs {
+        println!(" -> {}", log);
+    }
+
+    // Only the declared size is logged; neither content nor schema is derived from the body
+    assert!(logs_contain(&logs, "too large to log fully"));
+    assert!(logs_contain(&logs, "5000000"));
+    assert!(!logs_contain_body_content(&logs));
+    assert!(!logs_contain(&logs, "body.keys"));
+    assert!(!logs_contain(&logs, "do not format"));
+}
+
+#[test]
+fn test_dynamic_fields_capped_with_truncation_marker() {
+    // Set up the test subscriber with debug level
+    let (subscriber, buffer) = create_test_subscriber(Level::DEBUG);
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    // Far more headers and JSON keys than the cap allows
+    let method = Method::POST;
+    let uri = Uri::from_static("https://example.com/v1/messages");
+    let mut headers = HeaderMap::new();
+    for i in 0..20 {
+        headers.insert(
+            hyper::header::HeaderName::from_bytes(format!("x-dynamic-{:02}", i).as_bytes())
+                .unwrap(),
+            "value".parse().unwrap(),
+        );
+    }
+    let keys: Vec<String> = (0..20).map(|i| format!("\"k{:02}\":{}", i, i)).collect();
+    let body = Bytes::from(format!("{{{}}}", keys.join(",")));
+    let options = BodyLogOptions {
+        log_bodies: true,
+        max_body_size: 1000,
+        schema_only: true,
+        max_span_fields: 5,
+        ..BodyLogOptions::default()
+    };
+
+    log_request_details_with_options(&method, &uri, &headers, &body, &options);
+
+    let logs: Vec<String> = buffer.lock().unwrap().clone();
+    for log in &logs {
+        println!(" -> {}", log);
+    }
+
+    // Only the first keys up to the cap are logged, with the truncation marker
+    let schema = logs
+        .iter()
+        .find(|log| log.starts_with("Request body schema"))
+        .expect("Schema should be logged");
+    assert!(schema.contains(r#"["k00", "k01", "k02", "k03", "k04"]"#));
+    assert!(!schema.contains("k05"));
+    assert!(schema.contains("fields_truncated=true"));
+
+    let headers_log = logs
+        .iter()
+        .find(|log| log.contains("http.request.headers"))
+        .expect("Headers should be logged");
+    assert_eq!(headers_log.matches("x-dynamic-").count(), 5);
+    assert!(headers_log.contains("fields_truncated=true"));
+}
@@ -0,0 +1,26 @@
+// Integration test for splitting console logs between stdout and stderr
+mod common;
+
+use switchboard::config::Config;
+use switchboard::logger;
+use tracing::{info, warn};
+
+/// Tests that logging initializes with WARN/ERROR routed to stderr
+///
+/// init_tracing installs a global subscriber, so this is the only test in this binary.
+/// The routing itself is covered by the split_by_level unit test in logger.rs.
+#[test]
+fn test_init_tracing_with_error_log_to_stderr() {
+    let config = Config {
+        log_file_path: common::generate_test_log_path("stderr_split")
+            .to_string_lossy()
+            .into_owned(),
+        error_log_to_stderr: true,
+        ..Default::default()
+    };
+
+    let _guard = logger::init_tracing(&config).expect("Logging should initialize with the split");
+
+    info!("Routed to stdout");
+    warn!("Routed to stderr");
+}
@@ -0,0 +1,46 @@
+// Integration tests for maintenance mode, which stops proxying but keeps health checks up
+mod common;
+
+use axum::body::Body;
+use axum::http::{header, Request, StatusCode};
+use serde_json::json;
+use tower::ServiceExt;
+use wiremock::matchers::any;
+use wiremock::{Mock, ResponseTemplate};
+
+/// Tests that proxied paths get 503 with Retry-After while /healthz stays 200
+#[tokio::test]
+async fn test_maintenance_rejects_proxied_requests_but_serves_health() {
+    let test_setup = common::setup_test_environment_with_config(|config| {
+        config.maintenance_mode = true;
+        config.maintenance_retry_after_secs = 120;
+    })
+    .await;
+    Mock::given(any())
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&test_setup.mock_server)
+        .await;
+
+    for (method, uri) in [("POST", "/v1/messages"), ("GET", "/v1/models")] {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .body(Body::from("{}"))
+            .unwrap();
+        let response = test_setup.app.clone().oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "120");
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body, json!({"error": "maintenance"}));
+    }
+
+    let health = Request::builder()
+        .uri("/healthz")
+        .body(Body::empty())
+        .unwrap();
+    let response = test_setup.app.oneshot(health).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
@@ -0,0 +1,49 @@
+// Integration tests for refusing overlong request paths
+mod common;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use tower::ServiceExt;
+use wiremock::{Mock, ResponseTemplate};
+
+/// Sends `uri` through a proxy limited to 32-byte paths, returning the status and upstream hits
+async fn send_with_limit(uri: &str) -> (StatusCode, usize) {
+    let test_setup = common::setup_test_environment_with_config(|config| {
+        config.max_path_length = Some(32);
+    })
+    .await;
+    Mock::given(wiremock::matchers::method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&test_setup.mock_server)
+        .await;
+
+    let request = Request::builder()
+        .method("POST")
+        .uri(uri)
+        .body(Body::from("{}"))
+        .unwrap();
+    let response = test_setup.app.oneshot(request).await.unwrap();
+
+    let received = test_setup.mock_server.received_requests().await.unwrap();
+    (response.status(), received.len())
+}
+
+/// Tests that a path longer than the limit is refused with 414 and not forwarded
+#[tokio::test]
+async fn test_overlong_path_rejected() {
+    let uri = format!("/v1/{}", "a".repeat(64));
+    assert_eq!(send_with_limit(&uri).await, (StatusCode::URI_TOO_LONG, 0));
+}
+
+/// Tests that the query string counts towards the limit
+#[tokio::test]
+async fn test_query_counts_towards_limit() {
+    let uri = format!("/v1/messages?{}", "q".repeat(32));
+    assert_eq!(send_with_limit(&uri).await, (StatusCode::URI_TOO_LONG, 0));
+}
+
+/// Tests that a path within the limit is forwarded
+#[tokio::test]
+async fn test_normal_path_forwarded() {
+    assert_eq!(send_with_limit("/v1/messages").await, (StatusCode::OK, 1));
+}
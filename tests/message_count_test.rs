@@ -0,0 +1,60 @@
+// Integration tests for recording the number of messages in Messages API requests
+mod common;
+
+use axum::body::Body;
+use axum::http::{header, Request, StatusCode};
+use common::SpanRecordCapture;
+use serde_json::json;
+use tower::ServiceExt;
+use tracing_subscriber::layer::SubscriberExt;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+async fn send_body(body: Body) -> SpanRecordCapture {
+    let capture = SpanRecordCapture::default();
+    let _guard =
+        tracing::subscriber::set_default(tracing_subscriber::registry().with(capture.clone()));
+
+    let test_setup = common::setup_test_environment().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/messages"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"ok": true})))
+        .mount(&test_setup.mock_server)
+        .await;
+
+    let request = Request::builder()
+        .method("POST")
+        .uri("/v1/messages")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(body)
+        .unwrap();
+    let response = test_setup.app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    capture
+}
+
+/// Tests that the length of the messages array is recorded on the request span
+#[tokio::test]
+async fn test_message_count_recorded() {
+    let body = json!({
+        "model": "claude-3-opus-20240229",
+        "messages": [
+            {"role": "user", "content": "Hello"},
+            {"role": "assistant", "content": "Hi there"},
+            {"role": "user", "content": "How are you?"}
+        ]
+    });
+
+    let capture = send_body(Body::from(body.to_string())).await;
+
+    assert_eq!(capture.values("anthropic.message_count"), vec!["3"]);
+}
+
+/// Tests that a body that is not JSON is forwarded without recording a count
+#[tokio::test]
+async fn test_non_json_body_records_no_message_count() {
+    let capture = send_body(Body::from("not json")).await;
+
+    assert!(capture.values("anthropic.message_count").is_empty());
+}
@@ -0,0 +1,82 @@
+// Integration tests for per-model rate limiting
+mod common;
+
+use axum::body::Body;
+use axum::http::{header, Request, StatusCode};
+use serde_json::json;
+use std::collections::HashMap;
+use tower::ServiceExt;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+fn messages_request(model: &str) -> Request<Body> {
+    let body = json!({
+        "model": model,
+        "messages": [{"role": "user", "content": "Hello"}]
+    });
+    Request::builder()
+        .method("POST")
+        .uri("/v1/messages")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
+
+/// Tests that an exhausted model gets 429 while another model still proceeds
+#[tokio::test]
+async fn test_exhausted_model_rejected_while_other_model_proceeds() {
+    let test_setup = common::setup_test_environment_with_config(|config| {
+        config.model_rate_limits = HashMap::from([("claude-3-opus-20240229".to_string(), 2)]);
+    })
+    .await;
+
+    Mock::given(method("POST"))
+        .and(path("/v1/messages"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"ok": true})))
+        .mount(&test_setup.mock_server)
+        .await;
+
+    // The bucket holds a minute's allowance: two requests pass, the third is limited
+    for _ in 0..2 {
+        let response = test_setup
+            .app
+            .clone()
+            .oneshot(messages_request("claude-3-opus-20240229"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    let limited = test_setup
+        .app
+        .clone()
+        .oneshot(messages_request("claude-3-opus-20240229"))
+        .await
+        .unwrap();
+    assert_eq!(limited.status(), StatusCode::TOO_MANY_REQUESTS);
+    let retry_after: u64 = limited.headers()[header::RETRY_AFTER]
+        .to_str()
+        .unwrap()
+        .parse()
+        .expect("Retry-After should be whole seconds");
+    assert!((1..=30).contains(&retry_after));
+
+    // A model without a limit is unaffected
+    let other = test_setup
+        .app
+        .oneshot(messages_request("claude-3-haiku-20240307"))
+        .await
+        .unwrap();
+    assert_eq!(other.status(), StatusCode::OK);
+
+    assert_eq!(
+        test_setup
+            .mock_server
+            .received_requests()
+            .await
+            .unwrap()
+            .len(),
+        3,
+        "The limited request should not reach upstream"
+    );
+}
@@ -0,0 +1,62 @@
+// Integration tests for collapsing duplicate slashes in forwarded paths
+mod common;
+
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode};
+use tower::ServiceExt;
+use wiremock::{Mock, ResponseTemplate};
+
+/// Sends `uri` through the proxy and returns the path and query the upstream received
+async fn forwarded_path_and_query(normalize_path: bool, uri: &str) -> String {
+    let test_setup = common::setup_test_environment_with_config(|config| {
+        config.normalize_path = normalize_path;
+    })
+    .await;
+    Mock::given(wiremock::matchers::method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&test_setup.mock_server)
+        .await;
+
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri(uri)
+        .body(Body::from("{}"))
+        .unwrap();
+    let response = test_setup.app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let received = test_setup.mock_server.received_requests().await.unwrap();
+    assert_eq!(received.len(), 1);
+    let url = &received[0].url;
+    match url.query() {
+        Some(query) => format!("{}?{}", url.path(), query),
+        None => url.path().to_string(),
+    }
+}
+
+/// Tests that duplicate slashes in the path are collapsed when enabled
+#[tokio::test]
+async fn test_duplicate_slashes_collapsed() {
+    assert_eq!(
+        forwarded_path_and_query(true, "/v1//messages").await,
+        "/v1/messages"
+    );
+}
+
+/// Tests that the query string is forwarded unchanged, slashes included
+#[tokio::test]
+async fn test_query_string_untouched() {
+    assert_eq!(
+        forwarded_path_and_query(true, "//v1///messages?next=a//b&x=1").await,
+        "/v1/messages?next=a//b&x=1"
+    );
+}
+
+/// Tests that paths are forwarded as received by default
+#[tokio::test]
+async fn test_paths_unchanged_by_default() {
+    assert_eq!(
+        forwarded_path_and_query(false, "/v1//messages").await,
+        "/v1//messages"
+    );
+}
@@ -0,0 +1,70 @@
+// Integration tests for upstreams closing the connection before the response body is complete
+mod common;
+
+use axum::body::Body;
+use axum::http::{header, Request, StatusCode};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tower::ServiceExt;
+
+/// The part of the body the fake upstream sends before hanging up
+const PARTIAL_BODY: &str = r#"{"id":"msg_"#;
+
+/// Starts an upstream that declares a 100-byte body, sends a few bytes and closes
+///
+/// wiremock always sends complete bodies, so the truncation needs a raw socket.
+async fn start_truncating_upstream() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        while let Ok((mut socket, _)) = listener.accept().await {
+            let mut request = [0u8; 4096];
+            let _ = socket.read(&mut request).await;
+            let response = format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: 100\r\n\r\n{}",
+                PARTIAL_BODY
+            );
+            let _ = socket.write_all(response.as_bytes()).await;
+            // Dropping the socket closes the connection mid-body
+        }
+    });
+    format!("http://{}", addr)
+}
+
+async fn send_to_truncating_upstream(forward_partial_bodies: bool) -> axum::response::Response {
+    let upstream_url = start_truncating_upstream().await;
+    let test_setup = common::setup_test_environment_with_config(|config| {
+        config.anthropic_target_url = upstream_url;
+        config.forward_partial_bodies = forward_partial_bodies;
+    })
+    .await;
+
+    let request = Request::builder()
+        .method("POST")
+        .uri("/v1/messages")
+        .body(Body::from("{}"))
+        .unwrap();
+    test_setup.app.oneshot(request).await.unwrap()
+}
+
+/// Tests that a truncated body is a 502 by default
+#[tokio::test]
+async fn test_truncated_body_is_bad_gateway_by_default() {
+    let response = send_to_truncating_upstream(false).await;
+
+    assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+}
+
+/// Tests that the received part is forwarded, with a matching Content-Length, when enabled
+#[tokio::test]
+async fn test_truncated_body_forwarded_when_enabled() {
+    let response = send_to_truncating_upstream(true).await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers()[header::CONTENT_LENGTH],
+        PARTIAL_BODY.len().to_string()
+    );
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    assert_eq!(body, PARTIAL_BODY.as_bytes());
+}
@@ -21,6 +21,7 @@ fn create_test_config(log_file_path: &str, log_directory_mode: LogDirectoryMode)
         log_max_body_size: 1024,
         log_directory_mode,
         log_max_age_days: None,
+        ..Default::default()
     }
 }
 
@@ -269,6 +270,7 @@ fn test_subdir_creation() {
         log_max_body_size: 1024,
         log_directory_mode: LogDirectoryMode::Default,
         log_max_age_days: None,
+        ..Default::default()
     };
 
     let resolver = LogPathResolver::new(&config, LogType::Application);
@@ -397,6 +399,7 @@ fn test_unix_directory_permissions() {
         log_max_body_size: 1024,
         log_directory_mode: LogDirectoryMode::Default,
         log_max_age_days: None,
+        ..Default::default()
     };
 
     let resolver = LogPathResolver::new(&config, LogType::Application);
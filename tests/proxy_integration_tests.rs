@@ -7,6 +7,7 @@ use axum::http::{header, HeaderValue, Request, StatusCode};
 // chunk by chunk instead of the current approach that processes the whole body at once
 // Removed unused import: futures_util::StreamExt
 use serde_json::{json, Value};
+use switchboard::config::EmptyBodyPolicy;
 use tower::ServiceExt;
 use wiremock::matchers::{method, path};
 use wiremock::{Mock, ResponseTemplate};
@@ -206,3 +207,227 @@ async fn test_streaming_response_forward_success() {
         parsed_events, expected_events
     );
 }
+
+/// Tests that requests whose bodies would exceed the global buffering budget are
+/// rejected with 503 and Retry-After, while requests that fit are still forwarded.
+#[tokio::test]
+async fn test_request_over_buffer_budget_rejected() {
+    // Budget allows small bodies only
+    let test_setup = common::setup_test_environment_with_config(|config| {
+        config.max_total_buffered_bytes = Some(64);
+    })
+    .await;
+
+    Mock::given(method("POST"))
+        .and(path("/v1/messages"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"ok": true})))
+        .mount(&test_setup.mock_server)
+        .await;
+
+    // A body larger than the whole budget is turned away before reaching upstream
+    let large_body = "x".repeat(128);
+    let request = Request::builder()
+        .method("POST")
+        .uri("/v1/messages")
+        .header(header::CONTENT_LENGTH, large_body.len())
+        .body(Body::from(large_body))
+        .unwrap();
+    let response = test_setup.app.clone().oneshot(request).await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert!(
+        response.headers().contains_key(header::RETRY_AFTER),
+        "Budget rejection should include Retry-After"
+    );
+    assert!(
+        test_setup
+            .mock_server
+            .received_requests()
+            .await
+            .unwrap()
+            .is_empty(),
+        "Rejected request should not be forwarded upstream"
+    );
+
+    // Once the rejected request is gone, a request that fits goes through
+    let request = Request::builder()
+        .method("POST")
+        .uri("/v1/messages")
+        .body(Body::from("{}"))
+        .unwrap();
+    let response = test_setup.app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+/// Tests that a chunked body without Content-Length is held to the buffering budget
+/// as it arrives, rather than being read in full before it is accounted for.
+#[tokio::test]
+async fn test_chunked_request_over_buffer_budget_rejected() {
+    let test_setup = common::setup_test_environment_with_config(|config| {
+        config.max_total_buffered_bytes = Some(64);
+    })
+    .await;
+
+    Mock::given(method("POST"))
+        .and(path("/v1/messages"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"ok": true})))
+        .mount(&test_setup.mock_server)
+        .await;
+
+    // Four 32-byte chunks, with no declared length to reserve up front
+    let (mut sender, body) = Body::channel();
+    tokio::spawn(async move {
+        for _ in 0..4 {
+            if sender.send_data("x".repeat(32).into()).await.is_err() {
+                break;
+            }
+        }
+    });
+    let request = Request::builder()
+        .method("POST")
+        .uri("/v1/messages")
+        .body(body)
+        .unwrap();
+    let response = test_setup.app.oneshot(request).await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert!(response.headers().contains_key(header::RETRY_AFTER));
+    assert!(
+        test_setup
+            .mock_server
+            .received_requests()
+            .await
+            .unwrap()
+            .is_empty(),
+        "Rejected request should not be forwarded upstream"
+    );
+}
+
+/// Tests that HEAD requests are forwarded and answered with headers only,
+/// keeping the upstream Content-Length rather than the (empty) proxied body length.
+#[tokio::test]
+async fn test_head_request_returns_headers_without_body() {
+    let test_setup = common::setup_test_environment().await;
+
+    Mock::given(method("HEAD"))
+        .and(path("/v1/models"))
+        .respond_with(ResponseTemplate::new(200).insert_header("content-length", "1234"))
+        .mount(&test_setup.mock_server)
+        .await;
+
+    let request = Request::builder()
+        .method("HEAD")
+        .uri("/v1/models")
+        .body(Body::empty())
+        .unwrap();
+    let response = test_setup.app.oneshot(request).await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response
+            .headers()
+            .get(header::CONTENT_LENGTH)
+            .expect("HEAD response should keep Content-Length"),
+        "1234"
+    );
+
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    assert!(body.is_empty(), "HEAD response should have no body");
+}
+
+/// Sends an empty-bodied POST through a router using `policy` and returns the upstream request
+async fn forward_empty_post(policy: EmptyBodyPolicy) -> wiremock::Request {
+    let test_setup = common::setup_test_environment_with_config(|config| {
+        config.empty_post_body = policy;
+    })
+    .await;
+
+    Mock::given(method("POST"))
+        .and(path("/v1/messages/count_tokens"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"ok": true})))
+        .mount(&test_setup.mock_server)
+        .await;
+
+    let request = Request::builder()
+        .method("POST")
+        .uri("/v1/messages/count_tokens")
+        .body(Body::empty())
+        .unwrap();
+    let response = test_setup.app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let mut received = test_setup.mock_server.received_requests().await.unwrap();
+    assert_eq!(
+        received.len(),
+        1,
+        "Exactly one request should reach upstream"
+    );
+    received.remove(0)
+}
+
+/// Tests that the default policy forwards an empty POST body unchanged
+#[tokio::test]
+async fn test_empty_post_body_passthrough() {
+    let forwarded = forward_empty_post(EmptyBodyPolicy::Passthrough).await;
+
+    assert!(
+        forwarded.body.is_empty(),
+        "Empty body should be forwarded as-is"
+    );
+    assert!(
+        forwarded.headers.get("content-type").is_none(),
+        "No Content-Type should be added"
+    );
+}
+
+/// Tests that the empty_json policy substitutes `{}` with matching headers
+#[tokio::test]
+async fn test_empty_post_body_empty_json() {
+    let forwarded = forward_empty_post(EmptyBodyPolicy::EmptyJson).await;
+
+    assert_eq!(forwarded.body, b"{}");
+    assert_eq!(
+        forwarded.headers.get("content-type").unwrap(),
+        "application/json"
+    );
+    assert_eq!(forwarded.headers.get("content-length").unwrap(), "2");
+}
+
+/// Tests that client-sent proxy-internal headers are neither forwarded nor echoed back
+#[tokio::test]
+async fn test_internal_headers_stripped_from_client_request() {
+    let test_setup = common::setup_test_environment().await;
+
+    Mock::given(method("POST"))
+        .and(path("/v1/messages"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"ok": true})))
+        .mount(&test_setup.mock_server)
+        .await;
+
+    let request = Request::builder()
+        .method("POST")
+        .uri("/v1/messages")
+        .header("x-switchboard-cache", "HIT")
+        .header("x-request-source", "client")
+        .body(Body::from("{}"))
+        .unwrap();
+    let response = test_setup.app.oneshot(request).await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(
+        response.headers().get("x-switchboard-cache").is_none(),
+        "Spoofed internal header should not be reflected in the response"
+    );
+
+    let received = test_setup.mock_server.received_requests().await.unwrap();
+    assert_eq!(received.len(), 1);
+    assert!(
+        received[0].headers.get("x-switchboard-cache").is_none(),
+        "Spoofed internal header should not be forwarded upstream"
+    );
+    assert_eq!(
+        received[0].headers.get("x-request-source").unwrap(),
+        "client",
+        "Other client headers should still be forwarded"
+    );
+}
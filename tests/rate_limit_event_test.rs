@@ -0,0 +1,83 @@
+// Integration tests for the dedicated upstream rate limiting event
+mod common;
+
+use axum::body::Body;
+use axum::http::Request;
+use common::{CapturedEvent, EventCapture};
+use switchboard::config::key_fingerprint;
+use tower::ServiceExt;
+use tracing_subscriber::layer::SubscriberExt;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+/// Message of the dedicated event
+const RATE_LIMIT_EVENT: &str = "Upstream is rate limiting requests";
+
+/// Proxies a Messages request to an upstream answering `upstream`, returning the logged events
+async fn proxy_to(upstream: ResponseTemplate, log_rate_limit_events: bool) -> Vec<CapturedEvent> {
+    let capture = EventCapture::default();
+    let _guard =
+        tracing::subscriber::set_default(tracing_subscriber::registry().with(capture.clone()));
+
+    let test_setup = common::setup_test_environment_with_config(|config| {
+        config.log_rate_limit_events = log_rate_limit_events;
+    })
+    .await;
+    Mock::given(method("POST"))
+        .and(path("/v1/messages"))
+        .respond_with(upstream)
+        .mount(&test_setup.mock_server)
+        .await;
+
+    let request = Request::builder()
+        .method("POST")
+        .uri("/v1/messages")
+        .body(Body::from(r#"{"model":"claude-3-haiku","messages":[]}"#))
+        .unwrap();
+    test_setup.app.oneshot(request).await.unwrap();
+
+    let events = capture.events.lock().unwrap().clone();
+    events
+}
+
+/// Tests that a 429 gets the dedicated event with Retry-After, model and key fingerprint
+#[tokio::test]
+async fn test_rate_limit_event_logged_for_429() {
+    let events = proxy_to(
+        ResponseTemplate::new(429).insert_header("retry-after", "30"),
+        true,
+    )
+    .await;
+
+    let event = common::find_event(&events, RATE_LIMIT_EVENT);
+    assert_eq!(event["http.status_code"], "429");
+    assert_eq!(event["retry_after"], "\"30\"");
+    assert_eq!(event["model"], "\"claude-3-haiku\"");
+    assert_eq!(
+        event["key_fingerprint"],
+        format!("{:?}", key_fingerprint("test-api-key"))
+    );
+}
+
+/// Tests that an overloaded 529 gets the event too, without a Retry-After
+#[tokio::test]
+async fn test_rate_limit_event_logged_for_529() {
+    let events = proxy_to(ResponseTemplate::new(529), true).await;
+
+    let event = common::find_event(&events, RATE_LIMIT_EVENT);
+    assert_eq!(event["http.status_code"], "529");
+    assert!(!event.contains_key("retry_after"));
+}
+
+/// Tests that successes and disabled events produce no dedicated event
+#[tokio::test]
+async fn test_no_rate_limit_event_otherwise() {
+    for events in [
+        proxy_to(ResponseTemplate::new(200), true).await,
+        proxy_to(ResponseTemplate::new(429), false).await,
+    ] {
+        assert!(!events
+            .iter()
+            .any(|event| event.get("message").map(String::as_str) == Some(RATE_LIMIT_EVENT)));
+    }
+}
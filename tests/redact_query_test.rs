@@ -0,0 +1,50 @@
+// Integration tests for redacting query parameters in logged URLs
+mod common;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use common::SpanRecordCapture;
+use tower::ServiceExt;
+use tracing_subscriber::layer::SubscriberExt;
+use wiremock::matchers::{method, path, query_param};
+use wiremock::{Mock, ResponseTemplate};
+
+/// Tests that a configured parameter is redacted in the log but forwarded unchanged
+#[tokio::test]
+async fn test_query_param_redacted_in_log_only() {
+    let capture = SpanRecordCapture::default();
+    let _guard =
+        tracing::subscriber::set_default(tracing_subscriber::registry().with(capture.clone()));
+
+    let test_setup = common::setup_test_environment_with_config(|config| {
+        config.redact_query_params = vec!["token".to_string()];
+    })
+    .await;
+    Mock::given(method("GET"))
+        .and(path("/v1/models"))
+        .and(query_param("token", "secret-token"))
+        .and(query_param("limit", "5"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&test_setup.mock_server)
+        .await;
+
+    let request = Request::builder()
+        .uri("/v1/models?token=secret-token&limit=5")
+        .body(Body::empty())
+        .unwrap();
+    let response = test_setup.app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    test_setup.mock_server.verify().await;
+
+    // String fields are captured in Debug form, i.e. quoted
+    assert_eq!(
+        capture.values("url.query"),
+        vec![format!("{:?}", "token=[REDACTED]&limit=5")]
+    );
+    let records = capture.records.lock().unwrap();
+    assert!(records
+        .iter()
+        .flat_map(|record| record.values())
+        .all(|value| !value.contains("secret-token")));
+}
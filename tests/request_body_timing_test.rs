@@ -0,0 +1,54 @@
+// Integration tests for recording the client request body read time
+mod common;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use common::SpanRecordCapture;
+use tower::ServiceExt;
+use tracing_subscriber::layer::SubscriberExt;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+async fn body_read_times(log_body_read_timing: bool) -> Vec<String> {
+    let capture = SpanRecordCapture::default();
+    let _guard =
+        tracing::subscriber::set_default(tracing_subscriber::registry().with(capture.clone()));
+
+    let test_setup = common::setup_test_environment_with_config(|config| {
+        config.log_body_read_timing = log_body_read_timing;
+    })
+    .await;
+    Mock::given(method("POST"))
+        .and(path("/v1/messages"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&test_setup.mock_server)
+        .await;
+
+    let request = Request::builder()
+        .method("POST")
+        .uri("/v1/messages")
+        .body(Body::from(
+            r#"{"model": "claude-3-haiku", "max_tokens": 10}"#,
+        ))
+        .unwrap();
+    let response = test_setup.app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    capture.values("request_body_read_ms")
+}
+
+/// Tests that the body read time is recorded as a non-negative duration when enabled
+#[tokio::test]
+async fn test_request_body_read_time_recorded() {
+    let values = body_read_times(true).await;
+
+    assert_eq!(values.len(), 1);
+    let read_ms: f64 = values[0].parse().expect("read time should be numeric");
+    assert!(read_ms >= 0.0);
+}
+
+/// Tests that nothing is recorded unless enabled
+#[tokio::test]
+async fn test_request_body_read_time_not_recorded_by_default() {
+    assert!(body_read_times(false).await.is_empty());
+}
@@ -0,0 +1,85 @@
+// Integration tests for the request deadline and the header sharing it with the upstream
+mod common;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use std::time::Duration;
+use switchboard::config::Config;
+use tower::ServiceExt;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+/// Header name the tests configure
+const TIMEOUT_HEADER: &str = "x-timeout-ms";
+
+/// Proxies a request to an upstream answering `upstream`, returning the client status
+/// and the timeout header the upstream received, if any
+async fn proxy_with(
+    upstream: ResponseTemplate,
+    configure: impl FnOnce(&mut Config),
+) -> (StatusCode, Option<String>) {
+    let test_setup = common::setup_test_environment_with_config(configure).await;
+    Mock::given(method("POST"))
+        .and(path("/v1/messages"))
+        .respond_with(upstream)
+        .mount(&test_setup.mock_server)
+        .await;
+
+    let request = Request::builder()
+        .method("POST")
+        .uri("/v1/messages")
+        .body(Body::from("{}"))
+        .unwrap();
+    let response = test_setup.app.oneshot(request).await.unwrap();
+
+    let received = test_setup.mock_server.received_requests().await.unwrap();
+    let header = received.first().and_then(|request| {
+        request
+            .headers
+            .get(TIMEOUT_HEADER)
+            .map(|value| value.to_str().unwrap().to_string())
+    });
+    (response.status(), header)
+}
+
+/// Tests that the upstream is told the remaining budget, close to the full deadline
+#[tokio::test]
+async fn test_remaining_deadline_sent_upstream() {
+    let (status, header) = proxy_with(ResponseTemplate::new(200), |config| {
+        config.request_deadline_ms = Some(30_000);
+        config.upstream_timeout_header = Some(TIMEOUT_HEADER.to_string());
+    })
+    .await;
+
+    assert_eq!(status, StatusCode::OK);
+    let remaining_ms: u64 = header.expect("header should be sent").parse().unwrap();
+    assert!(
+        (29_000..=30_000).contains(&remaining_ms),
+        "remaining budget should be near the deadline, got {}",
+        remaining_ms
+    );
+}
+
+/// Tests that no header is sent without a deadline, even with a header name
+#[tokio::test]
+async fn test_header_omitted_without_deadline() {
+    let (status, header) = proxy_with(ResponseTemplate::new(200), |config| {
+        config.upstream_timeout_header = Some(TIMEOUT_HEADER.to_string());
+    })
+    .await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(header, None);
+}
+
+/// Tests that an upstream slower than the deadline is cut off
+#[tokio::test]
+async fn test_deadline_bounds_upstream_request() {
+    let (status, _) = proxy_with(
+        ResponseTemplate::new(200).set_delay(Duration::from_secs(5)),
+        |config| config.request_deadline_ms = Some(100),
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::BAD_GATEWAY);
+}
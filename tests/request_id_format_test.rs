@@ -0,0 +1,61 @@
+// Integration tests for the configurable request ID format
+mod common;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use common::SpanRecordCapture;
+use switchboard::config::RequestIdFormat;
+use tower::ServiceExt;
+use tracing_subscriber::layer::SubscriberExt;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+/// Sends one request and returns the req_id recorded on its span
+async fn recorded_req_id(format: RequestIdFormat) -> String {
+    let capture = SpanRecordCapture::default();
+    let _guard =
+        tracing::subscriber::set_default(tracing_subscriber::registry().with(capture.clone()));
+
+    let test_setup = common::setup_test_environment_with_config(|config| {
+        config.request_id_format = format;
+    })
+    .await;
+    Mock::given(method("GET"))
+        .and(path("/v1/models"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&test_setup.mock_server)
+        .await;
+
+    let request = Request::builder()
+        .uri("/v1/models")
+        .body(Body::empty())
+        .unwrap();
+    let response = test_setup.app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let values = capture.values("req_id");
+    assert_eq!(values.len(), 1);
+    // String fields are captured in Debug form, i.e. quoted
+    values[0].trim_matches('"').to_string()
+}
+
+/// Tests that short request IDs are 11 base62 characters
+#[tokio::test]
+async fn test_short_request_id_format() {
+    let req_id = recorded_req_id(RequestIdFormat::Short).await;
+
+    assert_eq!(req_id.len(), 11, "Unexpected short ID {}", req_id);
+    assert!(req_id.chars().all(|c| c.is_ascii_alphanumeric()));
+}
+
+/// Tests that the default format is still a UUID
+#[tokio::test]
+async fn test_uuid_request_id_format() {
+    let req_id = recorded_req_id(RequestIdFormat::Uuid).await;
+
+    assert!(
+        uuid::Uuid::parse_str(&req_id).is_ok(),
+        "Not a UUID: {}",
+        req_id
+    );
+}
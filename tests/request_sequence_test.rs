@@ -0,0 +1,50 @@
+// Integration tests for the per-process request sequence number
+mod common;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use common::SpanRecordCapture;
+use tower::ServiceExt;
+use tracing_subscriber::layer::SubscriberExt;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+async fn send_requests(log_request_sequence: bool) -> Vec<String> {
+    let capture = SpanRecordCapture::default();
+    let _guard =
+        tracing::subscriber::set_default(tracing_subscriber::registry().with(capture.clone()));
+
+    let test_setup = common::setup_test_environment_with_config(|config| {
+        config.log_request_sequence = log_request_sequence;
+    })
+    .await;
+
+    Mock::given(method("GET"))
+        .and(path("/v1/models"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&test_setup.mock_server)
+        .await;
+
+    for _ in 0..2 {
+        let request = Request::builder()
+            .uri("/v1/models")
+            .body(Body::empty())
+            .unwrap();
+        let response = test_setup.app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    capture.values("req_seq")
+}
+
+/// Tests that sequential requests are numbered in increasing order
+#[tokio::test]
+async fn test_sequential_requests_get_increasing_req_seq() {
+    assert_eq!(send_requests(true).await, vec!["1", "2"]);
+}
+
+/// Tests that no sequence number is recorded unless enabled
+#[tokio::test]
+async fn test_req_seq_not_recorded_by_default() {
+    assert!(send_requests(false).await.is_empty());
+}
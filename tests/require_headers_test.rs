@@ -0,0 +1,84 @@
+// Integration tests for rejecting requests without required client headers
+mod common;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use common::SpanRecordCapture;
+use serde_json::{json, Value};
+use tower::ServiceExt;
+use tracing_subscriber::layer::SubscriberExt;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+/// Result of one request through a proxy requiring `x-team-id` and `x-project`
+struct Outcome {
+    status: StatusCode,
+    body: Value,
+    forwarded: usize,
+    recorded: Vec<String>,
+}
+
+/// Sends a request carrying `headers` through a proxy with required headers
+async fn send_with_headers(headers: &[(&str, &str)]) -> Outcome {
+    let capture = SpanRecordCapture::default();
+    let _guard =
+        tracing::subscriber::set_default(tracing_subscriber::registry().with(capture.clone()));
+
+    let test_setup = common::setup_test_environment_with_config(|config| {
+        config.require_headers = vec!["x-team-id".to_string(), "x-project".to_string()];
+    })
+    .await;
+    Mock::given(method("POST"))
+        .and(path("/v1/messages"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"ok": true})))
+        .mount(&test_setup.mock_server)
+        .await;
+
+    let mut request = Request::builder().method("POST").uri("/v1/messages");
+    for (name, value) in headers {
+        request = request.header(*name, *value);
+    }
+    let response = test_setup
+        .app
+        .oneshot(request.body(Body::from("{}")).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+
+    Outcome {
+        status,
+        body: serde_json::from_slice(&body).unwrap(),
+        forwarded: test_setup
+            .mock_server
+            .received_requests()
+            .await
+            .unwrap()
+            .len(),
+        recorded: capture.values("client.required_headers"),
+    }
+}
+
+/// Tests that a request missing a required header is rejected with 400, naming it
+#[tokio::test]
+async fn test_missing_required_header_rejected() {
+    let outcome = send_with_headers(&[("x-team-id", "search")]).await;
+
+    assert_eq!(outcome.status, StatusCode::BAD_REQUEST);
+    assert_eq!(outcome.body["missing_header"], json!("x-project"));
+    assert_eq!(outcome.forwarded, 0);
+}
+
+/// Tests that a request with every required header is forwarded and attributed
+#[tokio::test]
+async fn test_required_headers_present_recorded() {
+    let outcome = send_with_headers(&[("X-Team-Id", "search"), ("x-project", "ranker")]).await;
+
+    assert_eq!(outcome.status, StatusCode::OK);
+    assert_eq!(outcome.body, json!({"ok": true}));
+    assert_eq!(outcome.forwarded, 1);
+    assert_eq!(
+        outcome.recorded,
+        vec![r#"{"x-project": "ranker", "x-team-id": "search"}"#]
+    );
+}
@@ -0,0 +1,62 @@
+// Integration tests for streaming responses that outgrow the buffering limit
+mod common;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use tower::ServiceExt;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+/// Buffering limit used by these tests
+const LIMIT: usize = 1024;
+
+/// Proxies a Messages request to an upstream answering with `body`, returning the client's body
+async fn proxy_body(body: Vec<u8>) -> (StatusCode, Vec<u8>) {
+    let test_setup = common::setup_test_environment_with_config(|config| {
+        config.response_buffer_limit_bytes = Some(LIMIT);
+    })
+    .await;
+    Mock::given(method("POST"))
+        .and(path("/v1/messages"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("content-type", "application/json")
+                .set_body_bytes(body),
+        )
+        .mount(&test_setup.mock_server)
+        .await;
+
+    let request = Request::builder()
+        .method("POST")
+        .uri("/v1/messages")
+        .header("content-type", "application/json")
+        .body(Body::from(r#"{"model":"claude-3-haiku","messages":[]}"#))
+        .unwrap();
+    let response = test_setup.app.oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    (status, body.to_vec())
+}
+
+/// Tests that a response just over the limit reaches the client completely
+#[tokio::test]
+async fn test_response_over_limit_delivered_completely() {
+    let body: Vec<u8> = (0..=LIMIT).map(|i| b'a' + (i % 26) as u8).collect();
+
+    let (status, received) = proxy_body(body.clone()).await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(received.len(), LIMIT + 1);
+    assert_eq!(received, body);
+}
+
+/// Tests that a response within the limit is still forwarded as buffered
+#[tokio::test]
+async fn test_response_within_limit_delivered() {
+    let body = vec![b'x'; LIMIT];
+
+    let (status, received) = proxy_body(body.clone()).await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(received, body);
+}
@@ -0,0 +1,114 @@
+// Integration tests for caching responses to deterministic requests
+mod common;
+
+use axum::body::Body;
+use axum::http::{header, Request, StatusCode};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+fn messages_request(temperature: f64) -> Request<Body> {
+    let body = json!({
+        "model": "claude-3-haiku-20240307",
+        "temperature": temperature,
+        "messages": [{"role": "user", "content": "Hello"}]
+    });
+    Request::builder()
+        .method("POST")
+        .uri("/v1/messages")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
+
+/// Sends a request and returns its cache status header and JSON body
+async fn send(app: &axum::Router, request: Request<Body>) -> (Option<String>, Value) {
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let cache_status = response
+        .headers()
+        .get("x-switchboard-cache")
+        .map(|value| value.to_str().unwrap().to_string());
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    (cache_status, serde_json::from_slice(&body).unwrap())
+}
+
+async fn setup() -> common::TestSetup {
+    let test_setup = common::setup_test_environment_with_config(|config| {
+        config.response_cache_ttl_secs = Some(60);
+    })
+    .await;
+    Mock::given(method("POST"))
+        .and(path("/v1/messages"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"id": "msg_1"})))
+        .mount(&test_setup.mock_server)
+        .await;
+    test_setup
+}
+
+/// Tests that a repeated temperature-0 request is served from the cache
+#[tokio::test]
+async fn test_identical_deterministic_requests_hit_cache() {
+    let test_setup = setup().await;
+
+    let (first_status, first_body) = send(&test_setup.app, messages_request(0.0)).await;
+    let (second_status, second_body) = send(&test_setup.app, messages_request(0.0)).await;
+
+    assert_eq!(first_status.as_deref(), Some("MISS"));
+    assert_eq!(second_status.as_deref(), Some("HIT"));
+    assert_eq!(first_body, second_body);
+
+    let received = test_setup.mock_server.received_requests().await.unwrap();
+    assert_eq!(
+        received.len(),
+        1,
+        "The second request should not reach upstream"
+    );
+}
+
+/// Tests that non-deterministic requests always go upstream
+#[tokio::test]
+async fn test_nonzero_temperature_bypasses_cache() {
+    let test_setup = setup().await;
+
+    for _ in 0..2 {
+        let (cache_status, _) = send(&test_setup.app, messages_request(0.7)).await;
+        assert_eq!(cache_status, None);
+    }
+
+    let received = test_setup.mock_server.received_requests().await.unwrap();
+    assert_eq!(received.len(), 2);
+}
+
+/// Tests that in passthrough mode one client's cached response is never served to another
+#[tokio::test]
+async fn test_passthrough_cache_is_per_client_key() {
+    let test_setup = common::setup_test_environment_with_config(|config| {
+        config.response_cache_ttl_secs = Some(60);
+        config.auth_mode = switchboard::config::AuthMode::Passthrough;
+    })
+    .await;
+    Mock::given(method("POST"))
+        .and(path("/v1/messages"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"id": "msg_1"})))
+        .mount(&test_setup.mock_server)
+        .await;
+    let with_key = |api_key: &str| {
+        let mut request = messages_request(0.0);
+        request
+            .headers_mut()
+            .insert("x-api-key", api_key.parse().unwrap());
+        request
+    };
+
+    let (first, _) = send(&test_setup.app, with_key("client-a-key")).await;
+    let (other_client, _) = send(&test_setup.app, with_key("client-b-key")).await;
+    let (same_client, _) = send(&test_setup.app, with_key("client-a-key")).await;
+
+    assert_eq!(first.as_deref(), Some("MISS"));
+    assert_eq!(other_client.as_deref(), Some("MISS"));
+    assert_eq!(same_client.as_deref(), Some("HIT"));
+    let received = test_setup.mock_server.received_requests().await.unwrap();
+    assert_eq!(received.len(), 2);
+}
@@ -0,0 +1,71 @@
+// Integration tests for the breakdown of response latency into upstream and body read time
+mod common;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use common::EventCapture;
+use std::time::Duration;
+use tower::ServiceExt;
+use tracing_subscriber::layer::SubscriberExt;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+/// Tests that non-streaming responses log total, upstream and body read times that add up
+#[tokio::test]
+async fn test_response_logs_upstream_and_body_read_timings() {
+    let capture = EventCapture::default();
+    let _guard =
+        tracing::subscriber::set_default(tracing_subscriber::registry().with(capture.clone()));
+
+    let test_setup = common::setup_test_environment().await;
+
+    // The delay happens before headers are sent, so it lands in upstream_ms
+    let upstream_delay = Duration::from_millis(100);
+    Mock::given(method("POST"))
+        .and(path("/v1/messages"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_string("{\"ok\":true}")
+                .set_delay(upstream_delay),
+        )
+        .mount(&test_setup.mock_server)
+        .await;
+
+    let request = Request::builder()
+        .method("POST")
+        .uri("/v1/messages")
+        .body(Body::from("{}"))
+        .unwrap();
+    let response = test_setup.app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let events = capture.events.lock().unwrap().clone();
+    let timings = events
+        .iter()
+        .find(|event| event.contains_key("upstream_ms"))
+        .expect("Response details should be logged with upstream_ms");
+
+    let field = |name: &str| -> u128 {
+        timings
+            .get(name)
+            .unwrap_or_else(|| panic!("{} should be logged", name))
+            .parse()
+            .unwrap_or_else(|_| panic!("{} should be a whole number of milliseconds", name))
+    };
+    let duration_ms = field("duration_ms");
+    let upstream_ms = field("upstream_ms");
+    let body_read_ms = field("body_read_ms");
+
+    assert!(
+        upstream_ms >= upstream_delay.as_millis(),
+        "upstream_ms ({}) should include the upstream delay",
+        upstream_ms
+    );
+    assert!(
+        upstream_ms + body_read_ms <= duration_ms,
+        "upstream_ms ({}) + body_read_ms ({}) should not exceed duration_ms ({})",
+        upstream_ms,
+        body_read_ms,
+        duration_ms
+    );
+}
@@ -0,0 +1,65 @@
+// Integration tests for injecting API keys chosen by route
+mod common;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use std::collections::HashMap;
+use tower::ServiceExt;
+use wiremock::matchers::{header, method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+/// Sends a POST to `uri` through a proxy routing batch requests to their own key
+///
+/// The upstream only answers 200 when the request carries `expected_key`.
+async fn post_with_route_keys(uri: &str, expected_key: &str) -> StatusCode {
+    post_with_route_keys_via(uri, "", expected_key).await
+}
+
+/// Like `post_with_route_keys`, with `base_path` appended to the target URL
+async fn post_with_route_keys_via(uri: &str, base_path: &str, expected_key: &str) -> StatusCode {
+    let test_setup = common::setup_test_environment_with_config(|config| {
+        config.anthropic_target_url.push_str(base_path);
+        config.route_api_keys = vec![(
+            "/v1/messages/batches".to_string(),
+            "BATCH_API_KEY".to_string(),
+        )];
+        config.route_api_key_values =
+            HashMap::from([("BATCH_API_KEY".to_string(), "batch-key".to_string())]);
+    })
+    .await;
+    Mock::given(method("POST"))
+        .and(path(format!("{}{}", base_path, uri)))
+        .and(header("x-api-key", expected_key))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&test_setup.mock_server)
+        .await;
+
+    let request = Request::builder()
+        .method("POST")
+        .uri(uri)
+        .body(Body::from("{}"))
+        .unwrap();
+    let response = test_setup.app.oneshot(request).await.unwrap();
+    response.status()
+}
+
+/// Tests that a request matching a route is sent with the route's key
+#[tokio::test]
+async fn test_matching_route_uses_mapped_key() {
+    let status = post_with_route_keys("/v1/messages/batches", "batch-key").await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+/// Tests that other requests keep using the default key
+#[tokio::test]
+async fn test_other_paths_use_default_key() {
+    let status = post_with_route_keys("/v1/messages", "test-api-key").await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+/// Tests that routes match the client's path when the target URL has a base path
+#[tokio::test]
+async fn test_route_matches_client_path_under_target_base_path() {
+    let status = post_with_route_keys_via("/v1/messages/batches", "/anthropic", "batch-key").await;
+    assert_eq!(status, StatusCode::OK);
+}
@@ -0,0 +1,79 @@
+// Integration tests for the Server-Timing response header
+mod common;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use serde_json::json;
+use tower::ServiceExt;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+/// Extracts the `dur` parameter (milliseconds) from a `proxy;dur=<ms>` value
+fn parse_proxy_duration(value: &str) -> Option<f64> {
+    let (name, params) = value.split_once(';')?;
+    if name.trim() != "proxy" {
+        return None;
+    }
+    params.trim().strip_prefix("dur=")?.parse::<f64>().ok()
+}
+
+/// Sends a POST to /v1/messages through a router built with the given setting
+async fn send_with_server_timing(enabled: bool, streaming: bool) -> axum::response::Response {
+    let test_setup = common::setup_test_environment_with_config(|config| {
+        config.server_timing = enabled;
+    })
+    .await;
+
+    let template = if streaming {
+        ResponseTemplate::new(200)
+            .insert_header("content-type", "text/event-stream")
+            .set_body_bytes("data: {\"type\": \"message_stop\"}\n\n")
+    } else {
+        ResponseTemplate::new(200).set_body_json(json!({"status": "ok"}))
+    };
+
+    Mock::given(method("POST"))
+        .and(path("/v1/messages"))
+        .respond_with(template)
+        .mount(&test_setup.mock_server)
+        .await;
+
+    let request = Request::builder()
+        .method("POST")
+        .uri("/v1/messages")
+        .body(Body::from("{}"))
+        .unwrap();
+
+    test_setup.app.oneshot(request).await.unwrap()
+}
+
+#[tokio::test]
+async fn test_server_timing_present_when_enabled() {
+    for streaming in [false, true] {
+        let response = send_with_server_timing(true, streaming).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let value = response
+            .headers()
+            .get("server-timing")
+            .expect("Server-Timing header should be present when enabled")
+            .to_str()
+            .unwrap();
+
+        let duration = parse_proxy_duration(value)
+            .unwrap_or_else(|| panic!("Server-Timing should be parseable, got {}", value));
+        assert!(duration >= 0.0, "Proxy overhead should not be negative");
+    }
+}
+
+#[tokio::test]
+async fn test_server_timing_absent_when_disabled() {
+    for streaming in [false, true] {
+        let response = send_with_server_timing(false, streaming).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(
+            response.headers().get("server-timing").is_none(),
+            "Server-Timing header should be absent when disabled"
+        );
+    }
+}
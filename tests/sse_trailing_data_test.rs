@@ -0,0 +1,80 @@
+// Integration tests for detecting data after the terminal event of a stream
+mod common;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use common::{CapturedEvent, EventCapture};
+use tower::ServiceExt;
+use tracing_subscriber::layer::SubscriberExt;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+/// Message of the trailing data warning
+const TRAILING_DATA_EVENT: &str = "Upstream sent data after the terminal SSE event";
+
+/// A complete Messages stream
+const STREAM: &str = "event: message_start\ndata: {\"type\":\"message_start\"}\n\n\
+    event: message_stop\ndata: {\"type\":\"message_stop\"}\n\n";
+
+/// Streams `upstream_body` through the proxy, checking it reaches the client unchanged
+///
+/// Returns the logged events.
+async fn stream_through_proxy(upstream_body: String, warn: bool) -> Vec<CapturedEvent> {
+    let capture = EventCapture::default();
+    let _guard =
+        tracing::subscriber::set_default(tracing_subscriber::registry().with(capture.clone()));
+
+    let test_setup = common::setup_test_environment_with_config(|config| {
+        config.warn_on_sse_trailing_data = warn;
+    })
+    .await;
+    Mock::given(method("POST"))
+        .and(path("/v1/messages"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("content-type", "text/event-stream")
+                .set_body_bytes(upstream_body.clone()),
+        )
+        .mount(&test_setup.mock_server)
+        .await;
+
+    let request = Request::builder()
+        .method("POST")
+        .uri("/v1/messages")
+        .body(Body::from("{}"))
+        .unwrap();
+    let response = test_setup.app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    assert_eq!(body, upstream_body.as_bytes());
+
+    let events = capture.events.lock().unwrap().clone();
+    events
+}
+
+/// Returns true if the trailing data warning was logged
+fn warned(events: &[CapturedEvent]) -> bool {
+    events
+        .iter()
+        .any(|event| event.get("message").map(String::as_str) == Some(TRAILING_DATA_EVENT))
+}
+
+/// Tests that bytes after message_stop are forwarded and trigger the warning
+#[tokio::test]
+async fn test_trailing_data_warns_and_is_forwarded() {
+    let events = stream_through_proxy(format!("{}leftover", STREAM), true).await;
+
+    let event = common::find_event(&events, TRAILING_DATA_EVENT);
+    assert_eq!(event["trailing_bytes"], "8");
+}
+
+/// Tests that a clean stream, or a disabled check, logs no warning
+#[tokio::test]
+async fn test_no_warning_for_clean_stream_or_when_disabled() {
+    assert!(!warned(
+        &stream_through_proxy(STREAM.to_string(), true).await
+    ));
+    assert!(!warned(
+        &stream_through_proxy(format!("{}leftover", STREAM), false).await
+    ));
+}
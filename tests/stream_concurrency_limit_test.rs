@@ -0,0 +1,109 @@
+// Integration tests for the limit on concurrently open streaming responses
+mod common;
+
+use axum::body::Body;
+use axum::http::{header, Request, StatusCode};
+use tower::ServiceExt;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+fn request(uri: &str) -> Request<Body> {
+    Request::builder()
+        .method("POST")
+        .uri(uri)
+        .body(Body::from("{}"))
+        .unwrap()
+}
+
+/// Sets up a proxy allowing one open stream, with a streaming and a JSON endpoint upstream
+async fn setup_single_stream_proxy() -> common::TestSetup {
+    let test_setup = common::setup_test_environment_with_config(|config| {
+        config.max_concurrent_streams = Some(1);
+    })
+    .await;
+
+    Mock::given(method("POST"))
+        .and(path("/v1/messages"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(
+            "data: {\"type\": \"message_stop\"}\n\n",
+            "text/event-stream",
+        ))
+        .mount(&test_setup.mock_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/v1/messages/count_tokens"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({})))
+        .mount(&test_setup.mock_server)
+        .await;
+
+    test_setup
+}
+
+/// Tests that a second stream is rejected while the only stream slot is held
+#[tokio::test]
+async fn test_stream_rejected_when_stream_slots_exhausted() {
+    let test_setup = setup_single_stream_proxy().await;
+
+    // The first stream holds its slot until its body is consumed or dropped
+    let open_stream = test_setup
+        .app
+        .clone()
+        .oneshot(request("/v1/messages"))
+        .await
+        .unwrap();
+    assert_eq!(open_stream.status(), StatusCode::OK);
+
+    let rejected = test_setup
+        .app
+        .clone()
+        .oneshot(request("/v1/messages"))
+        .await
+        .unwrap();
+    assert_eq!(rejected.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert!(
+        rejected.headers().contains_key(header::RETRY_AFTER),
+        "Stream limit rejection should include Retry-After"
+    );
+
+    // Non-streaming requests do not consume stream slots
+    let non_streaming = test_setup
+        .app
+        .oneshot(request("/v1/messages/count_tokens"))
+        .await
+        .unwrap();
+    assert_eq!(non_streaming.status(), StatusCode::OK);
+
+    drop(open_stream);
+}
+
+/// Tests that the stream slot is released once a stream completes or is dropped
+#[tokio::test]
+async fn test_stream_slot_released_on_completion_and_disconnect() {
+    let test_setup = setup_single_stream_proxy().await;
+
+    // Reading a stream to the end releases its slot
+    let completed = test_setup
+        .app
+        .clone()
+        .oneshot(request("/v1/messages"))
+        .await
+        .unwrap();
+    hyper::body::to_bytes(completed.into_body()).await.unwrap();
+
+    // Dropping a stream unread (client disconnect) releases its slot too
+    let disconnected = test_setup
+        .app
+        .clone()
+        .oneshot(request("/v1/messages"))
+        .await
+        .unwrap();
+    assert_eq!(disconnected.status(), StatusCode::OK);
+    drop(disconnected);
+
+    let next = test_setup
+        .app
+        .oneshot(request("/v1/messages"))
+        .await
+        .unwrap();
+    assert_eq!(next.status(), StatusCode::OK);
+}
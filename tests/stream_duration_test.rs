@@ -0,0 +1,83 @@
+// Integration tests for the maximum duration of streaming responses
+mod common;
+
+use axum::body::Body;
+use axum::http::{header, Request, StatusCode};
+use axum::response::Response;
+use axum::routing::post;
+use axum::Router;
+use common::{find_event, EventCapture};
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+use tower::ServiceExt;
+use tracing_subscriber::layer::SubscriberExt;
+
+/// Starts an upstream whose /v1/messages streams an SSE event every 50ms, forever
+async fn start_endless_stream_upstream() -> String {
+    let app = Router::new().route(
+        "/v1/messages",
+        post(|| async {
+            let events = futures_util::stream::unfold((), |()| async {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                Some((
+                    Ok::<_, std::io::Error>("data: {\"type\": \"ping\"}\n\n"),
+                    (),
+                ))
+            });
+            Response::builder()
+                .header(header::CONTENT_TYPE, "text/event-stream")
+                .body(Body::wrap_stream(events))
+                .unwrap()
+        }),
+    );
+
+    let server =
+        axum::Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0))).serve(app.into_make_service());
+    let url = format!("http://{}", server.local_addr());
+    tokio::spawn(server);
+    url
+}
+
+/// Tests that a stream running past the configured maximum is truncated with a warning
+#[tokio::test]
+async fn test_endless_stream_truncated_after_max_duration() {
+    let capture = EventCapture::default();
+    let _guard =
+        tracing::subscriber::set_default(tracing_subscriber::registry().with(capture.clone()));
+
+    let upstream_url = start_endless_stream_upstream().await;
+    let test_setup = common::setup_test_environment_with_config(|config| {
+        config.anthropic_target_url = upstream_url;
+        config.max_stream_duration_secs = Some(1);
+    })
+    .await;
+
+    let request = Request::builder()
+        .method("POST")
+        .uri("/v1/messages")
+        .body(Body::from("{}"))
+        .unwrap();
+    let started = Instant::now();
+    let response = test_setup.app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // The body ends even though the upstream never stops sending
+    let body = hyper::body::to_bytes(response.into_body())
+        .await
+        .expect("Truncated stream should end cleanly");
+    let elapsed = started.elapsed();
+
+    assert!(!body.is_empty(), "Events sent before the cap should arrive");
+    assert!(
+        elapsed >= Duration::from_secs(1) && elapsed < Duration::from_secs(3),
+        "Stream should end shortly after the 1s cap, took {:?}",
+        elapsed
+    );
+
+    let events = capture.events.lock().unwrap().clone();
+    let warning = find_event(
+        &events,
+        "Streaming response exceeded maximum duration, truncating",
+    );
+    assert_eq!(warning["max_stream_duration_secs"], "1");
+}
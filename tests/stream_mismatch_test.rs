@@ -0,0 +1,72 @@
+// Integration tests for comparing the requested and actual streaming mode
+mod common;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use common::{find_event, EventCapture, SpanRecordCapture};
+use tower::ServiceExt;
+use tracing_subscriber::layer::SubscriberExt;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+const MISMATCH_MESSAGE: &str = "Response streaming does not match the request's stream field";
+
+/// Sends a `stream: true` request answered with plain JSON, returning events and span records
+async fn stream_request_answered_with_json(
+    warn_on_stream_mismatch: bool,
+) -> (Vec<common::CapturedEvent>, SpanRecordCapture) {
+    let events = EventCapture::default();
+    let records = SpanRecordCapture::default();
+    let _guard = tracing::subscriber::set_default(
+        tracing_subscriber::registry()
+            .with(events.clone())
+            .with(records.clone()),
+    );
+
+    let test_setup = common::setup_test_environment_with_config(|config| {
+        config.warn_on_stream_mismatch = warn_on_stream_mismatch;
+    })
+    .await;
+    Mock::given(method("POST"))
+        .and(path("/v1/messages"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({})))
+        .mount(&test_setup.mock_server)
+        .await;
+
+    let request = Request::builder()
+        .method("POST")
+        .uri("/v1/messages")
+        .body(Body::from(
+            r#"{"model":"claude-3-haiku","stream":true,"messages":[]}"#,
+        ))
+        .unwrap();
+    let response = test_setup.app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let events = events.events.lock().unwrap().clone();
+    (events, records)
+}
+
+/// Tests that a stream request answered without a stream logs the mismatch
+#[tokio::test]
+async fn test_stream_requested_but_not_streamed_warns() {
+    let (events, records) = stream_request_answered_with_json(true).await;
+
+    let warning = find_event(&events, MISMATCH_MESSAGE);
+    assert_eq!(warning["request.stream_requested"], "true");
+    assert_eq!(warning["response.is_streaming"], "false");
+
+    assert_eq!(records.values("request.stream_requested"), vec!["true"]);
+    assert_eq!(records.values("response.is_streaming"), vec!["false"]);
+}
+
+/// Tests that the warning can be disabled while the span fields are still recorded
+#[tokio::test]
+async fn test_stream_mismatch_warning_can_be_disabled() {
+    let (events, records) = stream_request_answered_with_json(false).await;
+
+    assert!(events
+        .iter()
+        .all(|event| event.get("message").map(String::as_str) != Some(MISMATCH_MESSAGE)));
+    assert_eq!(records.values("response.is_streaming"), vec!["false"]);
+}
@@ -0,0 +1,82 @@
+// Integration tests for streaming request bodies to the upstream without buffering
+mod common;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use bytes::Bytes;
+use tower::ServiceExt;
+use wiremock::matchers::{body_bytes, method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+const CHUNK_SIZE: usize = 64 * 1024;
+const CHUNK_COUNT: usize = 64;
+
+/// Builds a 4 MiB body whose chunks differ, so reordering or truncation is detected
+fn large_body() -> Vec<u8> {
+    (0..CHUNK_COUNT)
+        .flat_map(|chunk| std::iter::repeat_n(chunk as u8, CHUNK_SIZE))
+        .collect()
+}
+
+/// Builds a request sending `body` in chunks without a Content-Length header
+fn chunked_upload(body: &[u8]) -> Request<Body> {
+    let chunks: Vec<Result<Bytes, std::io::Error>> = body
+        .chunks(CHUNK_SIZE)
+        .map(|chunk| Ok(Bytes::copy_from_slice(chunk)))
+        .collect();
+    Request::builder()
+        .method("POST")
+        .uri("/v1/files")
+        .body(Body::wrap_stream(futures_util::stream::iter(chunks)))
+        .unwrap()
+}
+
+/// Tests that a large body is forwarded intact in streaming mode, without being buffered
+#[tokio::test]
+async fn test_large_body_forwarded_when_streaming() {
+    // A budget far below the body size proves the body never passes through the buffer
+    let test_setup = common::setup_test_environment_with_config(|config| {
+        config.stream_request_body = true;
+        config.log_bodies = false;
+        config.max_total_buffered_bytes = Some(1024);
+    })
+    .await;
+
+    let body = large_body();
+    Mock::given(method("POST"))
+        .and(path("/v1/files"))
+        .and(body_bytes(body.clone()))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&test_setup.mock_server)
+        .await;
+
+    let response = test_setup.app.oneshot(chunked_upload(&body)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+/// Tests that bodies are still buffered when body logging needs them
+#[tokio::test]
+async fn test_body_buffered_when_logging_bodies() {
+    let test_setup = common::setup_test_environment_with_config(|config| {
+        config.stream_request_body = true;
+        config.log_bodies = true;
+        config.max_total_buffered_bytes = Some(1024);
+    })
+    .await;
+
+    Mock::given(method("POST"))
+        .and(path("/v1/files"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&test_setup.mock_server)
+        .await;
+
+    // Buffering counts against the budget, which the body exceeds
+    let response = test_setup
+        .app
+        .oneshot(chunked_upload(&large_body()))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+}
@@ -0,0 +1,60 @@
+// Integration tests for time-to-first-byte timing of streaming responses
+mod common;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use common::{find_event, EventCapture};
+use tower::ServiceExt;
+use tracing_subscriber::layer::SubscriberExt;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+/// Tests that streaming responses record time to first byte below the total duration
+#[tokio::test]
+async fn test_streaming_response_records_ttfb() {
+    let capture = EventCapture::default();
+    let _guard =
+        tracing::subscriber::set_default(tracing_subscriber::registry().with(capture.clone()));
+
+    let test_setup = common::setup_test_environment().await;
+
+    Mock::given(method("POST"))
+        .and(path("/v1/messages"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("content-type", "text/event-stream")
+                .set_body_bytes("data: {\"type\": \"message_stop\"}\n\n"),
+        )
+        .mount(&test_setup.mock_server)
+        .await;
+
+    let request = Request::builder()
+        .method("POST")
+        .uri("/v1/messages")
+        .body(Body::from("{}"))
+        .unwrap();
+    let response = test_setup.app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Consuming (and then dropping) the body drives the stream to completion
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    assert!(!body.is_empty());
+
+    let events = capture.events.lock().unwrap().clone();
+
+    let first_chunk = find_event(&events, "First streaming chunk received from Anthropic API");
+    let ttfb_ms: f64 = first_chunk["ttfb_ms"]
+        .parse()
+        .expect("ttfb_ms should be recorded");
+
+    let finished = find_event(&events, "Streaming response finished");
+    let total_ms: f64 = finished["stream_duration_ms"].parse().unwrap();
+
+    assert!(ttfb_ms > 0.0, "ttfb_ms should be positive, got {}", ttfb_ms);
+    assert!(
+        ttfb_ms < total_ms,
+        "ttfb_ms ({}) should be less than the total stream duration ({})",
+        ttfb_ms,
+        total_ms
+    );
+}
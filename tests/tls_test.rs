@@ -0,0 +1,82 @@
+// Integration tests for serving HTTPS with TLS termination at the proxy
+mod common;
+
+use serde_json::json;
+use std::net::TcpListener;
+use switchboard::tls::load_rustls_config;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+/// Self-signed test CA and a localhost certificate issued by it
+const TEST_CA_PATH: &str = "tests/fixtures/tls/ca.pem";
+const TEST_CERT_PATH: &str = "tests/fixtures/tls/cert.pem";
+const TEST_KEY_PATH: &str = "tests/fixtures/tls/key.pem";
+
+/// Tests that an HTTPS request to the proxy succeeds and is forwarded upstream
+#[tokio::test]
+async fn test_https_request_succeeds_with_test_certificate() {
+    let test_setup = common::setup_test_environment_with_config(|config| {
+        config.tls_cert_path = Some(TEST_CERT_PATH.to_string());
+        config.tls_key_path = Some(TEST_KEY_PATH.to_string());
+    })
+    .await;
+
+    Mock::given(method("POST"))
+        .and(path("/v1/messages"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"status": "ok"})))
+        .mount(&test_setup.mock_server)
+        .await;
+
+    // Load the TLS config the same way main.rs does
+    let tls_config = load_rustls_config(&test_setup.config)
+        .await
+        .expect("Test certificate should load")
+        .expect("TLS should be enabled when both paths are set");
+
+    // Serve the router over HTTPS on a random local port
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let app = test_setup.app.clone();
+    tokio::spawn(async move {
+        axum_server::from_tcp_rustls(listener, tls_config)
+            .serve(app.into_make_service())
+            .await
+            .unwrap();
+    });
+
+    // Trust only the test CA, so success proves the proxy presented the test certificate
+    let ca = reqwest::Certificate::from_pem(&std::fs::read(TEST_CA_PATH).unwrap()).unwrap();
+    let client = reqwest::Client::builder()
+        .use_rustls_tls()
+        .add_root_certificate(ca)
+        .build()
+        .unwrap();
+
+    let response = client
+        .post(format!("https://localhost:{}/v1/messages", port))
+        .body("{}")
+        .send()
+        .await
+        .expect("HTTPS request should succeed");
+
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body, json!({"status": "ok"}));
+}
+
+/// Tests that unparseable PEM files are reported as a load error
+#[tokio::test]
+async fn test_invalid_pem_is_rejected() {
+    let config = switchboard::config::Config {
+        // The CA file has no private key, so using it as the key must fail
+        tls_cert_path: Some(TEST_CERT_PATH.to_string()),
+        tls_key_path: Some(TEST_CA_PATH.to_string()),
+        ..Default::default()
+    };
+
+    let result = load_rustls_config(&config).await;
+    assert!(
+        matches!(result, Err(switchboard::tls::TlsConfigError::Load(_))),
+        "Invalid key PEM should fail to load"
+    );
+}
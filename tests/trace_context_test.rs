@@ -0,0 +1,72 @@
+// Integration tests for W3C traceparent propagation to the upstream API
+mod common;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use serde_json::json;
+use switchboard::trace_context::{TraceParent, TRACEPARENT_HEADER};
+use tower::ServiceExt;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+const INCOMING_TRACEPARENT: &str = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+
+/// Sends a POST to /v1/messages and returns the traceparent the upstream received
+async fn forwarded_traceparent(generate: bool, incoming: Option<&str>) -> Option<String> {
+    let test_setup = common::setup_test_environment_with_config(|config| {
+        config.generate_traceparent = generate;
+    })
+    .await;
+
+    Mock::given(method("POST"))
+        .and(path("/v1/messages"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"status": "ok"})))
+        .mount(&test_setup.mock_server)
+        .await;
+
+    let mut builder = Request::builder().method("POST").uri("/v1/messages");
+    if let Some(traceparent) = incoming {
+        builder = builder.header(TRACEPARENT_HEADER, traceparent);
+    }
+    let request = builder.body(Body::from("{}")).unwrap();
+
+    let response = test_setup.app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let received = test_setup.mock_server.received_requests().await.unwrap();
+    assert_eq!(
+        received.len(),
+        1,
+        "Exactly one request should reach upstream"
+    );
+    received[0]
+        .headers
+        .get(TRACEPARENT_HEADER)
+        .map(|value| value.to_str().unwrap().to_string())
+}
+
+/// An incoming traceparent is forwarded unchanged, even when generation is enabled
+#[tokio::test]
+async fn test_existing_traceparent_propagated_unchanged() {
+    for generate in [false, true] {
+        let forwarded = forwarded_traceparent(generate, Some(INCOMING_TRACEPARENT)).await;
+        assert_eq!(forwarded.as_deref(), Some(INCOMING_TRACEPARENT));
+    }
+}
+
+/// Without an incoming traceparent, a valid one is generated when enabled
+#[tokio::test]
+async fn test_traceparent_generated_when_absent() {
+    let forwarded = forwarded_traceparent(true, None)
+        .await
+        .expect("A traceparent should be generated for the upstream request");
+
+    let parsed = TraceParent::parse(&forwarded).expect("Generated traceparent should be valid");
+    assert_eq!(parsed.to_string(), forwarded);
+}
+
+/// Without an incoming traceparent and with generation disabled, none is added
+#[tokio::test]
+async fn test_no_traceparent_added_when_generation_disabled() {
+    assert_eq!(forwarded_traceparent(false, None).await, None);
+}
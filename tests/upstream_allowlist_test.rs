@@ -0,0 +1,49 @@
+// Integration tests for restricting which upstream hosts requests are forwarded to
+mod common;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use tower::ServiceExt;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+/// Sends a request with the given allowlist, returning the status and upstream request count
+async fn send_with_allowlist(allowlist: &[&str]) -> (StatusCode, usize) {
+    let test_setup = common::setup_test_environment_with_config(|config| {
+        config.upstream_host_allowlist =
+            Some(allowlist.iter().map(|host| host.to_string()).collect());
+    })
+    .await;
+    Mock::given(method("GET"))
+        .and(path("/v1/models"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&test_setup.mock_server)
+        .await;
+
+    let request = Request::builder()
+        .uri("/v1/models")
+        .body(Body::empty())
+        .unwrap();
+    let response = test_setup.app.oneshot(request).await.unwrap();
+    let received = test_setup.mock_server.received_requests().await.unwrap();
+    (response.status(), received.len())
+}
+
+/// Tests that requests to an allowed host are forwarded
+#[tokio::test]
+async fn test_allowed_host_is_forwarded() {
+    // The mock upstream listens on 127.0.0.1
+    assert_eq!(
+        send_with_allowlist(&["api.anthropic.com", "127.0.0.1"]).await,
+        (StatusCode::OK, 1)
+    );
+}
+
+/// Tests that requests to a host outside the allowlist are rejected without forwarding
+#[tokio::test]
+async fn test_disallowed_host_is_rejected() {
+    assert_eq!(
+        send_with_allowlist(&["api.anthropic.com"]).await,
+        (StatusCode::FORBIDDEN, 0)
+    );
+}
@@ -0,0 +1,50 @@
+// Integration tests for recording Anthropic's request ID on the request span
+mod common;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use common::SpanRecordCapture;
+use tower::ServiceExt;
+use tracing_subscriber::layer::SubscriberExt;
+use wiremock::{Mock, ResponseTemplate};
+
+/// Proxies a request to an upstream answering with a `request-id`, returning the recorded values
+async fn recorded_upstream_ids(log_upstream_request_id: bool) -> Vec<String> {
+    let capture = SpanRecordCapture::default();
+    let _guard =
+        tracing::subscriber::set_default(tracing_subscriber::registry().with(capture.clone()));
+
+    let test_setup = common::setup_test_environment_with_config(|config| {
+        config.log_upstream_request_id = log_upstream_request_id;
+    })
+    .await;
+    Mock::given(wiremock::matchers::method("POST"))
+        .respond_with(ResponseTemplate::new(200).insert_header("request-id", "req_011CRQ7x"))
+        .mount(&test_setup.mock_server)
+        .await;
+
+    let request = Request::builder()
+        .method("POST")
+        .uri("/v1/messages")
+        .body(Body::from("{}"))
+        .unwrap();
+    let response = test_setup.app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    capture.values("anthropic.request_id")
+}
+
+/// Tests that the upstream request ID is recorded as `anthropic.request_id`
+#[tokio::test]
+async fn test_upstream_request_id_recorded() {
+    assert_eq!(
+        recorded_upstream_ids(true).await,
+        vec![format!("{:?}", "req_011CRQ7x")]
+    );
+}
+
+/// Tests that nothing is recorded when the option is off
+#[tokio::test]
+async fn test_upstream_request_id_not_recorded_when_disabled() {
+    assert!(recorded_upstream_ids(false).await.is_empty());
+}
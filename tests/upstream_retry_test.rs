@@ -0,0 +1,112 @@
+// Integration tests for retrying configured upstream statuses
+mod common;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use tower::ServiceExt;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+/// Sends a GET to an upstream that first answers `first_status`, then 200
+///
+/// Returns the status relayed to the client and the number of upstream attempts.
+async fn proxy_with_first_status(
+    first_status: u16,
+    retryable_statuses: Vec<u16>,
+) -> (StatusCode, usize) {
+    let test_setup = common::setup_test_environment_with_config(|config| {
+        config.upstream_retries = 2;
+        config.retryable_statuses = retryable_statuses;
+    })
+    .await;
+    Mock::given(method("GET"))
+        .and(path("/v1/models"))
+        .respond_with(ResponseTemplate::new(first_status))
+        .up_to_n_times(1)
+        .with_priority(1)
+        .mount(&test_setup.mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/v1/models"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("{}"))
+        .mount(&test_setup.mock_server)
+        .await;
+
+    let request = Request::builder()
+        .method("GET")
+        .uri("/v1/models")
+        .body(Body::empty())
+        .unwrap();
+    let response = test_setup.app.oneshot(request).await.unwrap();
+
+    let received = test_setup.mock_server.received_requests().await.unwrap();
+    (response.status(), received.len())
+}
+
+/// Tests that a status configured as retryable, even a 4xx, triggers a retry
+#[tokio::test]
+async fn test_configured_status_is_retried() {
+    let (status, attempts) = proxy_with_first_status(408, vec![408, 503]).await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(attempts, 2);
+}
+
+/// Tests that a status missing from the list is relayed without retrying
+#[tokio::test]
+async fn test_unlisted_status_is_not_retried() {
+    let (status, attempts) = proxy_with_first_status(503, vec![408]).await;
+
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(attempts, 1);
+}
+
+/// Tests that the last response is relayed once retries are used up
+#[tokio::test]
+async fn test_last_response_relayed_when_retries_run_out() {
+    let test_setup = common::setup_test_environment_with_config(|config| {
+        config.upstream_retries = 2;
+    })
+    .await;
+    Mock::given(method("GET"))
+        .and(path("/v1/models"))
+        .respond_with(ResponseTemplate::new(529))
+        .mount(&test_setup.mock_server)
+        .await;
+
+    let request = Request::builder()
+        .method("GET")
+        .uri("/v1/models")
+        .body(Body::empty())
+        .unwrap();
+    let response = test_setup.app.oneshot(request).await.unwrap();
+
+    assert_eq!(response.status().as_u16(), 529);
+    let received = test_setup.mock_server.received_requests().await.unwrap();
+    assert_eq!(received.len(), 3);
+}
+
+/// Tests that a POST is never resent, as it could create a second message
+#[tokio::test]
+async fn test_post_is_not_retried() {
+    let test_setup = common::setup_test_environment_with_config(|config| {
+        config.upstream_retries = 2;
+    })
+    .await;
+    Mock::given(method("POST"))
+        .and(path("/v1/messages"))
+        .respond_with(ResponseTemplate::new(503))
+        .mount(&test_setup.mock_server)
+        .await;
+
+    let request = Request::builder()
+        .method("POST")
+        .uri("/v1/messages")
+        .body(Body::from("{}"))
+        .unwrap();
+    let response = test_setup.app.oneshot(request).await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    let received = test_setup.mock_server.received_requests().await.unwrap();
+    assert_eq!(received.len(), 1);
+}
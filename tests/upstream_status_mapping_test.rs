@@ -0,0 +1,59 @@
+// Integration tests for reporting upstream 5xx responses under a configured status
+mod common;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use switchboard::proxy_handler::ORIGINAL_STATUS_HEADER;
+use tower::ServiceExt;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+const ERROR_BODY: &str = r#"{"type":"error","error":{"type":"api_error","message":"bad gateway"}}"#;
+
+/// Proxies a Messages request to an upstream answering `status`, returning the response
+async fn proxy_status(status: u16, map_upstream_5xx_to: Option<u16>) -> axum::response::Response {
+    let test_setup = common::setup_test_environment_with_config(|config| {
+        config.map_upstream_5xx_to = map_upstream_5xx_to;
+    })
+    .await;
+    Mock::given(method("POST"))
+        .and(path("/v1/messages"))
+        .respond_with(
+            ResponseTemplate::new(status)
+                .insert_header("content-type", "application/json")
+                .set_body_string(ERROR_BODY),
+        )
+        .mount(&test_setup.mock_server)
+        .await;
+
+    let request = Request::builder()
+        .method("POST")
+        .uri("/v1/messages")
+        .header("content-type", "application/json")
+        .body(Body::from(r#"{"model":"claude-3-haiku","messages":[]}"#))
+        .unwrap();
+    test_setup.app.oneshot(request).await.unwrap()
+}
+
+/// Tests that a mapped 502 is sent as 503 with the original status and body
+#[tokio::test]
+async fn test_upstream_502_mapped_to_503() {
+    let response = proxy_status(502, Some(503)).await;
+
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(response.headers()[ORIGINAL_STATUS_HEADER], "502");
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    assert_eq!(body, ERROR_BODY);
+}
+
+/// Tests that the real status is forwarded without a mapping, or for non-5xx statuses
+#[tokio::test]
+async fn test_status_forwarded_when_not_mapped() {
+    let unmapped = proxy_status(502, None).await;
+    assert_eq!(unmapped.status(), StatusCode::BAD_GATEWAY);
+    assert!(!unmapped.headers().contains_key(ORIGINAL_STATUS_HEADER));
+
+    let client_error = proxy_status(429, Some(503)).await;
+    assert_eq!(client_error.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert!(!client_error.headers().contains_key(ORIGINAL_STATUS_HEADER));
+}
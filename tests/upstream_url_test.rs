@@ -0,0 +1,66 @@
+// Integration tests for recording the resolved upstream URL on the request span
+mod common;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use common::SpanRecordCapture;
+use tower::ServiceExt;
+use tracing_subscriber::layer::SubscriberExt;
+use wiremock::{Mock, ResponseTemplate};
+
+/// Sends `uri` through the proxy, returning the recorded `upstream.url` values and the upstream base
+async fn recorded_upstream_urls(normalize_path: bool, uri: &str) -> (Vec<String>, String) {
+    let capture = SpanRecordCapture::default();
+    let _guard =
+        tracing::subscriber::set_default(tracing_subscriber::registry().with(capture.clone()));
+
+    let test_setup = common::setup_test_environment_with_config(|config| {
+        config.normalize_path = normalize_path;
+        config.redact_query_params = vec!["token".to_string()];
+    })
+    .await;
+    Mock::given(wiremock::matchers::method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&test_setup.mock_server)
+        .await;
+
+    let request = Request::builder()
+        .method("POST")
+        .uri(uri)
+        .body(Body::from("{}"))
+        .unwrap();
+    let response = test_setup.app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    (capture.values("upstream.url"), test_setup.mock_server.uri())
+}
+
+/// Tests that the upstream URL is recorded with sensitive query values redacted
+#[tokio::test]
+async fn test_upstream_url_recorded_with_query_redacted() {
+    let (urls, upstream) = recorded_upstream_urls(false, "/v1/messages?token=secret&beta=1").await;
+
+    assert_eq!(
+        urls,
+        vec![format!(
+            "{:?}",
+            format!("{}/v1/messages?token=[REDACTED]&beta=1", upstream)
+        )]
+    );
+}
+
+/// Tests that the recorded URL is the rewritten one when paths are normalized
+#[tokio::test]
+async fn test_upstream_url_reflects_path_rewrite() {
+    let (urls, upstream) = recorded_upstream_urls(true, "//v1///messages").await;
+    assert_eq!(
+        urls,
+        vec![format!("{:?}", format!("{}/v1/messages", upstream))]
+    );
+
+    let (urls, upstream) = recorded_upstream_urls(false, "//v1///messages").await;
+    assert_eq!(
+        urls,
+        vec![format!("{:?}", format!("{}//v1///messages", upstream))]
+    );
+}
@@ -0,0 +1,55 @@
+// Integration tests for priming the upstream connection pool at startup
+mod common;
+
+use switchboard::warmup::spawn_warmup;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+/// Tests that one warm-up request reaches the upstream when enabled
+#[tokio::test]
+async fn test_warmup_request_sent_when_enabled() {
+    let test_setup = common::setup_test_environment_with_config(|config| {
+        config.warmup_on_start = true;
+    })
+    .await;
+    Mock::given(method("GET"))
+        .and(path("/"))
+        .respond_with(ResponseTemplate::new(404))
+        .expect(1)
+        .mount(&test_setup.mock_server)
+        .await;
+
+    let warmup = spawn_warmup(reqwest::Client::new(), &test_setup.config)
+        .expect("Warm-up should start when enabled");
+
+    // Any response counts, even an error status
+    assert!(warmup.await.unwrap());
+    test_setup.mock_server.verify().await;
+}
+
+/// Tests that no request is made when disabled
+#[tokio::test]
+async fn test_no_warmup_request_when_disabled() {
+    let test_setup = common::setup_test_environment().await;
+
+    assert!(spawn_warmup(reqwest::Client::new(), &test_setup.config).is_none());
+    assert!(test_setup
+        .mock_server
+        .received_requests()
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+/// Tests that an unreachable upstream is reported without failing
+#[tokio::test]
+async fn test_warmup_failure_is_not_fatal() {
+    let test_setup = common::setup_test_environment_with_config(|config| {
+        config.warmup_on_start = true;
+        config.anthropic_target_url = "http://127.0.0.1:1".to_string();
+    })
+    .await;
+
+    let warmup = spawn_warmup(reqwest::Client::new(), &test_setup.config).unwrap();
+    assert!(!warmup.await.unwrap());
+}
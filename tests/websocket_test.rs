@@ -0,0 +1,176 @@
+// Integration tests for WebSocket upgrade requests
+mod common;
+
+use axum::body::Body;
+use axum::http::{header, Request, StatusCode};
+use std::net::SocketAddr;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tower::ServiceExt;
+
+/// The client handshake, as a browser would send it
+const HANDSHAKE: &str = "GET /v1/realtime HTTP/1.1\r\n\
+    Host: proxy.test\r\n\
+    Upgrade: websocket\r\n\
+    Connection: Upgrade\r\n\
+    Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\
+    Sec-WebSocket-Version: 13\r\n\r\n";
+
+/// Reads from `socket` until the end of an HTTP head, returning the head
+async fn read_head(socket: &mut TcpStream) -> String {
+    let mut head = Vec::new();
+    let mut byte = [0u8; 1];
+    while !head.ends_with(b"\r\n\r\n") {
+        if socket.read(&mut byte).await.unwrap() == 0 {
+            break;
+        }
+        head.push(byte[0]);
+    }
+    String::from_utf8(head).unwrap()
+}
+
+/// Starts an upstream that accepts one WebSocket handshake and echoes every byte
+///
+/// The proxy relays raw bytes, so the echo does not need to speak the frame format.
+/// Returns the upstream URL and a channel yielding the handshake it received.
+async fn start_echo_upstream() -> (String, tokio::sync::oneshot::Receiver<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let (head_tx, head_rx) = tokio::sync::oneshot::channel();
+    tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let head = read_head(&mut socket).await;
+        let _ = head_tx.send(head);
+        socket
+            .write_all(
+                b"HTTP/1.1 101 Switching Protocols\r\n\
+                  Upgrade: websocket\r\n\
+                  Connection: Upgrade\r\n\
+                  Sec-WebSocket-Accept: s3pPLMBiTxaQ9kYGzzhZRbK+xOo=\r\n\r\n",
+            )
+            .await
+            .unwrap();
+        let (mut reader, mut writer) = socket.split();
+        let _ = tokio::io::copy(&mut reader, &mut writer).await;
+    });
+    (format!("http://{}", addr), head_rx)
+}
+
+/// Tests that upgrade requests are refused with 501 when tunnelling is disabled
+#[tokio::test]
+async fn test_websocket_upgrade_rejected_when_disabled() {
+    let test_setup = common::setup_test_environment().await;
+
+    let request = Request::builder()
+        .method("GET")
+        .uri("/v1/realtime")
+        .header(header::UPGRADE, "websocket")
+        .header(header::CONNECTION, "Upgrade")
+        .body(Body::empty())
+        .unwrap();
+    let response = test_setup.app.oneshot(request).await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::NOT_IMPLEMENTED);
+    // Nothing reached the upstream
+    let received = test_setup.mock_server.received_requests().await.unwrap();
+    assert!(received.is_empty());
+}
+
+/// Tests that an accepted upgrade is tunnelled, relaying bytes both ways
+#[tokio::test]
+async fn test_websocket_tunnel_relays_bytes() {
+    let (upstream_url, upstream_head) = start_echo_upstream().await;
+    let test_setup = common::setup_test_environment_with_config(|config| {
+        config.anthropic_target_url = upstream_url;
+        config.allow_websocket = true;
+    })
+    .await;
+
+    // Upgrades need a real connection, so serve the app instead of calling it directly
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let proxy_addr = listener.local_addr().unwrap();
+    let server = axum::Server::from_tcp(listener).unwrap().serve(
+        test_setup
+            .app
+            .into_make_service_with_connect_info::<SocketAddr>(),
+    );
+    tokio::spawn(server);
+
+    let mut client = TcpStream::connect(proxy_addr).await.unwrap();
+    client.write_all(HANDSHAKE.as_bytes()).await.unwrap();
+    let response_head = read_head(&mut client).await;
+    assert!(
+        response_head.starts_with("HTTP/1.1 101"),
+        "{}",
+        response_head
+    );
+    assert!(response_head
+        .to_lowercase()
+        .contains("sec-websocket-accept: s3pplmbitxaq9kygzzhzrbk+xoo="));
+
+    // The upstream saw the handshake with the proxy's credentials
+    let head = upstream_head.await.unwrap().to_lowercase();
+    assert!(head.starts_with("get /v1/realtime "), "{}", head);
+    assert!(head.contains("upgrade: websocket"), "{}", head);
+    assert!(head.contains("x-api-key: "), "{}", head);
+
+    client.write_all(b"\x81\x05hello").await.unwrap();
+    let mut echoed = [0u8; 7];
+    client.read_exact(&mut echoed).await.unwrap();
+    assert_eq!(&echoed, b"\x81\x05hello");
+}
+
+/// Sends an ordinary request to the proxy at `addr`, returning the status line
+async fn status_line(addr: SocketAddr) -> String {
+    let mut socket = TcpStream::connect(addr).await.unwrap();
+    socket
+        .write_all(b"GET /v1/models HTTP/1.1\r\nHost: proxy.test\r\nConnection: close\r\n\r\n")
+        .await
+        .unwrap();
+    let head = read_head(&mut socket).await;
+    head.lines().next().unwrap_or_default().to_string()
+}
+
+/// Tests that an open tunnel keeps its concurrency slot until it closes
+#[tokio::test]
+async fn test_websocket_tunnel_holds_concurrency_slot() {
+    let (upstream_url, _upstream_head) = start_echo_upstream().await;
+    let test_setup = common::setup_test_environment_with_config(|config| {
+        config.anthropic_target_url = upstream_url;
+        config.allow_websocket = true;
+        config.max_concurrent_requests = Some(1);
+    })
+    .await;
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let proxy_addr = listener.local_addr().unwrap();
+    let server = axum::Server::from_tcp(listener).unwrap().serve(
+        test_setup
+            .app
+            .into_make_service_with_connect_info::<SocketAddr>(),
+    );
+    tokio::spawn(server);
+
+    let mut client = TcpStream::connect(proxy_addr).await.unwrap();
+    client.write_all(HANDSHAKE.as_bytes()).await.unwrap();
+    let response_head = read_head(&mut client).await;
+    assert!(
+        response_head.starts_with("HTTP/1.1 101"),
+        "{}",
+        response_head
+    );
+
+    // The only slot belongs to the tunnel
+    let status = status_line(proxy_addr).await;
+    assert!(status.starts_with("HTTP/1.1 503"), "{}", status);
+
+    // Closing the tunnel frees the slot
+    drop(client);
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+    let mut status = status_line(proxy_addr).await;
+    while status.starts_with("HTTP/1.1 503") && std::time::Instant::now() < deadline {
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        status = status_line(proxy_addr).await;
+    }
+    assert!(!status.starts_with("HTTP/1.1 503"), "{}", status);
+}